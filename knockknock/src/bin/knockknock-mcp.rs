@@ -8,6 +8,7 @@
 //! reachable right now" question. The CLI's default of 3 doesn't carry
 //! over.
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use rmcp::{
@@ -20,9 +21,12 @@ use rmcp::{
 };
 use serde::{Deserialize, Serialize};
 use zpinger::{
-    DnsPinger, GrpcPinger, GrpcStreamPinger, HlsPinger, HttpMethod, HttpPinger, MqttPinger,
-    MqttVersion, NtpPinger, Pinger, QuicPinger, RecordType, RtmpPinger, RtspPinger, StunPinger,
-    TcpPinger, TlsPinger, TurnPinger, UdpPinger, WebSocketPinger,
+    AssertionError, CancellationToken, Cancelled, CertInfo, ClientIdentity, CorsPreflight,
+    DnsPinger, DtlsPinger, Expect, Expectation, GrpcPinger, GrpcStreamPinger, GrpcWebPinger,
+    HlsPinger, Http3Pinger, HttpAuth, HttpComparePinger, HttpMethod, HttpPinger, HttpVersion,
+    MqttPinger, MqttVersion, NtpPinger, Pinger, QuicPinger, RecordType, ResumptionMode, RtmpPinger,
+    RtspPinger, SsePinger, StartTls, StunPinger, TcpMode, TcpPinger, TlsFailure, TlsOptions,
+    TlsPinger, TlsScanPinger, TurnPinger, UdpPinger, WebSocketPinger,
 };
 
 const DEFAULT_COUNT: u64 = 1;
@@ -40,6 +44,311 @@ struct TargetArgs {
     /// Per-ping timeout in milliseconds. Defaults to 5000.
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TlsTargetArgs {
+    /// Target endpoint, `host:port` or a URL with the protocol's scheme.
+    target: String,
+    /// Number of ping iterations. Defaults to 1.
+    #[serde(default)]
+    count: Option<u64>,
+    /// Per-ping timeout in milliseconds. Defaults to 5000.
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TlsPingArgs {
+    /// Target `host:port`, `host` (port 443) or `https://` URL.
+    target: String,
+    /// Add a `warning` detail when a certificate in the chain has
+    /// fewer than this many days left.
+    #[serde(default)]
+    warn_days: Option<u32>,
+    /// Fail the ping when a certificate in the chain has fewer than
+    /// this many days left.
+    #[serde(default)]
+    critical_days: Option<u32>,
+    /// Report the stapled OCSP response (`ocsp`, `ocsp_this_update`,
+    /// `ocsp_next_update`, `ocsp_responder`) and SCT count (`scts`);
+    /// a missing or stale staple adds a `warning`.
+    #[serde(default)]
+    stapling: bool,
+    /// Also time a second handshake resuming the first one's session.
+    /// Defaults to `off`.
+    #[serde(default)]
+    resumption: Option<ResumptionArg>,
+    /// Upgrade a plaintext connection in-band with this protocol's
+    /// STARTTLS dialogue first; the default port becomes the
+    /// protocol's.
+    #[serde(default)]
+    starttls: Option<StartTlsArg>,
+    #[serde(default)]
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TlsScanArgs {
+    /// Target `host:port`, `host` (port 443) or `https://` URL.
+    target: String,
+    /// Upgrade each connection in-band with this protocol's STARTTLS
+    /// dialogue first; the default port becomes the protocol's.
+    #[serde(default)]
+    starttls: Option<StartTlsArg>,
+    /// Per-connection timeout in milliseconds. Defaults to 5000.
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+/// Client certificate and trust settings shared by the TLS-capable
+/// tools. Ignored on plaintext schemes.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct TlsArgs {
+    /// Path to a PEM client certificate (leaf first, then any
+    /// intermediates). Requires `key`.
+    #[serde(default)]
+    cert: Option<String>,
+    /// Path to the PEM private key for `cert`.
+    #[serde(default)]
+    key: Option<String>,
+    /// Password for an encrypted PKCS#8 `key`.
+    #[serde(default)]
+    key_password: Option<String>,
+    /// Paths to PEM CA bundles to trust on top of the bundled roots.
+    #[serde(default)]
+    ca_files: Vec<String>,
+    /// Also trust the operating system's certificate store (e.g. a
+    /// corporate root CA installed in `/etc/ssl/certs`).
+    #[serde(default)]
+    system_roots: bool,
+    /// Trust only `ca_files` (and `system_roots`), not the bundled
+    /// roots.
+    #[serde(default)]
+    no_default_roots: bool,
+    /// SHA-256 SubjectPublicKeyInfo pins (base64, as `tls_ping`
+    /// returns in `certificates[].spki_sha256`, or hex). Some
+    /// certificate in the chain must match one.
+    #[serde(default)]
+    pin_sha256: Vec<String>,
+    /// Accept an untrusted certificate; the outcome is reported in
+    /// `details.verification` instead. Pins still apply.
+    #[serde(default)]
+    insecure: bool,
+    /// Name to send as SNI and verify the certificate against instead
+    /// of the target's host (probe a backend IP as the public name).
+    #[serde(default)]
+    server_name: Option<String>,
+    /// Send no SNI extension.
+    #[serde(default)]
+    no_sni: bool,
+    /// ALPN protocol(s) to offer, comma-separated, most preferred
+    /// first. `quic_ping` defaults to `h3`.
+    #[serde(default)]
+    alpn: Option<String>,
+}
+
+impl TlsArgs {
+    fn tls_options(&self) -> Result<TlsOptions, McpError> {
+        let invalid = |e: std::io::Error| McpError::invalid_params(e.to_string(), None);
+        if self.no_default_roots && self.ca_files.is_empty() && !self.system_roots {
+            return Err(McpError::invalid_params(
+                "no_default_roots needs a ca_files entry or system_roots",
+                None,
+            ));
+        }
+        let mut options = TlsOptions::new()
+            .with_replace_roots(self.no_default_roots)
+            .with_insecure(self.insecure)
+            .with_sni(!self.no_sni);
+        if let Some(name) = &self.server_name {
+            options = options.with_server_name(name.clone());
+        }
+        if let Some(alpn) = &self.alpn {
+            options = options.with_alpn(
+                alpn.split(',')
+                    .filter(|p| !p.is_empty())
+                    .map(|p| p.as_bytes().to_vec())
+                    .collect(),
+            );
+        }
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => {
+                let identity = ClientIdentity::from_files(cert, key, self.key_password.as_deref())
+                    .map_err(invalid)?;
+                options = options.with_client_identity(identity);
+            }
+            (None, None) if self.key_password.is_none() => {}
+            _ => {
+                return Err(McpError::invalid_params(
+                    "cert and key must be given together (key_password needs both)",
+                    None,
+                ))
+            }
+        }
+        for path in &self.ca_files {
+            options = options.with_ca_file(path).map_err(invalid)?;
+        }
+        if self.system_roots {
+            options = options.with_system_roots().map_err(invalid)?;
+        }
+        for pin in &self.pin_sha256 {
+            options = options.with_pin_sha256(pin).map_err(invalid)?;
+        }
+        Ok(options)
+    }
+}
+
+/// HTTP credentials shared by `http_ping`, `hls_ping` and `ws_ping`.
+/// With none given, `user:password@` in the URL is sent as Basic.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct AuthArgs {
+    /// Username for Basic auth (Digest with `digest`).
+    #[serde(default)]
+    username: Option<String>,
+    /// Password for `username`.
+    #[serde(default)]
+    password: Option<String>,
+    /// Answer the server's Digest challenge (MD5 or SHA-256) with
+    /// `username` / `password`, or the URL's, instead of sending
+    /// Basic.
+    #[serde(default)]
+    digest: bool,
+    /// Token to send as `Authorization: Bearer <token>`.
+    #[serde(default)]
+    bearer: Option<String>,
+    /// Environment variable of the MCP server holding the Bearer
+    /// token, so it stays out of the conversation.
+    #[serde(default)]
+    bearer_env: Option<String>,
+}
+
+impl AuthArgs {
+    /// Credentials for `url`; `None` leaves the pinger to its
+    /// userinfo.
+    fn http_auth(&self, url: &str) -> Result<Option<HttpAuth>, McpError> {
+        let given = [
+            self.username.is_some(),
+            self.bearer.is_some(),
+            self.bearer_env.is_some(),
+        ];
+        if given.iter().filter(|&&g| g).count() > 1 {
+            return Err(McpError::invalid_params(
+                "give one of username, bearer or bearer_env",
+                None,
+            ));
+        }
+        if self.digest && (self.bearer.is_some() || self.bearer_env.is_some()) {
+            return Err(McpError::invalid_params(
+                "digest needs username / password, not a bearer token",
+                None,
+            ));
+        }
+        if let Some(token) = &self.bearer {
+            return Ok(Some(HttpAuth::bearer(token.clone())));
+        }
+        if let Some(var) = &self.bearer_env {
+            return HttpAuth::bearer_from_env(var)
+                .map(Some)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None));
+        }
+        let (username, password) = match &self.username {
+            Some(username) => (username.clone(), self.password.clone().unwrap_or_default()),
+            None if self.digest => match HttpAuth::from_userinfo(&zpinger::uri::get_uri(url)) {
+                Some(HttpAuth::Basic { username, password }) => (username, password),
+                _ => {
+                    return Err(McpError::invalid_params(
+                        "digest needs username or user:password@ in the URL",
+                        None,
+                    ))
+                }
+            },
+            None => return Ok(None),
+        };
+        Ok(Some(if self.digest {
+            HttpAuth::digest(username, password)
+        } else {
+            HttpAuth::basic(username, password)
+        }))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct WsPingArgs {
+    /// WebSocket URL, `ws://` or `wss://`.
+    target: String,
+    /// Number of ping iterations. Defaults to 1.
+    #[serde(default)]
+    count: Option<u64>,
+    /// Per-ping timeout in milliseconds. Defaults to 5000.
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    auth: AuthArgs,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TcpPingArgs {
+    /// Target endpoint, format `host:port` (e.g. `db.example.com:5432`).
+    target: String,
+    /// How to decide the peer answered. Defaults to `echo` (write one
+    /// byte, wait for any reply), which only suits echo-style peers.
+    #[serde(default)]
+    mode: Option<TcpModeArg>,
+    /// Payload written before reading, required for mode `send`
+    /// (e.g. `"PING\r\n"` as a JSON string).
+    #[serde(default)]
+    send: Option<String>,
+    /// Regex the banner / reply must match. Not valid with `connect`.
+    #[serde(default)]
+    expect: Option<String>,
+    #[serde(default)]
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum TcpModeArg {
+    /// Write one byte, wait for any reply.
+    Echo,
+    /// Three-way handshake only (classic tcping).
+    Connect,
+    /// Wait for the server's greeting (SSH, SMTP, FTP, ...).
+    Banner,
+    /// Write `send`, then wait for the reply.
+    Send,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -47,35 +356,129 @@ struct HttpPingArgs {
     /// HTTP / HTTPS URL, e.g. `http://example.com/api` or
     /// `https://example.com:443/`.
     target: String,
-    /// HTTP method. Defaults to `get`.
+    /// HTTP method: `get`, `head`, `options`, `post`, `put`,
+    /// `delete`, `patch`, `connect`, or any other method token, sent
+    /// as given (e.g. `PROPFIND`). Defaults to `get`.
+    #[serde(default)]
+    method: Option<String>,
+    /// CORS preflight: send this `Origin` (usually with `method:
+    /// "options"`) and fail unless the response's
+    /// `Access-Control-Allow-*` headers allow it.
+    #[serde(default)]
+    origin: Option<String>,
+    /// With `origin`, the method the real request would use
+    /// (`Access-Control-Request-Method`).
+    #[serde(default)]
+    request_method: Option<String>,
+    /// With `origin`, the headers the real request would send
+    /// (`Access-Control-Request-Headers`).
+    #[serde(default)]
+    request_headers: Vec<String>,
+    /// For https://, send the request again over a resumed session
+    /// (`early_data`: as TLS 1.3 0-RTT). Defaults to `off`.
+    #[serde(default)]
+    resumption: Option<ResumptionArg>,
+    /// Most response body bytes to read. Defaults to 1 MiB; raise it
+    /// to time a large download end to end.
     #[serde(default)]
-    method: Option<HttpMethodArg>,
+    max_body_bytes: Option<usize>,
+    /// For post / put / patch, upload this many generated bytes
+    /// instead of `{}` and report upload throughput.
+    #[serde(default)]
+    upload_bytes: Option<usize>,
+    /// Extra request headers, each `Name: value`. A `Host`,
+    /// `User-Agent` or `Content-Type` header replaces the default.
+    #[serde(default)]
+    headers: Vec<String>,
+    /// Request body, sent with any method. Not with `body_file`.
+    #[serde(default)]
+    body: Option<String>,
+    /// Path of a file to send as the request body.
+    #[serde(default)]
+    body_file: Option<String>,
+    /// `Content-Type` for the body. Defaults to `application/json`.
+    #[serde(default)]
+    content_type: Option<String>,
+    /// `User-Agent` header. Defaults to `Knock Knock`.
+    #[serde(default)]
+    user_agent: Option<String>,
+    /// Statuses that count as success, e.g. `200`, `2xx,401`,
+    /// `200-299,304`. Defaults to 2xx and 3xx.
+    #[serde(default)]
+    expect_status: Option<String>,
+    /// Follow up to this many redirects and judge the final response;
+    /// each hop's URL, status and time land in `details`. Defaults to
+    /// 0 (don't follow).
+    #[serde(default)]
+    max_redirects: Option<usize>,
+    /// Don't follow redirects to another scheme, host or port.
+    #[serde(default)]
+    same_origin_redirects: bool,
+    /// Speak HTTP/2: ALPN `h2` for https://, prior knowledge (h2c)
+    /// for http://. Adds `settings_ms`.
+    #[serde(default)]
+    http2: bool,
+    /// With `http2`, time this many PING frames after the response
+    /// (`ping_rtt_ms`, `ping_rtt_min_ms`, `ping_rtt_max_ms`).
+    #[serde(default)]
+    h2_pings: Option<usize>,
     #[serde(default)]
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    auth: AuthArgs,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ResumptionArg {
+    /// One full handshake.
+    Off,
+    /// A full handshake, then one resuming its session.
+    Ticket,
+    /// `ticket`, offering 0-RTT early data on the second connection.
+    EarlyData,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
-enum HttpMethodArg {
-    Connect,
-    Get,
-    Post,
-    Put,
-    Delete,
-    Patch,
+enum StartTlsArg {
+    Smtp,
+    Imap,
+    Pop3,
+    Ftp,
+    Xmpp,
+    Ldap,
+    Postgres,
+}
+
+impl From<StartTlsArg> for StartTls {
+    fn from(value: StartTlsArg) -> Self {
+        match value {
+            StartTlsArg::Smtp => StartTls::Smtp,
+            StartTlsArg::Imap => StartTls::Imap,
+            StartTlsArg::Pop3 => StartTls::Pop3,
+            StartTlsArg::Ftp => StartTls::Ftp,
+            StartTlsArg::Xmpp => StartTls::Xmpp,
+            StartTlsArg::Ldap => StartTls::Ldap,
+            StartTlsArg::Postgres => StartTls::Postgres,
+        }
+    }
 }
 
-impl From<HttpMethodArg> for HttpMethod {
-    fn from(value: HttpMethodArg) -> Self {
+impl From<ResumptionArg> for ResumptionMode {
+    fn from(value: ResumptionArg) -> Self {
         match value {
-            HttpMethodArg::Connect => HttpMethod::Connect,
-            HttpMethodArg::Get => HttpMethod::Get,
-            HttpMethodArg::Post => HttpMethod::Post,
-            HttpMethodArg::Put => HttpMethod::Put,
-            HttpMethodArg::Delete => HttpMethod::Delete,
-            HttpMethodArg::Patch => HttpMethod::Patch,
+            ResumptionArg::Off => ResumptionMode::Off,
+            ResumptionArg::Ticket => ResumptionMode::Ticket,
+            ResumptionArg::EarlyData => ResumptionMode::EarlyData,
         }
     }
 }
@@ -93,6 +496,10 @@ struct DnsPingArgs {
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -104,6 +511,7 @@ enum RecordTypeArg {
     Mx,
     Ns,
     Txt,
+    Https,
 }
 
 impl From<RecordTypeArg> for RecordType {
@@ -115,6 +523,7 @@ impl From<RecordTypeArg> for RecordType {
             RecordTypeArg::Mx => RecordType::Mx,
             RecordTypeArg::Ns => RecordType::Ns,
             RecordTypeArg::Txt => RecordType::Txt,
+            RecordTypeArg::Https => RecordType::Https,
         }
     }
 }
@@ -134,6 +543,12 @@ struct MqttPingArgs {
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -149,6 +564,12 @@ struct GrpcPingArgs {
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -156,14 +577,82 @@ struct QuicPingArgs {
     /// QUIC endpoint, e.g. `quic://example.com:443`,
     /// `https://example.com`, or schemeless `host:port`.
     target: String,
-    /// ALPN protocol(s), comma-separated. Defaults to `h3`. Pass an
-    /// empty string to send no ALPN extension.
     #[serde(default)]
-    alpn: Option<String>,
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct Http3PingArgs {
+    /// HTTP/3 URL, e.g. `https://example.com/health`, `h3://host:8443/`,
+    /// or schemeless `host:port/path`.
+    target: String,
+    /// Statuses that count as success, e.g. `200`, `2xx,401`.
+    /// Defaults to 2xx and 3xx.
+    #[serde(default)]
+    expect_status: Option<String>,
+    #[serde(default)]
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct HttpCompareArgs {
+    /// HTTPS URL, e.g. `https://example.com/` or schemeless
+    /// `host:port/path`.
+    target: String,
+    /// DNS server for the HTTPS record lookup, e.g. `1.1.1.1`.
+    /// Defaults to the first nameserver in /etc/resolv.conf.
+    #[serde(default)]
+    resolver: Option<String>,
+    #[serde(default)]
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DtlsPingArgs {
+    /// DTLS endpoint, e.g. `coaps://sensor.example.com`,
+    /// `dtls://host:4433`, or schemeless `host:port`.
+    target: String,
     #[serde(default)]
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// PSK identity; with `psk_key`, handshake with a pre-shared key
+    /// instead of certificates.
+    #[serde(default)]
+    psk_identity: Option<String>,
+    /// Pre-shared key as hex, e.g. `73336372`.
+    #[serde(default)]
+    psk_key: Option<String>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -176,6 +665,41 @@ struct HlsPingArgs {
     count: Option<u64>,
     #[serde(default)]
     timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    auth: AuthArgs,
+    #[serde(flatten)]
+    tls: TlsArgs,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SsePingArgs {
+    /// Event stream URL, e.g. `https://example.com/events`.
+    url: String,
+    /// Wait for an event of this type; `message` matches events sent
+    /// without an `event:` field. Defaults to any type.
+    #[serde(default)]
+    event: Option<String>,
+    /// Wait for an event whose data matches this regex.
+    #[serde(default)]
+    data_pattern: Option<String>,
+    /// Sent as `Last-Event-ID`, asking the server to resume after
+    /// that event.
+    #[serde(default)]
+    last_event_id: Option<String>,
+    #[serde(default)]
+    count: Option<u64>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Response checks, e.g. `status=2xx`, `body=ok`,
+    /// `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    #[serde(default)]
+    assert: Vec<String>,
+    #[serde(flatten)]
+    tls: TlsArgs,
 }
 
 // -- result type ------------------------------------------------------
@@ -186,15 +710,81 @@ struct Iteration {
     success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    /// Pinger-specific facts from `Report::details` (e.g. `banner`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    details: BTreeMap<String, String>,
+    /// Failed `assert` checks; the endpoint answered, but not as
+    /// expected. Empty for transport errors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    assertion_failures: Vec<String>,
+    /// Set when the client cancelled the request mid-ping.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cancelled: bool,
+    /// Phases the cancelled ping finished (`connect`, `tls`, ...).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    completed_phases: Vec<String>,
+    /// Server certificate chain, leaf first (`tls_ping`). On a failed
+    /// handshake, what the server presented before it was rejected.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    certificates: Vec<Certificate>,
+    /// Why the TLS handshake failed, when it could be classified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tls_failure: Option<TlsFailureInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct TlsFailureInfo {
+    /// `expired`, `hostname_mismatch`, `missing_intermediate`, ...
+    cause: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Certificate {
+    subject: String,
+    issuer: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subject_alt_names: Vec<String>,
+    serial: String,
+    not_before: String,
+    not_after: String,
+    days_left: i64,
+    key_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_bits: Option<u32>,
+    signature_algorithm: String,
+    spki_sha256: String,
+}
+
+impl From<&CertInfo> for Certificate {
+    fn from(cert: &CertInfo) -> Self {
+        Self {
+            subject: cert.subject.clone(),
+            issuer: cert.issuer.clone(),
+            subject_alt_names: cert.subject_alt_names.clone(),
+            serial: cert.serial.clone(),
+            not_before: zpinger::format_utc(cert.not_before),
+            not_after: zpinger::format_utc(cert.not_after),
+            days_left: cert.days_until_expiry(),
+            key_type: cert.key_type.clone(),
+            key_bits: cert.key_bits,
+            signature_algorithm: cert.signature_algorithm.clone(),
+            spki_sha256: cert.spki_sha256.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
 struct Summary {
+    /// Pings that ran to completion; fewer than asked when cancelled.
     count: u64,
     recv: u64,
     lose: u64,
     lose_pct: u64,
     total_ms: f64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cancelled: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -203,39 +793,92 @@ struct PingReport {
     summary: Summary,
 }
 
-async fn run_pings(pinger: &dyn Pinger, count: u64) -> PingReport {
+async fn run_pings(
+    pinger: &dyn Pinger,
+    count: u64,
+    asserts: &[String],
+    cancel: &CancellationToken,
+) -> Result<PingReport, McpError> {
+    let expectations = asserts
+        .iter()
+        .map(|a| a.parse::<Expectation>())
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    let checked = Expect::new(pinger).with_expectations(expectations);
     let mut iterations = Vec::with_capacity(count as usize);
     let mut total = Duration::ZERO;
     let mut recv = 0u64;
+    let mut done = 0u64;
     for _ in 0..count {
-        match zpinger::timed(pinger).await {
-            Ok(elapsed) => {
+        match zpinger::timed_probe_with_cancel(&checked, cancel).await {
+            Ok((elapsed, report)) => {
                 total += elapsed;
                 recv += 1;
                 iterations.push(Iteration {
                     elapsed_ms: elapsed.as_secs_f64() * 1000.0,
                     success: true,
                     error: None,
+                    certificates: report.certificates.iter().map(Into::into).collect(),
+                    details: report.details.into_iter().collect(),
+                    assertion_failures: Vec::new(),
+                    cancelled: false,
+                    completed_phases: Vec::new(),
+                    tls_failure: None,
+                });
+            }
+            Err(e) => {
+                if let Some(cancelled) = Cancelled::from_io(&e) {
+                    iterations.push(Iteration {
+                        elapsed_ms: cancelled.elapsed.as_secs_f64() * 1000.0,
+                        success: false,
+                        error: Some(e.to_string()),
+                        details: BTreeMap::new(),
+                        assertion_failures: Vec::new(),
+                        cancelled: true,
+                        completed_phases: cancelled
+                            .phases
+                            .iter()
+                            .map(|p| p.name.to_string())
+                            .collect(),
+                        certificates: Vec::new(),
+                        tls_failure: None,
+                    });
+                    break;
+                }
+                iterations.push(Iteration {
+                    elapsed_ms: 0.0,
+                    success: false,
+                    error: Some(e.to_string()),
+                    details: BTreeMap::new(),
+                    assertion_failures: AssertionError::from_io(&e)
+                        .map(|a| a.failures.clone())
+                        .unwrap_or_default(),
+                    cancelled: false,
+                    completed_phases: Vec::new(),
+                    certificates: TlsFailure::from_io(&e)
+                        .map(|f| f.certificates.iter().map(Into::into).collect())
+                        .unwrap_or_default(),
+                    tls_failure: TlsFailure::from_io(&e).map(|f| TlsFailureInfo {
+                        cause: f.cause.name(),
+                        hint: f.cause.hint(),
+                    }),
                 });
             }
-            Err(e) => iterations.push(Iteration {
-                elapsed_ms: 0.0,
-                success: false,
-                error: Some(e.to_string()),
-            }),
         }
+        done += 1;
     }
-    let lose = count - recv;
-    PingReport {
+    let lose = done - recv;
+    Ok(PingReport {
         iterations,
         summary: Summary {
-            count,
+            count: done,
             recv,
             lose,
-            lose_pct: if count == 0 { 0 } else { lose * 100 / count },
+            lose_pct: (lose * 100).checked_div(done).unwrap_or(0),
             total_ms: total.as_secs_f64() * 1000.0,
+            cancelled: done < count,
         },
-    }
+    })
 }
 
 fn report_to_result(report: &PingReport) -> Result<CallToolResult, McpError> {
@@ -268,15 +911,37 @@ impl KnockknockServer {
     }
 
     #[tool(
-        description = "TCP ping — TCP connect + 1-byte probe + read. Measures full round trip from connect to first byte back."
+        description = "TCP ping. mode `echo` (default): connect + 1-byte probe + read, needs an echo-style peer. `connect`: three-way handshake only (classic tcping). `banner`: wait for the server's greeting (SSH/SMTP/FTP). `send`: write `send`, read the reply. Banner/reply text is returned in `details.banner`; `expect` is a regex it must match."
     )]
     async fn tcp_ping(
         &self,
-        Parameters(args): Parameters<TargetArgs>,
+        Parameters(args): Parameters<TcpPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = TcpPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        // Same inference as the CLI: a payload implies `send`, a bare
+        // `expect` implies `banner`.
+        let mode = match (args.mode, args.send) {
+            (Some(TcpModeArg::Connect), _) => TcpMode::Connect,
+            (Some(TcpModeArg::Banner), _) => TcpMode::Banner,
+            (Some(TcpModeArg::Echo), _) => TcpMode::Echo,
+            (Some(TcpModeArg::Send) | None, Some(payload)) => TcpMode::Send(payload.into_bytes()),
+            (Some(TcpModeArg::Send), None) => {
+                return Err(McpError::invalid_params(
+                    "mode `send` requires the `send` payload",
+                    None,
+                ))
+            }
+            (None, None) if args.expect.is_some() => TcpMode::Banner,
+            (None, None) => TcpMode::Echo,
+        };
+        let mut p = TcpPinger::new(args.target)
+            .with_mode(mode)
+            .with_timeout(timeout_or_default(args.timeout_ms));
+        if let Some(pattern) = args.expect {
+            p = p.with_expect(pattern);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -286,38 +951,123 @@ impl KnockknockServer {
     async fn udp_ping(
         &self,
         Parameters(args): Parameters<TargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
         let p = UdpPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
     #[tool(
-        description = "HTTP / HTTPS ping — full HTTP/1.1 request + response. https:// uses rustls + webpki-roots. Method defaults to GET."
+        description = "HTTP / HTTPS ping — full HTTP/1.1 request + response. https:// uses rustls + webpki-roots. Method defaults to GET; `head` never reads a body, and any other method token (e.g. `PROPFIND`) is sent as given. `origin` (with `request_method` / `request_headers`) makes it a CORS preflight that fails unless the `Access-Control-Allow-*` response headers allow them, adding `cors`. Succeeds on 2xx / 3xx unless `expect_status` says otherwise, and takes custom `headers`, `body` / `body_file`, `content_type`, `user_agent`. `max_redirects` follows redirects with per-hop timing. `http2` speaks HTTP/2 (ALPN h2, or h2c for http://) and adds `settings_ms`; `h2_pings` times PING frames. Reports `header_ms`, `body_ms`, `body_bytes` and `download_bytes_per_sec`; `upload_bytes` times a generated request body. `resumption` (`ticket` / `early_data`) sends the request a second time over a resumed TLS session and adds `full_ms`, `resumed_ms`, `resumption` and `early_data` details. `username` / `password` (Basic, or Digest with `digest`), `bearer` or `bearer_env` authenticate; URL userinfo is sent as Basic. Digest adds `auth_challenge_ms`."
     )]
     async fn http_ping(
         &self,
         Parameters(args): Parameters<HttpPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let method: HttpMethod = args.method.map(Into::into).unwrap_or(HttpMethod::Get);
-        let p =
-            HttpPinger::new(method, args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let method = match &args.method {
+            Some(method) => method
+                .parse()
+                .map_err(|e: std::io::Error| McpError::invalid_params(e.to_string(), None))?,
+            None => HttpMethod::Get,
+        };
+        let auth = args.auth.http_auth(&args.target)?;
+        let mut p = HttpPinger::new(method, args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?)
+            .with_resumption(args.resumption.map(Into::into).unwrap_or_default());
+        if let Some(auth) = auth {
+            p = p.with_auth(auth);
+        }
+        if let Some(origin) = args.origin {
+            let mut preflight =
+                CorsPreflight::new(origin).with_request_headers(args.request_headers);
+            if let Some(request_method) = args.request_method {
+                preflight = preflight.with_request_method(request_method);
+            }
+            p = p.with_cors(preflight);
+        }
+        if let Some(bytes) = args.max_body_bytes {
+            p = p.with_max_body_bytes(bytes);
+        }
+        if let Some(bytes) = args.upload_bytes {
+            p = p.with_upload_bytes(bytes);
+        }
+        for header in &args.headers {
+            let (name, value) = header
+                .split_once(':')
+                .filter(|(name, _)| !name.trim().is_empty())
+                .ok_or_else(|| {
+                    McpError::invalid_params(
+                        format!("header {header:?} is not `Name: value`"),
+                        None,
+                    )
+                })?;
+            p = p.with_header(name.trim(), value.trim());
+        }
+        match (args.body, args.body_file) {
+            (Some(_), Some(_)) => {
+                return Err(McpError::invalid_params(
+                    "give body or body_file, not both",
+                    None,
+                ))
+            }
+            (Some(body), None) => p = p.with_body(body),
+            (None, Some(path)) => {
+                let body = std::fs::read(&path).map_err(|e| {
+                    McpError::invalid_params(format!("body_file {path}: {e}"), None)
+                })?;
+                p = p.with_body(body);
+            }
+            (None, None) => {}
+        }
+        if let Some(content_type) = args.content_type {
+            p = p.with_content_type(content_type);
+        }
+        if let Some(user_agent) = args.user_agent {
+            p = p.with_user_agent(user_agent);
+        }
+        if let Some(set) = &args.expect_status {
+            let set = set
+                .parse()
+                .map_err(|e: std::io::Error| McpError::invalid_params(e.to_string(), None))?;
+            p = p.with_expected_status(set);
+        }
+        if let Some(hops) = args.max_redirects {
+            p = p
+                .with_redirects(hops)
+                .with_same_origin_redirects(args.same_origin_redirects);
+        }
+        if args.http2 {
+            p = p.with_version(HttpVersion::Http2);
+        }
+        if let Some(count) = args.h2_pings {
+            p = p.with_h2_pings(count);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
     #[tool(
-        description = "WebSocket ping — RFC 6455 upgrade handshake plus a control PING/PONG round trip. Accepts ws:// and wss://."
+        description = "WebSocket ping — RFC 6455 upgrade handshake plus a control PING/PONG round trip. Accepts ws:// and wss://. Takes the same `username` / `password` / `digest` / `bearer` / `bearer_env` credentials as http_ping for the Upgrade request."
     )]
     async fn ws_ping(
         &self,
-        Parameters(args): Parameters<TargetArgs>,
+        Parameters(args): Parameters<WsPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = WebSocketPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let auth = args.auth.http_auth(&args.target)?;
+        let mut p = WebSocketPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(auth) = auth {
+            p = p.with_auth(auth);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -327,13 +1077,14 @@ impl KnockknockServer {
     async fn dns_ping(
         &self,
         Parameters(args): Parameters<DnsPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
         let record_type = args.record_type.map(Into::into).unwrap_or(RecordType::A);
         let p = DnsPinger::new(args.server, args.query)
             .with_record_type(record_type)
             .with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -343,16 +1094,19 @@ impl KnockknockServer {
     async fn mqtt_ping(
         &self,
         Parameters(args): Parameters<MqttPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let mut p = MqttPinger::new(args.broker).with_timeout(timeout_or_default(args.timeout_ms));
+        let mut p = MqttPinger::new(args.broker)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
         if let Some(cid) = args.client_id {
             p = p.with_client_id(cid);
         }
         if args.v5 {
             p = p.with_version(MqttVersion::V5);
         }
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -362,14 +1116,35 @@ impl KnockknockServer {
     async fn grpc_ping(
         &self,
         Parameters(args): Parameters<GrpcPingArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let count = count_or_default(args.count);
+        let mut p = GrpcPinger::new(args.endpoint)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(service) = args.service {
+            p = p.with_service(service);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "gRPC-Web ping — sends grpc.health.v1.Health/Check as `application/grpc-web+proto` over HTTP/1.1, for services behind a gRPC-Web proxy (Envoy's grpc_web filter) that native gRPC can't reach. Decodes the trailers frame and fails unless grpc-status is 0 and the status is SERVING; a non-zero grpc-status is reported with its name and grpc-message. Details: the HTTP timings (`header_ms`, ...) plus `grpc_status` and `serving_status`. Accepts grpc:// / http:// (plaintext) and grpcs:// / https:// (TLS); a path in the endpoint is kept as a prefix."
+    )]
+    async fn grpc_web_ping(
+        &self,
+        Parameters(args): Parameters<GrpcPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let mut p =
-            GrpcPinger::new(args.endpoint).with_timeout(timeout_or_default(args.timeout_ms));
+        let mut p = GrpcWebPinger::new(args.endpoint)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
         if let Some(service) = args.service {
             p = p.with_service(service);
         }
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -379,40 +1154,106 @@ impl KnockknockServer {
     async fn grpc_watch_ping(
         &self,
         Parameters(args): Parameters<GrpcPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let mut p =
-            GrpcStreamPinger::new(args.endpoint).with_timeout(timeout_or_default(args.timeout_ms));
+        let mut p = GrpcStreamPinger::new(args.endpoint)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
         if let Some(service) = args.service {
             p = p.with_service(service);
         }
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
     #[tool(
-        description = "HLS ping — fetches the M3U8 playlist (following a variant if it's a master playlist) and times the first segment's first byte. Captures realistic player startup latency."
+        description = "HLS ping — fetches the M3U8 playlist (following a variant if it's a master playlist) and times the first segment's first byte. Captures realistic player startup latency. Takes the same credentials as http_ping, sent only to the playlist's host."
     )]
     async fn hls_ping(
         &self,
         Parameters(args): Parameters<HlsPingArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let count = count_or_default(args.count);
+        let auth = args.auth.http_auth(&args.url)?;
+        let mut p = HlsPinger::new(args.url)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(auth) = auth {
+            p = p.with_auth(auth);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "Server-Sent Events ping — opens a `text/event-stream` over HTTP/1.1 (http:// or https://) and times the first event, or the first matching `event` (type) and `data_pattern` (regex). Details: `header_ms` (request to response head), `first_event_ms` (request to the event), `event`, `events_seen`, plus `id` and `retry_ms` when the server sent them; the event's data is the body, so `body=` assertions check it. `last_event_id` resumes the stream after that event. Fails on a status other than 200, a content type other than text/event-stream, or a stream that closes before a match; a quiet stream fails at the timeout."
+    )]
+    async fn sse_ping(
+        &self,
+        Parameters(args): Parameters<SsePingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = HlsPinger::new(args.url).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let mut p = SsePinger::new(args.url)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(event) = args.event {
+            p = p.with_event(event);
+        }
+        if let Some(pattern) = args.data_pattern {
+            p = p.with_data_pattern(pattern);
+        }
+        if let Some(id) = args.last_event_id {
+            p = p.with_last_event_id(id);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
     #[tool(
-        description = "TLS handshake ping — TCP connect + TLS handshake (ClientHello → ServerHello → Certificate → Finished). Measures pure handshake latency including cert validation. Default port 443. target accepts host:port or https:// URL."
+        description = "TLS handshake ping — TCP connect + TLS handshake (ClientHello → ServerHello → Certificate → Finished). Measures pure handshake latency including cert validation. Returns the server's certificate chain (subject, SANs, issuer, serial, validity, days_left, key, signature, spki_sha256 pin) in `certificates`, and the negotiated `version`, `cipher_suite`, `key_exchange_group`, `alpn` and `resumed` in `details`. `warn_days` adds a `warning` detail and `critical_days` fails the ping when a certificate is that close to expiry. `resumption` (`ticket` / `early_data`) adds a second, resumed handshake and reports `full_ms`, `resumed_ms`, `resumption` and `early_data` (accepted / rejected). `stapling` reports the stapled OCSP response (`ocsp` status, `ocsp_this_update`, `ocsp_next_update`, `ocsp_responder`) and the SCT count (`scts`), warning on a missing or stale staple. `starttls` (`smtp`, `imap`, `pop3`, `ftp`, `xmpp`, `ldap`, `postgres`) upgrades a plaintext connection in-band first and reports `starttls_ms` (the plaintext negotiation) and `handshake_ms`. A failed handshake is classified in `tls_failure` (`cause` such as `expired`, `hostname_mismatch`, `missing_intermediate`, `self_signed`, `not_tls`, plus a fix `hint`), with the chain the server presented in `certificates`. Default port 443, or the STARTTLS protocol's. target accepts host:port or https:// URL."
     )]
     async fn tls_ping(
         &self,
-        Parameters(args): Parameters<TargetArgs>,
+        Parameters(args): Parameters<TlsPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = TlsPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let mut p = TlsPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?)
+            .with_resumption(args.resumption.map(Into::into).unwrap_or_default())
+            .with_stapling_checks(args.stapling);
+        if let Some(protocol) = args.starttls {
+            p = p.with_starttls(protocol.into());
+        }
+        if let Some(days) = args.warn_days {
+            p = p.with_expiry_warning(days);
+        }
+        if let Some(days) = args.critical_days {
+            p = p.with_expiry_critical(days);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "TLS scan — maps which protocol versions, cipher suites and key-exchange groups the server accepts, with one handshake per candidate (about 20 connections). Reports `tls1_0` … `tls1_3` (accepted / refused; TLS 1.0 / 1.1 via a raw ClientHello), `tls1_2_suites` / `tls1_3_suites` in the server's preference order, `tls1_2_order` / `tls1_3_order` (`server` or `client` — whose preference wins) and `groups` in `details`, with a `warning` per accepted TLS 1.0 / 1.1. Only suites rustls implements are tried. Certificates are not verified. `starttls` scans a port that upgrades in-band. Default port 443. target accepts host:port or https:// URL."
+    )]
+    async fn tls_scan(
+        &self,
+        Parameters(args): Parameters<TlsScanArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let mut p = TlsScanPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(protocol) = args.starttls {
+            p = p.with_starttls(protocol.into());
+        }
+        let report = run_pings(&p, 1, &[], &cancel).await?;
         report_to_result(&report)
     }
 
@@ -422,10 +1263,11 @@ impl KnockknockServer {
     async fn ntp_ping(
         &self,
         Parameters(args): Parameters<TargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
         let p = NtpPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -435,10 +1277,11 @@ impl KnockknockServer {
     async fn stun_ping(
         &self,
         Parameters(args): Parameters<TargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
         let p = StunPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -448,10 +1291,11 @@ impl KnockknockServer {
     async fn turn_ping(
         &self,
         Parameters(args): Parameters<TargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
         let p = TurnPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -460,11 +1304,14 @@ impl KnockknockServer {
     )]
     async fn rtsp_ping(
         &self,
-        Parameters(args): Parameters<TargetArgs>,
+        Parameters(args): Parameters<TlsTargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = RtspPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let p = RtspPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -473,11 +1320,14 @@ impl KnockknockServer {
     )]
     async fn rtmp_ping(
         &self,
-        Parameters(args): Parameters<TargetArgs>,
+        Parameters(args): Parameters<TlsTargetArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let p = RtmpPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        let report = run_pings(&p, count).await;
+        let p = RtmpPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 
@@ -487,25 +1337,101 @@ impl KnockknockServer {
     async fn quic_ping(
         &self,
         Parameters(args): Parameters<QuicPingArgs>,
+        cancel: CancellationToken,
     ) -> Result<CallToolResult, McpError> {
         let count = count_or_default(args.count);
-        let alpns: Vec<Vec<u8>> = match args.alpn.as_deref() {
-            Some(s) => s
-                .split(',')
-                .filter(|p| !p.is_empty())
-                .map(|p| p.as_bytes().to_vec())
-                .collect(),
-            None => vec![b"h3".to_vec()],
-        };
-        let mut p = QuicPinger::new(args.target).with_timeout(timeout_or_default(args.timeout_ms));
-        if !alpns.is_empty() {
-            p = p.with_alpn(alpns);
+        let p = QuicPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "HTTP/3 ping — QUIC handshake with ALPN `h3`, then a GET on an HTTP/3 request stream (QPACK-encoded headers). Unlike `quic_ping`, fails when the server completes QUIC but doesn't serve HTTP/3. Succeeds on 2xx / 3xx unless `expect_status` says otherwise. Returns status, headers and body; details `handshake_ms` (QUIC + TLS), `request_ms` (request to last body byte), `header_ms`, `body_bytes`. Default port 443. `target` accepts `https://`, `h3://`, or schemeless host:port/path."
+    )]
+    async fn http3_ping(
+        &self,
+        Parameters(args): Parameters<Http3PingArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let count = count_or_default(args.count);
+        let mut p = Http3Pinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(set) = &args.expect_status {
+            let set = set
+                .parse()
+                .map_err(|e: std::io::Error| McpError::invalid_params(e.to_string(), None))?;
+            p = p.with_expected_status(set);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "HTTP protocol comparison — fetches one HTTPS URL over HTTP/1.1, HTTP/2 and HTTP/3 (each on a fresh connection) and reports them side by side: details `http1_ms` / `http2_ms` / `http3_ms` with `*_status`, or `*_error` for a protocol that failed; `alt_svc` (the header on the HTTP/1.1 response), `https_rr` (the HTTPS DNS records), `h3_advertised` (alt-svc / https-rr / no), `fastest`, `failed`, and `warning` when HTTP/3 is advertised but fails. HTTP/3 is probed on the advertised port. Fails only when no protocol answers. `target` accepts `https://` or schemeless host:port/path."
+    )]
+    async fn http_compare(
+        &self,
+        Parameters(args): Parameters<HttpCompareArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let count = count_or_default(args.count);
+        let mut p = HttpComparePinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        if let Some(server) = args.resolver {
+            p = p.with_resolver(server);
+        }
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
+        report_to_result(&report)
+    }
+
+    #[tool(
+        description = "DTLS ping — completes a DTLS 1.2 handshake over UDP (HelloVerifyRequest cookie exchange included) and reports the time taken, for CoAP over DTLS and WebRTC media endpoints. Certificate mode (default) verifies the server's chain with the usual trust parameters and returns it in `certificates`; `psk_identity` + `psk_key` (hex) switch to pre-shared key suites. Details: `mode` (certificate / psk), `version`, `handshake_ms`. Default port 5684. `target` accepts `dtls://`, `coaps://`, or schemeless host:port."
+    )]
+    async fn dtls_ping(
+        &self,
+        Parameters(args): Parameters<DtlsPingArgs>,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, McpError> {
+        let count = count_or_default(args.count);
+        let mut p = DtlsPinger::new(args.target)
+            .with_timeout(timeout_or_default(args.timeout_ms))
+            .with_tls_options(args.tls.tls_options()?);
+        match (args.psk_identity, args.psk_key) {
+            (Some(identity), Some(key)) => p = p.with_psk(identity, parse_hex(&key)?),
+            (None, None) => {}
+            _ => {
+                return Err(McpError::invalid_params(
+                    "psk_identity and psk_key go together",
+                    None,
+                ))
+            }
         }
-        let report = run_pings(&p, count).await;
+        let report = run_pings(&p, count, &args.assert, &cancel).await?;
         report_to_result(&report)
     }
 }
 
+/// `psk_key`: an even number of hex digits.
+fn parse_hex(input: &str) -> Result<Vec<u8>, McpError> {
+    let invalid = || McpError::invalid_params(format!("psk_key is not hex: {input}"), None);
+    if input.is_empty() || !input.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|i| {
+            input
+                .get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
 impl Default for KnockknockServer {
     fn default() -> Self {
         Self::new()
@@ -525,7 +1451,16 @@ impl ServerHandler for KnockknockServer {
                 "knock_knock latency probe — every supported protocol exposed as a tool. \
                  Each tool returns per-iteration timings plus a summary. Default count is 1; \
                  increase via the count argument when you want statistical RTT info. \
-                 Default timeout is 5000 ms; override via timeout_ms when probing slow endpoints."
+                 Default timeout is 5000 ms; override via timeout_ms when probing slow endpoints. \
+                 Every tool takes an optional `assert` list (status=2xx, header=Name:regex, \
+                 body=regex, json=$.path=value, answer=regex, latency=200ms) to check the response. \
+                 Cancelling a call stops the ping in flight; the result marks it `cancelled` \
+                 with the phases it completed. TLS-capable tools accept `cert`, `key` and \
+                 `key_password` (PEM file paths) for servers that require a client certificate, \
+                 plus `ca_files`, `system_roots`, `no_default_roots`, `pin_sha256` and `insecure` \
+                 to control which server certificates are trusted (gRPC supports only the CA \
+                 ones), and \
+                 `server_name`, `no_sni` and `alpn` to shape the ClientHello."
                     .into(),
             ),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
@@ -1,11 +1,15 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use std::io::Result;
+use std::path::PathBuf;
 use std::time::Duration;
 use zpinger::{
-    DnsPinger, GrpcPinger, GrpcStreamPinger, HlsPinger, HttpPinger, MqttPinger, MqttVersion,
-    NtpPinger, Pinger, QuicPinger, RtmpPinger, RtspPinger, StunPinger, TcpPinger, TlsPinger,
-    TurnPinger, UdpPinger, WebSocketPinger,
+    AssertionError, CancellationToken, Cancelled, CertInfo, ClientIdentity, CorsPreflight,
+    DnsPinger, DtlsPinger, Expect, Expectation, GrpcPinger, GrpcStreamPinger, GrpcWebPinger,
+    HlsPinger, Http3Pinger, HttpAuth, HttpComparePinger, HttpPinger, HttpVersion, MqttPinger,
+    MqttVersion, NtpPinger, Pinger, QuicPinger, Report, ResumptionMode, RtmpPinger, RtspPinger,
+    SsePinger, StatusSet, StunPinger, TcpMode, TcpPinger, TlsFailure, TlsOptions, TlsPinger,
+    TlsScanPinger, TurnPinger, UdpPinger, WebSocketPinger,
 };
 
 #[derive(Parser)]
@@ -17,18 +21,176 @@ struct Cli {
     /// ping times
     #[arg(short, long, default_value_t = 3, global = true)]
     count: u64,
+
+    /// Check each response, e.g. `status=2xx`, `header=Server`,
+    /// `body=ok`, `json=$.status=up`, `answer=^10\.`, `latency=200ms`.
+    /// Repeatable; a ping that answers but misses one fails as an
+    /// assertion.
+    #[arg(long = "assert", value_name = "EXPR", global = true)]
+    asserts: Vec<Expectation>,
+
+    /// Client certificate chain (PEM) for servers that require mutual
+    /// TLS. Applies to every TLS-capable protocol.
+    #[arg(long, value_name = "PATH", global = true, requires = "key")]
+    cert: Option<PathBuf>,
+
+    /// Private key (PEM: PKCS#8, PKCS#1 or SEC1) matching `--cert`.
+    #[arg(long, value_name = "PATH", global = true, requires = "cert")]
+    key: Option<PathBuf>,
+
+    /// Password for an encrypted PKCS#8 `--key`.
+    #[arg(long, value_name = "PASSWORD", global = true, requires = "key")]
+    key_password: Option<String>,
+
+    /// Extra CA certificates (PEM bundle) to trust, on top of the
+    /// bundled roots. Repeatable.
+    #[arg(long = "ca-file", value_name = "PATH", global = true)]
+    ca_files: Vec<PathBuf>,
+
+    /// Also trust the operating system's certificate store
+    /// (`/etc/ssl/certs`, or `SSL_CERT_FILE` / `SSL_CERT_DIR`), e.g.
+    /// for a corporate root CA installed there.
+    #[arg(long, global = true)]
+    system_roots: bool,
+
+    /// Trust only the `--ca-file` / `--system-roots` certificates, not
+    /// the bundled roots.
+    #[arg(long, global = true)]
+    no_default_roots: bool,
+
+    /// Require a certificate in the chain whose public key hashes to
+    /// PIN (SHA-256 of the SubjectPublicKeyInfo, base64 as `tls
+    /// --chain` prints it, or hex). Repeatable; any match passes.
+    #[arg(long = "pin-sha256", value_name = "PIN", global = true)]
+    pins: Vec<String>,
+
+    /// Don't fail on an untrusted certificate. The verification result
+    /// is still shown (`verification=`), and pins are still enforced.
+    #[arg(short = 'k', long, global = true)]
+    insecure: bool,
+
+    /// Server name to send as SNI and verify the certificate against,
+    /// instead of the target's host — e.g. to probe one backend IP as
+    /// the production hostname.
+    #[arg(long, value_name = "NAME", global = true, conflicts_with = "no_sni")]
+    sni: Option<String>,
+
+    /// Send no SNI at all.
+    #[arg(long, global = true)]
+    no_sni: bool,
+
+    /// ALPN protocol(s) to offer, comma-separated, most preferred
+    /// first (`h2,http/1.1`). QUIC defaults to `h3`.
+    #[arg(long, value_name = "LIST", global = true)]
+    alpn: Option<String>,
+
+    /// `tls` / `http`: follow each handshake with a second connection
+    /// resuming its session, and show both handshake times.
+    #[arg(long, global = true)]
+    resume: bool,
+
+    /// `--resume`, offering TLS 1.3 0-RTT early data on the second
+    /// connection; `http` sends its request as early data.
+    #[arg(long, global = true)]
+    early_data: bool,
+
+    /// `http` / `hls` / `ws`: Basic credentials, `USER:PASSWORD`.
+    /// Without it, `user:password@` in the URL is used.
+    #[arg(short = 'u', long, value_name = "USER:PASSWORD", global = true)]
+    user: Option<String>,
+
+    /// Send the `--user` (or URL) credentials as Digest (MD5 or
+    /// SHA-256, whichever the server's challenge asks for) instead of
+    /// Basic.
+    #[arg(long, global = true, conflicts_with_all = ["bearer", "bearer_env"])]
+    digest: bool,
+
+    /// `http` / `hls` / `ws`: send `Authorization: Bearer TOKEN`.
+    #[arg(long, value_name = "TOKEN", global = true, conflicts_with_all = ["user", "bearer_env"])]
+    bearer: Option<String>,
+
+    /// Like `--bearer`, reading the token from environment variable
+    /// VAR so it stays out of shell history.
+    #[arg(long, value_name = "VAR", global = true, conflicts_with = "user")]
+    bearer_env: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Command {
-    /// TCP ping
-    Tcp { target: String },
+    /// TCP ping. By default writes one byte and waits for any reply,
+    /// which needs an echo-style peer; for real services pick
+    /// --connect-only, --banner or --send.
+    Tcp {
+        target: String,
+        /// Only complete the three-way handshake, then close (classic
+        /// tcping). Nothing is written or read.
+        #[arg(long, conflicts_with_all = ["banner", "send", "expect"])]
+        connect_only: bool,
+        /// Send nothing and wait for the server's greeting (SSH, SMTP,
+        /// FTP, ...). The greeting is printed after `time=`.
+        #[arg(long, conflicts_with = "send")]
+        banner: bool,
+        /// Payload to write before reading the reply. `\r`, `\n`,
+        /// `\t`, `\0`, `\\` and `\xNN` escapes are decoded.
+        #[arg(long)]
+        send: Option<String>,
+        /// Regex the reply must match. Implies --banner unless --send
+        /// is given.
+        #[arg(long)]
+        expect: Option<String>,
+    },
     /// UDP ping
     Udp { target: String },
-    /// HTTP ping
+    /// HTTP ping — reads the whole response and reports header time,
+    /// body time, bytes received and throughput.
     Http {
         #[command(subcommand)]
-        method: HttpMethod,
+        method: Box<HttpMethod>,
+        /// Most response body bytes to read (e.g. `64k`, `10M`);
+        /// default 1M.
+        #[arg(long, global = true, value_parser = parse_size)]
+        max_body: Option<usize>,
+        /// Upload this many generated bytes as the POST / PUT / PATCH
+        /// body instead of `{}`, and time it (e.g. `1M`).
+        #[arg(long, global = true, value_parser = parse_size, conflicts_with = "data")]
+        upload_size: Option<usize>,
+        /// Extra request header, `Name: value`. Repeatable; a `Host`,
+        /// `User-Agent` or `Content-Type` header replaces the default.
+        #[arg(short = 'H', long = "header", value_name = "HEADER", global = true, value_parser = parse_header)]
+        headers: Vec<(String, String)>,
+        /// Request body, sent with any method. `@path` reads it from a
+        /// file.
+        #[arg(short = 'd', long, global = true, value_parser = parse_data)]
+        data: Option<RequestBody>,
+        /// `Content-Type` for the body [default: application/json].
+        #[arg(long, global = true)]
+        content_type: Option<String>,
+        /// `User-Agent` header [default: Knock Knock].
+        #[arg(long, global = true)]
+        user_agent: Option<String>,
+        /// Statuses that count as success, e.g. `200`, `2xx,401`,
+        /// `200-299,304` [default: 2xx,3xx].
+        #[arg(long, value_name = "SET", global = true)]
+        expect_status: Option<StatusSet>,
+        /// Follow redirects and judge the final response, reporting
+        /// each hop's URL, status and time.
+        #[arg(short = 'L', long, global = true)]
+        location: bool,
+        /// Most redirects `-L` follows.
+        #[arg(long, value_name = "N", global = true, default_value_t = 10)]
+        max_redirects: usize,
+        /// With `-L`, don't follow redirects to another scheme, host or
+        /// port.
+        #[arg(long, global = true, requires = "location")]
+        same_origin: bool,
+        /// Speak HTTP/2: ALPN `h2` for https://, prior knowledge (h2c)
+        /// for http://. Adds `settings_ms`.
+        #[arg(long, global = true)]
+        http2: bool,
+        /// With `--http2`, time this many PING frames after the
+        /// response (`ping_rtt_ms`, min, max).
+        #[arg(long, value_name = "N", global = true, requires = "http2")]
+        h2_pings: Option<usize>,
     },
     /// WebSocket ping (ws:// or wss://) — runs full upgrade handshake
     /// plus a control PING/PONG round trip.
@@ -50,7 +212,9 @@ enum Command {
     /// `grpc.health.v1.Health/Check` unary RPC. Accepts `grpc://` /
     /// `http://` for plaintext H2C and `grpcs://` / `https://` for
     /// TLS. Pass `--watch` to call `Health/Watch` server-streaming
-    /// instead and measure time-to-first-status-message.
+    /// instead and measure time-to-first-status-message, or `--web`
+    /// to send the Check as gRPC-Web over HTTP/1.1, for services
+    /// behind a gRPC-Web proxy such as Envoy.
     Grpc {
         /// gRPC endpoint, e.g. `grpc://localhost:50051` or
         /// `https://api.example.com:443`.
@@ -63,6 +227,11 @@ enum Command {
         /// Health/Check.
         #[arg(long)]
         watch: bool,
+        /// Send Health/Check as gRPC-Web (`application/grpc-web+proto`
+        /// over HTTP/1.1) instead of native gRPC. A path in the
+        /// endpoint is kept as a prefix.
+        #[arg(long, conflicts_with = "watch")]
+        web: bool,
     },
     /// HLS ping — fetches a master / media `.m3u8`, follows a variant
     /// if needed, and times the first segment fetch (Range:
@@ -72,6 +241,26 @@ enum Command {
         /// `https://example.com/stream/master.m3u8`.
         url: String,
     },
+    /// Server-Sent Events ping — opens the `text/event-stream` with a
+    /// GET and times the first event (`first_event_ms`), or the first
+    /// one matching --event / --data. Fails on a status other than
+    /// 200, another content type, or a stream that closes first.
+    /// `http://` or `https://`.
+    Sse {
+        /// Event stream URL, e.g. `https://example.com/events`.
+        url: String,
+        /// Wait for an event of this type (`message` for events sent
+        /// without an `event:` field).
+        #[arg(long, value_name = "NAME")]
+        event: Option<String>,
+        /// Wait for an event whose data matches this regex.
+        #[arg(long, value_name = "REGEX")]
+        data: Option<String>,
+        /// Send `Last-Event-ID`, asking the server to resume the
+        /// stream after that event.
+        #[arg(long, value_name = "ID")]
+        last_event_id: Option<String>,
+    },
     /// MQTT 3.1.1 ping (mqtt:// or mqtts://). Runs the
     /// CONNECT/CONNACK handshake plus a PINGREQ/PINGRESP control
     /// round trip, then DISCONNECT. Default port 1883 plain, 8883
@@ -92,11 +281,54 @@ enum Command {
     /// TLS handshake ping — TCP connect + TLS handshake (ClientHello
     /// → ServerHello → Certificate → Finished), then close. Reports
     /// success when the handshake completes; cert validation errors
-    /// surface as protocol errors. Default port 443.
+    /// surface as protocol errors. Default port 443. The leaf
+    /// certificate's subject, issuer and expiry are printed after
+    /// `time=`.
+    ///
+    /// With --warn-days / --critical-days the exit status follows the
+    /// monitoring-plugin convention: 0 OK, 1 warning, 2 critical (or
+    /// any failed ping).
+    ///
+    /// --starttls upgrades a plaintext mail, directory or database
+    /// connection in-band first; the default port becomes the
+    /// protocol's (587, 143, 110, 21, 5222, 389, 5432).
+    ///
+    /// --scan maps the protocol versions, cipher suites and
+    /// key-exchange groups the server accepts instead; an accepted
+    /// TLS 1.0 / 1.1 is a warning (exit status 1).
     Tls {
         /// Target host:port or https:// URL, e.g.
         /// `example.com:443` or `https://api.example.com`.
         target: String,
+        /// Warn when a certificate in the chain has fewer than this
+        /// many days left.
+        #[arg(long, value_name = "DAYS")]
+        warn_days: Option<u32>,
+        /// Fail when a certificate in the chain has fewer than this
+        /// many days left.
+        #[arg(long, value_name = "DAYS")]
+        critical_days: Option<u32>,
+        /// Print every certificate in the chain after the first
+        /// successful ping.
+        #[arg(long)]
+        chain: bool,
+        /// Check OCSP stapling and Certificate Transparency: show the
+        /// stapled response's status, validity and responder, and the
+        /// SCT count; warn on a missing or stale staple.
+        #[arg(long)]
+        stapling: bool,
+        /// Run this protocol's STARTTLS dialogue before the
+        /// handshake, timing it separately (`starttls_ms`).
+        #[arg(long, value_enum, value_name = "PROTOCOL")]
+        starttls: Option<StartTlsProtocol>,
+        /// Scan instead of timing one handshake: one handshake per
+        /// protocol version, cipher suite and key-exchange group,
+        /// printed as a table. Certificates aren't verified.
+        #[arg(
+            long,
+            conflicts_with_all = ["warn_days", "critical_days", "chain", "stapling"]
+        )]
+        scan: bool,
     },
     /// NTP ping — sends one 48-byte NTP v4 client packet
     /// (RFC 5905 §7.3) and validates the server response (mode +
@@ -142,10 +374,46 @@ enum Command {
     Quic {
         /// QUIC endpoint, e.g. `quic://host:443` or `example.com`.
         endpoint: String,
-        /// ALPN protocol(s) to advertise, comma-separated. Default
-        /// is `h3`.
-        #[arg(long, default_value = "h3")]
-        alpn: String,
+    },
+    /// HTTP/3 ping — QUIC handshake with ALPN `h3`, then a GET on a
+    /// request stream. Reports handshake and request time separately
+    /// and fails on a status outside --expect-status. `https://`,
+    /// `h3://`, or schemeless `host:port/path`. Default port 443.
+    Http3 {
+        /// HTTP/3 URL, e.g. `https://example.com/health`.
+        url: String,
+        /// Statuses that count as success [default: 2xx,3xx].
+        #[arg(long, value_name = "SET")]
+        expect_status: Option<StatusSet>,
+    },
+    /// Protocol comparison — fetches the URL over HTTP/1.1, HTTP/2
+    /// and HTTP/3, each on a fresh connection, and prints their
+    /// latencies side by side with what the origin advertises about
+    /// HTTP/3 (`Alt-Svc` and its HTTPS DNS record). HTTP/3 goes to
+    /// the advertised port. `https://` or schemeless `host:port/path`.
+    Compare {
+        /// HTTPS URL, e.g. `https://example.com/`.
+        url: String,
+        /// DNS server for the HTTPS record lookup [default: the first
+        /// nameserver in /etc/resolv.conf].
+        #[arg(long, value_name = "SERVER")]
+        resolver: Option<String>,
+    },
+    /// DTLS ping — completes a DTLS 1.2 handshake over UDP (cookie
+    /// exchange included) and reports the time taken. Authenticates
+    /// the server by certificate, honouring the global trust flags,
+    /// or with a pre-shared key. `dtls://`, `coaps://`, or schemeless
+    /// `host:port` all accepted. Default port 5684 (CoAP over DTLS).
+    Dtls {
+        /// DTLS endpoint, e.g. `coaps://host` or `host:4433`.
+        endpoint: String,
+        /// PSK identity sent to the server; switches to pre-shared
+        /// key mode.
+        #[arg(long, requires = "psk_key")]
+        psk_identity: Option<String>,
+        /// Pre-shared key, as hex.
+        #[arg(long, value_parser = parse_hex, requires = "psk_identity")]
+        psk_key: Option<PskKey>,
     },
 }
 
@@ -157,6 +425,7 @@ enum DnsType {
     Mx,
     Ns,
     Txt,
+    Https,
 }
 
 impl From<DnsType> for zpinger::RecordType {
@@ -168,34 +437,197 @@ impl From<DnsType> for zpinger::RecordType {
             DnsType::Mx => zpinger::RecordType::Mx,
             DnsType::Ns => zpinger::RecordType::Ns,
             DnsType::Txt => zpinger::RecordType::Txt,
+            DnsType::Https => zpinger::RecordType::Https,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum StartTlsProtocol {
+    Smtp,
+    Imap,
+    Pop3,
+    Ftp,
+    Xmpp,
+    Ldap,
+    Postgres,
+}
+
+impl From<StartTlsProtocol> for zpinger::StartTls {
+    fn from(value: StartTlsProtocol) -> Self {
+        match value {
+            StartTlsProtocol::Smtp => zpinger::StartTls::Smtp,
+            StartTlsProtocol::Imap => zpinger::StartTls::Imap,
+            StartTlsProtocol::Pop3 => zpinger::StartTls::Pop3,
+            StartTlsProtocol::Ftp => zpinger::StartTls::Ftp,
+            StartTlsProtocol::Xmpp => zpinger::StartTls::Xmpp,
+            StartTlsProtocol::Ldap => zpinger::StartTls::Ldap,
+            StartTlsProtocol::Postgres => zpinger::StartTls::Postgres,
         }
     }
 }
 
 #[derive(Subcommand)]
 enum HttpMethod {
-    Connect { target: String },
-    Get { target: String },
-    Post { target: String },
-    Put { target: String },
-    Delete { target: String },
-    Patch { target: String },
+    Connect {
+        target: String,
+    },
+    Get {
+        target: String,
+    },
+    Post {
+        target: String,
+    },
+    Put {
+        target: String,
+    },
+    Delete {
+        target: String,
+    },
+    Patch {
+        target: String,
+    },
+    /// HEAD — the response's body is never read, whatever its
+    /// `Content-Length` says.
+    Head {
+        target: String,
+    },
+    /// OPTIONS — with --origin, a CORS preflight: fails unless the
+    /// response's `Access-Control-Allow-*` headers allow the origin,
+    /// method and headers.
+    Options {
+        target: String,
+        /// `Origin` to send, e.g. `https://app.example.com`.
+        #[arg(long)]
+        origin: Option<String>,
+        /// Method the real request would use
+        /// (`Access-Control-Request-Method`).
+        #[arg(long, value_name = "METHOD", requires = "origin")]
+        request_method: Option<String>,
+        /// Headers the real request would send, comma-separated
+        /// (`Access-Control-Request-Headers`).
+        #[arg(long, value_name = "NAMES", value_delimiter = ',', requires = "origin")]
+        request_headers: Vec<String>,
+    },
+    /// Any other method, sent as given — e.g. `request PROPFIND
+    /// <target>` for WebDAV.
+    Request {
+        #[arg(value_parser = parse_method)]
+        method: zpinger::HttpMethod,
+        target: String,
+    },
 }
 
-fn display_ping_info(target: &str, elapsed_time: Duration) {
-    let console_str = format!(
+fn display_ping_info(target: &str, elapsed_time: Duration, report: &Report) {
+    let mut console_str = format!(
         "{}: time={:>10} ms",
         target,
         format!("{:.5}", elapsed_time.as_secs_f64() * 1000.0)
     );
+    for (key, value) in &report.details {
+        console_str.push_str(&format!(" {key}={value:?}"));
+    }
     println!("{}", console_str.green());
 }
 
-fn display_ping_fail(target: &str) {
-    let console_str = format!("{}: fail", target);
+fn display_cert_chain(certificates: &[CertInfo]) {
+    for (depth, cert) in certificates.iter().enumerate() {
+        println!("  {depth}: {}", cert.subject.bold());
+        println!("     issuer:    {}", cert.issuer);
+        if !cert.subject_alt_names.is_empty() {
+            println!("     SANs:      {}", cert.subject_alt_names.join(", "));
+        }
+        println!("     serial:    {}", cert.serial);
+        println!(
+            "     validity:  {} .. {} ({} days left)",
+            zpinger::format_utc(cert.not_before),
+            zpinger::format_utc(cert.not_after),
+            cert.days_until_expiry()
+        );
+        let bits = cert
+            .key_bits
+            .map(|b| format!(" {b} bits"))
+            .unwrap_or_default();
+        println!("     key:       {}{bits}", cert.key_type);
+        println!("     signature: {}", cert.signature_algorithm);
+        println!("     pin:       sha256//{}", cert.spki_sha256);
+    }
+}
+
+/// One scan result per line, after the first successful `tls --scan`.
+fn display_scan(report: &Report) {
+    for (key, value) in &report.details {
+        let line = format!("  {key:<14} {value}");
+        match value.as_str() {
+            "refused" => println!("{}", line.dimmed()),
+            _ if key == "warning" => println!("{}", line.yellow()),
+            _ => println!("{line}"),
+        }
+    }
+}
+
+/// The `compare` table, after the first successful comparison.
+fn display_comparison(report: &Report) {
+    println!("  {:<14} {:>10}  result", "protocol", "time (ms)");
+    for (key, name) in [
+        ("http1", "HTTP/1.1"),
+        ("http2", "HTTP/2"),
+        ("http3", "HTTP/3"),
+    ] {
+        let time = report.detail(&format!("{key}_ms")).unwrap_or("-");
+        match report.detail(&format!("{key}_error")) {
+            Some(err) => println!("{}", format!("  {name:<14} {time:>10}  {err}").red()),
+            None => {
+                let status = report.detail(&format!("{key}_status")).unwrap_or("-");
+                let line = format!("  {name:<14} {time:>10}  {status}");
+                match report.detail("fastest") == Some(name) {
+                    true => println!("{}", line.bold()),
+                    false => println!("{line}"),
+                }
+            }
+        }
+    }
+    for key in ["alt_svc", "https_rr", "h3_advertised"] {
+        if let Some(value) = report.detail(key) {
+            println!("  {key:<14} {value}");
+        }
+    }
+    if let Some(warning) = report.detail("warning") {
+        println!("{}", format!("  {:<14} {warning}", "warning").yellow());
+    }
+}
+
+fn display_ping_fail(target: &str, err: &std::io::Error) {
+    let console_str = match (AssertionError::from_io(err), TlsFailure::from_io(err)) {
+        (Some(assertion), _) => format!("{}: fail ({})", target, assertion),
+        (None, Some(failure)) => format!("{}: fail ({})", target, failure.cause),
+        (None, None) => format!("{}: fail", target),
+    };
     println!("{}", console_str.red());
 }
 
+/// What the server presented and the likely fix, after the first
+/// classified TLS failure.
+fn display_tls_failure(failure: &TlsFailure) {
+    println!("  error: {}", failure.message);
+    for (depth, cert) in failure.certificates.iter().enumerate() {
+        println!(
+            "  {depth}: {} (issuer: {}, expires {}, {} days left)",
+            cert.subject.bold(),
+            cert.issuer,
+            zpinger::format_utc(cert.not_after),
+            cert.days_until_expiry()
+        );
+    }
+    if let Some(hint) = failure.cause.hint() {
+        println!("{}", format!("  hint: {hint}").yellow());
+    }
+}
+
+fn display_ping_cancelled(target: &str, cancelled: &Cancelled) {
+    println!("{}", format!("{}: {}", target, cancelled).yellow());
+}
+
 fn display_statistic(total_time: Duration, count: u64, recv_count: u64, lose_count: u64) {
     println!("{}", "----- statistic -----".bold());
     println!("total time: {:?}", total_time);
@@ -217,6 +649,112 @@ fn display_statistic(total_time: Duration, count: u64, recv_count: u64, lose_cou
     );
 }
 
+/// Decode the small escape set `--send` accepts so binary-ish probes
+/// (`QUIT\r\n`, `\x00\x01`) can be typed on a shell line. Unknown
+/// escapes are kept verbatim.
+fn unescape(input: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push(b'\r'),
+            Some('n') => out.push(b'\n'),
+            Some('t') => out.push(b'\t'),
+            Some('0') => out.push(0),
+            Some('\\') => out.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.clone().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(byte) if hex.len() == 2 => {
+                        out.push(byte);
+                        chars.nth(1);
+                    }
+                    _ => out.extend_from_slice(b"\\x"),
+                }
+            }
+            Some(other) => {
+                out.push(b'\\');
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+            None => out.push(b'\\'),
+        }
+    }
+    out
+}
+
+/// `--psk-key` bytes. A newtype because clap reads a bare `Vec<u8>`
+/// field as "many values".
+#[derive(Clone, Debug)]
+struct PskKey(Vec<u8>);
+
+/// `--psk-key`: an even number of hex digits.
+fn parse_hex(input: &str) -> std::result::Result<PskKey, String> {
+    if input.is_empty() || !input.len().is_multiple_of(2) {
+        return Err("expected an even number of hex digits".into());
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|i| {
+            input
+                .get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("not hex: {input}"))
+        })
+        .collect::<std::result::Result<_, _>>()
+        .map(PskKey)
+}
+
+/// `-H`: `Name: value`.
+fn parse_header(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("expected `Name: value`, got {input:?}")),
+    }
+}
+
+/// `http request`: an HTTP method token.
+fn parse_method(input: &str) -> std::result::Result<zpinger::HttpMethod, String> {
+    input.parse().map_err(|e: std::io::Error| e.to_string())
+}
+
+/// `-d` bytes. A newtype for the same reason as `PskKey`.
+#[derive(Clone, Debug)]
+struct RequestBody(Vec<u8>);
+
+/// `-d`: the body itself, or `@path` to read it from a file.
+fn parse_data(input: &str) -> std::result::Result<RequestBody, String> {
+    match input.strip_prefix('@') {
+        Some(path) => std::fs::read(path)
+            .map(RequestBody)
+            .map_err(|e| format!("{path}: {e}")),
+        None => Ok(RequestBody(input.as_bytes().to_vec())),
+    }
+}
+
+/// `--max-body` / `--upload-size`: bytes, with an optional binary
+/// `k` / `M` / `G` suffix.
+fn parse_size(input: &str) -> std::result::Result<usize, String> {
+    let (digits, unit) = match input.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&input[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&input[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&input[..i], 1 << 30),
+        _ => (input, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("expected a size like 4096, 64k or 10M, got {input}"))
+}
+
 fn default_port_target(target: &str, default_port: u16) -> String {
     let uri = zpinger::uri::get_uri(target);
     if uri.port == 0 && !uri.domain.is_empty() {
@@ -228,36 +766,161 @@ fn default_port_target(target: &str, default_port: u16) -> String {
 
 fn target_of(command: &Command) -> &str {
     match command {
-        Command::Tcp { target } => target,
+        Command::Tcp { target, .. } => target,
         Command::Udp { target } => target,
         Command::Ws { target } => target,
         Command::Dns { server, .. } => server,
         Command::Mqtt { broker, .. } => broker,
         Command::Grpc { endpoint, .. } => endpoint,
         Command::Hls { url } => url,
-        Command::Tls { target } => target,
+        Command::Sse { url, .. } => url,
+        Command::Tls { target, .. } => target,
         Command::Ntp { server } => server,
         Command::Stun { server } => server,
         Command::Turn { server } => server,
         Command::Rtsp { target } => target,
         Command::Rtmp { target } => target,
         Command::Quic { endpoint, .. } => endpoint,
-        Command::Http { method } => match method {
+        Command::Http3 { url, .. } => url,
+        Command::Compare { url, .. } => url,
+        Command::Dtls { endpoint, .. } => endpoint,
+        Command::Http { method, .. } => match method.as_ref() {
             HttpMethod::Connect { target }
             | HttpMethod::Get { target }
             | HttpMethod::Post { target }
             | HttpMethod::Put { target }
             | HttpMethod::Delete { target }
-            | HttpMethod::Patch { target } => target,
+            | HttpMethod::Patch { target }
+            | HttpMethod::Head { target }
+            | HttpMethod::Options { target, .. }
+            | HttpMethod::Request { target, .. } => target,
         },
     }
 }
 
-fn build_pinger(command: &Command) -> Box<dyn Pinger> {
+/// Shared TLS settings from the global `--cert` / `--key`, trust,
+/// SNI and ALPN flags.
+fn tls_options(cli: &Cli) -> Result<TlsOptions> {
+    // clap's `requires` can't name "either of two" global flags.
+    if cli.no_default_roots && cli.ca_files.is_empty() && !cli.system_roots {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--no-default-roots needs --ca-file or --system-roots",
+        ));
+    }
+    let mut options = TlsOptions::new()
+        .with_replace_roots(cli.no_default_roots)
+        .with_insecure(cli.insecure)
+        .with_sni(!cli.no_sni);
+    if let Some(name) = &cli.sni {
+        options = options.with_server_name(name.clone());
+    }
+    if let Some(alpn) = &cli.alpn {
+        options = options.with_alpn(
+            alpn.split(',')
+                .filter(|p| !p.is_empty())
+                .map(|p| p.as_bytes().to_vec())
+                .collect(),
+        );
+    }
+    if let (Some(cert), Some(key)) = (&cli.cert, &cli.key) {
+        let identity = ClientIdentity::from_files(cert, key, cli.key_password.as_deref())?;
+        options = options.with_client_identity(identity);
+    }
+    for path in &cli.ca_files {
+        options = options.with_ca_file(path)?;
+    }
+    if cli.system_roots {
+        options = options.with_system_roots()?;
+    }
+    for pin in &cli.pins {
+        options = options.with_pin_sha256(pin)?;
+    }
+    Ok(options)
+}
+
+/// Credentials from `--user` / `--digest` / `--bearer` /
+/// `--bearer-env`. `None` leaves the pinger to the URL's userinfo.
+fn http_auth(cli: &Cli) -> Result<Option<HttpAuth>> {
+    if let Some(token) = &cli.bearer {
+        return Ok(Some(HttpAuth::bearer(token.clone())));
+    }
+    if let Some(var) = &cli.bearer_env {
+        return HttpAuth::bearer_from_env(var).map(Some);
+    }
+    let (username, password) = match &cli.user {
+        Some(user) => match user.split_once(':') {
+            Some((username, password)) => (username.to_string(), password.to_string()),
+            None => (user.clone(), String::new()),
+        },
+        None if cli.digest => {
+            match HttpAuth::from_userinfo(&zpinger::uri::get_uri(target_of(&cli.command))) {
+                Some(HttpAuth::Basic { username, password }) => (username, password),
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "--digest needs --user or user:password@ in the URL",
+                    ))
+                }
+            }
+        }
+        None => return Ok(None),
+    };
+    Ok(Some(if cli.digest {
+        HttpAuth::digest(username, password)
+    } else {
+        HttpAuth::basic(username, password)
+    }))
+}
+
+/// Resumption mode from `--resume` / `--early-data`.
+fn resumption(cli: &Cli) -> ResumptionMode {
+    if cli.early_data {
+        ResumptionMode::EarlyData
+    } else if cli.resume {
+        ResumptionMode::Ticket
+    } else {
+        ResumptionMode::Off
+    }
+}
+
+fn build_pinger(
+    command: &Command,
+    tls: &TlsOptions,
+    resume: ResumptionMode,
+    auth: Option<&HttpAuth>,
+) -> Box<dyn Pinger> {
     match command {
-        Command::Tcp { target } => Box::new(TcpPinger::new(target.clone())),
+        Command::Tcp {
+            target,
+            connect_only,
+            banner,
+            send,
+            expect,
+        } => {
+            let mode = if *connect_only {
+                TcpMode::Connect
+            } else if let Some(payload) = send {
+                TcpMode::Send(unescape(payload))
+            } else if *banner || expect.is_some() {
+                TcpMode::Banner
+            } else {
+                TcpMode::Echo
+            };
+            let mut p = TcpPinger::new(target.clone()).with_mode(mode);
+            if let Some(pattern) = expect {
+                p = p.with_expect(pattern.clone());
+            }
+            Box::new(p)
+        }
         Command::Udp { target } => Box::new(UdpPinger::new(target.clone())),
-        Command::Ws { target } => Box::new(WebSocketPinger::new(target.clone())),
+        Command::Ws { target } => {
+            let mut p = WebSocketPinger::new(target.clone()).with_tls_options(tls.clone());
+            if let Some(auth) = auth {
+                p = p.with_auth(auth.clone());
+            }
+            Box::new(p)
+        }
         Command::Dns {
             server,
             query,
@@ -269,29 +932,122 @@ fn build_pinger(command: &Command) -> Box<dyn Pinger> {
             endpoint,
             service,
             watch,
+            web,
         } => {
-            if *watch {
-                Box::new(GrpcStreamPinger::new(endpoint.clone()).with_service(service.clone()))
+            if *web {
+                Box::new(
+                    GrpcWebPinger::new(endpoint.clone())
+                        .with_service(service.clone())
+                        .with_tls_options(tls.clone()),
+                )
+            } else if *watch {
+                Box::new(
+                    GrpcStreamPinger::new(endpoint.clone())
+                        .with_service(service.clone())
+                        .with_tls_options(tls.clone()),
+                )
             } else {
-                Box::new(GrpcPinger::new(endpoint.clone()).with_service(service.clone()))
+                Box::new(
+                    GrpcPinger::new(endpoint.clone())
+                        .with_service(service.clone())
+                        .with_tls_options(tls.clone()),
+                )
+            }
+        }
+        Command::Hls { url } => {
+            let mut p = HlsPinger::new(url.clone()).with_tls_options(tls.clone());
+            if let Some(auth) = auth {
+                p = p.with_auth(auth.clone());
             }
+            Box::new(p)
+        }
+        Command::Sse {
+            url,
+            event,
+            data,
+            last_event_id,
+        } => {
+            let mut p = SsePinger::new(url.clone()).with_tls_options(tls.clone());
+            if let Some(event) = event {
+                p = p.with_event(event.clone());
+            }
+            if let Some(pattern) = data {
+                p = p.with_data_pattern(pattern.clone());
+            }
+            if let Some(id) = last_event_id {
+                p = p.with_last_event_id(id.clone());
+            }
+            Box::new(p)
+        }
+        Command::Tls {
+            target,
+            starttls,
+            scan: true,
+            ..
+        } => {
+            let mut p = TlsScanPinger::new(target.clone()).with_tls_options(tls.clone());
+            if let Some(protocol) = starttls {
+                p = p.with_starttls((*protocol).into());
+            }
+            Box::new(p)
+        }
+        Command::Tls {
+            target,
+            warn_days,
+            critical_days,
+            stapling,
+            starttls,
+            ..
+        } => {
+            let mut p = TlsPinger::new(target.clone())
+                .with_tls_options(tls.clone())
+                .with_resumption(resume)
+                .with_stapling_checks(*stapling);
+            if let Some(protocol) = starttls {
+                p = p.with_starttls((*protocol).into());
+            }
+            if let Some(days) = warn_days {
+                p = p.with_expiry_warning(*days);
+            }
+            if let Some(days) = critical_days {
+                p = p.with_expiry_critical(*days);
+            }
+            Box::new(p)
         }
-        Command::Hls { url } => Box::new(HlsPinger::new(url.clone())),
-        Command::Tls { target } => Box::new(TlsPinger::new(target.clone())),
         Command::Ntp { server } => Box::new(NtpPinger::new(server.clone())),
         Command::Stun { server } => Box::new(StunPinger::new(server.clone())),
         Command::Turn { server } => Box::new(TurnPinger::new(server.clone())),
-        Command::Rtsp { target } => Box::new(RtspPinger::new(target.clone())),
-        Command::Rtmp { target } => Box::new(RtmpPinger::new(target.clone())),
-        Command::Quic { endpoint, alpn } => {
-            let alpns: Vec<Vec<u8>> = alpn
-                .split(',')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.as_bytes().to_vec())
-                .collect();
-            let mut p = QuicPinger::new(endpoint.clone());
-            if !alpns.is_empty() {
-                p = p.with_alpn(alpns);
+        Command::Rtsp { target } => {
+            Box::new(RtspPinger::new(target.clone()).with_tls_options(tls.clone()))
+        }
+        Command::Rtmp { target } => {
+            Box::new(RtmpPinger::new(target.clone()).with_tls_options(tls.clone()))
+        }
+        Command::Quic { endpoint } => {
+            Box::new(QuicPinger::new(endpoint.clone()).with_tls_options(tls.clone()))
+        }
+        Command::Http3 { url, expect_status } => {
+            let mut p = Http3Pinger::new(url.clone()).with_tls_options(tls.clone());
+            if let Some(set) = expect_status {
+                p = p.with_expected_status(set.clone());
+            }
+            Box::new(p)
+        }
+        Command::Compare { url, resolver } => {
+            let mut p = HttpComparePinger::new(url.clone()).with_tls_options(tls.clone());
+            if let Some(server) = resolver {
+                p = p.with_resolver(server.clone());
+            }
+            Box::new(p)
+        }
+        Command::Dtls {
+            endpoint,
+            psk_identity,
+            psk_key,
+        } => {
+            let mut p = DtlsPinger::new(endpoint.clone()).with_tls_options(tls.clone());
+            if let (Some(identity), Some(key)) = (psk_identity, psk_key) {
+                p = p.with_psk(identity.as_bytes(), key.0.clone());
             }
             Box::new(p)
         }
@@ -300,7 +1056,7 @@ fn build_pinger(command: &Command) -> Box<dyn Pinger> {
             client_id,
             v5,
         } => {
-            let mut p = MqttPinger::new(broker.clone());
+            let mut p = MqttPinger::new(broker.clone()).with_tls_options(tls.clone());
             if let Some(cid) = client_id {
                 p = p.with_client_id(cid.clone());
             }
@@ -309,26 +1065,121 @@ fn build_pinger(command: &Command) -> Box<dyn Pinger> {
             }
             Box::new(p)
         }
-        Command::Http { method } => {
-            let (m, target) = match method {
+        Command::Http {
+            method,
+            max_body,
+            upload_size,
+            headers,
+            data,
+            content_type,
+            user_agent,
+            expect_status,
+            location,
+            max_redirects,
+            same_origin,
+            http2,
+            h2_pings,
+        } => {
+            let (m, target) = match method.as_ref() {
                 HttpMethod::Connect { target } => (zpinger::HttpMethod::Connect, target),
                 HttpMethod::Get { target } => (zpinger::HttpMethod::Get, target),
                 HttpMethod::Post { target } => (zpinger::HttpMethod::Post, target),
                 HttpMethod::Put { target } => (zpinger::HttpMethod::Put, target),
                 HttpMethod::Delete { target } => (zpinger::HttpMethod::Delete, target),
                 HttpMethod::Patch { target } => (zpinger::HttpMethod::Patch, target),
+                HttpMethod::Head { target } => (zpinger::HttpMethod::Head, target),
+                HttpMethod::Options { target, .. } => (zpinger::HttpMethod::Options, target),
+                HttpMethod::Request { method, target } => (method.clone(), target),
             };
-            Box::new(HttpPinger::new(m, target.clone()))
+            let mut p = HttpPinger::new(m, target.clone())
+                .with_tls_options(tls.clone())
+                .with_resumption(resume);
+            if let HttpMethod::Options {
+                origin: Some(origin),
+                request_method,
+                request_headers,
+                ..
+            } = method.as_ref()
+            {
+                let mut preflight = CorsPreflight::new(origin.clone())
+                    .with_request_headers(request_headers.clone());
+                if let Some(request_method) = request_method {
+                    preflight = preflight.with_request_method(request_method.clone());
+                }
+                p = p.with_cors(preflight);
+            }
+            if let Some(auth) = auth {
+                p = p.with_auth(auth.clone());
+            }
+            if let Some(bytes) = max_body {
+                p = p.with_max_body_bytes(*bytes);
+            }
+            if let Some(bytes) = upload_size {
+                p = p.with_upload_bytes(*bytes);
+            }
+            for (name, value) in headers {
+                p = p.with_header(name.clone(), value.clone());
+            }
+            if let Some(RequestBody(body)) = data {
+                p = p.with_body(body.clone());
+            }
+            if let Some(content_type) = content_type {
+                p = p.with_content_type(content_type.clone());
+            }
+            if let Some(user_agent) = user_agent {
+                p = p.with_user_agent(user_agent.clone());
+            }
+            if let Some(set) = expect_status {
+                p = p.with_expected_status(set.clone());
+            }
+            if *location {
+                p = p
+                    .with_redirects(*max_redirects)
+                    .with_same_origin_redirects(*same_origin);
+            }
+            if *http2 {
+                p = p.with_version(HttpVersion::Http2);
+            }
+            if let Some(count) = h2_pings {
+                p = p.with_h2_pings(*count);
+            }
+            Box::new(p)
         }
     }
 }
 
+/// Wrap `pinger` in `Expect` when any `--assert` was given.
+fn with_assertions(pinger: Box<dyn Pinger>, asserts: &[Expectation]) -> Box<dyn Pinger> {
+    if asserts.is_empty() {
+        pinger
+    } else {
+        Box::new(Expect::new(pinger).with_expectations(asserts.iter().cloned()))
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     let target = target_of(&cli.command).to_string();
     let count = cli.count;
-    let pinger = build_pinger(&cli.command);
+    let tls = match tls_options(&cli) {
+        Ok(tls) => tls,
+        Err(err) => {
+            eprintln!("{}", format!("error: {err}").red());
+            std::process::exit(2);
+        }
+    };
+    let auth = match http_auth(&cli) {
+        Ok(auth) => auth,
+        Err(err) => {
+            eprintln!("{}", format!("error: {err}").red());
+            std::process::exit(2);
+        }
+    };
+    let pinger = with_assertions(
+        build_pinger(&cli.command, &tls, resumption(&cli), auth.as_ref()),
+        &cli.asserts,
+    );
 
     let resolve_target = match &cli.command {
         // DNS / MQTT / gRPC subcommands: zpinger::resolve defaults
@@ -359,8 +1210,14 @@ async fn main() -> Result<()> {
         }
         // TLS handshake speaks to port 443 by default (same as HTTPS).
         // If the user passed a schemeless host, prepend `https://` so
-        // `resolve()` picks the right default port.
-        Command::Tls { target } => {
+        // `resolve()` picks the right default port. STARTTLS targets
+        // default to the protocol's port instead.
+        Command::Tls {
+            target,
+            starttls: Some(protocol),
+            ..
+        } => default_port_target(target, zpinger::StartTls::from(*protocol).default_port()),
+        Command::Tls { target, .. } => {
             if target.contains("://") || target.contains(':') {
                 target.clone()
             } else {
@@ -374,6 +1231,7 @@ async fn main() -> Result<()> {
         Command::Ntp { server } => default_port_target(server, 123),
         Command::Stun { server } => default_port_target(server, 3478),
         Command::Turn { server } => default_port_target(server, 3478),
+        Command::Dtls { endpoint, .. } => default_port_target(endpoint, 5684),
         // RTSP / RTMP carry their own scheme. Pick the right default
         // port for the "DNS lookup:" banner so it matches the
         // address the pinger will actually dial.
@@ -397,30 +1255,101 @@ async fn main() -> Result<()> {
                 default_port_target(endpoint, 443)
             }
         }
+        Command::Http3 { url, .. } => {
+            if let Some(rest) = url.strip_prefix("h3://") {
+                format!("https://{rest}")
+            } else {
+                default_port_target(url, 443)
+            }
+        }
+        Command::Compare { url, .. } => default_port_target(url, 443),
         _ => target.clone(),
     };
     let server = zpinger::resolve(&resolve_target).await;
     println!("DNS lookup: {:?}", server);
 
+    // Ctrl-C stops the ping in flight; statistics still cover the
+    // pings that finished before it.
+    let cancel = CancellationToken::new();
+    let on_ctrl_c = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            on_ctrl_c.cancel();
+        }
+    });
+
     let mut total_time = Duration::new(0, 0);
+    let mut sent_count: u64 = 0;
     let mut lose_count: u64 = 0;
+    let mut warned = false;
+    let mut show_chain = matches!(cli.command, Command::Tls { chain: true, .. });
+    let mut show_scan = matches!(cli.command, Command::Tls { scan: true, .. });
+    let mut show_comparison = matches!(cli.command, Command::Compare { .. });
+    let mut show_diagnosis = true;
     for _ in 0..count {
-        match zpinger::timed(pinger.as_ref()).await {
-            Ok(elapsed_time) => {
-                display_ping_info(&target, elapsed_time);
+        match zpinger::timed_probe_with_cancel(pinger.as_ref(), &cancel).await {
+            Ok((elapsed_time, report)) => {
+                display_ping_info(&target, elapsed_time, &report);
+                if show_chain {
+                    display_cert_chain(&report.certificates);
+                    show_chain = false;
+                }
+                if show_scan {
+                    display_scan(&report);
+                    show_scan = false;
+                }
+                if show_comparison {
+                    display_comparison(&report);
+                    show_comparison = false;
+                }
+                warned |= report.detail("warning").is_some();
                 total_time += elapsed_time;
             }
-            Err(_) => {
+            Err(err) => {
+                if let Some(cancelled) = Cancelled::from_io(&err) {
+                    display_ping_cancelled(&target, cancelled);
+                    break;
+                }
                 lose_count += 1;
-                display_ping_fail(&target);
+                display_ping_fail(&target, &err);
+                if let Some(failure) = TlsFailure::from_io(&err).filter(|_| show_diagnosis) {
+                    display_tls_failure(failure);
+                    show_diagnosis = false;
+                }
             }
         };
+        sent_count += 1;
     }
 
-    display_statistic(total_time, count, count - lose_count, lose_count);
+    display_statistic(total_time, sent_count, sent_count - lose_count, lose_count);
+    if let Command::Tls {
+        warn_days,
+        critical_days,
+        stapling,
+        scan,
+        ..
+    } = &cli.command
+    {
+        if warn_days.is_some() || critical_days.is_some() || *stapling || *scan {
+            std::process::exit(expiry_exit_code(lose_count, warned));
+        }
+    }
     Ok(())
 }
 
+/// Monitoring-plugin exit status for `tls` expiry, stapling and
+/// scan checks: any failed ping (critical expiry included) is 2, a warning
+/// is 1.
+fn expiry_exit_code(lose_count: u64, warned: bool) -> i32 {
+    if lose_count > 0 {
+        2
+    } else if warned {
+        1
+    } else {
+        0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -437,6 +1366,49 @@ mod tests {
         assert_eq!(cli.count, 3);
     }
 
+    #[test]
+    fn parses_tcp_modes() {
+        let cli = parse(&["knockknock", "tcp", "db:5432", "--connect-only"]);
+        match &cli.command {
+            Command::Tcp { connect_only, .. } => assert!(connect_only),
+            other => panic!("expected Tcp, got {:?}", std::mem::discriminant(other)),
+        }
+        let cli = parse(&[
+            "knockknock",
+            "tcp",
+            "mail:25",
+            "--send",
+            "EHLO x\\r\\n",
+            "--expect",
+            "^250",
+        ]);
+        match &cli.command {
+            Command::Tcp { send, expect, .. } => {
+                assert_eq!(send.as_deref(), Some("EHLO x\\r\\n"));
+                assert_eq!(expect.as_deref(), Some("^250"));
+            }
+            other => panic!("expected Tcp, got {:?}", std::mem::discriminant(other)),
+        }
+    }
+
+    #[test]
+    fn tcp_connect_only_conflicts_with_reading_modes() {
+        for extra in [&["--banner"][..], &["--send", "x"], &["--expect", "x"]] {
+            let mut args = vec!["knockknock", "tcp", "h:1", "--connect-only"];
+            args.extend_from_slice(extra);
+            assert!(Cli::try_parse_from(args).is_err(), "{extra:?}");
+        }
+    }
+
+    #[test]
+    fn unescape_decodes_send_payloads() {
+        assert_eq!(unescape("QUIT\\r\\n"), b"QUIT\r\n");
+        assert_eq!(unescape("\\x00\\x7f\\t"), b"\x00\x7f\t");
+        assert_eq!(unescape("a\\\\b"), b"a\\b");
+        // Malformed / unknown escapes pass through untouched.
+        assert_eq!(unescape("\\xZZ\\q\\"), b"\\xZZ\\q\\");
+    }
+
     #[test]
     fn parses_udp() {
         let cli = parse(&["knockknock", "udp", "localhost:12000"]);
@@ -457,9 +1429,7 @@ mod tests {
     fn parses_http_get_variant() {
         let cli = parse(&["knockknock", "http", "get", "x:80"]);
         match cli.command {
-            Command::Http {
-                method: HttpMethod::Get { .. },
-            } => {}
+            Command::Http { method, .. } if matches!(*method, HttpMethod::Get { .. }) => {}
             other => panic!(
                 "expected http get, got {:?}",
                 std::mem::discriminant(&other)
@@ -467,6 +1437,72 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_http_options_preflight() {
+        let cli = parse(&[
+            "knockknock",
+            "http",
+            "options",
+            "https://api.example.com/items",
+            "--origin",
+            "https://app.example.com",
+            "--request-method",
+            "DELETE",
+            "--request-headers",
+            "x-trace,content-type",
+        ]);
+        let Command::Http { method, .. } = &cli.command else {
+            panic!("expected http");
+        };
+        match method.as_ref() {
+            HttpMethod::Options {
+                target,
+                origin,
+                request_method,
+                request_headers,
+            } => {
+                assert_eq!(target, "https://api.example.com/items");
+                assert_eq!(origin.as_deref(), Some("https://app.example.com"));
+                assert_eq!(request_method.as_deref(), Some("DELETE"));
+                assert_eq!(request_headers, &["x-trace", "content-type"]);
+            }
+            other => panic!(
+                "expected http options, got {:?}",
+                std::mem::discriminant(other)
+            ),
+        }
+        assert!(Cli::try_parse_from([
+            "knockknock",
+            "http",
+            "options",
+            "x:80",
+            "--request-method",
+            "PUT"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn parses_http_request_method() {
+        let cli = parse(&["knockknock", "http", "request", "PROPFIND", "x:80/dav/"]);
+        let Command::Http { method, .. } = &cli.command else {
+            panic!("expected http");
+        };
+        match method.as_ref() {
+            HttpMethod::Request { method, target } => {
+                assert_eq!(method.as_str(), "PROPFIND");
+                assert_eq!(target, "x:80/dav/");
+            }
+            other => panic!(
+                "expected http request, got {:?}",
+                std::mem::discriminant(other)
+            ),
+        }
+        assert!(
+            Cli::try_parse_from(["knockknock", "http", "request", "BAD METHOD", "x:80"]).is_err()
+        );
+    }
+
     #[test]
     fn count_default_is_3() {
         let cli = parse(&["knockknock", "tcp", "localhost:8000"]);
@@ -517,6 +1553,10 @@ mod tests {
         // verify the dispatch table covers every case.
         let cases: &[&[&str]] = &[
             &["knockknock", "tcp", "localhost:1"],
+            &["knockknock", "tcp", "localhost:1", "--connect-only"],
+            &["knockknock", "tcp", "localhost:1", "--banner"],
+            &["knockknock", "tcp", "localhost:1", "--expect", "^SSH-"],
+            &["knockknock", "tcp", "localhost:1", "--send", "PING\\r\\n"],
             &["knockknock", "udp", "localhost:1"],
             &["knockknock", "http", "connect", "localhost:1"],
             &["knockknock", "http", "get", "localhost:1"],
@@ -524,6 +1564,19 @@ mod tests {
             &["knockknock", "http", "put", "localhost:1"],
             &["knockknock", "http", "delete", "localhost:1"],
             &["knockknock", "http", "patch", "localhost:1"],
+            &["knockknock", "http", "head", "localhost:1"],
+            &["knockknock", "http", "options", "localhost:1"],
+            &[
+                "knockknock",
+                "http",
+                "options",
+                "localhost:1",
+                "--origin",
+                "https://a.example",
+                "--request-method",
+                "PUT",
+            ],
+            &["knockknock", "http", "request", "PROPFIND", "localhost:1"],
             &["knockknock", "ws", "ws://localhost:1"],
             &["knockknock", "ws", "wss://localhost:1"],
             &["knockknock", "dns", "127.0.0.1:1", "-q", "example.com"],
@@ -556,11 +1609,42 @@ mod tests {
                 "my.svc",
             ],
             &["knockknock", "grpc", "grpc://localhost:50051", "--watch"],
+            &["knockknock", "grpc", "https://localhost:1/rpc", "--web"],
             &["knockknock", "hls", "http://localhost:18007/playlist.m3u8"],
+            &["knockknock", "sse", "http://localhost:1/events"],
+            &[
+                "knockknock",
+                "sse",
+                "https://localhost:1/events",
+                "--event",
+                "tick",
+                "--data",
+                "^ok",
+                "--last-event-id",
+                "41",
+            ],
+            &["knockknock", "compare", "https://localhost:1/"],
+            &[
+                "knockknock",
+                "compare",
+                "localhost:1",
+                "--resolver",
+                "127.0.0.1:53",
+            ],
+            &[
+                "knockknock",
+                "dns",
+                "8.8.8.8",
+                "-q",
+                "example.com",
+                "-t",
+                "https",
+            ],
         ];
         for args in cases {
             let cli = parse(args);
-            let _: Box<dyn Pinger> = build_pinger(&cli.command);
+            let _: Box<dyn Pinger> =
+                build_pinger(&cli.command, &TlsOptions::new(), resumption(&cli), None);
         }
     }
 
@@ -725,6 +1809,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_grpc_subcommand_with_web_flag() {
+        let cli = parse(&["knockknock", "grpc", "https://api.example.com/rpc", "--web"]);
+        match &cli.command {
+            Command::Grpc { web, watch, .. } => assert!(*web && !*watch),
+            other => panic!("expected Grpc, got {:?}", std::mem::discriminant(other)),
+        }
+        let both = ["knockknock", "grpc", "localhost:8080", "--web", "--watch"];
+        assert!(Cli::try_parse_from(both).is_err());
+    }
+
     #[test]
     fn parses_hls_subcommand() {
         let cli = parse(&["knockknock", "hls", "http://example.com/master.m3u8"]);
@@ -734,6 +1829,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parses_sse_subcommand() {
+        let cli = parse(&[
+            "knockknock",
+            "sse",
+            "https://example.com/events",
+            "--event",
+            "tick",
+            "--last-event-id",
+            "41",
+        ]);
+        match &cli.command {
+            Command::Sse {
+                url,
+                event,
+                data,
+                last_event_id,
+            } => {
+                assert_eq!(url, "https://example.com/events");
+                assert_eq!(event.as_deref(), Some("tick"));
+                assert_eq!(data, &None);
+                assert_eq!(last_event_id.as_deref(), Some("41"));
+            }
+            other => panic!("expected Sse, got {:?}", std::mem::discriminant(other)),
+        }
+        assert_eq!(target_of(&cli.command), "https://example.com/events");
+    }
+
     #[test]
     fn hls_subcommand_requires_url() {
         let result = Cli::try_parse_from(["knockknock", "hls"]);
@@ -744,11 +1867,60 @@ mod tests {
     fn parses_tls_subcommand() {
         let cli = parse(&["knockknock", "tls", "example.com:443"]);
         match &cli.command {
-            Command::Tls { target } => assert_eq!(target, "example.com:443"),
+            Command::Tls { target, .. } => assert_eq!(target, "example.com:443"),
             other => panic!("expected Tls, got {:?}", std::mem::discriminant(other)),
         }
     }
 
+    #[test]
+    fn parses_tls_expiry_thresholds() {
+        let cli = parse(&[
+            "knockknock",
+            "tls",
+            "example.com",
+            "--warn-days",
+            "30",
+            "--critical-days",
+            "7",
+            "--chain",
+            "--stapling",
+            "--starttls",
+            "smtp",
+        ]);
+        match &cli.command {
+            Command::Tls {
+                warn_days,
+                critical_days,
+                chain,
+                stapling,
+                starttls,
+                ..
+            } => {
+                assert_eq!(*warn_days, Some(30));
+                assert_eq!(*critical_days, Some(7));
+                assert!(chain);
+                assert!(stapling);
+                assert_eq!(*starttls, Some(StartTlsProtocol::Smtp));
+            }
+            other => panic!("expected Tls, got {:?}", std::mem::discriminant(other)),
+        }
+    }
+
+    #[test]
+    fn parses_tls_scan() {
+        let cli = parse(&["knockknock", "tls", "example.com", "--scan"]);
+        assert!(matches!(cli.command, Command::Tls { scan: true, .. }));
+        let result = Cli::try_parse_from(["knockknock", "tls", "example.com", "--scan", "--chain"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn expiry_exit_code_follows_plugin_convention() {
+        assert_eq!(expiry_exit_code(0, false), 0);
+        assert_eq!(expiry_exit_code(0, true), 1);
+        assert_eq!(expiry_exit_code(1, true), 2);
+    }
+
     #[test]
     fn parses_ntp_subcommand() {
         let cli = parse(&["knockknock", "ntp", "time.cloudflare.com"]);
@@ -816,9 +1988,9 @@ mod tests {
     fn parses_quic_subcommand() {
         let cli = parse(&["knockknock", "quic", "quic://example.com:443"]);
         match &cli.command {
-            Command::Quic { endpoint, alpn } => {
+            Command::Quic { endpoint } => {
                 assert_eq!(endpoint, "quic://example.com:443");
-                assert_eq!(alpn, "h3");
+                assert!(cli.alpn.is_none(), "QuicPinger keeps its h3 default");
             }
             other => panic!("expected Quic, got {:?}", std::mem::discriminant(other)),
         }
@@ -827,9 +1999,330 @@ mod tests {
     #[test]
     fn parses_quic_with_custom_alpn() {
         let cli = parse(&["knockknock", "quic", "example.com", "--alpn", "h3,hq-29"]);
+        assert!(matches!(cli.command, Command::Quic { .. }));
+        assert_eq!(cli.alpn.as_deref(), Some("h3,hq-29"));
+        let options = tls_options(&cli).unwrap();
+        assert_eq!(options.alpn, vec![b"h3".to_vec(), b"hq-29".to_vec()]);
+    }
+
+    #[test]
+    fn parses_http3_subcommand() {
+        let cli = parse(&[
+            "knockknock",
+            "http3",
+            "https://example.com/health",
+            "--expect-status",
+            "2xx,401",
+        ]);
+        match cli.command {
+            Command::Http3 { url, expect_status } => {
+                assert_eq!(url, "https://example.com/health");
+                assert_eq!(expect_status.unwrap().to_string(), "200-299,401");
+            }
+            other => panic!("expected Http3, got {:?}", std::mem::discriminant(&other)),
+        }
+    }
+
+    #[test]
+    fn parses_compare_subcommand() {
+        let cli = parse(&[
+            "knockknock",
+            "compare",
+            "https://example.com/",
+            "--resolver",
+            "1.1.1.1",
+        ]);
         match &cli.command {
-            Command::Quic { alpn, .. } => assert_eq!(alpn, "h3,hq-29"),
-            other => panic!("expected Quic, got {:?}", std::mem::discriminant(other)),
+            Command::Compare { url, resolver } => {
+                assert_eq!(url, "https://example.com/");
+                assert_eq!(resolver.as_deref(), Some("1.1.1.1"));
+            }
+            other => panic!("expected Compare, got {:?}", std::mem::discriminant(other)),
+        }
+        assert_eq!(target_of(&cli.command), "https://example.com/");
+    }
+
+    #[test]
+    fn parses_dtls_psk() {
+        let cli = parse(&[
+            "knockknock",
+            "dtls",
+            "coaps://sensor.example.com",
+            "--psk-identity",
+            "sensor-7",
+            "--psk-key",
+            "73336372",
+        ]);
+        match &cli.command {
+            Command::Dtls {
+                endpoint,
+                psk_identity,
+                psk_key,
+            } => {
+                assert_eq!(endpoint, "coaps://sensor.example.com");
+                assert_eq!(psk_identity.as_deref(), Some("sensor-7"));
+                assert_eq!(psk_key.as_ref().map(|k| &k.0[..]), Some(&b"s3cr"[..]));
+            }
+            other => panic!("expected Dtls, got {:?}", std::mem::discriminant(other)),
+        }
+        // A key needs its identity, and must be hex.
+        let result = Cli::try_parse_from(["knockknock", "dtls", "host", "--psk-key", "00"]);
+        assert!(result.is_err());
+        let result = Cli::try_parse_from([
+            "knockknock",
+            "dtls",
+            "host",
+            "--psk-identity",
+            "id",
+            "--psk-key",
+            "xyz",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_http_body_sizes() {
+        // Either side of the method subcommand.
+        for args in [
+            &[
+                "knockknock",
+                "http",
+                "--max-body",
+                "10M",
+                "put",
+                "x:80",
+                "--upload-size",
+                "64k",
+            ][..],
+            &[
+                "knockknock",
+                "http",
+                "put",
+                "x:80",
+                "--max-body=10M",
+                "--upload-size=64k",
+            ][..],
+        ] {
+            match parse(args).command {
+                Command::Http {
+                    max_body,
+                    upload_size,
+                    ..
+                } => {
+                    assert_eq!(max_body, Some(10 << 20));
+                    assert_eq!(upload_size, Some(64 << 10));
+                }
+                other => panic!("expected Http, got {:?}", std::mem::discriminant(&other)),
+            }
+        }
+        assert!(
+            Cli::try_parse_from(["knockknock", "http", "get", "x", "--max-body", "lots"]).is_err()
+        );
+    }
+
+    #[test]
+    fn parses_http_redirect_flags() {
+        match parse(&["knockknock", "http", "get", "x:80", "-L", "--same-origin"]).command {
+            Command::Http {
+                location,
+                max_redirects,
+                same_origin,
+                ..
+            } => {
+                assert!(location && same_origin);
+                assert_eq!(max_redirects, 10);
+            }
+            other => panic!("expected Http, got {:?}", std::mem::discriminant(&other)),
+        }
+        assert!(Cli::try_parse_from(["knockknock", "http", "get", "x", "--same-origin"]).is_err());
+    }
+
+    #[test]
+    fn parses_http2_flags() {
+        match parse(&[
+            "knockknock",
+            "http",
+            "get",
+            "x:80",
+            "--http2",
+            "--h2-pings",
+            "3",
+        ])
+        .command
+        {
+            Command::Http {
+                http2, h2_pings, ..
+            } => {
+                assert!(http2);
+                assert_eq!(h2_pings, Some(3));
+            }
+            other => panic!("expected Http, got {:?}", std::mem::discriminant(&other)),
+        }
+        assert!(
+            Cli::try_parse_from(["knockknock", "http", "get", "x", "--h2-pings", "3"]).is_err()
+        );
+    }
+
+    #[test]
+    fn parses_http_request_options() {
+        let cli = parse(&[
+            "knockknock",
+            "http",
+            "post",
+            "x:80/api",
+            "-H",
+            "Authorization: Bearer t0k3n",
+            "-H",
+            "X-Trace:1",
+            "-d",
+            r#"{"q":1}"#,
+            "--expect-status",
+            "2xx,401",
+        ]);
+        match cli.command {
+            Command::Http {
+                headers,
+                data,
+                expect_status,
+                ..
+            } => {
+                assert_eq!(
+                    headers,
+                    vec![
+                        ("Authorization".to_string(), "Bearer t0k3n".to_string()),
+                        ("X-Trace".to_string(), "1".to_string()),
+                    ]
+                );
+                assert_eq!(data.map(|d| d.0), Some(br#"{"q":1}"#.to_vec()));
+                assert_eq!(expect_status.unwrap().to_string(), "200-299,401");
+            }
+            other => panic!("expected Http, got {:?}", std::mem::discriminant(&other)),
+        }
+
+        let path = std::env::temp_dir().join("knockknock-data-test.json");
+        std::fs::write(&path, "from file").unwrap();
+        let data = format!("@{}", path.display());
+        match parse(&["knockknock", "http", "put", "x:80", "-d", &data]).command {
+            Command::Http { data, .. } => {
+                assert_eq!(data.map(|d| d.0), Some(b"from file".to_vec()))
+            }
+            other => panic!("expected Http, got {:?}", std::mem::discriminant(&other)),
+        }
+        let _ = std::fs::remove_file(path);
+
+        for bad in [
+            &["knockknock", "http", "get", "x", "-H", "no colon"][..],
+            &["knockknock", "http", "get", "x", "--expect-status", "2x"][..],
+            &["knockknock", "http", "get", "x", "-d", "@/nonexistent/body"][..],
+            &[
+                "knockknock",
+                "http",
+                "put",
+                "x",
+                "-d",
+                "a",
+                "--upload-size",
+                "1k",
+            ][..],
+        ] {
+            assert!(Cli::try_parse_from(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn parses_sni_flags() {
+        let cli = parse(&[
+            "knockknock",
+            "tls",
+            "10.0.0.7:443",
+            "--sni",
+            "www.example.com",
+        ]);
+        let options = tls_options(&cli).unwrap();
+        assert_eq!(options.server_name.as_deref(), Some("www.example.com"));
+        assert!(!options.disable_sni);
+        let cli = parse(&["knockknock", "tls", "h", "--no-sni"]);
+        assert!(tls_options(&cli).unwrap().disable_sni);
+        assert!(Cli::try_parse_from(["knockknock", "tls", "h", "--sni", "a", "--no-sni"]).is_err());
+    }
+
+    #[test]
+    fn parses_resumption_flags() {
+        let cli = parse(&["knockknock", "tls", "example.com"]);
+        assert_eq!(resumption(&cli), ResumptionMode::Off);
+        let cli = parse(&["knockknock", "tls", "example.com", "--resume"]);
+        assert_eq!(resumption(&cli), ResumptionMode::Ticket);
+        let cli = parse(&[
+            "knockknock",
+            "http",
+            "get",
+            "https://example.com",
+            "--early-data",
+        ]);
+        assert_eq!(resumption(&cli), ResumptionMode::EarlyData);
+    }
+
+    #[test]
+    fn parses_auth_flags() {
+        let cli = parse(&["knockknock", "http", "get", "x", "-u", "alice:s3:cr3t"]);
+        assert_eq!(
+            http_auth(&cli).unwrap(),
+            Some(HttpAuth::basic("alice", "s3:cr3t"))
+        );
+        let cli = parse(&["knockknock", "hls", "x", "--user", "alice", "--digest"]);
+        assert_eq!(
+            http_auth(&cli).unwrap(),
+            Some(HttpAuth::digest("alice", ""))
+        );
+        let cli = parse(&["knockknock", "ws", "ws://bob:pw@x/", "--digest"]);
+        assert_eq!(
+            http_auth(&cli).unwrap(),
+            Some(HttpAuth::digest("bob", "pw"))
+        );
+        let cli = parse(&["knockknock", "http", "get", "x", "--digest"]);
+        assert!(http_auth(&cli).is_err());
+        let cli = parse(&["knockknock", "http", "get", "x", "--bearer", "t0k3n"]);
+        assert_eq!(http_auth(&cli).unwrap(), Some(HttpAuth::bearer("t0k3n")));
+        let cli = parse(&["knockknock", "http", "get", "x"]);
+        assert_eq!(http_auth(&cli).unwrap(), None);
+
+        let var = "KNOCKKNOCK_TEST_BEARER_UNSET";
+        let cli = parse(&["knockknock", "http", "get", "x", "--bearer-env", var]);
+        let err = http_auth(&cli).unwrap_err();
+        assert!(err.to_string().contains(var), "{err}");
+
+        for args in [
+            &[
+                "knockknock",
+                "http",
+                "get",
+                "x",
+                "-u",
+                "a:b",
+                "--bearer",
+                "t",
+            ][..],
+            &[
+                "knockknock",
+                "http",
+                "get",
+                "x",
+                "--digest",
+                "--bearer",
+                "t",
+            ],
+            &[
+                "knockknock",
+                "http",
+                "get",
+                "x",
+                "--bearer",
+                "t",
+                "--bearer-env",
+                "V",
+            ],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
         }
     }
 
@@ -838,4 +2331,119 @@ mod tests {
         let result = Cli::try_parse_from(["knockknock", "quic"]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn parses_repeated_assertions() {
+        let cli = parse(&[
+            "knockknock",
+            "http",
+            "get",
+            "http://example.com",
+            "--assert",
+            "status=2xx",
+            "--assert",
+            "latency=200ms",
+        ]);
+        assert_eq!(cli.asserts.len(), 2);
+        assert!(matches!(cli.asserts[0], Expectation::Status(_)));
+        assert!(matches!(
+            cli.asserts[1],
+            Expectation::MaxLatency(d) if d == Duration::from_millis(200)
+        ));
+        assert!(parse(&["knockknock", "tcp", "h:1"]).asserts.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_assertion() {
+        let result = Cli::try_parse_from([
+            "knockknock",
+            "dns",
+            "8.8.8.8",
+            "-q",
+            "a",
+            "--assert",
+            "nope",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_client_certificate_flags() {
+        let cli = parse(&[
+            "knockknock",
+            "http",
+            "get",
+            "https://api.internal",
+            "--cert",
+            "client.pem",
+            "--key",
+            "client.key",
+        ]);
+        assert_eq!(cli.cert, Some(PathBuf::from("client.pem")));
+        assert_eq!(cli.key, Some(PathBuf::from("client.key")));
+        assert!(cli.key_password.is_none());
+    }
+
+    #[test]
+    fn cert_and_key_require_each_other() {
+        assert!(Cli::try_parse_from(["knockknock", "tls", "h", "--cert", "c.pem"]).is_err());
+        assert!(Cli::try_parse_from(["knockknock", "tls", "h", "--key", "k.pem"]).is_err());
+    }
+
+    #[test]
+    fn missing_certificate_file_is_reported() {
+        let cli = parse(&[
+            "knockknock",
+            "tls",
+            "h",
+            "--cert",
+            "/nonexistent/client.pem",
+            "--key",
+            "/nonexistent/client.key",
+        ]);
+        let err = tls_options(&cli).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/client.pem"), "{err}");
+    }
+
+    #[test]
+    fn parses_trust_flags() {
+        let cli = parse(&[
+            "knockknock",
+            "tls",
+            "example.com",
+            "--ca-file",
+            "a.pem",
+            "--ca-file",
+            "b.pem",
+            "--no-default-roots",
+            "--pin-sha256",
+            "sha256//qlBKawxj1X3x+dRPcAfJhYhLaEhCuAcaikHtaTIP/Jk=",
+            "-k",
+        ]);
+        assert_eq!(
+            cli.ca_files,
+            vec![PathBuf::from("a.pem"), PathBuf::from("b.pem")]
+        );
+        assert!(cli.no_default_roots);
+        assert_eq!(cli.pins.len(), 1);
+        assert!(cli.insecure);
+        let cli = parse(&["knockknock", "tls", "h", "--no-default-roots"]);
+        let err = tls_options(&cli).unwrap_err();
+        assert!(err.to_string().contains("--ca-file"), "{err}");
+        let cli = parse(&[
+            "knockknock",
+            "tls",
+            "h",
+            "--system-roots",
+            "--no-default-roots",
+        ]);
+        assert!(cli.system_roots && cli.no_default_roots);
+    }
+
+    #[test]
+    fn malformed_pin_is_reported() {
+        let cli = parse(&["knockknock", "tls", "h", "--pin-sha256", "nope"]);
+        let err = tls_options(&cli).unwrap_err();
+        assert!(err.to_string().contains("pin"), "{err}");
+    }
 }
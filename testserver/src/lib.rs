@@ -1,5 +1,5 @@
 use std::io::{Read, Result, Write};
-use std::net::{SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
 use std::sync::Arc;
 use std::thread;
 
@@ -7,8 +7,9 @@ use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
 use rustls::{ClientConfig, RootCertStore, ServerConfig, ServerConnection, StreamOwned};
 use tokio_stream::wrappers::TcpListenerStream;
 use tonic::transport::ServerTlsConfig;
-use tungstenite::accept;
+use tungstenite::handshake::server::{Request, Response};
 use tungstenite::protocol::Message;
+use tungstenite::{accept, accept_hdr};
 
 const BUF_SIZE: usize = 1024;
 
@@ -29,6 +30,32 @@ pub fn start_tcp_echo<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
     Ok(bound)
 }
 
+/// Spin up a TCP server that greets every connection with `banner`
+/// before reading anything, the way SSH / SMTP / FTP servers do, then
+/// echoes whatever the client sends. Exercises `TcpPinger`'s banner
+/// mode; the echo half keeps send-then-expect tests on one fixture.
+pub fn start_tcp_banner<A: ToSocketAddrs>(addr: A, banner: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let banner = banner.as_bytes().to_vec();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let banner = banner.clone();
+            thread::spawn(move || {
+                let mut s = stream;
+                if s.write_all(&banner).is_err() {
+                    return;
+                }
+                let mut buf = [0u8; BUF_SIZE];
+                if let Ok(n) = s.read(&mut buf) {
+                    let _ = s.write_all(&buf[..n]);
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
 pub fn start_udp_echo<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
     let socket = UdpSocket::bind(addr)?;
     let bound = socket.local_addr()?;
@@ -49,50 +76,1311 @@ pub fn start_http_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
     thread::spawn(move || {
         for stream in listener.incoming().flatten() {
             thread::spawn(move || {
-                let mut s = stream;
-                let mut buf = [0u8; BUF_SIZE];
-                let _ = s.read(&mut buf);
-                let _ = s.write_all(
-                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
-                );
+                let mut s = stream;
+                let mut buf = [0u8; BUF_SIZE];
+                let _ = s.read(&mut buf);
+                let _ = s.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// Spin up an HTTP server that answers every request with the raw
+/// `response` bytes (status line, headers, body — caller's choice),
+/// then closes. For tests that need a specific status, header or
+/// body rather than `start_http_ok`'s empty 200.
+pub fn start_http_canned<A: ToSocketAddrs>(addr: A, response: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let response = response.as_bytes().to_vec();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let response = response.clone();
+            thread::spawn(move || {
+                let mut s = stream;
+                let mut buf = [0u8; BUF_SIZE];
+                let _ = s.read(&mut buf);
+                let _ = s.write_all(&response);
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTP server answering every request with `size` bytes of body,
+/// framed by `Content-Length` or, with `chunked`, as 8 KiB chunks.
+/// For body-reading and throughput tests.
+pub fn start_http_download<A: ToSocketAddrs>(
+    addr: A,
+    size: usize,
+    chunked: bool,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut s = stream;
+                if read_until(&mut s, b"\r\n\r\n").is_err() {
+                    return;
+                }
+                let body = vec![b'z'; size];
+                if !chunked {
+                    let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {size}\r\n\r\n");
+                    let _ = s.write_all(head.as_bytes());
+                    let _ = s.write_all(&body);
+                    return;
+                }
+                let _ = s.write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n");
+                for chunk in body.chunks(8 * 1024) {
+                    let _ = s.write_all(format!("{:x}\r\n", chunk.len()).as_bytes());
+                    let _ = s.write_all(chunk);
+                    let _ = s.write_all(b"\r\n");
+                }
+                let _ = s.write_all(b"0\r\n\r\n");
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTP server that reads each request's `Content-Length` body in
+/// full, then answers `200` with `received N` as the body.
+pub fn start_http_upload_sink<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut s = stream;
+                let Ok(head) = read_until(&mut s, b"\r\n\r\n") else {
+                    return;
+                };
+                let len: u64 = String::from_utf8_lossy(&head)
+                    .lines()
+                    .filter_map(|line| line.split_once(':'))
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .and_then(|(_, value)| value.trim().parse().ok())
+                    .unwrap_or(0);
+                let Ok(received) = std::io::copy(&mut (&mut s).take(len), &mut std::io::sink())
+                else {
+                    return;
+                };
+                let body = format!("received {received}");
+                let _ = s.write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .as_bytes(),
+                );
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTP server that answers `200` with the request it received —
+/// head and `Content-Length` body, byte for byte — as the body.
+pub fn start_http_echo<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut s = stream;
+                let Ok(request) = read_http_request(&mut s) else {
+                    return;
+                };
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n",
+                    request.len()
+                );
+                let _ = s.write_all(head.as_bytes());
+                let _ = s.write_all(&request);
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTP server answering by request path: each route maps a path
+/// (query included) to a raw response. Unknown paths get a `404`.
+/// For redirect chains.
+pub fn start_http_routes<A: ToSocketAddrs>(addr: A, routes: &[(&str, &str)]) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let routes: Arc<Vec<(String, String)>> = Arc::new(
+        routes
+            .iter()
+            .map(|(path, response)| (path.to_string(), response.to_string()))
+            .collect(),
+    );
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let routes = routes.clone();
+            thread::spawn(move || {
+                let mut s = stream;
+                let Ok(request) = read_http_request(&mut s) else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let response = routes.iter().find(|(route, _)| route == path).map_or(
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+                    |(_, r)| r.as_str(),
+                );
+                let _ = s.write_all(response.as_bytes());
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTP server behind authentication. Accepts `Basic` for
+/// `username:password`, `Bearer <password>`, or a Digest answer
+/// (SHA-256, `qop=auth`) to the challenge it sends with every `401`;
+/// answers `200` with the scheme that got in (`basic`, `bearer`,
+/// `digest`) as the body.
+pub fn start_http_auth<A: ToSocketAddrs>(
+    addr: A,
+    username: &str,
+    password: &str,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let credentials = Arc::new((username.to_string(), password.to_string()));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let credentials = credentials.clone();
+            thread::spawn(move || {
+                let mut s = stream;
+                let Ok(request) = read_http_request(&mut s) else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&request);
+                let (user, pass) = &*credentials;
+                let Some(scheme) = authorized_request(&request, user, pass) else {
+                    let _ = s.write_all(unauthorized_response().as_bytes());
+                    return;
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{scheme}",
+                    scheme.len()
+                );
+                let _ = s.write_all(response.as_bytes());
+            });
+        }
+    });
+    Ok(bound)
+}
+
+const AUTH_REALM: &str = "knock";
+const AUTH_NONCE: &str = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+const AUTH_OPAQUE: &str = "FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS";
+
+/// The SHA-256 Digest challenge the auth fixtures send.
+fn digest_challenge() -> String {
+    format!(
+        "Digest realm=\"{AUTH_REALM}\", qop=\"auth\", algorithm=SHA-256, \
+         nonce=\"{AUTH_NONCE}\", opaque=\"{AUTH_OPAQUE}\""
+    )
+}
+
+/// The `401` every auth fixture sends: the Digest challenge and a
+/// Basic one.
+fn unauthorized_response() -> String {
+    format!(
+        "HTTP/1.1 401 Unauthorized\r\n\
+         WWW-Authenticate: {}\r\n\
+         WWW-Authenticate: Basic realm=\"{AUTH_REALM}\"\r\n\
+         Content-Length: 0\r\n\
+         Connection: close\r\n\r\n",
+        digest_challenge()
+    )
+}
+
+/// `authorized` for a raw HTTP/1.1 request head.
+fn authorized_request(request: &str, username: &str, password: &str) -> Option<&'static str> {
+    let mut request_line = request.lines().next()?.split(' ');
+    let method = request_line.next()?;
+    let target = request_line.next()?;
+    let authorization = request
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .map(|(_, value)| value.trim());
+    authorized(method, target, authorization, username, password)
+}
+
+/// The scheme `authorization` proves `username` / `password` with,
+/// if it does. Digest must answer `digest_challenge()` for `method`
+/// on `target`.
+fn authorized(
+    method: &str,
+    target: &str,
+    authorization: Option<&str>,
+    username: &str,
+    password: &str,
+) -> Option<&'static str> {
+    use base64::Engine as _;
+
+    let (scheme, rest) = authorization?.split_once(' ')?;
+    let rest = rest.trim();
+    match scheme.to_ascii_lowercase().as_str() {
+        "basic" => {
+            let expected =
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+            (rest == expected).then_some("basic")
+        }
+        "bearer" => (rest == password).then_some("bearer"),
+        "digest" => {
+            let params = auth_params(rest);
+            let get = |key: &str| {
+                params
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                    .map(|(_, v)| v.as_str())
+            };
+            let sha256 = |data: String| {
+                ring::digest::digest(&ring::digest::SHA256, data.as_bytes())
+                    .as_ref()
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>()
+            };
+            let expected_fields = [
+                ("username", username),
+                ("realm", AUTH_REALM),
+                ("nonce", AUTH_NONCE),
+                ("opaque", AUTH_OPAQUE),
+                ("uri", target),
+                ("qop", "auth"),
+            ];
+            if expected_fields
+                .iter()
+                .any(|(key, value)| get(key) != Some(value))
+                || !get("algorithm")?.eq_ignore_ascii_case("SHA-256")
+            {
+                return None;
+            }
+            let ha1 = sha256(format!("{username}:{AUTH_REALM}:{password}"));
+            let ha2 = sha256(format!("{method}:{target}"));
+            let expected = sha256(format!(
+                "{ha1}:{AUTH_NONCE}:{}:{}:auth:{ha2}",
+                get("nc")?,
+                get("cnonce")?
+            ));
+            (get("response")? == expected).then_some("digest")
+        }
+        _ => None,
+    }
+}
+
+/// `key=value` / `key="value"` pairs of an auth header, in order.
+fn auth_params(value: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = value.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let after = after.trim_start();
+        let (value, remainder) = match after.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+            }
+            None => {
+                let end = after.find(',').unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        params.push((key, value.to_string()));
+        rest = remainder.trim_start().trim_start_matches(',');
+    }
+    params
+}
+
+/// One HTTP request: the head, then its `Content-Length` body.
+fn read_http_request(sock: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut request = read_until(sock, b"\r\n\r\n")?;
+    let len: u64 = String::from_utf8_lossy(&request)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    sock.take(len).read_to_end(&mut request)?;
+    Ok(request)
+}
+
+/// Handle returned by `start_https_ok` — exposes the bound address
+/// plus a `ClientConfig` whose only trust anchor is the self-signed
+/// cert this server uses, so test code can speak HTTPS to the server
+/// without pulling in the system trust store. `cert_pem` is that
+/// same cert, for tests that build their own trust.
+pub struct HttpsServer {
+    pub addr: SocketAddr,
+    pub client_config: Arc<ClientConfig>,
+    pub cert_pem: String,
+}
+
+/// A server config, a client config trusting only its self-signed
+/// cert, and that cert as PEM.
+type TlsPair = (Arc<ServerConfig>, Arc<ClientConfig>, String);
+
+/// Generate a fresh self-signed cert for SAN `localhost` and return
+/// the matching `ServerConfig` (for the listener side) plus a
+/// `ClientConfig` whose only trust anchor is that cert (for the
+/// client side). Used by every TLS-wrapped test endpoint —
+/// `start_https_ok`, `start_wss_ok`, `start_mqtts_ok`.
+fn make_test_tls_pair() -> Result<TlsPair> {
+    let params = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    make_tls_pair_from(params)
+}
+
+/// `make_test_tls_pair` for a caller-shaped certificate (validity
+/// window, subject) — still self-signed with a fresh key.
+fn make_tls_pair_from(params: rcgen::CertificateParams) -> Result<TlsPair> {
+    make_stapled_tls_pair(params, None)
+}
+
+/// `make_tls_pair_from`, with the server stapling `ocsp` (a DER
+/// OCSPResponse) to every handshake.
+fn make_stapled_tls_pair(
+    params: rcgen::CertificateParams,
+    ocsp: Option<Vec<u8>>,
+) -> Result<TlsPair> {
+    let key_pair =
+        rcgen::KeyPair::generate().map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let cert = params
+        .self_signed(&key_pair)
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let cert_der = CertificateDer::from(cert.der().to_vec());
+    let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+        .with_no_client_auth();
+    let server_config = match ocsp {
+        Some(ocsp) => builder.with_single_cert_with_ocsp(vec![cert_der.clone()], key_der, ocsp),
+        None => builder.with_single_cert(vec![cert_der.clone()], key_der),
+    }
+    .map_err(|e| std::io::Error::other(format!("rustls server cert: {e}")))?;
+
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(cert_der)
+        .map_err(|e| std::io::Error::other(format!("trust anchor: {e}")))?;
+    let client_config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    Ok((Arc::new(server_config), Arc::new(client_config), cert.pem()))
+}
+
+/// Spin up an HTTPS 200-OK responder on `addr` using a freshly
+/// generated self-signed cert for the SAN `localhost`. Returns the
+/// bound address and a `ClientConfig` pre-loaded with the cert as a
+/// trust anchor.
+pub fn start_https_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    serve_https_ok(addr, make_test_tls_pair()?)
+}
+
+/// `start_https_ok` with a certificate (CN `expiring.localhost`, SAN
+/// `localhost`) that expires `days_left` days and an hour from now —
+/// for exercising certificate expiry thresholds.
+pub fn start_https_expiring<A: ToSocketAddrs>(addr: A, days_left: u32) -> Result<HttpsServer> {
+    let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "expiring.localhost");
+    let now = time::OffsetDateTime::now_utc();
+    params.not_before = now - time::Duration::days(1);
+    params.not_after = now + time::Duration::days(days_left.into()) + time::Duration::hours(1);
+    serve_https_ok(addr, make_tls_pair_from(params)?)
+}
+
+/// `start_https_ok` with a certificate that expired yesterday.
+pub fn start_https_expired<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let now = time::OffsetDateTime::now_utc();
+    params.not_before = now - time::Duration::days(30);
+    params.not_after = now - time::Duration::days(1);
+    serve_https_ok(addr, make_tls_pair_from(params)?)
+}
+
+/// HTTPS 200 responder whose leaf (SAN `localhost`) is issued by an
+/// intermediate under a fresh root, but which sends only the leaf.
+/// `cert_pem` and `client_config` trust the root, so the chain can't
+/// be completed.
+pub fn start_https_leaf_only<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let rcgen_err = |e: rcgen::Error| std::io::Error::other(format!("rcgen: {e}"));
+    let ca = |name: &str| -> Result<rcgen::CertificateParams> {
+        let mut params = rcgen::CertificateParams::new(Vec::new()).map_err(rcgen_err)?;
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, name);
+        Ok(params)
+    };
+    let root_key = rcgen::KeyPair::generate().map_err(rcgen_err)?;
+    let root = ca("testserver root CA")?
+        .self_signed(&root_key)
+        .map_err(rcgen_err)?;
+    let intermediate_key = rcgen::KeyPair::generate().map_err(rcgen_err)?;
+    let intermediate = ca("testserver intermediate CA")?
+        .signed_by(&intermediate_key, &root, &root_key)
+        .map_err(rcgen_err)?;
+    let leaf_key = rcgen::KeyPair::generate().map_err(rcgen_err)?;
+    let mut leaf_params =
+        rcgen::CertificateParams::new(vec!["localhost".to_string()]).map_err(rcgen_err)?;
+    leaf_params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "localhost");
+    let leaf = leaf_params
+        .signed_by(&leaf_key, &intermediate, &intermediate_key)
+        .map_err(rcgen_err)?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let server_config = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(leaf.der().to_vec())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf_key.serialize_der())),
+        )
+        .map_err(|e| std::io::Error::other(format!("rustls server cert: {e}")))?;
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(root.der().clone())
+        .map_err(|e| std::io::Error::other(format!("trust anchor: {e}")))?;
+    let client_config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    serve_https_ok(
+        addr,
+        (Arc::new(server_config), Arc::new(client_config), root.pem()),
+    )
+}
+
+/// Serial number `start_https_stapled` gives its certificate, so the
+/// stapled response can name it.
+const STAPLED_SERIAL: &[u8] = &[0x4b, 0x4e, 0x4f, 0x43, 0x4b];
+
+/// HTTPS 200 responder that staples an OCSP response (status `good`,
+/// thisUpdate a day ago, nextUpdate `next_update_days` from now —
+/// negative for a stale one, responder `CN=Test OCSP Responder`,
+/// carrying one SCT) and whose certificate embeds `embedded_scts`
+/// SCTs. Neither the response nor the SCTs are signed for real.
+pub fn start_https_stapled<A: ToSocketAddrs>(
+    addr: A,
+    next_update_days: i64,
+    embedded_scts: usize,
+) -> Result<HttpsServer> {
+    let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    params.serial_number = Some(rcgen::SerialNumber::from_slice(STAPLED_SERIAL));
+    if embedded_scts > 0 {
+        params
+            .custom_extensions
+            .push(rcgen::CustomExtension::from_oid_content(
+                &[1, 3, 6, 1, 4, 1, 11129, 2, 4, 2],
+                der(0x04, &sct_list(embedded_scts)),
+            ));
+    }
+    let now = time::OffsetDateTime::now_utc();
+    let ocsp = ocsp_response(
+        now - time::Duration::days(1),
+        now + time::Duration::days(next_update_days),
+    );
+    serve_https_ok(addr, make_stapled_tls_pair(params, Some(ocsp))?)
+}
+
+/// A basic OCSPResponse saying `good` for `STAPLED_SERIAL`, with a
+/// zeroed signature.
+fn ocsp_response(this_update: time::OffsetDateTime, next_update: time::OffsetDateTime) -> Vec<u8> {
+    const OID_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+    const OID_SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+    const OID_SHA256_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    const OID_CN: &[u8] = &[0x55, 0x04, 0x03];
+    const OID_OCSP_SCTS: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x05];
+
+    let cert_id = der(
+        0x30,
+        &[
+            der(0x30, &[der(0x06, OID_SHA1), der(0x05, &[])].concat()),
+            der(0x04, &[0; 20]),
+            der(0x04, &[0; 20]),
+            der(0x02, STAPLED_SERIAL),
+        ]
+        .concat(),
+    );
+    let sct_extension = der(
+        0x30,
+        &der(
+            0x30,
+            &[
+                der(0x06, OID_OCSP_SCTS),
+                der(0x04, &der(0x04, &sct_list(1))),
+            ]
+            .concat(),
+        ),
+    );
+    let single = der(
+        0x30,
+        &[
+            cert_id,
+            der(0x80, &[]),
+            generalized_time(this_update),
+            der(0xa0, &generalized_time(next_update)),
+            der(0xa1, &sct_extension),
+        ]
+        .concat(),
+    );
+    let responder_name = der(
+        0x30,
+        &der(
+            0x31,
+            &der(
+                0x30,
+                &[der(0x06, OID_CN), der(0x0c, b"Test OCSP Responder")].concat(),
+            ),
+        ),
+    );
+    let tbs = der(
+        0x30,
+        &[
+            der(0xa1, &responder_name),
+            generalized_time(this_update),
+            der(0x30, &single),
+        ]
+        .concat(),
+    );
+    let basic = der(
+        0x30,
+        &[
+            tbs,
+            der(0x30, &[der(0x06, OID_SHA256_RSA), der(0x05, &[])].concat()),
+            der(0x03, &[0; 65]),
+        ]
+        .concat(),
+    );
+    der(
+        0x30,
+        &[
+            der(0x0a, &[0]),
+            der(
+                0xa0,
+                &der(
+                    0x30,
+                    &[der(0x06, OID_OCSP_BASIC), der(0x04, &basic)].concat(),
+                ),
+            ),
+        ]
+        .concat(),
+    )
+}
+
+/// A TLS-encoded SignedCertificateTimestampList of `count` v1 SCTs
+/// with zeroed log IDs and signatures.
+fn sct_list(count: usize) -> Vec<u8> {
+    let mut sct = vec![0u8]; // v1
+    sct.extend_from_slice(&[0; 32]); // log ID
+    sct.extend_from_slice(&1_700_000_000_000u64.to_be_bytes());
+    sct.extend_from_slice(&[0, 0]); // no extensions
+    sct.extend_from_slice(&[4, 3, 0, 8]); // SHA-256 / ECDSA, 8 bytes
+    sct.extend_from_slice(&[0; 8]);
+
+    let mut body = Vec::new();
+    for _ in 0..count {
+        body.extend_from_slice(&(sct.len() as u16).to_be_bytes());
+        body.extend_from_slice(&sct);
+    }
+    let mut list = (body.len() as u16).to_be_bytes().to_vec();
+    list.extend_from_slice(&body);
+    list
+}
+
+/// `time` as a DER GeneralizedTime (`YYYYMMDDHHMMSSZ`).
+fn generalized_time(time: time::OffsetDateTime) -> Vec<u8> {
+    let text = format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}Z",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    );
+    der(0x18, text.as_bytes())
+}
+
+/// One DER element: `tag`, definite length, `content`.
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            out.push(0x82);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// HTTPS 200 responder that resumes sessions and accepts TLS 1.3
+/// 0-RTT early data. A request that arrived as early data is
+/// answered like any other; the body says which it was (`early` or
+/// `normal`).
+pub fn start_https_resumable<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let mut server_config = (*server_config).clone();
+    server_config.max_early_data_size = 16 * 1024;
+    let server_config = Arc::new(server_config);
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let cfg = Arc::clone(&server_config);
+            thread::spawn(move || {
+                let _ = answer_with_early_data(cfg, stream);
+            });
+        }
+    });
+
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+fn answer_with_early_data(cfg: Arc<ServerConfig>, mut sock: TcpStream) -> Result<()> {
+    let mut conn = ServerConnection::new(cfg).map_err(std::io::Error::other)?;
+    let mut early = Vec::new();
+    while conn.is_handshaking() {
+        conn.complete_io(&mut sock)?;
+        if let Some(mut data) = conn.early_data() {
+            data.read_to_end(&mut early)?;
+        }
+    }
+    let mut tls = StreamOwned::new(conn, sock);
+    let body = if early.is_empty() {
+        let mut buf = [0u8; BUF_SIZE];
+        let _ = tls.read(&mut buf)?;
+        "normal"
+    } else {
+        "early"
+    };
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    tls.write_all(response.as_bytes())
+}
+
+/// Plaintext server that runs the server half of `protocol`'s
+/// STARTTLS dialogue (`smtp`, `imap`, `pop3`, `ftp`, `xmpp`, `ldap`
+/// or `postgres`), then upgrades the connection to TLS and closes
+/// once the handshake is done. `smtp` also accepts `smtp-refuse`,
+/// a server that doesn't advertise STARTTLS.
+pub fn start_starttls<A: ToSocketAddrs>(addr: A, protocol: &str) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let protocol = protocol.to_string();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let cfg = Arc::clone(&server_config);
+            let protocol = protocol.clone();
+            thread::spawn(move || {
+                let _ = upgrade_in_band(cfg, &protocol, stream);
+            });
+        }
+    });
+
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+fn upgrade_in_band(cfg: Arc<ServerConfig>, protocol: &str, mut sock: TcpStream) -> Result<()> {
+    match protocol {
+        "smtp" => {
+            sock.write_all(b"220 test ESMTP\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"250-test greets you\r\n250-PIPELINING\r\n250 STARTTLS\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"220 2.0.0 Ready to start TLS\r\n")?;
+        }
+        "smtp-refuse" => {
+            sock.write_all(b"220 test ESMTP\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"250-test greets you\r\n250 PIPELINING\r\n")?;
+            return Ok(());
+        }
+        "imap" => {
+            sock.write_all(b"* OK [CAPABILITY IMAP4rev1 STARTTLS] ready\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"a OK Begin TLS negotiation now\r\n")?;
+        }
+        "pop3" => {
+            sock.write_all(b"+OK POP3 ready\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"+OK Begin TLS negotiation\r\n")?;
+        }
+        "ftp" => {
+            sock.write_all(b"220-test FTP\r\n220 ready\r\n")?;
+            read_line(&mut sock)?;
+            sock.write_all(b"234 AUTH TLS successful\r\n")?;
+        }
+        "xmpp" => {
+            read_until(&mut sock, b">")?;
+            read_until(&mut sock, b">")?;
+            sock.write_all(
+                b"<?xml version='1.0'?><stream:stream from='localhost' id='1' \
+                  xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams' \
+                  version='1.0'><stream:features><starttls \
+                  xmlns='urn:ietf:params:xml:ns:xmpp-tls'><required/></starttls>\
+                  </stream:features>",
+            )?;
+            read_until(&mut sock, b"/>")?;
+            sock.write_all(b"<proceed xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")?;
+        }
+        "ldap" => {
+            // The 31-byte StartTLS ExtendedRequest, answered with a
+            // `success` ExtendedResponse.
+            let mut request = [0u8; 31];
+            sock.read_exact(&mut request)?;
+            sock.write_all(&[
+                0x30, 0x0c, 0x02, 0x01, 0x01, 0x78, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+            ])?;
+        }
+        "postgres" => {
+            let mut request = [0u8; 8];
+            sock.read_exact(&mut request)?;
+            sock.write_all(b"S")?;
+        }
+        other => return Err(std::io::Error::other(format!("unknown protocol {other}"))),
+    }
+    let mut conn = ServerConnection::new(cfg).map_err(std::io::Error::other)?;
+    while conn.is_handshaking() {
+        conn.complete_io(&mut sock)?;
+    }
+    if conn.wants_write() {
+        conn.complete_io(&mut sock)?;
+    }
+    // Wait for the client to hang up.
+    let mut rest = Vec::new();
+    let _ = sock.read_to_end(&mut rest);
+    Ok(())
+}
+
+/// Read one CRLF-terminated command line.
+fn read_line(sock: &mut TcpStream) -> Result<Vec<u8>> {
+    read_until(sock, b"\n")
+}
+
+/// Read byte by byte until the input ends with `end`, so nothing
+/// meant for the TLS handshake gets consumed.
+fn read_until(sock: &mut TcpStream, end: &[u8]) -> Result<Vec<u8>> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !line.ends_with(end) {
+        sock.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    Ok(line)
+}
+
+/// HTTPS 200 responder for TLS scans: TLS 1.3 only, offering just
+/// ChaCha20-Poly1305 then AES-256-GCM, in that order regardless of
+/// the client's, and only the secp384r1 group.
+pub fn start_https_preferring<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let params = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let key_pair =
+        rcgen::KeyPair::generate().map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let cert = params
+        .self_signed(&key_pair)
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let cert_der = CertificateDer::from(cert.der().to_vec());
+    let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+
+    let ring = rustls::crypto::ring::default_provider();
+    let provider = rustls::crypto::CryptoProvider {
+        cipher_suites: vec![
+            rustls::crypto::ring::cipher_suite::TLS13_CHACHA20_POLY1305_SHA256,
+            rustls::crypto::ring::cipher_suite::TLS13_AES_256_GCM_SHA384,
+        ],
+        kx_groups: vec![rustls::crypto::ring::kx_group::SECP384R1],
+        ..ring
+    };
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(provider))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der.clone()], key_der)
+        .map_err(|e| std::io::Error::other(format!("rustls server cert: {e}")))?;
+    server_config.ignore_client_order = true;
+
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(cert_der)
+        .map_err(|e| std::io::Error::other(format!("trust anchor: {e}")))?;
+    let client_config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+    serve_https_ok(
+        addr,
+        (Arc::new(server_config), Arc::new(client_config), cert.pem()),
+    )
+}
+
+/// A server stuck in the past: answers every ClientHello with a
+/// ServerHello at the version the client offered, then hangs up —
+/// enough for a scan to see TLS 1.0 / 1.1 accepted, while real TLS
+/// 1.2 / 1.3 handshakes fail on the bogus reply.
+pub fn start_tls_legacy<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut s = stream;
+                // Record header (5) + handshake header (4), then the
+                // ClientHello's legacy_version.
+                let mut hello = [0u8; 11];
+                if s.read_exact(&mut hello).is_err() {
+                    return;
+                }
+                let version = [hello[9], hello[10]];
+                let mut body = vec![version[0], version[1]];
+                body.extend_from_slice(&[0x11; 32]);
+                body.extend_from_slice(&[0x00, 0xc0, 0x13, 0x00]);
+                let mut message = vec![0x02, 0x00, 0x00, body.len() as u8];
+                message.extend_from_slice(&body);
+                let mut record = vec![0x16, version[0], version[1], 0x00, message.len() as u8];
+                record.extend_from_slice(&message);
+                let _ = s.write_all(&record);
+            });
+        }
+    });
+    Ok(bound)
+}
+
+/// HTTPS responder whose 200 body reports the SNI the client sent
+/// (`sni=<name>`, or `sni=-` for none). Offers ALPN `h2` and
+/// `http/1.1`, though it always answers in HTTP/1.1.
+pub fn start_https_sni_echo<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let mut server_config = (*server_config).clone();
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    serve_https(
+        addr,
+        (Arc::new(server_config), client_config, cert_pem),
+        |conn| {
+            let body = format!("sni={}", conn.server_name().unwrap_or("-"));
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .into_bytes()
+        },
+    )
+}
+
+fn serve_https_ok<A: ToSocketAddrs>(addr: A, pair: TlsPair) -> Result<HttpsServer> {
+    serve_https(addr, pair, |_| {
+        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+    })
+}
+
+/// Answer the first read on each connection with `respond(conn)`.
+fn serve_https<A: ToSocketAddrs>(
+    addr: A,
+    (server_config, client_config, cert_pem): TlsPair,
+    respond: fn(&ServerConnection) -> Vec<u8>,
+) -> Result<HttpsServer> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+
+    {
+        let server_config = Arc::clone(&server_config);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let cfg = Arc::clone(&server_config);
+                thread::spawn(move || {
+                    let conn = match ServerConnection::new(cfg) {
+                        Ok(c) => c,
+                        Err(_) => return,
+                    };
+                    let mut tls = StreamOwned::new(conn, stream);
+                    let mut buf = [0u8; BUF_SIZE];
+                    let _ = tls.read(&mut buf);
+                    let response = respond(&tls.conn);
+                    let _ = tls.write_all(&response);
+                });
+            }
+        });
+    }
+
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+/// Spin up a cleartext HTTP/2 (h2c, prior knowledge) server on
+/// `addr`. Every stream gets `200` with body `"<METHOD> <PATH> <N>"`,
+/// `N` being the request body bytes received; `/bytes/<N>` answers
+/// with `N` bytes instead. PINGs are answered by the h2 stack.
+pub fn start_h2c_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
+    let std_listener = TcpListener::bind(addr)?;
+    std_listener.set_nonblocking(true)?;
+    let bound = std_listener.local_addr()?;
+
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("tokio runtime");
+        rt.block_on(async move {
+            let listener =
+                tokio::net::TcpListener::from_std(std_listener).expect("from_std listener");
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_h2(stream));
+            }
+        });
+    });
+
+    Ok(bound)
+}
+
+/// TLS variant of `start_h2c_ok`: a self-signed `localhost` cert,
+/// ALPN `h2` only. The returned `client_config` has no ALPN set.
+pub fn start_h2_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let mut server_config = (*server_config).clone();
+    server_config.alpn_protocols = vec![b"h2".to_vec()];
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+
+    let std_listener = TcpListener::bind(addr)?;
+    std_listener.set_nonblocking(true)?;
+    let bound = std_listener.local_addr()?;
+
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("tokio runtime");
+        rt.block_on(async move {
+            let listener =
+                tokio::net::TcpListener::from_std(std_listener).expect("from_std listener");
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(tls) = acceptor.accept(stream).await {
+                        serve_h2(tls).await;
+                    }
+                });
+            }
+        });
+    });
+
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+/// One HTTP/2 server connection for `start_h2c_ok` / `start_h2_ok`.
+async fn serve_h2<S>(io: S)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let Ok(mut connection) = h2::server::handshake(io).await else {
+        return;
+    };
+    while let Some(Ok((request, mut respond))) = connection.accept().await {
+        tokio::spawn(async move {
+            let (parts, mut body) = request.into_parts();
+            let mut received = 0;
+            while let Some(Ok(chunk)) = body.data().await {
+                received += chunk.len();
+                let _ = body.flow_control().release_capacity(chunk.len());
+            }
+            let path = parts.uri.path();
+            let reply = match path.strip_prefix("/bytes/").and_then(|n| n.parse().ok()) {
+                Some(n) => vec![b'x'; n],
+                None => format!("{} {path} {received}", parts.method).into_bytes(),
+            };
+            let response = http::Response::builder()
+                .status(200)
+                .header("content-type", "text/plain")
+                .header("content-length", reply.len())
+                .body(())
+                .expect("h2 response");
+            let Ok(mut send) = respond.send_response(response, false) else {
+                return;
+            };
+            let _ = send.send_data(bytes::Bytes::from(reply), true);
+        });
+    }
+}
+
+/// HTTPS origin advertising HTTP/3 the way a CDN does: ALPN `h2` and
+/// `http/1.1` on TCP (h2 connections are served like `start_h2_ok`),
+/// and every HTTP/1.1 response carries `Alt-Svc: h3=":<port>"`. With
+/// `h3` set, the same port on UDP serves HTTP/3 like `start_h3_ok`,
+/// with the same certificate; without it, the advertisement is a lie.
+pub fn start_https_alt_svc<A: ToSocketAddrs>(addr: A, h3: bool) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let mut tcp_config = (*server_config).clone();
+    tcp_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tcp_config));
+
+    let std_listener = TcpListener::bind(addr)?;
+    std_listener.set_nonblocking(true)?;
+    let bound = std_listener.local_addr()?;
+
+    if h3 {
+        let mut quic_config = (*server_config).clone();
+        quic_config.alpn_protocols = vec![b"h3".to_vec()];
+        let quic_config = quinn::crypto::rustls::QuicServerConfig::try_from(quic_config)
+            .map_err(|e| std::io::Error::other(format!("quinn QuicServerConfig: {e}")))?;
+        serve_quic_with(
+            bound,
+            quinn::ServerConfig::with_crypto(Arc::new(quic_config)),
+            serve_h3,
+        )?;
+    }
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nAlt-Svc: h3=\":{}\"; ma=86400\r\n\
+         Content-Length: 2\r\nConnection: close\r\n\r\nok",
+        bound.port()
+    );
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("tokio runtime");
+        rt.block_on(async move {
+            let listener =
+                tokio::net::TcpListener::from_std(std_listener).expect("from_std listener");
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                let response = response.clone();
+                tokio::spawn(async move {
+                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+                    let Ok(mut tls) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    if tls.get_ref().1.alpn_protocol() == Some(b"h2") {
+                        serve_h2(tls).await;
+                        return;
+                    }
+                    let mut buf = [0u8; BUF_SIZE];
+                    let _ = tls.read(&mut buf).await;
+                    let _ = tls.write_all(response.as_bytes()).await;
+                    let _ = tls.shutdown().await;
+                });
+            }
+        });
+    });
+
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+/// Server-Sent Events server: every request gets a `text/event-stream`
+/// opening with a comment and `retry: 1000`, then `events` as
+/// (type, data) pairs with ids 1, 2, …, one chunk each, 10 ms apart.
+/// A `Last-Event-ID: N` request resumes after event N. Type `message`
+/// is sent without an `event:` line. The stream stays open until the
+/// client closes it, as a live feed would.
+pub fn start_sse<A: ToSocketAddrs>(addr: A, events: &[(&str, &str)]) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let events = owned_events(events);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let events = events.clone();
+            thread::spawn(move || serve_sse(stream, &events));
+        }
+    });
+    Ok(bound)
+}
+
+/// TLS variant of `start_sse`, on a self-signed `localhost` cert.
+pub fn start_sse_tls<A: ToSocketAddrs>(addr: A, events: &[(&str, &str)]) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let events = owned_events(events);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let cfg = Arc::clone(&server_config);
+            let events = events.clone();
+            thread::spawn(move || {
+                if let Ok(conn) = ServerConnection::new(cfg) {
+                    serve_sse(StreamOwned::new(conn, stream), &events);
+                }
             });
         }
     });
-    Ok(bound)
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
 }
 
-/// Handle returned by `start_https_ok` — exposes the bound address
-/// plus a `ClientConfig` whose only trust anchor is the self-signed
-/// cert this server uses, so test code can speak HTTPS to the server
-/// without pulling in the system trust store.
-pub struct HttpsServer {
+fn owned_events(events: &[(&str, &str)]) -> Vec<(String, String)> {
+    events
+        .iter()
+        .map(|(name, data)| (name.to_string(), data.to_string()))
+        .collect()
+}
+
+fn serve_sse<S: Read + Write>(mut s: S, events: &[(String, String)]) {
+    let mut request = Vec::new();
+    let mut byte = [0u8; 1];
+    while !request.ends_with(b"\r\n\r\n") {
+        match s.read(&mut byte) {
+            Ok(1) => request.push(byte[0]),
+            _ => return,
+        }
+    }
+    let after: usize = String::from_utf8_lossy(&request)
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("last-event-id"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+
+    let mut chunks = vec![": hello\n\nretry: 1000\n\n".to_string()];
+    for (n, (name, data)) in events.iter().enumerate().skip(after) {
+        let mut event = format!("id: {}\n", n + 1);
+        if name != "message" {
+            event.push_str(&format!("event: {name}\n"));
+        }
+        for line in data.split('\n') {
+            event.push_str(&format!("data: {line}\n"));
+        }
+        event.push('\n');
+        chunks.push(event);
+    }
+
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\nTransfer-Encoding: chunked\r\n\r\n";
+    if s.write_all(head.as_bytes()).is_err() {
+        return;
+    }
+    for chunk in chunks {
+        let framed = format!("{:x}\r\n{chunk}\r\n", chunk.len());
+        if s.write_all(framed.as_bytes())
+            .and_then(|_| s.flush())
+            .is_err()
+        {
+            return;
+        }
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let _ = s.read(&mut byte);
+}
+
+/// Handle returned by `start_https_mtls` — the bound address, a
+/// `ClientConfig` trusting the server's self-signed cert, and a
+/// client certificate + key (PEM) issued by the CA the server
+/// requires.
+pub struct MtlsServer {
     pub addr: SocketAddr,
     pub client_config: Arc<ClientConfig>,
+    pub client_cert_pem: String,
+    pub client_key_pem: String,
 }
 
-/// Generate a fresh self-signed cert for SAN `localhost` and return
-/// the matching `ServerConfig` (for the listener side) plus a
-/// `ClientConfig` whose only trust anchor is that cert (for the
-/// client side). Used by every TLS-wrapped test endpoint —
-/// `start_https_ok`, `start_wss_ok`, `start_mqtts_ok`.
-fn make_test_tls_pair() -> Result<(Arc<ServerConfig>, Arc<ClientConfig>)> {
-    let issued = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
-        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
-    let cert_der = CertificateDer::from(issued.cert.der().to_vec());
-    let key_der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(issued.key_pair.serialize_der()));
+/// HTTPS 200-OK responder that demands a client certificate signed
+/// by its own freshly generated CA. A client that presents none (or
+/// another CA's) fails the handshake and gets no response.
+pub fn start_https_mtls<A: ToSocketAddrs>(addr: A) -> Result<MtlsServer> {
+    let rcgen_err = |e: rcgen::Error| std::io::Error::other(format!("rcgen: {e}"));
+    let ca_key = rcgen::KeyPair::generate().map_err(rcgen_err)?;
+    let mut ca_params = rcgen::CertificateParams::new(Vec::new()).map_err(rcgen_err)?;
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    ca_params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "testserver client CA");
+    let ca_cert = ca_params.self_signed(&ca_key).map_err(rcgen_err)?;
 
-    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let client_key = rcgen::KeyPair::generate().map_err(rcgen_err)?;
+    let mut client_params =
+        rcgen::CertificateParams::new(vec!["client".to_string()]).map_err(rcgen_err)?;
+    client_params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+    let client_cert = client_params
+        .signed_by(&client_key, &ca_cert, &ca_key)
+        .map_err(rcgen_err)?;
 
+    let server =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).map_err(rcgen_err)?;
+    let server_cert = CertificateDer::from(server.cert.der().to_vec());
+    let server_key =
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(server.key_pair.serialize_der()));
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut client_roots = RootCertStore::empty();
+    client_roots
+        .add(ca_cert.der().clone())
+        .map_err(|e| std::io::Error::other(format!("client CA: {e}")))?;
+    let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+        Arc::new(client_roots),
+        provider.clone(),
+    )
+    .build()
+    .map_err(|e| std::io::Error::other(format!("client verifier: {e}")))?;
     let server_config = ServerConfig::builder_with_provider(provider.clone())
         .with_safe_default_protocol_versions()
         .map_err(|e| std::io::Error::other(format!("rustls protocol: {e}")))?
-        .with_no_client_auth()
-        .with_single_cert(vec![cert_der.clone()], key_der)
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(vec![server_cert.clone()], server_key)
         .map_err(|e| std::io::Error::other(format!("rustls server cert: {e}")))?;
+    let server_config = Arc::new(server_config);
 
     let mut roots = RootCertStore::empty();
     roots
-        .add(cert_der)
+        .add(server_cert)
         .map_err(|e| std::io::Error::other(format!("trust anchor: {e}")))?;
     let client_config = ClientConfig::builder_with_provider(provider)
         .with_safe_default_protocol_versions()
@@ -100,42 +1388,33 @@ fn make_test_tls_pair() -> Result<(Arc<ServerConfig>, Arc<ClientConfig>)> {
         .with_root_certificates(roots)
         .with_no_client_auth();
 
-    Ok((Arc::new(server_config), Arc::new(client_config)))
-}
-
-/// Spin up an HTTPS 200-OK responder on `addr` using a freshly
-/// generated self-signed cert for the SAN `localhost`. Returns the
-/// bound address and a `ClientConfig` pre-loaded with the cert as a
-/// trust anchor.
-pub fn start_https_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
-    let (server_config, client_config) = make_test_tls_pair()?;
     let listener = TcpListener::bind(addr)?;
     let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let cfg = Arc::clone(&server_config);
+            thread::spawn(move || {
+                let conn = match ServerConnection::new(cfg) {
+                    Ok(c) => c,
+                    Err(_) => return,
+                };
+                let mut tls = StreamOwned::new(conn, stream);
+                let mut buf = [0u8; BUF_SIZE];
+                if tls.read(&mut buf).is_err() {
+                    return;
+                }
+                let _ = tls.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            });
+        }
+    });
 
-    {
-        let server_config = Arc::clone(&server_config);
-        thread::spawn(move || {
-            for stream in listener.incoming().flatten() {
-                let cfg = Arc::clone(&server_config);
-                thread::spawn(move || {
-                    let conn = match ServerConnection::new(cfg) {
-                        Ok(c) => c,
-                        Err(_) => return,
-                    };
-                    let mut tls = StreamOwned::new(conn, stream);
-                    let mut buf = [0u8; BUF_SIZE];
-                    let _ = tls.read(&mut buf);
-                    let _ = tls.write_all(
-                        b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
-                    );
-                });
-            }
-        });
-    }
-
-    Ok(HttpsServer {
+    Ok(MtlsServer {
         addr: bound,
-        client_config,
+        client_config: Arc::new(client_config),
+        client_cert_pem: client_cert.pem(),
+        client_key_pem: client_key.serialize_pem(),
     })
 }
 
@@ -158,6 +1437,37 @@ pub fn start_hls_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
     Ok(bound)
 }
 
+/// `start_hls_ok` behind `start_http_auth`'s authentication: every
+/// request, playlist and segment alike, needs credentials.
+pub fn start_hls_auth<A: ToSocketAddrs>(
+    addr: A,
+    username: &str,
+    password: &str,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let credentials = Arc::new((username.to_string(), password.to_string()));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let credentials = credentials.clone();
+            thread::spawn(move || {
+                let mut stream = stream;
+                let Ok(req) = read_http_request(&mut stream) else {
+                    return;
+                };
+                let req = String::from_utf8_lossy(&req);
+                let (user, pass) = &*credentials;
+                if authorized_request(&req, user, pass).is_some() {
+                    answer_hls(stream, &req);
+                } else {
+                    let _ = stream.write_all(unauthorized_response().as_bytes());
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
 fn handle_hls(mut stream: std::net::TcpStream) {
     let mut buf = [0u8; BUF_SIZE];
     let n = match stream.read(&mut buf) {
@@ -165,6 +1475,10 @@ fn handle_hls(mut stream: std::net::TcpStream) {
         Err(_) => return,
     };
     let req = String::from_utf8_lossy(&buf[..n]).to_string();
+    answer_hls(stream, &req);
+}
+
+fn answer_hls(mut stream: std::net::TcpStream, req: &str) {
     let request_line = req.lines().next().unwrap_or("");
     let path = request_line.split_whitespace().nth(1).unwrap_or("");
     let range_hdr = req
@@ -254,6 +1568,37 @@ pub fn start_dns_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
     Ok(bound)
 }
 
+/// Like `start_dns_ok`, but every response carries one A record for
+/// the queried name pointing at `answer` — enough to exercise answer
+/// parsing and `answer=` expectations.
+pub fn start_dns_answer<A: ToSocketAddrs>(addr: A, answer: Ipv4Addr) -> Result<SocketAddr> {
+    let socket = UdpSocket::bind(addr)?;
+    let bound = socket.local_addr()?;
+    thread::spawn(move || {
+        let mut buf = [0u8; BUF_SIZE];
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((n, src)) if n >= 12 => {
+                    let mut reply = buf[..n].to_vec();
+                    reply[2] |= 0x80; // QR = 1 (response)
+                    reply[3] &= 0xF0; // RCODE = 0
+                    reply[6..8].copy_from_slice(&1u16.to_be_bytes()); // ANCOUNT
+                    reply.extend_from_slice(&[0xC0, 12]); // name → question
+                    reply.extend_from_slice(&1u16.to_be_bytes()); // TYPE A
+                    reply.extend_from_slice(&1u16.to_be_bytes()); // CLASS IN
+                    reply.extend_from_slice(&60u32.to_be_bytes()); // TTL
+                    reply.extend_from_slice(&4u16.to_be_bytes()); // RDLENGTH
+                    reply.extend_from_slice(&answer.octets());
+                    let _ = socket.send_to(&reply, src);
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    Ok(bound)
+}
+
 /// Spin up a plain (`ws://`) WebSocket echo / ping server. Each
 /// connection is upgraded by tungstenite, then the server replies to
 /// any incoming PING with a PONG carrying the same payload.
@@ -287,7 +1632,7 @@ pub fn start_ws_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
 /// trusts the freshly-generated self-signed cert (and only that
 /// cert) — same shape as `start_https_ok`.
 pub fn start_wss_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
-    let (server_config, client_config) = make_test_tls_pair()?;
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
     let listener = TcpListener::bind(addr)?;
     let bound = listener.local_addr()?;
 
@@ -323,9 +1668,66 @@ pub fn start_wss_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
     Ok(HttpsServer {
         addr: bound,
         client_config,
+        cert_pem,
     })
 }
 
+/// `start_ws_ok` behind `start_http_auth`'s authentication: the
+/// Upgrade request needs credentials, or gets a `401` with the same
+/// challenges.
+pub fn start_ws_auth<A: ToSocketAddrs>(
+    addr: A,
+    username: &str,
+    password: &str,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    let credentials = Arc::new((username.to_string(), password.to_string()));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let credentials = credentials.clone();
+            thread::spawn(move || {
+                // tungstenite's callback signature, not ours.
+                #[allow(clippy::result_large_err)]
+                let check = |request: &Request, response: Response| {
+                    let (user, pass) = &*credentials;
+                    let target = request
+                        .uri()
+                        .path_and_query()
+                        .map_or("/", |target| target.as_str());
+                    let authorization = request
+                        .headers()
+                        .get("authorization")
+                        .and_then(|value| value.to_str().ok());
+                    if authorized("GET", target, authorization, user, pass).is_some() {
+                        return Ok(response);
+                    }
+                    Err(http::Response::builder()
+                        .status(401)
+                        .header("WWW-Authenticate", digest_challenge())
+                        .header("WWW-Authenticate", format!("Basic realm=\"{AUTH_REALM}\""))
+                        .body(None)
+                        .expect("static 401 response"))
+                };
+                let mut ws = match accept_hdr(stream, check) {
+                    Ok(ws) => ws,
+                    Err(_) => return,
+                };
+                while let Ok(msg) = ws.read() {
+                    match msg {
+                        Message::Ping(payload) => {
+                            let _ = ws.send(Message::Pong(payload));
+                        }
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
 /// Read a single MQTT control packet from `stream`. Returns
 /// `(packet_type, body)`. Supports the same 4-byte variable-byte-int
 /// remaining-length encoding the client side uses.
@@ -480,6 +1882,109 @@ pub fn start_grpcs_ok<A: ToSocketAddrs>(addr: A) -> Result<GrpcsServer> {
     })
 }
 
+/// gRPC-Web server (HTTP/1.1, `application/grpc-web+proto`) for
+/// `grpc.health.v1.Health/Check`, answering in chunks like Envoy's
+/// `grpc_web` filter: overall health (empty service) is `SERVING`,
+/// service `down` is `NOT_SERVING`, and any other service fails with
+/// `grpc-status: 5` in the trailers frame. Other paths get a
+/// trailers-only `grpc-status: 12` (UNIMPLEMENTED) in the headers.
+pub fn start_grpc_web<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || serve_grpc_web(stream));
+        }
+    });
+    Ok(bound)
+}
+
+/// TLS variant of `start_grpc_web`, on a self-signed `localhost`
+/// cert.
+pub fn start_grpc_web_tls<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let cfg = Arc::clone(&server_config);
+            thread::spawn(move || {
+                if let Ok(conn) = ServerConnection::new(cfg) {
+                    serve_grpc_web(StreamOwned::new(conn, stream));
+                }
+            });
+        }
+    });
+    Ok(HttpsServer {
+        addr: bound,
+        client_config,
+        cert_pem,
+    })
+}
+
+fn serve_grpc_web<S: Read + Write>(mut s: S) {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        match s.read(&mut byte) {
+            Ok(1) => head.push(byte[0]),
+            _ => return,
+        }
+    }
+    let head = String::from_utf8_lossy(&head).into_owned();
+    let len: usize = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; len];
+    if s.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    if head.split_whitespace().nth(1) != Some("/grpc.health.v1.Health/Check") {
+        let _ = s.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/grpc-web+proto\r\n\
+              grpc-status: 12\r\ngrpc-message: unknown%20method\r\nContent-Length: 0\r\n\r\n",
+        );
+        return;
+    }
+    // Past the 5-byte frame header, field 1 (`service`) if set; names
+    // here are short enough for a one-byte length.
+    let service = body
+        .get(5..)
+        .filter(|message| message.first() == Some(&0x0a))
+        .map(|message| String::from_utf8_lossy(message.get(2..).unwrap_or_default()).into_owned())
+        .unwrap_or_default();
+    let frame = |flags: u8, payload: &[u8]| {
+        let mut framed = vec![flags];
+        framed.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        framed.extend_from_slice(payload);
+        framed
+    };
+    let payload = match service.as_str() {
+        "" | "down" => {
+            let status = if service.is_empty() { 1 } else { 2 };
+            let mut payload = frame(0x00, &[0x08, status]);
+            payload.extend(frame(0x80, b"grpc-status:0\r\n"));
+            payload
+        }
+        other => frame(
+            0x80,
+            format!("grpc-status:5\r\ngrpc-message:unknown%20service%20{other}\r\n").as_bytes(),
+        ),
+    };
+    let mut response = b"HTTP/1.1 200 OK\r\nContent-Type: application/grpc-web+proto\r\n\
+                         Transfer-Encoding: chunked\r\n\r\n"
+        .to_vec();
+    response.extend(format!("{:x}\r\n", payload.len()).as_bytes());
+    response.extend(payload);
+    response.extend(b"\r\n0\r\n\r\n");
+    let _ = s.write_all(&response);
+    let _ = s.flush();
+}
+
 /// Spin up a minimal MQTT 3.1.1 broker on `addr`. Accepts the
 /// CONNECT / CONNACK handshake, replies to PINGREQ with PINGRESP,
 /// closes on DISCONNECT. No subscriptions, no PUBLISH support — just
@@ -500,7 +2005,7 @@ pub fn start_mqtt_ok<A: ToSocketAddrs>(addr: A) -> Result<SocketAddr> {
 /// freshly-generated self-signed cert (and only that cert) — same
 /// shape as `start_https_ok` / `start_wss_ok`.
 pub fn start_mqtts_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
-    let (server_config, client_config) = make_test_tls_pair()?;
+    let (server_config, client_config, cert_pem) = make_test_tls_pair()?;
     let listener = TcpListener::bind(addr)?;
     let bound = listener.local_addr()?;
 
@@ -524,6 +2029,7 @@ pub fn start_mqtts_ok<A: ToSocketAddrs>(addr: A) -> Result<HttpsServer> {
     Ok(HttpsServer {
         addr: bound,
         client_config,
+        cert_pem,
     })
 }
 
@@ -745,6 +2251,63 @@ pub struct QuicServer {
 /// runtime in a dedicated OS thread — keeps the binding API
 /// synchronous like the rest of `testserver`.
 pub fn start_quic_ok<A: ToSocketAddrs>(addr: A) -> Result<QuicServer> {
+    serve_quic(addr, |connection| async move {
+        // Handshake complete — close immediately so the pinger sees a
+        // clean ping. We don't open any HTTP/3 streams.
+        connection.close(0u32.into(), b"hello-and-goodbye");
+    })
+}
+
+/// HTTP/3 server on `addr`, same certificate setup as
+/// `start_quic_ok`. `GET /status/<N>` answers status `N`; any other
+/// request gets `200` with body `"<METHOD> <PATH>"`. Headers go out
+/// QPACK-encoded by the h3 crate.
+pub fn start_h3_ok<A: ToSocketAddrs>(addr: A) -> Result<QuicServer> {
+    serve_quic(addr, serve_h3)
+}
+
+/// One HTTP/3 server connection for `start_h3_ok` /
+/// `start_https_alt_svc`.
+async fn serve_h3(connection: quinn::Connection) {
+    let Ok(mut h3) =
+        h3::server::Connection::<_, bytes::Bytes>::new(h3_quinn::Connection::new(connection)).await
+    else {
+        return;
+    };
+    while let Ok(Some(resolver)) = h3.accept().await {
+        tokio::spawn(async move {
+            let Ok((request, mut stream)) = resolver.resolve_request().await else {
+                return;
+            };
+            let path = request.uri().path();
+            let status = path
+                .strip_prefix("/status/")
+                .and_then(|code| code.parse().ok())
+                .unwrap_or(200);
+            let body = format!("{} {path}", request.method());
+            let response = http::Response::builder()
+                .status(status)
+                .header("content-type", "text/plain")
+                .body(())
+                .expect("h3 response");
+            if stream.send_response(response).await.is_ok()
+                && stream.send_data(bytes::Bytes::from(body)).await.is_ok()
+            {
+                let _ = stream.finish().await;
+            }
+        });
+    }
+}
+
+/// Bind a QUIC endpoint (self-signed `localhost` cert, ALPN `h3`) on
+/// its own runtime thread and hand each established connection to
+/// `serve`.
+fn serve_quic<A, F, Fut>(addr: A, serve: F) -> Result<QuicServer>
+where
+    A: ToSocketAddrs,
+    F: Fn(quinn::Connection) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
     let bind: SocketAddr = addr
         .to_socket_addrs()?
         .next()
@@ -783,6 +2346,25 @@ pub fn start_quic_ok<A: ToSocketAddrs>(addr: A) -> Result<QuicServer> {
         .with_no_client_auth();
     let client_config = Arc::new(client_config);
 
+    let bound = serve_quic_with(bind, server_config, serve)?;
+    Ok(QuicServer {
+        addr: bound,
+        client_config,
+    })
+}
+
+/// Run a QUIC endpoint with `server_config` on `bind`, on its own
+/// runtime thread, handing each established connection to `serve`.
+/// Returns the bound address.
+fn serve_quic_with<F, Fut>(
+    bind: SocketAddr,
+    server_config: quinn::ServerConfig,
+    serve: F,
+) -> Result<SocketAddr>
+where
+    F: Fn(quinn::Connection) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
     // Spin up the QUIC server in a dedicated thread + tokio
     // current-thread runtime. We use a oneshot channel so we can
     // surface the bound addr (in case the caller asked for port 0)
@@ -815,22 +2397,134 @@ pub fn start_quic_ok<A: ToSocketAddrs>(addr: A) -> Result<QuicServer> {
                 }
             };
             let _ = tx.send(Ok(bound));
+            let serve = Arc::new(serve);
             while let Some(incoming) = endpoint.accept().await {
+                let serve = serve.clone();
                 tokio::spawn(async move {
                     if let Ok(connection) = incoming.await {
-                        // Handshake complete — close immediately so
-                        // the pinger sees a clean ping. We don't open
-                        // any HTTP/3 streams.
-                        connection.close(0u32.into(), b"hello-and-goodbye");
+                        serve(connection).await;
                     }
                 });
             }
         });
     });
 
-    let bound = rx.recv().map_err(std::io::Error::other)??;
-    Ok(QuicServer {
-        addr: bound,
-        client_config,
+    rx.recv().map_err(std::io::Error::other)?
+}
+
+/// A running DTLS server. `cert_pem` is the self-signed certificate
+/// it presents in certificate mode (empty for a PSK server), for the
+/// caller to trust.
+pub struct DtlsServer {
+    pub addr: SocketAddr,
+    pub cert_pem: String,
+}
+
+/// Spin up a DTLS 1.2 server on `addr` presenting a fresh self-signed
+/// certificate for SAN `localhost`. Every handshake goes through the
+/// HelloVerifyRequest cookie exchange; after it, the server waits
+/// for the client's `close_notify` and sends no data.
+pub fn start_dtls_ok<A: ToSocketAddrs>(addr: A) -> Result<DtlsServer> {
+    let issued = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])
+        .map_err(|e| std::io::Error::other(format!("rcgen: {e}")))?;
+    let private_key = webrtc_dtls::crypto::CryptoPrivateKey::from_key_pair(&issued.key_pair)
+        .map_err(|e| std::io::Error::other(format!("dtls key: {e}")))?;
+    let config = webrtc_dtls::config::Config {
+        certificates: vec![webrtc_dtls::crypto::Certificate {
+            certificate: vec![issued.cert.der().clone()],
+            private_key,
+        }],
+        extended_master_secret: webrtc_dtls::config::ExtendedMasterSecretType::Require,
+        ..Default::default()
+    };
+    Ok(DtlsServer {
+        addr: serve_dtls(addr, config)?,
+        cert_pem: issued.cert.pem(),
+    })
+}
+
+/// Like `start_dtls_ok`, authenticating with a pre-shared key over
+/// `TLS_PSK_WITH_AES_128_CCM_8`: only a client sending `identity`
+/// gets `key`; any other identity is refused.
+pub fn start_dtls_psk<A: ToSocketAddrs>(
+    addr: A,
+    identity: &[u8],
+    key: &[u8],
+) -> Result<DtlsServer> {
+    let (identity, key) = (identity.to_vec(), key.to_vec());
+    let config = webrtc_dtls::config::Config {
+        psk: Some(Arc::new(move |offered: &[u8]| {
+            if offered == identity.as_slice() {
+                Ok(key.clone())
+            } else {
+                Err(webrtc_dtls::Error::Other("unknown PSK identity".into()))
+            }
+        })),
+        psk_identity_hint: Some(b"testserver".to_vec()),
+        // CoAP's mandatory suite, and not the client's first choice.
+        cipher_suites: vec![webrtc_dtls::cipher_suite::CipherSuiteId::Tls_Psk_With_Aes_128_Ccm_8],
+        extended_master_secret: webrtc_dtls::config::ExtendedMasterSecretType::Require,
+        ..Default::default()
+    };
+    Ok(DtlsServer {
+        addr: serve_dtls(addr, config)?,
+        cert_pem: String::new(),
     })
 }
+
+fn serve_dtls<A: ToSocketAddrs>(
+    addr: A,
+    config: webrtc_dtls::config::Config,
+) -> Result<SocketAddr> {
+    use webrtc_util::conn::Listener;
+
+    let bind: SocketAddr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::other("start_dtls: no addresses to bind"))?;
+    // Same shape as `start_quic_ok`: a dedicated thread with its own
+    // runtime, reporting the bound address back before serving.
+    let (tx, rx) = std::sync::mpsc::channel::<Result<SocketAddr>>();
+    thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                let _ = tx.send(Err(e));
+                return;
+            }
+        };
+        rt.block_on(async move {
+            let listener = match webrtc_dtls::listener::listen(bind, config).await {
+                Ok(l) => l,
+                Err(e) => {
+                    let _ = tx.send(Err(std::io::Error::other(format!("dtls listen: {e}"))));
+                    return;
+                }
+            };
+            let bound = match listener.addr().await {
+                Ok(a) => a,
+                Err(e) => {
+                    let _ = tx.send(Err(std::io::Error::other(format!("dtls addr: {e}"))));
+                    return;
+                }
+            };
+            let _ = tx.send(Ok(bound));
+            loop {
+                // A failed handshake (wrong key, untrusted client)
+                // surfaces here; keep serving.
+                let Ok((conn, _)) = listener.accept().await else {
+                    continue;
+                };
+                tokio::spawn(async move {
+                    let mut buf = [0u8; BUF_SIZE];
+                    while conn.recv(&mut buf).await.is_ok() {}
+                    let _ = conn.close().await;
+                });
+            }
+        });
+    });
+    rx.recv().map_err(std::io::Error::other)?
+}
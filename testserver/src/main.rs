@@ -73,6 +73,22 @@ struct Args {
     #[arg(long, default_value_t = 18013)]
     quic: u16,
 
+    /// DTLS 1.2 server port — PSK identity `knockknock`, key
+    /// `6b6e6f636b` (hex) (use 0 for ephemeral)
+    #[arg(long, default_value_t = 18014)]
+    dtls: u16,
+
+    /// Server-Sent Events port — a `message` event then two `tick`
+    /// events, ids 1–3, honouring `Last-Event-ID` (use 0 for
+    /// ephemeral)
+    #[arg(long, default_value_t = 18015)]
+    sse: u16,
+
+    /// gRPC-Web (HTTP/1.1) Health/Check port — overall health
+    /// `SERVING` (use 0 for ephemeral)
+    #[arg(long, default_value_t = 18016)]
+    grpc_web: u16,
+
     /// Bind address (default 0.0.0.0; use 127.0.0.1 for loopback only)
     #[arg(long, default_value = "0.0.0.0")]
     bind: String,
@@ -137,6 +153,19 @@ fn main() {
     let quic_server = start_or_die("quic", args.quic, || {
         Ok(testserver::start_quic_ok(format!("{bind}:{}", args.quic))?.addr)
     });
+    let dtls = start_or_die("dtls", args.dtls, || {
+        let addr = format!("{bind}:{}", args.dtls);
+        Ok(testserver::start_dtls_psk(addr, b"knockknock", b"knock")?.addr)
+    });
+    let sse = start_or_die("sse", args.sse, || {
+        testserver::start_sse(
+            format!("{bind}:{}", args.sse),
+            &[("message", "hello"), ("tick", "1"), ("tick", "2")],
+        )
+    });
+    let grpc_web = start_or_die("grpc-web", args.grpc_web, || {
+        testserver::start_grpc_web(format!("{bind}:{}", args.grpc_web))
+    });
 
     println!("[tcp]  listening on {tcp}");
     println!("[udp]  listening on {udp}");
@@ -152,6 +181,9 @@ fn main() {
     println!("[rtsp] listening on {rtsp}");
     println!("[rtmp] listening on {rtmp}");
     println!("[quic] listening on {quic_server} (self-signed cert; pinger needs --tls override or skip-verify)");
+    println!("[dtls] listening on {dtls} (PSK)");
+    println!("[sse]  listening on {sse}");
+    println!("[grpc-web] listening on {grpc_web}");
     println!();
     println!("Try in another terminal:");
     println!("  knockknock tcp localhost:{}", tcp.port());
@@ -178,6 +210,18 @@ fn main() {
         "  # see the QUIC section of the README for the integration-test path that injects a custom ClientConfig."
     );
     println!("  knockknock quic localhost:{}", quic_server.port());
+    println!(
+        "  knockknock dtls localhost:{} --psk-identity knockknock --psk-key 6b6e6f636b",
+        dtls.port()
+    );
+    println!(
+        "  knockknock sse http://localhost:{}/events --event tick",
+        sse.port()
+    );
+    println!(
+        "  knockknock grpc grpc://localhost:{} --web",
+        grpc_web.port()
+    );
     println!();
     println!("Press Ctrl+C to stop.");
 
@@ -0,0 +1,180 @@
+//! Cooperative cancellation. Dropping a probe future stops it but
+//! throws away what it had done; `Pinger::probe_with_cancel` instead
+//! stops at the token's signal and returns a `Cancelled` error that
+//! lists the phases the probe completed first (`connect`, `tls`,
+//! `playlist`, ...) with their timings.
+//!
+//! Pingers mark phases with `observe::phase`, which records into the
+//! task-local log installed by `cancellable` (and tells any
+//! `Observer`). Outside `cancellable` the log is unset and recording
+//! is a failed lookup.
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::report::Report;
+
+/// One completed step of a probe and when it finished, measured from
+/// the start of the probe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Phase {
+    pub name: &'static str,
+    pub at: Duration,
+}
+
+/// A probe stopped by its `CancellationToken`. Wrapped in an
+/// `io::Error` of kind `Interrupted`; recover it with
+/// `Cancelled::from_io`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cancelled {
+    /// Phases finished before the cancel, in order.
+    pub phases: Vec<Phase>,
+    /// Time from the start of the probe to the cancel.
+    pub elapsed: Duration,
+}
+
+impl Cancelled {
+    /// The cancellation inside `err`, if that's what it carries.
+    pub fn from_io(err: &io::Error) -> Option<&Cancelled> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "probe cancelled after {:?}", self.elapsed)?;
+        if !self.phases.is_empty() {
+            let names: Vec<&str> = self.phases.iter().map(|p| p.name).collect();
+            write!(f, " (completed: {})", names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Cancelled {}
+
+impl From<Cancelled> for io::Error {
+    fn from(err: Cancelled) -> Self {
+        io::Error::new(io::ErrorKind::Interrupted, err)
+    }
+}
+
+struct PhaseLog {
+    start: Instant,
+    phases: Mutex<Vec<Phase>>,
+}
+
+tokio::task_local! {
+    static PHASES: Arc<PhaseLog>;
+}
+
+/// Append `name` to the running probe's phase log, if it has one.
+pub(crate) fn record_phase(name: &'static str) {
+    let _ = PHASES.try_with(|log| {
+        let at = log.start.elapsed();
+        if let Ok(mut phases) = log.phases.lock() {
+            phases.push(Phase { name, at });
+        }
+    });
+}
+
+/// Run `probe` until it finishes or `cancel` fires. On cancel the
+/// probe future is dropped and the result is a `Cancelled` error
+/// carrying the phases it recorded. A probe that notices the token
+/// itself and fails (to close a connection cleanly first, say) is
+/// reported as `Cancelled` too. This is the default behind
+/// `Pinger::probe_with_cancel`.
+pub async fn cancellable<F>(cancel: &CancellationToken, probe: F) -> Result<Report>
+where
+    F: Future<Output = Result<Report>>,
+{
+    let log = Arc::new(PhaseLog {
+        start: Instant::now(),
+        phases: Mutex::new(Vec::new()),
+    });
+    let cancelled = |log: &PhaseLog| -> io::Error {
+        Cancelled {
+            phases: log.phases.lock().map(|p| p.clone()).unwrap_or_default(),
+            elapsed: log.start.elapsed(),
+        }
+        .into()
+    };
+    if cancel.is_cancelled() {
+        return Err(cancelled(&log));
+    }
+    // `biased` polls the probe first, so one that reacts to the
+    // token gets to run its cleanup before we drop it.
+    tokio::select! {
+        biased;
+        result = PHASES.scope(log.clone(), probe) => match result {
+            Err(_) if cancel.is_cancelled() => Err(cancelled(&log)),
+            result => result,
+        },
+        _ = cancel.cancelled() => Err(cancelled(&log)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn two_phase_probe() -> Result<Report> {
+        record_phase("connect");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        record_phase("handshake");
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        Ok(Report::default())
+    }
+
+    #[tokio::test]
+    async fn cancel_reports_completed_phases() {
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trigger.cancel();
+        });
+        let err = cancellable(&token, two_phase_probe()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        let cancelled = Cancelled::from_io(&err).unwrap();
+        let names: Vec<_> = cancelled.phases.iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["connect", "handshake"]);
+        assert!(cancelled.phases[1].at >= Duration::from_millis(10));
+        assert!(cancelled.elapsed >= Duration::from_millis(50));
+        assert!(err.to_string().ends_with("(completed: connect, handshake)"));
+    }
+
+    #[tokio::test]
+    async fn uncancelled_probe_passes_through() {
+        let token = CancellationToken::new();
+        let report = cancellable(&token, async { Ok(Report::new().with_detail("k", "v")) })
+            .await
+            .unwrap();
+        assert_eq!(report.detail("k"), Some("v"));
+        let err = cancellable(&token, async { Err(io::Error::other("boom")) })
+            .await
+            .unwrap_err();
+        assert!(Cancelled::from_io(&err).is_none());
+    }
+
+    #[tokio::test]
+    async fn already_cancelled_token_skips_the_probe() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = cancellable(&token, async { panic!("probe must not run") })
+            .await
+            .unwrap_err();
+        assert_eq!(Cancelled::from_io(&err).unwrap().phases, vec![]);
+    }
+
+    #[test]
+    fn record_phase_outside_cancellable_is_a_no_op() {
+        record_phase("connect");
+    }
+}
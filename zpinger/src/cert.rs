@@ -0,0 +1,341 @@
+//! X.509 certificate summaries. `TlsPinger` parses the chain the
+//! server presented into `CertInfo`s and hands them back on the
+//! `Report`, so callers can see who issued what and when it expires
+//! without decoding DER themselves.
+//!
+//! The struct is plain data and always compiled (it's part of
+//! `Report`); parsing needs the `tls` feature, which pulls in
+//! `x509-parser`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// What one certificate in a chain says about itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertInfo {
+    /// Subject distinguished name, e.g. `CN=example.com, O=Example`.
+    pub subject: String,
+    /// Issuer distinguished name.
+    pub issuer: String,
+    /// Subject alternative names, typed as OpenSSL prints them:
+    /// `DNS:example.com`, `IP:10.0.0.1`, `email:...`, `URI:...`.
+    pub subject_alt_names: Vec<String>,
+    /// Serial number as colon-separated hex (`04:a1:...`).
+    pub serial: String,
+    pub not_before: SystemTime,
+    pub not_after: SystemTime,
+    /// Public key algorithm: `RSA`, `EC P-256`, `Ed25519`, ...
+    pub key_type: String,
+    /// Key size in bits, when the algorithm has one worth reporting.
+    pub key_bits: Option<u32>,
+    /// Signature algorithm as OpenSSL names it
+    /// (`sha256WithRSAEncryption`, `ecdsa-with-SHA384`).
+    pub signature_algorithm: String,
+    /// Base64 SHA-256 of the SubjectPublicKeyInfo — the value
+    /// `TlsOptions::with_pin_sha256` (`--pin-sha256`) takes.
+    pub spki_sha256: String,
+}
+
+impl CertInfo {
+    /// Whole days from now until `not_after`; negative once expired.
+    pub fn days_until_expiry(&self) -> i64 {
+        self.days_until_expiry_at(SystemTime::now())
+    }
+
+    /// Whole days from `now` until `not_after`, rounded down (a
+    /// certificate expiring in 23 hours has 0 days left, one that
+    /// expired an hour ago has -1).
+    pub fn days_until_expiry_at(&self, now: SystemTime) -> i64 {
+        match self.not_after.duration_since(now) {
+            Ok(left) => (left.as_secs() / SECS_PER_DAY) as i64,
+            Err(past) => -(past.duration().as_secs().div_ceil(SECS_PER_DAY) as i64),
+        }
+    }
+
+    /// Parse a DER-encoded certificate.
+    #[cfg(feature = "tls")]
+    pub fn from_der(der: &[u8]) -> std::io::Result<Self> {
+        parse::from_der(der)
+    }
+}
+
+/// `time` as an RFC 3339 UTC timestamp, `2026-10-19T08:30:00Z` — the
+/// form certificate dates take in `Report::details`. Times before
+/// 1970 are clamped to the epoch.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, rem) = (secs / SECS_PER_DAY, secs % SECS_PER_DAY);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+/// Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Seconds since the epoch to `SystemTime`, clamping pre-1970 dates.
+#[cfg(feature = "tls")]
+fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + std::time::Duration::from_secs(secs.max(0) as u64)
+}
+
+#[cfg(feature = "tls")]
+mod parse {
+    use std::io::{self, Result};
+    use std::net::IpAddr;
+
+    use x509_parser::extensions::GeneralName;
+    use x509_parser::objects::{oid2sn, oid_registry};
+    use x509_parser::oid_registry::{
+        OID_EC_P256, OID_KEY_TYPE_EC_PUBLIC_KEY, OID_NIST_EC_P384, OID_NIST_EC_P521,
+        OID_PKCS1_RSAENCRYPTION, OID_SIG_ED25519, OID_SIG_ED448,
+    };
+    use x509_parser::prelude::{parse_x509_certificate, X509Certificate};
+    use x509_parser::public_key::PublicKey;
+
+    use super::{from_unix, CertInfo};
+    use crate::tls_verify::{format_pin, hash_spki};
+
+    pub(super) fn from_der(der: &[u8]) -> Result<CertInfo> {
+        let (_, cert) = parse_x509_certificate(der).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad certificate: {e}"))
+        })?;
+        let validity = cert.validity();
+        let (key_type, key_bits) = key_summary(&cert);
+        Ok(CertInfo {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            subject_alt_names: subject_alt_names(&cert),
+            serial: cert.raw_serial_as_string(),
+            not_before: from_unix(validity.not_before.timestamp()),
+            not_after: from_unix(validity.not_after.timestamp()),
+            key_type,
+            key_bits,
+            signature_algorithm: oid_name(&cert.signature_algorithm.algorithm),
+            spki_sha256: format_pin(&hash_spki(cert.public_key().raw)),
+        })
+    }
+
+    fn subject_alt_names(cert: &X509Certificate<'_>) -> Vec<String> {
+        let Ok(Some(san)) = cert.subject_alternative_name() else {
+            return Vec::new();
+        };
+        san.value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(format!("DNS:{dns}")),
+                GeneralName::RFC822Name(email) => Some(format!("email:{email}")),
+                GeneralName::URI(uri) => Some(format!("URI:{uri}")),
+                GeneralName::IPAddress(bytes) => ip_from_bytes(bytes).map(|ip| format!("IP:{ip}")),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn ip_from_bytes(bytes: &[u8]) -> Option<IpAddr> {
+        match bytes.len() {
+            4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+            16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+            _ => None,
+        }
+    }
+
+    fn key_summary(cert: &X509Certificate<'_>) -> (String, Option<u32>) {
+        let spki = cert.public_key();
+        let algorithm = &spki.algorithm.algorithm;
+        if *algorithm == OID_PKCS1_RSAENCRYPTION {
+            let bits = match spki.parsed() {
+                Ok(PublicKey::RSA(rsa)) => Some(unsigned_bits(rsa.modulus)),
+                _ => None,
+            };
+            ("RSA".into(), bits)
+        } else if *algorithm == OID_KEY_TYPE_EC_PUBLIC_KEY {
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.as_oid().ok());
+            match curve {
+                Some(c) if c == OID_EC_P256 => ("EC P-256".into(), Some(256)),
+                Some(c) if c == OID_NIST_EC_P384 => ("EC P-384".into(), Some(384)),
+                Some(c) if c == OID_NIST_EC_P521 => ("EC P-521".into(), Some(521)),
+                Some(c) => (format!("EC {}", oid_name(&c)), None),
+                None => ("EC".into(), None),
+            }
+        } else if *algorithm == OID_SIG_ED25519 {
+            ("Ed25519".into(), Some(256))
+        } else if *algorithm == OID_SIG_ED448 {
+            ("Ed448".into(), Some(456))
+        } else {
+            (oid_name(algorithm), None)
+        }
+    }
+
+    /// Bit length of a big-endian unsigned integer, ignoring the
+    /// leading zero byte DER adds to keep it positive.
+    fn unsigned_bits(bytes: &[u8]) -> u32 {
+        let digits = match bytes.iter().position(|&b| b != 0) {
+            Some(start) => &bytes[start..],
+            None => return 0,
+        };
+        digits.len() as u32 * 8 - digits[0].leading_zeros()
+    }
+
+    fn oid_name(oid: &x509_parser::der_parser::oid::Oid<'_>) -> String {
+        oid2sn(oid, oid_registry())
+            .map(str::to_string)
+            .unwrap_or_else(|_| oid.to_id_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn info(not_after: SystemTime) -> CertInfo {
+        CertInfo {
+            subject: "CN=example.com".into(),
+            issuer: "CN=Example CA".into(),
+            subject_alt_names: vec!["DNS:example.com".into()],
+            serial: "01".into(),
+            not_before: UNIX_EPOCH,
+            not_after,
+            key_type: "EC P-256".into(),
+            key_bits: Some(256),
+            signature_algorithm: "ecdsa-with-SHA256".into(),
+            spki_sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn days_until_expiry_rounds_down_and_goes_negative() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000 * SECS_PER_DAY);
+        let day = Duration::from_secs(SECS_PER_DAY);
+        let hour = Duration::from_secs(3600);
+        assert_eq!(info(now + day * 30 + hour).days_until_expiry_at(now), 30);
+        assert_eq!(info(now + hour * 23).days_until_expiry_at(now), 0);
+        assert_eq!(info(now - hour).days_until_expiry_at(now), -1);
+        assert_eq!(info(now - day * 2).days_until_expiry_at(now), -2);
+    }
+
+    #[test]
+    fn formats_rfc3339_utc() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        // 2024-02-29T12:34:56Z, a leap day.
+        let t = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(format_utc(t), "2024-02-29T12:34:56Z");
+    }
+
+    // openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256
+    //   -subj /CN=knockknock.test -addext
+    //   subjectAltName=DNS:knockknock.test,IP:127.0.0.1 -days 365
+    #[cfg(feature = "tls")]
+    #[test]
+    fn parses_certificate_fields() {
+        let der = rustls::pki_types::CertificateDer::from_pem_slice(TEST_CERT.as_bytes()).unwrap();
+        let cert = CertInfo::from_der(&der).unwrap();
+        assert_eq!(cert.subject, "CN=knockknock.test");
+        assert_eq!(cert.issuer, "CN=knockknock.test");
+        assert_eq!(
+            cert.subject_alt_names,
+            vec!["DNS:knockknock.test", "IP:127.0.0.1"]
+        );
+        assert_eq!(cert.key_type, "EC P-256");
+        assert_eq!(cert.key_bits, Some(256));
+        assert_eq!(cert.signature_algorithm, "ecdsa-with-SHA256");
+        assert_eq!(format_utc(cert.not_before), "2026-10-19T06:39:24Z");
+        assert_eq!(format_utc(cert.not_after), "2027-10-19T06:39:24Z");
+        assert!(cert.serial.starts_with("14:2d:99:53"), "{}", cert.serial);
+        // openssl x509 -pubkey -noout | openssl pkey -pubin -outform der
+        //   | openssl dgst -sha256 -binary | base64
+        assert_eq!(
+            cert.spki_sha256,
+            "qlBKawxj1X3x+dRPcAfJhYhLaEhCuAcaikHtaTIP/Jk="
+        );
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn rejects_garbage() {
+        assert!(CertInfo::from_der(b"not a certificate").is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn reports_rsa_key_size() {
+        let der = rustls::pki_types::CertificateDer::from_pem_slice(RSA_CERT.as_bytes()).unwrap();
+        let cert = CertInfo::from_der(&der).unwrap();
+        assert_eq!(cert.subject, "O=Knock Knock, CN=rsa.knockknock.test");
+        assert!(cert.subject_alt_names.is_empty());
+        assert_eq!(cert.key_type, "RSA");
+        assert_eq!(cert.key_bits, Some(2048));
+        assert_eq!(cert.signature_algorithm, "sha256WithRSAEncryption");
+    }
+
+    #[cfg(feature = "tls")]
+    use rustls::pki_types::pem::PemObject;
+
+    #[cfg(feature = "tls")]
+    const TEST_CERT: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBqzCCAVGgAwIBAgIUFC2ZU5idytXt0gJrVSHUQf0oHpAwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPa25vY2trbm9jay50ZXN0MB4XDTI2MTAxOTA2MzkyNFoXDTI3
+MTAxOTA2MzkyNFowGjEYMBYGA1UEAwwPa25vY2trbm9jay50ZXN0MFkwEwYHKoZI
+zj0CAQYIKoZIzj0DAQcDQgAEIa5peKk7kf0nLq4IjBtFp8lN7DVtZx4wZnxyMvW1
+CgsS5ynL3Mtb5fonNRjmikzXUjDHRlJlHdzD/XhxscgVGKN1MHMwHQYDVR0OBBYE
+FCvUkiNBpT69E7oTRB/A0MDUnb1VMB8GA1UdIwQYMBaAFCvUkiNBpT69E7oTRB/A
+0MDUnb1VMA8GA1UdEwEB/wQFMAMBAf8wIAYDVR0RBBkwF4IPa25vY2trbm9jay50
+ZXN0hwR/AAABMAoGCCqGSM49BAMCA0gAMEUCIByQiIDPszxdNHOv7nBVH6Zwma+s
+g5SWbqaR/+pYIBXbAiEAx1Y1Vjpl2jHC6reY6l1Jp9dB13x4StGV5FlEBeB8H4o=
+-----END CERTIFICATE-----
+";
+
+    // openssl req -x509 -newkey rsa:2048
+    //   -subj "/O=Knock Knock/CN=rsa.knockknock.test" -days 30
+    #[cfg(feature = "tls")]
+    const RSA_CERT: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIDSTCCAjGgAwIBAgIUDTHfn+CvEwSyUpVFxP/LmnK/KT8wDQYJKoZIhvcNAQEL
+BQAwNDEUMBIGA1UECgwLS25vY2sgS25vY2sxHDAaBgNVBAMME3JzYS5rbm9ja2tu
+b2NrLnRlc3QwHhcNMjYxMDE5MDYzOTMxWhcNMjYxMTE4MDYzOTMxWjA0MRQwEgYD
+VQQKDAtLbm9jayBLbm9jazEcMBoGA1UEAwwTcnNhLmtub2Nra25vY2sudGVzdDCC
+ASIwDQYJKoZIhvcNAQEBBQADggEPADCCAQoCggEBAMDMqEZ1P+q2KAFBMCom3pHJ
+ayKqQxReg6fNrV1HzaUA5HGYf7hbza/Jvvjg4xABaVm784/d/hDay0wAQ6bqndUd
+CnftaMcKURyzqyEa2TV6nyS6xRfNo5p9wBeFlBOFlUoMC493nUKiwVtqjso8czhe
+9B8H8N2KhVkxIvqYWMzzpe2H6WHvEQyiCD7qM7ufX505cwsr4UO4ZtW3MYn9SMfI
+/p9eajecxQkwjuXoa0x80sSUuwS6LUswgsZHtJetT5imQaj5ANjneNPS1155OUQw
+nTyrlLZ1tAH4OX5WA48xVg2/O3ORgp23ltMZVaeiKPjx2KZu8jZGFJUM5Rwy4JcC
+AwEAAaNTMFEwHQYDVR0OBBYEFOcalYo2pJsXi2xiwh2TorGhemKIMB8GA1UdIwQY
+MBaAFOcalYo2pJsXi2xiwh2TorGhemKIMA8GA1UdEwEB/wQFMAMBAf8wDQYJKoZI
+hvcNAQELBQADggEBAFh/ynCEtrEzHgHmDxTf0FgH2a0zo53KEdv4ox+fH4wRvleq
+lxj55KpKornC3fRNNMtkivSYmnUAnrqov9nyDL+Dnis+FYmpGe2+NhH5Bx8074JB
+OD72AlhCPUBJgLFOM/bjxOyYiyRVGcD3NELbTLKQUc0JZu6DlJhAeEyXyM36ROY+
+Fo8IIxlNwHi3/lLRVC4idqHILjgijNtypE9Y7YTpIrWSxpZi3kiLlpHi+6eds540
+OUqy6QOmPKXvUpvhgR8MzXBQL9OjuI7fiqjUB3t/iZlfyYLNX5QG7XBBz1mxIAWB
+/+aoluhQZkLfY41oD9RGkfgQ4CVwGOxa7PvAA6w=
+-----END CERTIFICATE-----
+";
+}
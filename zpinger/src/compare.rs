@@ -0,0 +1,404 @@
+//! Protocol comparison — one `https://` URL fetched over HTTP/1.1,
+//! HTTP/2 and HTTP/3, side by side, plus what the origin advertises
+//! about HTTP/3: its `Alt-Svc` header (RFC 7838) and its HTTPS DNS
+//! record (RFC 9460).
+//!
+//! The HTTP/1.1 fetch goes first, since that's where a browser learns
+//! `Alt-Svc`; HTTP/3 is then probed on the advertised port (the
+//! record's, failing that, or the URL's). Each protocol is a fresh
+//! connection through the matching pinger, so every `*_ms` includes
+//! its own TCP / QUIC and TLS handshakes. Redirects aren't followed.
+
+use std::io::{self, Result};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rustls::ClientConfig;
+
+use crate::dns::{DnsPinger, RecordType};
+use crate::http::{HttpMethod, HttpPinger, HttpVersion};
+use crate::http3::Http3Pinger;
+use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::tls::TlsOptions;
+use crate::uri::get_uri;
+use crate::util::format_ms;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_PORT: u16 = 443;
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Compares HTTP/1.1, HTTP/2 and HTTP/3 on one URL. Each protocol
+/// passes on a 2xx / 3xx response, like the single-protocol pingers.
+///
+/// `probe` reports `http1_ms` / `http2_ms` / `http3_ms` with the
+/// matching `*_status`, or `*_error` for a protocol that failed;
+/// `alt_svc` (the header from the HTTP/1.1 response) and `https_rr`
+/// (the HTTPS records, looked up through `with_resolver` or the first
+/// `/etc/resolv.conf` nameserver); `h3_advertised` (`alt-svc`,
+/// `https-rr`, both, or `no`); `fastest`; and `failed` listing the
+/// protocols that didn't answer. HTTP/3 advertised but failing adds
+/// a `warning`. The probe only fails when no protocol answered.
+pub struct HttpComparePinger {
+    pub url: String,
+    /// Per protocol, and for the DNS lookup.
+    pub timeout: Duration,
+    /// DNS server for the HTTPS record lookup.
+    pub resolver: Option<String>,
+    tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
+}
+
+impl HttpComparePinger {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            resolver: None,
+            tls_config: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, t: Duration) -> Self {
+        self.timeout = t;
+        self
+    }
+
+    /// Look up the HTTPS record through `server` (`host[:port]`)
+    /// instead of the system's first nameserver.
+    pub fn with_resolver(mut self, server: impl Into<String>) -> Self {
+        self.resolver = Some(server.into());
+        self
+    }
+
+    /// Inject a custom rustls `ClientConfig`, used by all three
+    /// protocols (each sets its own ALPN).
+    pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Shared TLS settings, passed to all three protocols' requests.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    fn http(&self, url: &str, version: HttpVersion) -> HttpPinger {
+        let mut p = HttpPinger::new(HttpMethod::Get, url)
+            .with_timeout(self.timeout)
+            .with_version(version)
+            .with_tls_options(self.tls_options.clone());
+        if let Some(config) = &self.tls_config {
+            p = p.with_tls_config(config.clone());
+        }
+        p
+    }
+
+    fn http3(&self, url: &str) -> Http3Pinger {
+        let mut p = Http3Pinger::new(url)
+            .with_timeout(self.timeout)
+            .with_tls_options(self.tls_options.clone());
+        if let Some(config) = &self.tls_config {
+            p = p.with_tls_config(config.clone());
+        }
+        p
+    }
+
+    /// Rendered HTTPS records for `host`.
+    async fn https_records(&self, host: &str) -> Result<Vec<String>> {
+        let resolver = match &self.resolver {
+            Some(server) => server.clone(),
+            None => system_resolver()?,
+        };
+        let report = DnsPinger::new(resolver, host)
+            .with_record_type(RecordType::Https)
+            .with_timeout(self.timeout)
+            .exchange()
+            .await?;
+        Ok(report.answers)
+    }
+}
+
+#[async_trait]
+impl Pinger for HttpComparePinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let target = Target::parse(&self.url)?;
+
+        let http1 = timed(
+            self.http(&target.url(target.port), HttpVersion::Http1)
+                .probe(),
+        )
+        .await;
+        let alt_svc = http1
+            .result
+            .as_ref()
+            .ok()
+            .and_then(|report| report.header("alt-svc"))
+            .map(str::to_string);
+        let records = self.https_records(&target.host).await;
+        let http2 = timed(
+            self.http(&target.url(target.port), HttpVersion::Http2)
+                .probe(),
+        )
+        .await;
+
+        let from_alt_svc = alt_svc
+            .as_deref()
+            .and_then(|value| alt_svc_h3_port(value, target.port));
+        let from_record = records
+            .as_deref()
+            .ok()
+            .and_then(|answers| https_rr_h3_port(answers, target.port));
+        let h3_port = from_alt_svc.or(from_record).unwrap_or(target.port);
+        let http3 = timed(self.http3(&target.url(h3_port)).probe()).await;
+
+        let outcomes = [
+            ("http1", "HTTP/1.1", &http1),
+            ("http2", "HTTP/2", &http2),
+            ("http3", "HTTP/3", &http3),
+        ];
+        if outcomes.iter().all(|(_, _, o)| o.result.is_err()) {
+            let errors: Vec<_> = outcomes
+                .iter()
+                .filter_map(|(_, name, o)| o.result.as_ref().err().map(|e| format!("{name}: {e}")))
+                .collect();
+            return Err(io::Error::other(format!(
+                "no protocol answered ({})",
+                errors.join("; ")
+            )));
+        }
+
+        let mut report = Report::new();
+        for (key, _, outcome) in &outcomes {
+            report = match &outcome.result {
+                Ok(answer) => {
+                    let report =
+                        report.with_detail(format!("{key}_ms"), format_ms(outcome.elapsed));
+                    match answer.status {
+                        Some(status) => {
+                            report.with_detail(format!("{key}_status"), status.to_string())
+                        }
+                        None => report,
+                    }
+                }
+                Err(err) => report.with_detail(format!("{key}_error"), err.to_string()),
+            };
+        }
+        report = report.with_detail("alt_svc", alt_svc.as_deref().unwrap_or("none"));
+        report = report.with_detail(
+            "https_rr",
+            match &records {
+                Ok(answers) if answers.is_empty() => "none".to_string(),
+                Ok(answers) => answers.join("; "),
+                Err(err) => format!("lookup failed: {err}"),
+            },
+        );
+        let advertised: Vec<_> = [
+            from_alt_svc.map(|_| "alt-svc"),
+            from_record.map(|_| "https-rr"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        report = report.with_detail(
+            "h3_advertised",
+            if advertised.is_empty() {
+                "no".to_string()
+            } else {
+                advertised.join(", ")
+            },
+        );
+        if let Some((_, name, _)) = outcomes
+            .iter()
+            .filter(|(_, _, o)| o.result.is_ok())
+            .min_by_key(|(_, _, o)| o.elapsed)
+        {
+            report = report.with_detail("fastest", *name);
+        }
+        let failed: Vec<_> = outcomes
+            .iter()
+            .filter(|(_, _, o)| o.result.is_err())
+            .map(|(_, name, _)| *name)
+            .collect();
+        if !failed.is_empty() {
+            report = report.with_detail("failed", failed.join(", "));
+        }
+        if !advertised.is_empty() && http3.result.is_err() {
+            report = report.with_detail("warning", "HTTP/3 advertised but failed");
+        }
+        Ok(report)
+    }
+}
+
+/// One protocol's result and how long it took.
+struct Outcome {
+    elapsed: Duration,
+    result: Result<Report>,
+}
+
+async fn timed(probe: impl std::future::Future<Output = Result<Report>>) -> Outcome {
+    let start = Instant::now();
+    let result = probe.await;
+    Outcome {
+        elapsed: start.elapsed(),
+        result,
+    }
+}
+
+/// The URL, split so HTTP/3 can move to another port.
+#[derive(Debug, PartialEq)]
+struct Target {
+    host: String,
+    port: u16,
+    /// Path plus query, `/` when empty.
+    path: String,
+}
+
+impl Target {
+    /// `https://host[:port]/path?query` or schemeless `host[:port]/path`.
+    fn parse(url: &str) -> Result<Self> {
+        let uri = get_uri(url.trim());
+        match uri.scheme.to_ascii_lowercase().as_str() {
+            "" | "https" => {}
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "scheme '{other}' can't be compared: HTTP/3 needs https:// \
+                         (or a schemeless host)"
+                    ),
+                ));
+            }
+        }
+        if uri.domain.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "URL is missing a host",
+            ));
+        }
+        let port = if uri.port > 0 {
+            uri.port as u16
+        } else {
+            DEFAULT_PORT
+        };
+        let mut path = if uri.path.is_empty() {
+            "/".to_string()
+        } else {
+            uri.path.clone()
+        };
+        if !uri.query.is_empty() {
+            path = format!("{path}?{}", uri.query);
+        }
+        Ok(Self {
+            host: uri.domain,
+            port,
+            path,
+        })
+    }
+
+    fn url(&self, port: u16) -> String {
+        format!("https://{}:{port}{}", self.host, self.path)
+    }
+}
+
+/// Port of the first `h3` alternative in an `Alt-Svc` value such as
+/// `h3=":443"; ma=86400, h3-29=":443"`. An alternative on another host
+/// is taken on this one: we compare protocols, not hosts.
+fn alt_svc_h3_port(value: &str, default_port: u16) -> Option<u16> {
+    value.split(',').find_map(|alternative| {
+        let entry = alternative.split(';').next()?.trim();
+        let (protocol, authority) = entry.split_once('=')?;
+        if protocol.trim() != "h3" {
+            return None;
+        }
+        let authority = authority.trim().trim_matches('"');
+        match authority.rsplit_once(':') {
+            Some((_, port)) if !port.is_empty() => port.parse().ok(),
+            _ => Some(default_port),
+        }
+    })
+}
+
+/// Port of the first HTTPS record offering `h3`, from `DnsPinger`'s
+/// rendering (`1 . alpn=h3,h2 port=8443`).
+fn https_rr_h3_port(answers: &[String], default_port: u16) -> Option<u16> {
+    answers.iter().find_map(|answer| {
+        let mut params = answer.split_whitespace();
+        let offers_h3 = params.clone().any(|param| {
+            param
+                .strip_prefix("alpn=")
+                .is_some_and(|ids| ids.split(',').any(|id| id == "h3"))
+        });
+        if !offers_h3 {
+            return None;
+        }
+        Some(
+            params
+                .find_map(|param| param.strip_prefix("port=")?.parse().ok())
+                .unwrap_or(default_port),
+        )
+    })
+}
+
+/// First `nameserver` in `/etc/resolv.conf`.
+fn system_resolver() -> Result<String> {
+    let conf = std::fs::read_to_string(RESOLV_CONF)
+        .map_err(|e| io::Error::new(e.kind(), format!("{RESOLV_CONF}: {e}")))?;
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .map(str::trim)
+        .find(|server| !server.is_empty())
+        .map(|server| match server.contains(':') {
+            true => format!("[{server}]"),
+            false => server.to_string(),
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no nameserver in {RESOLV_CONF}"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_parse_keeps_path_and_defaults_port() {
+        let target = Target::parse("https://example.com/health?full=1").unwrap();
+        assert_eq!(target.url(443), "https://example.com:443/health?full=1");
+        assert_eq!(target.url(8443), "https://example.com:8443/health?full=1");
+        assert_eq!(Target::parse("example.com:8443").unwrap().port, 8443);
+        let err = Target::parse("http://example.com/").unwrap_err();
+        assert!(err.to_string().contains("scheme 'http'"), "{err}");
+    }
+
+    #[test]
+    fn alt_svc_h3_port_picks_first_h3_alternative() {
+        assert_eq!(alt_svc_h3_port(r#"h3=":443"; ma=86400"#, 443), Some(443));
+        assert_eq!(
+            alt_svc_h3_port(r#"h3-29=":8443", h3="alt.example.com:9443""#, 443),
+            Some(9443)
+        );
+        assert_eq!(alt_svc_h3_port(r#"h2=":443""#, 443), None);
+        assert_eq!(alt_svc_h3_port("clear", 443), None);
+    }
+
+    #[test]
+    fn https_rr_h3_port_reads_alpn_and_port() {
+        let answers = vec!["1 . alpn=h2".to_string()];
+        assert_eq!(https_rr_h3_port(&answers, 443), None);
+        let answers = vec!["1 . alpn=h3,h2".to_string()];
+        assert_eq!(https_rr_h3_port(&answers, 443), Some(443));
+        let answers = vec!["1 . alpn=h2,h3 port=8443 ipv4hint=192.0.2.1".to_string()];
+        assert_eq!(https_rr_h3_port(&answers, 443), Some(8443));
+    }
+}
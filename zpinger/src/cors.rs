@@ -0,0 +1,196 @@
+//! CORS preflight checks for `HttpPinger` — the request headers a
+//! browser sends ahead of a cross-origin request, and the checks it
+//! runs on the answer (Fetch standard, "CORS-preflight fetch").
+
+/// A CORS preflight: the `Origin` a page would send from, plus the
+/// method and headers its real request would use. Pass it to
+/// `HttpPinger::with_cors`.
+///
+/// The answer passes when `Access-Control-Allow-Origin` is the origin
+/// or `*`, `Access-Control-Allow-Methods` lists the method (GET, HEAD
+/// and POST are always allowed) and `Access-Control-Allow-Headers`
+/// lists every header. `*` allows any method or header except
+/// `Authorization`, as in a browser's uncredentialed request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorsPreflight {
+    pub origin: String,
+    /// Sent as `Access-Control-Request-Method`.
+    pub request_method: Option<String>,
+    /// Sent as `Access-Control-Request-Headers`.
+    pub request_headers: Vec<String>,
+}
+
+impl CorsPreflight {
+    pub fn new(origin: impl Into<String>) -> Self {
+        Self {
+            origin: origin.into(),
+            request_method: None,
+            request_headers: Vec::new(),
+        }
+    }
+
+    pub fn with_request_method(mut self, method: impl Into<String>) -> Self {
+        self.request_method = Some(method.into());
+        self
+    }
+
+    pub fn with_request_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.request_headers = headers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// `Origin` and the `Access-Control-Request-*` headers.
+    pub(crate) fn request_headers(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        let method = self
+            .request_method
+            .as_ref()
+            .map(|method| ("Access-Control-Request-Method".to_string(), method.clone()));
+        let headers = (!self.request_headers.is_empty()).then(|| {
+            (
+                "Access-Control-Request-Headers".to_string(),
+                self.request_headers.join(", ").to_ascii_lowercase(),
+            )
+        });
+        std::iter::once(("Origin".to_string(), self.origin.clone()))
+            .chain(method)
+            .chain(headers)
+    }
+
+    /// What the response's `Access-Control-Allow-*` headers don't
+    /// allow, one failure per line; empty when the preflight passes.
+    pub(crate) fn check(&self, headers: &[(String, String)]) -> Vec<String> {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.trim())
+        };
+        let list = |name: &str| -> Vec<&str> {
+            header(name)
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut failures = Vec::new();
+        match header("access-control-allow-origin") {
+            None => failures.push("no Access-Control-Allow-Origin".to_string()),
+            Some(allowed) if allowed != "*" && allowed != self.origin => failures.push(format!(
+                "Access-Control-Allow-Origin {allowed} doesn't match {}",
+                self.origin
+            )),
+            Some(_) => {}
+        }
+
+        if let Some(method) = &self.request_method {
+            let allowed = list("access-control-allow-methods");
+            let safelisted = ["GET", "HEAD", "POST"].contains(&method.as_str());
+            if !safelisted && !allowed.iter().any(|m| *m == method || *m == "*") {
+                failures.push(format!(
+                    "Access-Control-Allow-Methods doesn't allow {method}"
+                ));
+            }
+        }
+
+        let allowed = list("access-control-allow-headers");
+        for name in &self.request_headers {
+            let listed = allowed.iter().any(|h| h.eq_ignore_ascii_case(name));
+            let wildcard = allowed.contains(&"*") && !name.eq_ignore_ascii_case("authorization");
+            if !listed && !wildcard {
+                failures.push(format!("Access-Control-Allow-Headers doesn't allow {name}"));
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn request_headers_carry_origin_method_and_headers() {
+        let preflight = CorsPreflight::new("https://app.example.com")
+            .with_request_method("PUT")
+            .with_request_headers(["X-Trace", "Content-Type"]);
+        let sent: Vec<_> = preflight.request_headers().collect();
+        assert_eq!(
+            sent,
+            headers(&[
+                ("Origin", "https://app.example.com"),
+                ("Access-Control-Request-Method", "PUT"),
+                ("Access-Control-Request-Headers", "x-trace, content-type"),
+            ])
+        );
+        assert_eq!(CorsPreflight::new("https://a").request_headers().count(), 1);
+    }
+
+    #[test]
+    fn check_accepts_listed_and_wildcard_answers() {
+        let preflight = CorsPreflight::new("https://app.example.com")
+            .with_request_method("DELETE")
+            .with_request_headers(["X-Trace"]);
+        let listed = headers(&[
+            ("Access-Control-Allow-Origin", "https://app.example.com"),
+            ("Access-Control-Allow-Methods", "GET, DELETE"),
+            ("Access-Control-Allow-Headers", "x-trace"),
+        ]);
+        assert!(preflight.check(&listed).is_empty());
+        let wildcard = headers(&[
+            ("access-control-allow-origin", "*"),
+            ("access-control-allow-methods", "*"),
+            ("access-control-allow-headers", "*"),
+        ]);
+        assert!(preflight.check(&wildcard).is_empty());
+        // Safelisted methods need no Access-Control-Allow-Methods.
+        let get = CorsPreflight::new("https://a").with_request_method("GET");
+        assert!(get
+            .check(&headers(&[("Access-Control-Allow-Origin", "https://a")]))
+            .is_empty());
+    }
+
+    #[test]
+    fn check_reports_each_refusal() {
+        let preflight = CorsPreflight::new("https://app.example.com")
+            .with_request_method("DELETE")
+            .with_request_headers(["Authorization", "X-Trace"]);
+        let answer = headers(&[
+            ("Access-Control-Allow-Origin", "https://other.example.com"),
+            ("Access-Control-Allow-Methods", "GET, POST"),
+            ("Access-Control-Allow-Headers", "*"),
+        ]);
+        assert_eq!(
+            preflight.check(&answer),
+            [
+                "Access-Control-Allow-Origin https://other.example.com doesn't match \
+                 https://app.example.com",
+                "Access-Control-Allow-Methods doesn't allow DELETE",
+                "Access-Control-Allow-Headers doesn't allow Authorization",
+            ]
+        );
+        assert_eq!(
+            preflight.check(&[]),
+            [
+                "no Access-Control-Allow-Origin",
+                "Access-Control-Allow-Methods doesn't allow DELETE",
+                "Access-Control-Allow-Headers doesn't allow Authorization",
+                "Access-Control-Allow-Headers doesn't allow X-Trace",
+            ]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! TLS handshake failure diagnostics. rustls reports why a handshake
+//! failed precisely, but as one line of error text; `TlsFailure`
+//! names the cause, keeps the chain the server presented (captured
+//! by the verifier before it rejected it) and knows the usual fix.
+//!
+//! Like `AssertionError`, a `TlsFailure` travels inside the
+//! `io::Error` — with the original error's kind and text — and is
+//! recovered with `TlsFailure::from_io`.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use rustls::{AlertDescription, CertificateError, InvalidMessage, PeerIncompatible};
+
+use crate::cert::CertInfo;
+use crate::tls_verify::PinMismatch;
+
+/// Why a TLS handshake failed, as far as the client can tell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsFailureCause {
+    /// A certificate in the chain is past its `notAfter`.
+    Expired,
+    /// A certificate in the chain is before its `notBefore`.
+    NotYetValid,
+    /// The leaf doesn't cover the host name asked for.
+    HostnameMismatch,
+    /// The server sent its leaf without the intermediates that link
+    /// it to a trusted root.
+    MissingIntermediate,
+    /// The leaf is self-signed and not trusted.
+    SelfSigned,
+    /// The chain ends at a CA this client doesn't trust.
+    UnknownCa,
+    /// A certificate in the chain has been revoked.
+    Revoked,
+    /// A signature in the chain doesn't verify.
+    BadSignature,
+    /// No certificate in the chain matches a configured pin.
+    PinMismatch,
+    /// The server answered with something that isn't TLS —
+    /// typically plaintext on the TLS port.
+    NotTls,
+    /// No protocol version in common (the server speaks only TLS
+    /// 1.0 / 1.1, or the client's versions are restricted).
+    ProtocolVersion,
+    /// No cipher suite or key-exchange group in common.
+    NoSharedCipher,
+    /// The server rejected the client certificate, or wanted one.
+    ClientCertificate,
+    /// The server doesn't serve the SNI name sent.
+    UnrecognisedName,
+    /// The server aborted with a generic `handshake_failure` alert.
+    HandshakeFailure,
+    /// The server hung up mid-handshake.
+    ConnectionClosed,
+    /// Anything else; the message has the details.
+    Other,
+}
+
+impl TlsFailureCause {
+    /// The snake_case name used in reports (`hostname_mismatch`).
+    pub fn name(self) -> &'static str {
+        match self {
+            TlsFailureCause::Expired => "expired",
+            TlsFailureCause::NotYetValid => "not_yet_valid",
+            TlsFailureCause::HostnameMismatch => "hostname_mismatch",
+            TlsFailureCause::MissingIntermediate => "missing_intermediate",
+            TlsFailureCause::SelfSigned => "self_signed",
+            TlsFailureCause::UnknownCa => "unknown_ca",
+            TlsFailureCause::Revoked => "revoked",
+            TlsFailureCause::BadSignature => "bad_signature",
+            TlsFailureCause::PinMismatch => "pin_mismatch",
+            TlsFailureCause::NotTls => "not_tls",
+            TlsFailureCause::ProtocolVersion => "protocol_version",
+            TlsFailureCause::NoSharedCipher => "no_shared_cipher",
+            TlsFailureCause::ClientCertificate => "client_certificate",
+            TlsFailureCause::UnrecognisedName => "unrecognised_name",
+            TlsFailureCause::HandshakeFailure => "handshake_failure",
+            TlsFailureCause::ConnectionClosed => "connection_closed",
+            TlsFailureCause::Other => "other",
+        }
+    }
+
+    /// The likely fix, in a sentence. `None` for `Other`.
+    pub fn hint(self) -> Option<&'static str> {
+        Some(match self {
+            TlsFailureCause::Expired => {
+                "renew the certificate and make sure the server reloaded the new one"
+            }
+            TlsFailureCause::NotYetValid => {
+                "check the clocks on this machine and the issuer; the certificate starts in the future"
+            }
+            TlsFailureCause::HostnameMismatch => {
+                "add this host name to the certificate's SANs, or connect by a name it covers"
+            }
+            TlsFailureCause::MissingIntermediate => {
+                "configure the server with the full chain (leaf plus intermediates, e.g. fullchain.pem)"
+            }
+            TlsFailureCause::SelfSigned => {
+                "trust the certificate explicitly (CA file or key pin), or issue one from a CA"
+            }
+            TlsFailureCause::UnknownCa => {
+                "trust the issuing CA explicitly if it's private, or check the chain the server sends"
+            }
+            TlsFailureCause::Revoked => "replace the revoked certificate",
+            TlsFailureCause::BadSignature => {
+                "the chain is corrupt or mixes certificates from different keys; redeploy it"
+            }
+            TlsFailureCause::PinMismatch => {
+                "the server's key changed; update the pin if the rotation was planned"
+            }
+            TlsFailureCause::NotTls => {
+                "the port isn't speaking TLS; check the port, or whether it needs STARTTLS"
+            }
+            TlsFailureCause::ProtocolVersion => {
+                "enable TLS 1.2 or 1.3 on the server (a TLS scan shows what it accepts)"
+            }
+            TlsFailureCause::NoSharedCipher => {
+                "enable modern ECDHE + AEAD suites on the server (a TLS scan shows what it accepts)"
+            }
+            TlsFailureCause::ClientCertificate => {
+                "present a client certificate the server's CA issued"
+            }
+            TlsFailureCause::UnrecognisedName => {
+                "check the host name, or override the SNI name sent"
+            }
+            TlsFailureCause::HandshakeFailure => {
+                "usually no shared cipher suite or a missing client certificate; a TLS scan narrows it down"
+            }
+            TlsFailureCause::ConnectionClosed => {
+                "the server hung up: often a plaintext-only service, a firewall, or an SNI name it doesn't route"
+            }
+            TlsFailureCause::Other => return None,
+        })
+    }
+}
+
+impl fmt::Display for TlsFailureCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A failed TLS handshake, classified. `certificates` is the chain
+/// the server presented, leaf first — empty when the handshake
+/// failed before the certificate, or when the verifier couldn't be
+/// wrapped (`with_tls_config` without trust settings).
+#[derive(Debug)]
+pub struct TlsFailure {
+    pub cause: TlsFailureCause,
+    /// The underlying error's text.
+    pub message: String,
+    pub certificates: Vec<CertInfo>,
+}
+
+impl TlsFailure {
+    /// The classified failure inside `err`, if that's what it carries.
+    pub fn from_io(err: &io::Error) -> Option<&TlsFailure> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    /// Classify a handshake error; `chain` is what the server
+    /// presented (DER, leaf first). Errors that aren't the
+    /// handshake's — timeouts, cancellation — pass through.
+    pub(crate) fn classify(err: io::Error, chain: &[Vec<u8>]) -> io::Error {
+        let kind = err.kind();
+        if matches!(kind, io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) {
+            return err;
+        }
+        let certificates: Vec<CertInfo> = chain
+            .iter()
+            .filter_map(|der| CertInfo::from_der(der).ok())
+            .collect();
+        let cause = match err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<rustls::Error>())
+        {
+            Some(rustls) => cause_of(rustls, &certificates),
+            None => match kind {
+                io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted => TlsFailureCause::ConnectionClosed,
+                _ => TlsFailureCause::Other,
+            },
+        };
+        let failure = TlsFailure {
+            cause,
+            message: err.to_string(),
+            certificates,
+        };
+        io::Error::new(kind, failure)
+    }
+}
+
+fn cause_of(err: &rustls::Error, chain: &[CertInfo]) -> TlsFailureCause {
+    use TlsFailureCause as C;
+    match err {
+        rustls::Error::InvalidCertificate(cert) => match cert {
+            CertificateError::Expired | CertificateError::ExpiredContext { .. } => C::Expired,
+            CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+                C::NotYetValid
+            }
+            CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => {
+                C::HostnameMismatch
+            }
+            CertificateError::UnknownIssuer => match chain {
+                [leaf, ..] if leaf.subject == leaf.issuer => C::SelfSigned,
+                [_] => C::MissingIntermediate,
+                _ => C::UnknownCa,
+            },
+            CertificateError::Revoked => C::Revoked,
+            CertificateError::BadSignature => C::BadSignature,
+            _ => C::Other,
+        },
+        rustls::Error::Other(other) if other.0.is::<PinMismatch>() => C::PinMismatch,
+        rustls::Error::InvalidMessage(
+            InvalidMessage::InvalidContentType | InvalidMessage::UnknownProtocolVersion,
+        ) => C::NotTls,
+        rustls::Error::PeerIncompatible(incompatible) => match incompatible {
+            PeerIncompatible::ServerDoesNotSupportTls12Or13
+            | PeerIncompatible::SupportedVersionsExtensionRequired => C::ProtocolVersion,
+            PeerIncompatible::NoCipherSuitesInCommon | PeerIncompatible::NoKxGroupsInCommon => {
+                C::NoSharedCipher
+            }
+            _ => C::Other,
+        },
+        rustls::Error::AlertReceived(alert) => match alert {
+            AlertDescription::ProtocolVersion => C::ProtocolVersion,
+            AlertDescription::InsufficientSecurity => C::NoSharedCipher,
+            AlertDescription::CertificateRequired
+            | AlertDescription::BadCertificate
+            | AlertDescription::UnknownCA
+            | AlertDescription::CertificateUnknown => C::ClientCertificate,
+            AlertDescription::UnrecognisedName => C::UnrecognisedName,
+            AlertDescription::HandshakeFailure => C::HandshakeFailure,
+            _ => C::Other,
+        },
+        _ => C::Other,
+    }
+}
+
+impl fmt::Display for TlsFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TLS handshake failed ({}): {}", self.cause, self.message)
+    }
+}
+
+impl Error for TlsFailure {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(err: rustls::Error) -> TlsFailureCause {
+        let err = TlsFailure::classify(io::Error::new(io::ErrorKind::InvalidData, err), &[]);
+        TlsFailure::from_io(&err).unwrap().cause
+    }
+
+    #[test]
+    fn classifies_rustls_errors() {
+        assert_eq!(
+            classify(rustls::Error::InvalidCertificate(CertificateError::Expired)),
+            TlsFailureCause::Expired
+        );
+        assert_eq!(
+            classify(rustls::Error::InvalidMessage(
+                InvalidMessage::InvalidContentType
+            )),
+            TlsFailureCause::NotTls
+        );
+        assert_eq!(
+            classify(rustls::Error::AlertReceived(
+                AlertDescription::ProtocolVersion
+            )),
+            TlsFailureCause::ProtocolVersion
+        );
+        // Without the chain, an unknown issuer can't be narrowed down.
+        assert_eq!(
+            classify(rustls::Error::InvalidCertificate(
+                CertificateError::UnknownIssuer
+            )),
+            TlsFailureCause::UnknownCa
+        );
+    }
+
+    #[test]
+    fn timeouts_pass_through() {
+        let err = TlsFailure::classify(io::Error::from(io::ErrorKind::TimedOut), &[]);
+        assert!(TlsFailure::from_io(&err).is_none());
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
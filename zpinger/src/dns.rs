@@ -1,10 +1,13 @@
 use std::io::{self, Result};
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use tokio::net::UdpSocket;
 
+use crate::observe;
 use crate::pinger::Pinger;
+use crate::report::Report;
 use crate::uri::get_uri;
 use crate::util::with_timeout;
 
@@ -15,6 +18,7 @@ const BUF_SIZE: usize = 512;
 /// Subset of DNS resource-record TYPE codes (RFC 1035 + extensions).
 /// Only the ones a "speed test" CLI is likely to want; not exhaustive.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum RecordType {
     A,
     Aaaa,
@@ -22,6 +26,9 @@ pub enum RecordType {
     Mx,
     Ns,
     Txt,
+    /// Service binding for HTTPS origins (RFC 9460): ALPN ids, port
+    /// and address hints, e.g. to advertise HTTP/3.
+    Https,
 }
 
 impl RecordType {
@@ -33,6 +40,7 @@ impl RecordType {
             RecordType::Mx => 15,
             RecordType::Txt => 16,
             RecordType::Aaaa => 28,
+            RecordType::Https => 65,
         }
     }
 }
@@ -43,7 +51,8 @@ impl RecordType {
 /// validated only structurally (matching ID, response bit set, RCODE
 /// = NoError). Whether the answer section carries useful records is
 /// not checked — this is a "did the server respond" probe, not a
-/// resolver.
+/// resolver. `probe` does report the answers (and the RCODE as
+/// `status`) so an `Expectation` can check them.
 pub struct DnsPinger {
     pub server: String,
     pub query: String,
@@ -75,6 +84,16 @@ impl DnsPinger {
 #[async_trait]
 impl Pinger for DnsPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let report = self.exchange().await?;
+        check_rcode(&report)?;
+        Ok(report)
+    }
+
+    async fn exchange(&self) -> Result<Report> {
         if self.query.is_empty() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -89,11 +108,13 @@ impl Pinger for DnsPinger {
         with_timeout(self.timeout, async move {
             let socket = UdpSocket::bind("0.0.0.0:0").await?;
             socket.connect(&endpoint).await?;
-            socket.send(&request).await?;
+            observe::udp_connected(&socket);
+            observe::sent(socket.send(&request).await?);
 
             let mut buf = [0u8; BUF_SIZE];
             let n = socket.recv(&mut buf).await?;
-            validate_response(&buf[..n], &request, id)
+            observe::received(n);
+            parse_response(&buf[..n], &request, id)
         })
         .await
     }
@@ -174,7 +195,9 @@ fn build_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
     Ok(packet)
 }
 
-fn validate_response(buf: &[u8], request: &[u8], expected_id: u16) -> Result<()> {
+/// Structural checks plus answer extraction. The RCODE goes into
+/// `status` unjudged; `check_rcode` applies the NoError rule.
+fn parse_response(buf: &[u8], request: &[u8], expected_id: u16) -> Result<Report> {
     if buf.len() < 12 {
         return Err(io::Error::other("DNS response shorter than header"));
     }
@@ -192,11 +215,6 @@ fn validate_response(buf: &[u8], request: &[u8], expected_id: u16) -> Result<()>
         ));
     }
     let rcode = flags & 0x000F;
-    if rcode != 0 {
-        return Err(io::Error::other(format!(
-            "DNS server returned RCODE {rcode} (non-zero = error)"
-        )));
-    }
 
     // RFC 1035 §4.1.2: the response repeats the question section
     // verbatim. Servers can't apply name compression at the start of
@@ -220,7 +238,160 @@ fn validate_response(buf: &[u8], request: &[u8], expected_id: u16) -> Result<()>
             "DNS response question section does not match the query",
         ));
     }
-    Ok(())
+    let ancount = u16::from_be_bytes([buf[6], buf[7]]);
+    Ok(Report {
+        status: Some(rcode),
+        answers: parse_answers(buf, 12 + question.len(), ancount),
+        ..Report::default()
+    })
+}
+
+fn check_rcode(report: &Report) -> Result<()> {
+    match report.status {
+        Some(0) => Ok(()),
+        rcode => Err(io::Error::other(format!(
+            "DNS server returned RCODE {} (non-zero = error)",
+            rcode.unwrap_or_default()
+        ))),
+    }
+}
+
+/// Render up to `count` answer records starting at `pos`. Best
+/// effort: a truncated (TC) or malformed record ends the list rather
+/// than failing the probe, since the exchange itself succeeded.
+fn parse_answers(buf: &[u8], mut pos: usize, count: u16) -> Vec<String> {
+    let mut answers = Vec::new();
+    for _ in 0..count {
+        let Some((_, after_name)) = read_name(buf, pos) else {
+            break;
+        };
+        let Some(fixed) = buf.get(after_name..after_name + 10) else {
+            break;
+        };
+        let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let rdlength = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        let rdata_start = after_name + 10;
+        let Some(rdata) = buf.get(rdata_start..rdata_start + rdlength) else {
+            break;
+        };
+        if let Some(text) = render_rdata(buf, rtype, rdata_start, rdata) {
+            answers.push(text);
+        }
+        pos = rdata_start + rdlength;
+    }
+    answers
+}
+
+/// Text form of one record's RDATA, following zone-file conventions
+/// for the types `RecordType` can ask for. `None` for types we don't
+/// render (OPT, RRSIG, ...).
+fn render_rdata(buf: &[u8], rtype: u16, start: usize, rdata: &[u8]) -> Option<String> {
+    match rtype {
+        1 => <[u8; 4]>::try_from(rdata)
+            .ok()
+            .map(|b| Ipv4Addr::from(b).to_string()),
+        28 => <[u8; 16]>::try_from(rdata)
+            .ok()
+            .map(|b| Ipv6Addr::from(b).to_string()),
+        2 | 5 => read_name(buf, start).map(|(name, _)| name),
+        15 if rdata.len() > 2 => {
+            let preference = u16::from_be_bytes([rdata[0], rdata[1]]);
+            read_name(buf, start + 2).map(|(name, _)| format!("{preference} {name}"))
+        }
+        16 => {
+            let mut text = String::new();
+            let mut rest = rdata;
+            while let Some((&len, tail)) = rest.split_first() {
+                let chunk = tail.get(..len as usize)?;
+                text.push_str(&String::from_utf8_lossy(chunk));
+                rest = &tail[len as usize..];
+            }
+            Some(text)
+        }
+        65 if rdata.len() > 2 => {
+            let priority = u16::from_be_bytes([rdata[0], rdata[1]]);
+            let (target, params_start) = read_name(buf, start + 2)?;
+            let mut text = format!("{priority} {target}");
+            let mut params = buf.get(params_start..start + rdata.len())?;
+            while let [k0, k1, l0, l1, rest @ ..] = params {
+                let key = u16::from_be_bytes([*k0, *k1]);
+                let len = u16::from_be_bytes([*l0, *l1]) as usize;
+                let value = rest.get(..len)?;
+                text.push(' ');
+                text.push_str(&render_svc_param(key, value));
+                params = &rest[len..];
+            }
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+/// One SvcParam of an HTTPS record in presentation form
+/// (`alpn=h3,h2`, `port=8443`); keys without a text form are shown
+/// as `keyN`.
+fn render_svc_param(key: u16, value: &[u8]) -> String {
+    let addresses = |size: usize| -> Vec<String> {
+        value
+            .chunks_exact(size)
+            .map(|b| match size {
+                4 => Ipv4Addr::from(<[u8; 4]>::try_from(b).unwrap()).to_string(),
+                _ => Ipv6Addr::from(<[u8; 16]>::try_from(b).unwrap()).to_string(),
+            })
+            .collect()
+    };
+    match key {
+        1 => {
+            let mut ids = Vec::new();
+            let mut rest = value;
+            while let Some((&len, tail)) = rest.split_first() {
+                let Some(id) = tail.get(..len as usize) else {
+                    break;
+                };
+                ids.push(String::from_utf8_lossy(id).into_owned());
+                rest = &tail[len as usize..];
+            }
+            format!("alpn={}", ids.join(","))
+        }
+        2 => "no-default-alpn".to_string(),
+        3 if value.len() == 2 => format!("port={}", u16::from_be_bytes([value[0], value[1]])),
+        4 => format!("ipv4hint={}", addresses(4).join(",")),
+        5 => "ech".to_string(),
+        6 => format!("ipv6hint={}", addresses(16).join(",")),
+        other => format!("key{other}"),
+    }
+}
+
+/// Decode a possibly-compressed name at `pos`. Returns the dotted
+/// name (with trailing `.`) and the offset just past it in the
+/// original record. Pointer chains are capped so a malicious loop
+/// can't spin.
+fn read_name(buf: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    for _ in 0..128 {
+        let len = *buf.get(pos)?;
+        match len {
+            0 => {
+                if name.is_empty() {
+                    name.push('.');
+                }
+                return Some((name, end.unwrap_or(pos + 1)));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let target = u16::from_be_bytes([l & 0x3F, *buf.get(pos + 1)?]) as usize;
+                end.get_or_insert(pos + 2);
+                pos = target;
+            }
+            l => {
+                let label = buf.get(pos + 1..pos + 1 + l as usize)?;
+                name.push_str(&String::from_utf8_lossy(label));
+                name.push('.');
+                pos += 1 + l as usize;
+            }
+        }
+    }
+    None
 }
 
 #[cfg(test)]
@@ -276,67 +447,152 @@ mod tests {
     }
 
     #[test]
-    fn validate_response_rejects_short_buffer() {
+    fn parse_response_rejects_short_buffer() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
-        assert!(validate_response(&[0u8; 5], &request, 0).is_err());
+        assert!(parse_response(&[0u8; 5], &request, 0).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_id_mismatch() {
+    fn parse_response_rejects_id_mismatch() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let mut buf = well_formed_response(&request, 0xBEEF);
         buf[2..4].copy_from_slice(&0x8000u16.to_be_bytes());
-        assert!(validate_response(&buf, &request, 0xDEAD).is_err());
+        assert!(parse_response(&buf, &request, 0xDEAD).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_query_qr() {
+    fn parse_response_rejects_query_qr() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let mut buf = well_formed_response(&request, 0xDEAD);
         buf[2..4].copy_from_slice(&0u16.to_be_bytes()); // QR = 0
-        assert!(validate_response(&buf, &request, 0xDEAD).is_err());
+        assert!(parse_response(&buf, &request, 0xDEAD).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_nonzero_rcode() {
+    fn check_rcode_rejects_nonzero_rcode() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let mut buf = well_formed_response(&request, 0xDEAD);
         buf[2..4].copy_from_slice(&0x8003u16.to_be_bytes()); // QR=1, RCODE=3
-        assert!(validate_response(&buf, &request, 0xDEAD).is_err());
+        let report = parse_response(&buf, &request, 0xDEAD).unwrap();
+        assert_eq!(report.status, Some(3));
+        assert!(check_rcode(&report).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_question_mismatch() {
+    fn parse_response_rejects_question_mismatch() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let mut buf = well_formed_response(&request, 0xDEAD);
         // flip a byte inside the question section (qname's first label)
         buf[13] ^= 0xFF;
-        assert!(validate_response(&buf, &request, 0xDEAD).is_err());
+        assert!(parse_response(&buf, &request, 0xDEAD).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_zero_qdcount() {
+    fn parse_response_rejects_zero_qdcount() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let mut buf = well_formed_response(&request, 0xDEAD);
         buf[4..6].copy_from_slice(&0u16.to_be_bytes()); // QDCOUNT = 0
-        assert!(validate_response(&buf, &request, 0xDEAD).is_err());
+        assert!(parse_response(&buf, &request, 0xDEAD).is_err());
     }
 
     #[test]
-    fn validate_response_rejects_truncated_question() {
+    fn parse_response_rejects_truncated_question() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let buf = well_formed_response(&request, 0xDEAD);
         // chop off the qtype/qclass tail of the response so the
         // question section is incomplete.
         let cut = &buf[..buf.len() - 2];
-        assert!(validate_response(cut, &request, 0xDEAD).is_err());
+        assert!(parse_response(cut, &request, 0xDEAD).is_err());
     }
 
     #[test]
-    fn validate_response_accepts_well_formed() {
+    fn parse_response_accepts_well_formed() {
         let request = build_query(0xDEAD, "a.b", 1).unwrap();
         let buf = well_formed_response(&request, 0xDEAD);
-        validate_response(&buf, &request, 0xDEAD).unwrap();
+        let report = parse_response(&buf, &request, 0xDEAD).unwrap();
+        check_rcode(&report).unwrap();
+    }
+
+    /// Append one answer record whose owner name is a pointer back
+    /// to the question name at offset 12.
+    fn push_answer(buf: &mut Vec<u8>, rtype: u16, rdata: &[u8]) {
+        let ancount = u16::from_be_bytes([buf[6], buf[7]]) + 1;
+        buf[6..8].copy_from_slice(&ancount.to_be_bytes());
+        buf.extend_from_slice(&[0xC0, 12]);
+        buf.extend_from_slice(&rtype.to_be_bytes());
+        buf.extend_from_slice(&1u16.to_be_bytes()); // IN
+        buf.extend_from_slice(&300u32.to_be_bytes()); // TTL
+        buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        buf.extend_from_slice(rdata);
+    }
+
+    #[test]
+    fn parse_response_reports_answers() {
+        let request = build_query(0xDEAD, "a.b", 1).unwrap();
+        let mut buf = well_formed_response(&request, 0xDEAD);
+        push_answer(&mut buf, 1, &[10, 0, 0, 1]);
+        push_answer(&mut buf, 5, &[0xC0, 12]); // CNAME → a.b.
+        push_answer(&mut buf, 15, &[0, 10, 2, b'm', b'x', 0xC0, 12]);
+        push_answer(&mut buf, 16, b"\x05hello\x06 world");
+        push_answer(
+            &mut buf,
+            28,
+            &[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        );
+        push_answer(&mut buf, 46, &[1, 2, 3]); // RRSIG: not rendered
+        let report = parse_response(&buf, &request, 0xDEAD).unwrap();
+        assert_eq!(report.status, Some(0));
+        assert_eq!(
+            report.answers,
+            vec![
+                "10.0.0.1",
+                "a.b.",
+                "10 mx.a.b.",
+                "hello world",
+                "2001:db8::1"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_response_keeps_nonzero_rcode_as_status() {
+        let request = build_query(0xDEAD, "a.b", 1).unwrap();
+        let mut buf = well_formed_response(&request, 0xDEAD);
+        buf[2..4].copy_from_slice(&0x8003u16.to_be_bytes()); // NXDOMAIN
+        let report = parse_response(&buf, &request, 0xDEAD).unwrap();
+        assert_eq!(report.status, Some(3));
+        assert!(report.answers.is_empty());
+    }
+
+    #[test]
+    fn render_rdata_https_record() {
+        // priority 1, target ".", alpn h3,h2, port 8443, ipv4hint.
+        let mut rdata = vec![0, 1, 0];
+        rdata.extend_from_slice(&[0, 1, 0, 6, 2, b'h', b'3', 2, b'h', b'2']);
+        rdata.extend_from_slice(&[0, 3, 0, 2, 0x20, 0xfb]);
+        rdata.extend_from_slice(&[0, 4, 0, 4, 192, 0, 2, 1]);
+        rdata.extend_from_slice(&[0, 9, 0, 0]);
+        assert_eq!(
+            render_rdata(&rdata, 65, 0, &rdata).as_deref(),
+            Some("1 . alpn=h3,h2 port=8443 ipv4hint=192.0.2.1 key9")
+        );
+    }
+
+    #[test]
+    fn parse_answers_stops_at_truncated_record() {
+        let request = build_query(0xDEAD, "a.b", 1).unwrap();
+        let mut buf = well_formed_response(&request, 0xDEAD);
+        push_answer(&mut buf, 1, &[10, 0, 0, 1]);
+        push_answer(&mut buf, 1, &[10, 0, 0, 2]);
+        buf.truncate(buf.len() - 2);
+        let report = parse_response(&buf, &request, 0xDEAD).unwrap();
+        assert_eq!(report.answers, vec!["10.0.0.1"]);
+    }
+
+    #[test]
+    fn read_name_rejects_pointer_loops() {
+        let buf = [0xC0, 0x00];
+        assert!(read_name(&buf, 0).is_none());
     }
 
     #[test]
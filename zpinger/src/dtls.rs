@@ -0,0 +1,298 @@
+//! DTLS pinger — measures the time to complete a DTLS 1.2 handshake
+//! over UDP, the way `TlsPinger` does for TLS over TCP. CoAP over
+//! DTLS (`coaps://`, port 5684) and WebRTC media endpoints are the
+//! usual targets.
+//!
+//! Built on [`webrtc-dtls`](https://crates.io/crates/webrtc-dtls), a
+//! pure-Rust stack that retransmits flights on loss and handles the
+//! server's HelloVerifyRequest cookie exchange itself. Two modes:
+//!
+//! - **Certificate** (default): ECDHE suites, the server's chain is
+//!   checked by the same verifier `TlsOptions` installs for rustls —
+//!   extra or replacement CA roots, SPKI pins, insecure mode with a
+//!   `verification` detail.
+//! - **PSK** (`with_psk`): pre-shared key suites, no certificates.
+//!   A wrong key shows up as a failed handshake.
+//!
+//! `TlsOptions::client_identity`, `disable_sni` and `alpn` don't
+//! apply: webrtc-dtls has no resolver for rustls keys, always sends
+//! SNI, and DTLS targets rarely negotiate ALPN.
+//!
+//! Schemes accepted: `dtls://`, `coaps://`, or just `host:port`.
+//! Default port 5684.
+
+use std::io::{self, Result};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rustls::client::danger::ServerCertVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio::net::UdpSocket;
+use webrtc_dtls::cipher_suite::CipherSuiteId;
+use webrtc_dtls::config::{Config, ExtendedMasterSecretType};
+use webrtc_dtls::conn::DTLSConn;
+
+use crate::observe::{self, TlsInfo};
+use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::tls::TlsOptions;
+use crate::tls_verify::{TrustVerifier, Verdict};
+use crate::uri::get_uri;
+use crate::util::{format_ms, resolve_first, unspecified_for};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_PORT: u16 = 5684;
+
+/// DTLS handshake pinger. Reports the time taken to complete the
+/// handshake (cookie exchange included), then closes with a
+/// `close_notify`. No application data is sent.
+pub struct DtlsPinger {
+    pub endpoint: String,
+    pub timeout: Duration,
+    /// Identity and key for PSK mode; `None` authenticates the server
+    /// by certificate.
+    pub psk: Option<(Vec<u8>, Vec<u8>)>,
+    tls_options: TlsOptions,
+}
+
+impl DtlsPinger {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            timeout: DEFAULT_TIMEOUT,
+            psk: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, t: Duration) -> Self {
+        self.timeout = t;
+        self
+    }
+
+    /// Handshake with a pre-shared key instead of certificates:
+    /// `identity` is sent to the server, which looks up `key` by it.
+    pub fn with_psk(mut self, identity: impl Into<Vec<u8>>, key: impl Into<Vec<u8>>) -> Self {
+        self.psk = Some((identity.into(), key.into()));
+        self
+    }
+
+    /// Trust settings for certificate mode (CA roots, pins, insecure,
+    /// server name). Ignored in PSK mode.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+}
+
+#[async_trait]
+impl Pinger for DtlsPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    /// Details: `mode` (`certificate` / `psk`), `version` and
+    /// `handshake_ms`; certificate mode adds the server's chain to
+    /// `certificates` (with the `tls` feature).
+    async fn probe(&self) -> Result<Report> {
+        let (host, port) = parse_endpoint(&self.endpoint)?;
+        let server_addr = resolve_first(&host, port).await?;
+        let socket = UdpSocket::bind(unspecified_for(server_addr)).await?;
+        socket.connect(server_addr).await?;
+        observe::udp_connected(&socket);
+
+        let (config, verdict) = self.config(&host)?;
+        let start = Instant::now();
+        let conn = match tokio::time::timeout(
+            self.timeout,
+            DTLSConn::new(Arc::new(socket), config, true, None),
+        )
+        .await
+        {
+            Ok(Ok(conn)) => conn,
+            Ok(Err(e)) => return Err(handshake_error(e)),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "DTLS handshake timed out",
+                ))
+            }
+        };
+        let handshake = start.elapsed();
+        let peer_certificates = conn.connection_state().await.peer_certificates;
+        // Best effort: the handshake is what was measured.
+        let _ = conn.close().await;
+
+        observe::emit(|o| {
+            o.on_tls_handshake(&TlsInfo {
+                alpn: None,
+                version: Some("DTLSv1_2".into()),
+                cipher_suite: None,
+                key_exchange_group: None,
+                resumed: None,
+                peer_certificates: peer_certificates.clone(),
+            })
+        });
+        observe::phase("handshake");
+
+        let mode = if self.psk.is_some() {
+            "psk"
+        } else {
+            "certificate"
+        };
+        let report = Report::new()
+            .with_detail("mode", mode)
+            .with_detail("version", "DTLSv1_2")
+            .with_detail("handshake_ms", format_ms(handshake));
+        #[cfg(feature = "tls")]
+        let report = Report {
+            certificates: peer_certificates
+                .iter()
+                .filter_map(|der| crate::cert::CertInfo::from_der(der).ok())
+                .collect(),
+            ..report
+        };
+        Ok(verdict.annotate(report))
+    }
+}
+
+impl DtlsPinger {
+    /// The webrtc-dtls client config for this pinger, plus the handle
+    /// an insecure handshake's verification outcome lands in.
+    fn config(&self, host: &str) -> Result<(Config, Verdict)> {
+        let server_name = self.tls_options.sni_host(host).to_string();
+        if let Some((identity, key)) = &self.psk {
+            let key = key.clone();
+            let config = Config {
+                psk: Some(Arc::new(move |_hint: &[u8]| Ok(key.clone()))),
+                psk_identity_hint: Some(identity.clone()),
+                // webrtc-dtls' defaults are certificate suites only.
+                // CCM_8 is the one CoAP (RFC 7252) mandates.
+                cipher_suites: vec![
+                    CipherSuiteId::Tls_Psk_With_Aes_128_Gcm_Sha256,
+                    CipherSuiteId::Tls_Psk_With_Aes_128_Ccm,
+                    CipherSuiteId::Tls_Psk_With_Aes_128_Ccm_8,
+                ],
+                server_name,
+                extended_master_secret: ExtendedMasterSecretType::Request,
+                ..Default::default()
+            };
+            return Ok((config, Verdict::default()));
+        }
+
+        // webrtc-dtls' own verifier only takes a root store; skip it
+        // and run ours from the post-verification hook instead, so
+        // pins and insecure mode behave as they do over TCP.
+        let name = ServerName::try_from(server_name.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        let (verifier, verdict) = TrustVerifier::new(&self.tls_options, None);
+        let verify = move |chain: &[Vec<u8>], _: &[CertificateDer<'static>]| {
+            let (leaf, intermediates) = chain
+                .split_first()
+                .ok_or_else(|| webrtc_dtls::Error::Other("server sent no certificate".into()))?;
+            let intermediates: Vec<CertificateDer<'_>> = intermediates
+                .iter()
+                .map(|der| CertificateDer::from(der.as_slice()))
+                .collect();
+            verifier
+                .verify_server_cert(
+                    &CertificateDer::from(leaf.as_slice()),
+                    &intermediates,
+                    &name,
+                    &[],
+                    UnixTime::now(),
+                )
+                .map(|_| ())
+                .map_err(|e| webrtc_dtls::Error::Other(e.to_string()))
+        };
+        let config = Config {
+            insecure_skip_verify: true,
+            verify_peer_certificate: Some(Arc::new(verify)),
+            server_name,
+            extended_master_secret: ExtendedMasterSecretType::Request,
+            ..Default::default()
+        };
+        Ok((config, verdict))
+    }
+}
+
+/// webrtc-dtls errors as `io::Error`: certificate and alert failures
+/// are the peer's answer (`InvalidData`), the rest `Other`.
+fn handshake_error(err: webrtc_dtls::Error) -> io::Error {
+    let kind = match &err {
+        webrtc_dtls::Error::Other(_)
+        | webrtc_dtls::Error::ErrAlertFatalOrClose
+        | webrtc_dtls::Error::ErrVerifyDataMismatch
+        | webrtc_dtls::Error::ErrNoAvailableCipherSuites => io::ErrorKind::InvalidData,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, format!("DTLS handshake: {err}"))
+}
+
+/// Parse `dtls://host[:port]`, `coaps://host[:port]/...`, or
+/// `host[:port]` into `(host, port)`.
+fn parse_endpoint(endpoint: &str) -> Result<(String, u16)> {
+    let trimmed = endpoint.trim();
+    if trimmed.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "DTLS endpoint is empty",
+        ));
+    }
+    let uri = get_uri(trimmed);
+    let scheme = uri.scheme.to_ascii_lowercase();
+    match scheme.as_str() {
+        "" | "dtls" | "coaps" => {}
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "scheme '{other}' is not supported by DtlsPinger \
+                     (use dtls://, coaps://, or host:port)"
+                ),
+            ));
+        }
+    }
+    if uri.domain.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "DTLS endpoint is missing a host",
+        ));
+    }
+    let port = if uri.port > 0 {
+        uri.port as u16
+    } else {
+        DEFAULT_PORT
+    };
+    Ok((uri.domain, port))
+}
+
+/// Resolve `host:port` to one address, IPv4 first — the same choice
+/// `QuicPinger` makes, for the same loopback-fixture reasons.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_endpoint_handles_schemes() {
+        assert_eq!(
+            parse_endpoint("coaps://example.com/sensors").unwrap(),
+            ("example.com".to_string(), 5684)
+        );
+        assert_eq!(
+            parse_endpoint("dtls://example.com:4433").unwrap(),
+            ("example.com".to_string(), 4433)
+        );
+        assert_eq!(
+            parse_endpoint("example.com").unwrap(),
+            ("example.com".to_string(), 5684)
+        );
+    }
+
+    #[test]
+    fn parse_endpoint_rejects_unsupported_scheme() {
+        let err = parse_endpoint("https://example.com").unwrap_err();
+        assert!(err.to_string().contains("scheme 'https'"));
+    }
+}
@@ -0,0 +1,578 @@
+//! Response assertions that work with any pinger. Each protocol has
+//! a built-in notion of "success" (HTTP status in its `StatusSet`,
+//! DNS RCODE 0, RTSP 200); `Expect` layers caller-chosen checks on top of the
+//! `Report` a probe returns — status sets, headers, body regex, JSON
+//! values, DNS answers, latency budget.
+//!
+//! A failed check is an `AssertionError` wrapped in `io::Error`, so
+//! callers can tell "the server answered, but wrongly" apart from
+//! "the server didn't answer" with `AssertionError::from_io`.
+//!
+//! Always compiled; only the JSON check needs the `json` feature.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Result};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use regex::Regex;
+use tokio_util::sync::CancellationToken;
+
+use crate::pinger::Pinger;
+use crate::report::Report;
+
+/// One check against a probe's `Report`.
+///
+/// Parses from the `kind=value` strings the CLI's `--assert` and the
+/// MCP `assert` argument take:
+///
+/// | String | Check |
+/// |---|---|
+/// | `status=200`, `status=2xx`, `status=200-299,304` | status in set |
+/// | `header=Server`, `header=Content-Type:json` | header present / value matches regex |
+/// | `body=REGEX` | body matches regex |
+/// | `json=$.status`, `json=$.status=up` | JSON path exists / equals value (`json` feature) |
+/// | `answer=REGEX` | some DNS answer matches regex |
+/// | `latency=250ms`, `latency=1s` | probe finished within the limit |
+///
+/// Non-exhaustive: the `json` feature adds `JsonPath`.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Expectation {
+    /// Status code is in the set.
+    Status(StatusSet),
+    /// Header is present; when `value` is set it must match too.
+    /// Names compare case-insensitively.
+    Header { name: String, value: Option<Regex> },
+    /// Body (lossy UTF-8) matches the regex.
+    Body(Regex),
+    /// Body parses as JSON and `path` (`$.a.b[0]`) resolves. When
+    /// `equals` is set the value must equal it — strings compare
+    /// unquoted, everything else by its JSON text.
+    #[cfg(feature = "json")]
+    JsonPath {
+        path: String,
+        equals: Option<String>,
+    },
+    /// At least one DNS answer matches the regex.
+    Answer(Regex),
+    /// The probe took no longer than this.
+    MaxLatency(Duration),
+}
+
+impl Expectation {
+    /// Check `report` (produced in `elapsed`) and describe the
+    /// mismatch, if any.
+    pub fn check(&self, report: &Report, elapsed: Duration) -> std::result::Result<(), String> {
+        match self {
+            Expectation::Status(set) => match report.status {
+                None => Err("no status reported".into()),
+                Some(code) if set.contains(code) => Ok(()),
+                Some(code) => Err(format!("status {code} not in {set}")),
+            },
+            Expectation::Header { name, value } => match (report.header(name), value) {
+                (None, _) => Err(format!("header {name} missing")),
+                (Some(_), None) => Ok(()),
+                (Some(v), Some(re)) if re.is_match(v) => Ok(()),
+                (Some(v), Some(re)) => Err(format!("header {name}: {v:?} does not match /{re}/")),
+            },
+            Expectation::Body(re) => {
+                let body = report.body.as_deref().ok_or("no body reported")?;
+                let text = String::from_utf8_lossy(body);
+                if re.is_match(&text) {
+                    Ok(())
+                } else {
+                    Err(format!("body does not match /{re}/"))
+                }
+            }
+            #[cfg(feature = "json")]
+            Expectation::JsonPath { path, equals } => {
+                let body = report.body.as_deref().ok_or("no body reported")?;
+                let doc: serde_json::Value =
+                    serde_json::from_slice(body).map_err(|e| format!("body is not JSON: {e}"))?;
+                let found =
+                    json_lookup(&doc, path).ok_or_else(|| format!("JSON path {path} not found"))?;
+                match equals {
+                    None => Ok(()),
+                    Some(want) => {
+                        let got = match found {
+                            serde_json::Value::String(s) => s.clone(),
+                            other => other.to_string(),
+                        };
+                        if &got == want {
+                            Ok(())
+                        } else {
+                            Err(format!("JSON path {path} is {got:?}, expected {want:?}"))
+                        }
+                    }
+                }
+            }
+            Expectation::Answer(re) => {
+                if report.answers.is_empty() {
+                    Err("no DNS answers".into())
+                } else if report.answers.iter().any(|a| re.is_match(a)) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "no DNS answer matches /{re}/ (got {:?})",
+                        report.answers
+                    ))
+                }
+            }
+            Expectation::MaxLatency(limit) => {
+                if elapsed <= *limit {
+                    Ok(())
+                } else {
+                    Err(format!("took {elapsed:?}, limit {limit:?}"))
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Expectation {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let regex = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| invalid(format!("invalid regex in {s:?}: {e}")))
+        };
+        let (kind, value) = s
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("assertion {s:?} is not KIND=VALUE")))?;
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "status" => value.parse().map(Expectation::Status),
+            "header" => {
+                let (name, pattern) = match value.split_once(':') {
+                    Some((name, pattern)) => (name, Some(regex(pattern)?)),
+                    None => (value, None),
+                };
+                if name.is_empty() {
+                    return Err(invalid(format!("assertion {s:?} names no header")));
+                }
+                Ok(Expectation::Header {
+                    name: name.to_string(),
+                    value: pattern,
+                })
+            }
+            "body" => Ok(Expectation::Body(regex(value)?)),
+            #[cfg(feature = "json")]
+            "json" => {
+                let (path, equals) = match value.split_once('=') {
+                    Some((path, want)) => (path, Some(want.to_string())),
+                    None => (value, None),
+                };
+                Ok(Expectation::JsonPath {
+                    path: path.to_string(),
+                    equals,
+                })
+            }
+            "answer" => Ok(Expectation::Answer(regex(value)?)),
+            "latency" => parse_duration(value)
+                .map(Expectation::MaxLatency)
+                .ok_or_else(|| invalid(format!("invalid latency {value:?} (try 250ms or 1s)"))),
+            other => Err(invalid(format!("unknown assertion kind {other:?}"))),
+        }
+    }
+}
+
+/// A set of status codes — `200`, `2xx`, `200-299`, comma-separated
+/// in any mix. `HttpPinger` judges responses by one (2xx / 3xx by
+/// default).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusSet(Vec<RangeInclusive<u16>>);
+
+impl StatusSet {
+    pub fn new(ranges: Vec<RangeInclusive<u16>>) -> Self {
+        Self(ranges)
+    }
+
+    pub fn contains(&self, code: u16) -> bool {
+        self.0.iter().any(|r| r.contains(&code))
+    }
+
+    pub fn ranges(&self) -> &[RangeInclusive<u16>] {
+        &self.0
+    }
+}
+
+impl FromStr for StatusSet {
+    type Err = io::Error;
+
+    /// `200`, `2xx`, `200-299`, comma-separated in any mix.
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(|part| {
+                let part = part.trim();
+                if let Some(class) = part.strip_suffix("xx").or_else(|| part.strip_suffix("XX")) {
+                    let digit: u16 = class.parse().ok().filter(|d| (1..=9).contains(d))?;
+                    Some(digit * 100..=digit * 100 + 99)
+                } else if let Some((lo, hi)) = part.split_once('-') {
+                    let (lo, hi) = (lo.trim().parse().ok()?, hi.trim().parse().ok()?);
+                    (lo <= hi).then_some(lo..=hi)
+                } else {
+                    let code = part.parse().ok()?;
+                    Some(code..=code)
+                }
+            })
+            .collect::<Option<_>>()
+            .map(StatusSet)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid status set {s:?} (try 200, 2xx or 200-299,304)"),
+                )
+            })
+    }
+}
+
+impl fmt::Display for StatusSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<_> = self
+            .0
+            .iter()
+            .map(|r| {
+                if r.start() == r.end() {
+                    r.start().to_string()
+                } else {
+                    format!("{}-{}", r.start(), r.end())
+                }
+            })
+            .collect();
+        f.write_str(&ranges.join(","))
+    }
+}
+
+/// Every failed expectation from one probe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssertionError {
+    pub failures: Vec<String>,
+}
+
+impl AssertionError {
+    /// The assertion failure inside `err`, if that's what it carries.
+    /// `None` means a transport / protocol error.
+    pub fn from_io(err: &io::Error) -> Option<&AssertionError> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for AssertionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "assertion failed: {}", self.failures.join("; "))
+    }
+}
+
+impl Error for AssertionError {}
+
+impl From<AssertionError> for io::Error {
+    fn from(err: AssertionError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Wraps any pinger and checks its `Report` against a list of
+/// `Expectation`s. Transport errors pass through untouched; a probe
+/// that completes but misses an expectation fails with an
+/// `AssertionError`.
+///
+/// A `Status` expectation replaces the inner pinger's own status rule
+/// (the probe runs through `Pinger::exchange`), so
+/// `status=404` can assert that a path is really gone.
+pub struct Expect<P> {
+    pub inner: P,
+    pub expectations: Vec<Expectation>,
+}
+
+impl<P: Pinger> Expect<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            expectations: Vec::new(),
+        }
+    }
+
+    pub fn with_expectation(mut self, expectation: Expectation) -> Self {
+        self.expectations.push(expectation);
+        self
+    }
+
+    pub fn with_expectations(
+        mut self,
+        expectations: impl IntoIterator<Item = Expectation>,
+    ) -> Self {
+        self.expectations.extend(expectations);
+        self
+    }
+}
+
+#[async_trait]
+impl<P: Pinger> Pinger for Expect<P> {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let start = Instant::now();
+        let report = if self.overrides_status() {
+            self.inner.exchange().await?
+        } else {
+            self.inner.probe().await?
+        };
+        self.check(report, start.elapsed())
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        self.probe().await
+    }
+
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        let start = Instant::now();
+        let report = if self.overrides_status() {
+            self.inner.exchange_with_cancel(cancel).await?
+        } else {
+            self.inner.probe_with_cancel(cancel).await?
+        };
+        self.check(report, start.elapsed())
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        self.probe_with_cancel(cancel).await
+    }
+}
+
+impl<P> Expect<P> {
+    fn overrides_status(&self) -> bool {
+        self.expectations
+            .iter()
+            .any(|e| matches!(e, Expectation::Status(_)))
+    }
+
+    fn check(&self, report: Report, elapsed: Duration) -> Result<Report> {
+        let failures: Vec<String> = self
+            .expectations
+            .iter()
+            .filter_map(|e| e.check(&report, elapsed).err())
+            .collect();
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionError { failures }.into())
+        }
+    }
+}
+
+/// `250ms`, `1.5s`, or a bare number of milliseconds.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Some(ms) = value.strip_suffix("ms") {
+        ms.trim().parse().ok().map(Duration::from_millis)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        secs.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(Duration::from_secs_f64)
+    } else {
+        value.parse().ok().map(Duration::from_millis)
+    }
+}
+
+/// Resolve a small JSONPath subset — `$`, `.key`, `[index]` — against
+/// `doc`. The leading `$` is optional.
+#[cfg(feature = "json")]
+fn json_lookup<'a>(doc: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = doc;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, mut indices) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        while let Some(rest) = indices.strip_prefix('[') {
+            let (index, tail) = rest.split_once(']')?;
+            current = current.get(index.trim().parse::<usize>().ok()?)?;
+            indices = tail;
+        }
+        if !indices.is_empty() {
+            return None;
+        }
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http_report(status: u16, body: &str) -> Report {
+        Report {
+            status: Some(status),
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: Some(body.as_bytes().to_vec()),
+            ..Report::default()
+        }
+    }
+
+    fn check(expr: &str, report: &Report) -> std::result::Result<(), String> {
+        expr.parse::<Expectation>()
+            .unwrap()
+            .check(report, Duration::from_millis(10))
+    }
+
+    #[test]
+    fn status_sets_accept_codes_classes_and_ranges() {
+        let report = http_report(204, "");
+        check("status=204", &report).unwrap();
+        check("status=2xx", &report).unwrap();
+        check("status=200-299", &report).unwrap();
+        check("status=301,200-204", &report).unwrap();
+        let err = check("status=200,300-399", &report).unwrap_err();
+        assert_eq!(err, "status 204 not in 200,300-399");
+    }
+
+    #[test]
+    fn status_set_parses_and_displays() {
+        let set: StatusSet = "2xx, 304,400-404".parse().unwrap();
+        assert!(set.contains(204) && set.contains(304) && set.contains(404));
+        assert!(!set.contains(301) && !set.contains(500));
+        assert_eq!(set.to_string(), "200-299,304,400-404");
+        assert!("2x".parse::<StatusSet>().is_err());
+        assert!("300-200".parse::<StatusSet>().is_err());
+    }
+
+    #[test]
+    fn status_without_report_status_fails() {
+        let err = check("status=200", &Report::default()).unwrap_err();
+        assert_eq!(err, "no status reported");
+    }
+
+    #[test]
+    fn header_presence_and_value() {
+        let report = http_report(200, "");
+        check("header=content-type", &report).unwrap();
+        check("header=Content-Type:json$", &report).unwrap();
+        assert!(check("header=Content-Type:^text/", &report).is_err());
+        assert_eq!(
+            check("header=Server", &report).unwrap_err(),
+            "header Server missing"
+        );
+    }
+
+    #[test]
+    fn body_regex() {
+        let report = http_report(200, r#"{"status":"up"}"#);
+        check(r#"body="up""#, &report).unwrap();
+        assert!(check("body=down", &report).is_err());
+        assert!(check("body=x", &Report::default()).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_path_exists_and_equals() {
+        let report = http_report(
+            200,
+            r#"{"status":"up","checks":[{"ok":true},{"ok":false}]}"#,
+        );
+        check("json=$.status", &report).unwrap();
+        check("json=$.status=up", &report).unwrap();
+        check("json=checks[0].ok=true", &report).unwrap();
+        assert!(check("json=$.checks[1].ok=true", &report).is_err());
+        assert!(check("json=$.missing", &report).is_err());
+        let err = check("json=$.status", &http_report(200, "<html>")).unwrap_err();
+        assert!(err.starts_with("body is not JSON"), "{err}");
+    }
+
+    #[test]
+    fn answer_regex_matches_any_record() {
+        let report = Report {
+            answers: vec!["10.0.0.1".into(), "10.0.0.2".into()],
+            ..Report::default()
+        };
+        check(r"answer=^10\.0\.0\.2$", &report).unwrap();
+        assert!(check("answer=192.168", &report).is_err());
+        assert_eq!(
+            check("answer=.", &Report::default()).unwrap_err(),
+            "no DNS answers"
+        );
+    }
+
+    #[test]
+    fn latency_limit() {
+        let e: Expectation = "latency=250ms".parse().unwrap();
+        e.check(&Report::default(), Duration::from_millis(250))
+            .unwrap();
+        assert!(e
+            .check(&Report::default(), Duration::from_millis(251))
+            .is_err());
+        assert!(matches!(
+            "latency=1.5s".parse().unwrap(),
+            Expectation::MaxLatency(d) if d == Duration::from_millis(1500)
+        ));
+        assert!(matches!(
+            "latency=40".parse().unwrap(),
+            Expectation::MaxLatency(d) if d == Duration::from_millis(40)
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_assertions() {
+        for bad in [
+            "status",
+            "status=abc",
+            "status=299-200",
+            "status=0xx",
+            "header=",
+            "body=(",
+            "latency=soon",
+            "color=blue",
+        ] {
+            let err = bad.parse::<Expectation>().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{bad}");
+        }
+    }
+
+    #[test]
+    fn assertion_error_round_trips_through_io_error() {
+        let err: io::Error = AssertionError {
+            failures: vec!["a".into(), "b".into()],
+        }
+        .into();
+        assert_eq!(err.to_string(), "assertion failed: a; b");
+        let inner = AssertionError::from_io(&err).unwrap();
+        assert_eq!(inner.failures, vec!["a", "b"]);
+        assert!(AssertionError::from_io(&io::Error::other("reset")).is_none());
+    }
+
+    /// Answers 503 only through the cancel-aware exchange.
+    struct CancelAware;
+
+    #[async_trait]
+    impl Pinger for CancelAware {
+        async fn ping(&self) -> Result<()> {
+            Err(io::Error::other("unused"))
+        }
+
+        async fn exchange_with_cancel(&self, _cancel: &CancellationToken) -> Result<Report> {
+            Ok(http_report(503, ""))
+        }
+    }
+
+    #[tokio::test]
+    async fn status_override_forwards_the_cancel_token() {
+        let p = Expect::new(CancelAware).with_expectation("status=503".parse().unwrap());
+        let report = p
+            .probe_with_cancel(&CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(report.status, Some(503));
+    }
+}
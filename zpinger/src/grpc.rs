@@ -8,18 +8,24 @@
 //! (`grpcs://` or `https://`) endpoints. `with_ca_cert` injects a
 //! caller-supplied PEM trust anchor for self-signed test endpoints;
 //! the production default trusts the Mozilla root CA bundle via
-//! tonic's `tls-webpki-roots` feature.
+//! tonic's `tls-webpki-roots` feature. tonic owns its rustls config,
+//! so of `TlsOptions`' trust settings only the CA ones apply here;
+//! pins and insecure mode are refused up front, as are SNI and ALPN
+//! changes (the server name override works).
 
 use std::io::{self, Result};
 use std::time::Duration;
 
 use async_trait::async_trait;
-use tonic::transport::{Certificate, ClientTlsConfig, Endpoint};
+use base64::Engine;
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity};
 use tonic_health::pb::health_check_response::ServingStatus;
 use tonic_health::pb::health_client::HealthClient;
 use tonic_health::pb::HealthCheckRequest;
 
+use crate::observe;
 use crate::pinger::Pinger;
+use crate::tls::{system_root_certs, TlsOptions};
 use crate::uri::get_uri;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
@@ -29,6 +35,7 @@ pub struct GrpcPinger {
     pub service: String,
     pub timeout: Duration,
     ca_cert_pem: Option<Vec<u8>>,
+    tls_options: TlsOptions,
 }
 
 impl GrpcPinger {
@@ -38,6 +45,7 @@ impl GrpcPinger {
             service: String::new(),
             timeout: DEFAULT_TIMEOUT,
             ca_cert_pem: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -62,6 +70,13 @@ impl GrpcPinger {
         self.ca_cert_pem = Some(pem.into());
         self
     }
+
+    /// Shared TLS settings. tonic runs its own rustls stack, so only
+    /// the client identity (mutual TLS) carries over.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
 }
 
 #[async_trait]
@@ -80,13 +95,7 @@ impl Pinger for GrpcPinger {
         let is_tls = scheme == "grpcs" || scheme == "https";
 
         if is_tls {
-            let mut tls = ClientTlsConfig::new().with_webpki_roots();
-            if let Some(pem) = &self.ca_cert_pem {
-                tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(pem.clone()));
-            }
-            if !domain.is_empty() {
-                tls = tls.domain_name(domain);
-            }
+            let tls = client_tls(&self.ca_cert_pem, &self.tls_options, domain)?;
             endpoint = endpoint
                 .tls_config(tls)
                 .map_err(|e| io::Error::other(format!("tonic tls_config: {e}")))?;
@@ -96,6 +105,7 @@ impl Pinger for GrpcPinger {
             .connect()
             .await
             .map_err(|e| io::Error::other(format!("connect: {e}")))?;
+        observe::phase("connect");
         let mut client = HealthClient::new(channel);
         let req = HealthCheckRequest {
             service: self.service.clone(),
@@ -130,6 +140,7 @@ pub struct GrpcStreamPinger {
     pub service: String,
     pub timeout: Duration,
     ca_cert_pem: Option<Vec<u8>>,
+    tls_options: TlsOptions,
 }
 
 impl GrpcStreamPinger {
@@ -139,6 +150,7 @@ impl GrpcStreamPinger {
             service: String::new(),
             timeout: DEFAULT_TIMEOUT,
             ca_cert_pem: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -156,6 +168,13 @@ impl GrpcStreamPinger {
         self.ca_cert_pem = Some(pem.into());
         self
     }
+
+    /// Shared TLS settings. tonic runs its own rustls stack, so only
+    /// the client identity (mutual TLS) carries over.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
 }
 
 #[async_trait]
@@ -173,13 +192,7 @@ impl Pinger for GrpcStreamPinger {
         let scheme = uri.scheme.to_ascii_lowercase();
         let is_tls = scheme == "grpcs" || scheme == "https";
         if is_tls {
-            let mut tls = ClientTlsConfig::new().with_webpki_roots();
-            if let Some(pem) = &self.ca_cert_pem {
-                tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(pem.clone()));
-            }
-            if !domain.is_empty() {
-                tls = tls.domain_name(domain);
-            }
+            let tls = client_tls(&self.ca_cert_pem, &self.tls_options, domain)?;
             endpoint = endpoint
                 .tls_config(tls)
                 .map_err(|e| io::Error::other(format!("tonic tls_config: {e}")))?;
@@ -189,6 +202,7 @@ impl Pinger for GrpcStreamPinger {
             .connect()
             .await
             .map_err(|e| io::Error::other(format!("connect: {e}")))?;
+        observe::phase("connect");
         let mut client = HealthClient::new(channel);
         let req = HealthCheckRequest {
             service: self.service.clone(),
@@ -213,6 +227,62 @@ impl Pinger for GrpcStreamPinger {
     }
 }
 
+/// tonic TLS settings for a `grpcs://` endpoint: webpki roots unless
+/// a CA override was given, SNI from the endpoint's host, and the
+/// client identity if one is set.
+fn client_tls(
+    ca_cert_pem: &Option<Vec<u8>>,
+    options: &TlsOptions,
+    domain: String,
+) -> Result<ClientTlsConfig> {
+    if !options.pins.is_empty() || options.insecure {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "gRPC pinger does not support certificate pins or insecure mode",
+        ));
+    }
+    if options.disable_sni || !options.alpn.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "gRPC pinger always sends SNI and negotiates h2",
+        ));
+    }
+    let mut tls = ClientTlsConfig::new();
+    if ca_cert_pem.is_none() && !options.replace_roots {
+        tls = tls.with_webpki_roots();
+    }
+    if let Some(pem) = ca_cert_pem {
+        tls = tls.ca_certificate(Certificate::from_pem(pem.clone()));
+    }
+    for der in &options.ca_certs {
+        tls = tls.ca_certificate(Certificate::from_pem(der_to_pem(der)));
+    }
+    if options.system_roots {
+        for der in system_root_certs()? {
+            tls = tls.ca_certificate(Certificate::from_pem(der_to_pem(der)));
+        }
+    }
+    if !domain.is_empty() {
+        tls = tls.domain_name(options.sni_host(&domain));
+    }
+    if let Some(identity) = &options.client_identity {
+        tls = tls.identity(Identity::from_pem(identity.cert_pem(), identity.key_pem()));
+    }
+    Ok(tls)
+}
+
+/// tonic only takes PEM trust anchors.
+fn der_to_pem(der: &[u8]) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in b64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
 /// Translate `grpc://` / `grpcs://` schemes (used by tools like
 /// `grpcurl`) to the `http://` / `https://` form tonic's `Endpoint`
 /// expects. Everything else is passed through untouched, which is
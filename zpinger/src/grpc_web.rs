@@ -0,0 +1,431 @@
+//! gRPC-Web pinger — the `grpc.health.v1.Health/Check` call of
+//! `GrpcPinger`, framed as `application/grpc-web+proto` and sent as
+//! an HTTP/1.1 POST, for services behind a gRPC-Web proxy (Envoy's
+//! `grpc_web` filter, grpcwebproxy) that native HTTP/2 gRPC can't
+//! reach.
+//!
+//! The request and response go through `HttpPinger`, so TLS,
+//! `TlsOptions` and the HTTP timing details come from there. The
+//! protobuf messages are two fields in all, so they're hand-rolled
+//! rather than pulling tonic in.
+
+use std::io::{self, Result};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::http::{HttpMethod, HttpPinger};
+use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::tls::TlsOptions;
+use crate::uri::get_uri;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+const CONTENT_TYPE: &str = "application/grpc-web+proto";
+/// Frame flag bits (gRPC-Web protocol, "Protocol differences vs
+/// gRPC over HTTP2").
+const FLAG_COMPRESSED: u8 = 0x01;
+const FLAG_TRAILERS: u8 = 0x80;
+/// `HealthCheckResponse.ServingStatus.SERVING`.
+const SERVING: u64 = 1;
+
+/// gRPC-Web health check pinger. Same endpoints as `GrpcPinger` —
+/// `grpc://` / `http://` plaintext, `grpcs://` / `https://` TLS,
+/// schemeless `host:port` as plaintext — plus an optional path
+/// prefix (`https://api.example.com/rpc`) for proxies that route
+/// gRPC-Web under one.
+///
+/// Fails unless the answer is a gRPC-Web response whose trailers
+/// carry `grpc-status: 0` and whose message is `SERVING`. The report
+/// is the HTTP exchange's — status, headers, `header_ms`, `body_ms`
+/// — plus `grpc_status` and `serving_status`.
+pub struct GrpcWebPinger {
+    pub endpoint: String,
+    pub service: String,
+    pub timeout: Duration,
+    ca_cert_pem: Option<Vec<u8>>,
+    tls_options: TlsOptions,
+}
+
+impl GrpcWebPinger {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            service: String::new(),
+            timeout: DEFAULT_TIMEOUT,
+            ca_cert_pem: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    /// gRPC service name passed in `HealthCheckRequest.service`; empty
+    /// (the default) asks for the server's overall health.
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.service = service.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// PEM-encoded CA certificate that signs the server's TLS cert,
+    /// trusted instead of the default roots — as in `GrpcPinger`.
+    pub fn with_ca_cert(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_cert_pem = Some(pem.into());
+        self
+    }
+
+    /// Shared TLS settings. Unlike `GrpcPinger`, all of them apply:
+    /// the request rides `HttpPinger`'s rustls stack, not tonic's.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    fn request(&self) -> Result<HttpPinger> {
+        let mut tls_options = self.tls_options.clone();
+        if let Some(pem) = &self.ca_cert_pem {
+            tls_options = tls_options.with_ca_pem(pem)?.with_replace_roots(true);
+        }
+        let url = method_url(&self.endpoint)?;
+        Ok(HttpPinger::new(HttpMethod::Post, url)
+            .with_timeout(self.timeout)
+            .with_tls_options(tls_options)
+            .with_content_type(CONTENT_TYPE)
+            .with_header("Accept", CONTENT_TYPE)
+            .with_header("X-Grpc-Web", "1")
+            .with_body(frame(0, &encode_request(&self.service))))
+    }
+}
+
+#[async_trait]
+impl Pinger for GrpcWebPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let mut report = self.request()?.exchange().await?;
+        let status = check_response(&report)?;
+        // Byte counts and throughput say nothing about a health check.
+        report.details.retain(|(key, _)| {
+            !key.ends_with("_bytes") && !key.ends_with("_per_sec") && key != "upload_ms"
+        });
+        Ok(report
+            .with_detail("grpc_status", "0")
+            .with_detail("serving_status", serving_status_name(status)))
+    }
+}
+
+/// The Health/Check URL for `endpoint`: `grpc` schemes mapped to
+/// `http`, plaintext when there's none, any path kept as a prefix.
+fn method_url(endpoint: &str) -> Result<String> {
+    let uri = get_uri(endpoint.trim());
+    if uri.domain.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "missing host in gRPC-Web endpoint",
+        ));
+    }
+    let scheme = match uri.scheme.to_ascii_lowercase().as_str() {
+        "" | "grpc" | "http" => "http",
+        "grpcs" | "https" => "https",
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("scheme '{other}' is not supported by GrpcWebPinger"),
+            ))
+        }
+    };
+    let port = match uri.port {
+        0 => String::new(),
+        port => format!(":{port}"),
+    };
+    let prefix = uri.path.trim_end_matches('/');
+    Ok(format!(
+        "{scheme}://{}{port}{prefix}{HEALTH_CHECK_PATH}",
+        uri.domain
+    ))
+}
+
+/// The `ServingStatus` of a gRPC-Web Health/Check response, once it
+/// is known to be a successful call.
+fn check_response(report: &Report) -> Result<u64> {
+    let status = report.status.unwrap_or_default();
+    if status != 200 {
+        return Err(io::Error::other(format!(
+            "HTTP status {status} (expected 200 from a gRPC-Web endpoint)"
+        )));
+    }
+    let content_type = report.header("content-type").unwrap_or("none");
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    if !["application/grpc-web", CONTENT_TYPE]
+        .iter()
+        .any(|t| media_type.eq_ignore_ascii_case(t))
+    {
+        return Err(io::Error::other(format!(
+            "Content-Type {content_type} is not application/grpc-web+proto"
+        )));
+    }
+
+    let body = report.body.as_deref().unwrap_or_default();
+    let Frames { message, trailers } = parse_frames(body)?;
+    // A trailers-only response (an error with no message) carries the
+    // status in the HTTP headers instead.
+    let trailer = |name: &str| {
+        trailers
+            .iter()
+            .chain(&report.headers)
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim())
+    };
+    let code = trailer("grpc-status")
+        .ok_or_else(|| io::Error::other("gRPC-Web response has no grpc-status"))?;
+    if code != "0" {
+        let message = trailer("grpc-message")
+            .map(|m| format!(": {}", percent_decode(m)))
+            .unwrap_or_default();
+        return Err(io::Error::other(format!(
+            "Health/Check: grpc-status {code} ({}){message}",
+            status_code_name(code)
+        )));
+    }
+    let message =
+        message.ok_or_else(|| io::Error::other("Health/Check returned OK but no message"))?;
+    let serving = decode_response(message)?;
+    if serving != SERVING {
+        return Err(io::Error::other(format!(
+            "gRPC server returned status {serving} (expected SERVING=1)"
+        )));
+    }
+    Ok(serving)
+}
+
+/// One length-prefixed gRPC frame.
+fn frame(flags: u8, payload: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(5 + payload.len());
+    framed.push(flags);
+    framed.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    framed.extend_from_slice(payload);
+    framed
+}
+
+/// A response body, split into frames.
+struct Frames<'a> {
+    /// The first message frame's payload.
+    message: Option<&'a [u8]>,
+    /// The trailers frame's fields.
+    trailers: Vec<(String, String)>,
+}
+
+fn parse_frames(mut body: &[u8]) -> Result<Frames<'_>> {
+    let mut message = None;
+    let mut trailers = Vec::new();
+    while !body.is_empty() {
+        if body.len() < 5 {
+            return Err(invalid("truncated frame header"));
+        }
+        let flags = body[0];
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        let payload = body
+            .get(5..5 + len)
+            .ok_or_else(|| invalid(&format!("frame of {len} bytes is truncated")))?;
+        body = &body[5 + len..];
+        if flags & FLAG_TRAILERS != 0 {
+            trailers.extend(
+                String::from_utf8_lossy(payload)
+                    .split("\r\n")
+                    .filter_map(|line| line.split_once(':'))
+                    .map(|(name, value)| (name.trim().to_string(), value.trim().to_string())),
+            );
+        } else if flags & FLAG_COMPRESSED != 0 {
+            return Err(invalid("compressed message, but none was offered"));
+        } else if message.is_none() {
+            message = Some(payload);
+        }
+    }
+    Ok(Frames { message, trailers })
+}
+
+/// `HealthCheckRequest { service }`: field 1, omitted when empty.
+fn encode_request(service: &str) -> Vec<u8> {
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        message.push(0x0a);
+        put_varint(&mut message, service.len() as u64);
+        message.extend_from_slice(service.as_bytes());
+    }
+    message
+}
+
+/// `HealthCheckResponse.status` (field 1); absent means `UNKNOWN`.
+fn decode_response(mut message: &[u8]) -> Result<u64> {
+    let mut status = 0;
+    while !message.is_empty() {
+        let key = get_varint(&mut message)?;
+        let value_len = match key & 0x7 {
+            0 => {
+                let value = get_varint(&mut message)?;
+                if key >> 3 == 1 {
+                    status = value;
+                }
+                0
+            }
+            1 => 8,
+            2 => get_varint(&mut message)? as usize,
+            5 => 4,
+            wire => return Err(invalid(&format!("unsupported wire type {wire}"))),
+        };
+        message = message
+            .get(value_len..)
+            .ok_or_else(|| invalid("truncated field"))?;
+    }
+    Ok(status)
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn get_varint(input: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input
+            .split_first()
+            .ok_or_else(|| invalid("truncated varint"))?;
+        *input = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+/// `grpc-message` is percent-encoded (gRPC over HTTP2, "Responses").
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn serving_status_name(status: u64) -> String {
+    match status {
+        0 => "UNKNOWN".into(),
+        1 => "SERVING".into(),
+        2 => "NOT_SERVING".into(),
+        3 => "SERVICE_UNKNOWN".into(),
+        other => other.to_string(),
+    }
+}
+
+fn status_code_name(code: &str) -> &'static str {
+    const NAMES: [&str; 17] = [
+        "OK",
+        "CANCELLED",
+        "UNKNOWN",
+        "INVALID_ARGUMENT",
+        "DEADLINE_EXCEEDED",
+        "NOT_FOUND",
+        "ALREADY_EXISTS",
+        "PERMISSION_DENIED",
+        "RESOURCE_EXHAUSTED",
+        "FAILED_PRECONDITION",
+        "ABORTED",
+        "OUT_OF_RANGE",
+        "UNIMPLEMENTED",
+        "INTERNAL",
+        "UNAVAILABLE",
+        "DATA_LOSS",
+        "UNAUTHENTICATED",
+    ];
+    code.parse::<usize>()
+        .ok()
+        .and_then(|code| NAMES.get(code).copied())
+        .unwrap_or("unrecognised")
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("gRPC-Web: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_url_maps_schemes_and_keeps_a_path_prefix() {
+        assert_eq!(
+            method_url("grpc://localhost:8080").unwrap(),
+            "http://localhost:8080/grpc.health.v1.Health/Check"
+        );
+        assert_eq!(
+            method_url("grpcs://api.example.com/rpc/").unwrap(),
+            "https://api.example.com/rpc/grpc.health.v1.Health/Check"
+        );
+        assert_eq!(
+            method_url("localhost:8080").unwrap(),
+            "http://localhost:8080/grpc.health.v1.Health/Check"
+        );
+        assert!(method_url("ws://localhost").is_err());
+        assert!(method_url("").is_err());
+    }
+
+    #[test]
+    fn request_and_response_messages_round_trip() {
+        assert_eq!(encode_request(""), b"");
+        assert_eq!(encode_request("svc"), b"\x0a\x03svc");
+        assert_eq!(decode_response(b"\x08\x01").unwrap(), 1);
+        assert_eq!(decode_response(b"").unwrap(), 0);
+        // Unknown fields of every wire type are skipped.
+        let message = b"\x12\x02hi\x19\0\0\0\0\0\0\0\0\x25\0\0\0\0\x08\x02";
+        assert_eq!(decode_response(message).unwrap(), 2);
+        assert!(decode_response(b"\x08").is_err());
+        assert!(decode_response(b"\x12\x05hi").is_err());
+    }
+
+    #[test]
+    fn frames_split_into_message_and_trailers() {
+        let mut body = frame(0, b"\x08\x01");
+        body.extend(frame(
+            FLAG_TRAILERS,
+            b"grpc-status:0\r\ngrpc-message: all%20good\r\n",
+        ));
+        let Frames { message, trailers } = parse_frames(&body).unwrap();
+        assert_eq!(message, Some(&b"\x08\x01"[..]));
+        assert_eq!(
+            trailers,
+            [
+                ("grpc-status".to_string(), "0".to_string()),
+                ("grpc-message".to_string(), "all%20good".to_string()),
+            ]
+        );
+        assert_eq!(percent_decode("all%20good%"), "all good%");
+        assert!(parse_frames(&body[..body.len() - 1]).is_err());
+        assert!(parse_frames(&frame(FLAG_COMPRESSED, b"x")).is_err());
+    }
+}
@@ -9,20 +9,25 @@
 //! All three GETs are folded into the single `time=` the trait reports.
 //! `https://` reuses the rustls + webpki-roots layer from PR 8;
 //! `with_tls_config` overrides for self-signed test endpoints.
+//!
+//! Credentials (`with_auth`, or the URL's `user:password@` as Basic)
+//! go to every fetch on the playlist's host — Digest answering the
+//! first `401` challenge — and never to a CDN elsewhere.
 
 use std::io::{self, Result};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
 
+use crate::auth::{self, HttpAuth};
+use crate::observe;
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::tls::{client_config, TlsOptions};
 use crate::uri::{get_uri, URI};
 use crate::util::with_timeout;
 
@@ -31,7 +36,10 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 pub struct HlsPinger {
     pub url: String,
     pub timeout: Duration,
+    /// Credentials; `None` falls back to the URL's userinfo.
+    pub auth: Option<HttpAuth>,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl HlsPinger {
@@ -39,7 +47,9 @@ impl HlsPinger {
         Self {
             url: url.into(),
             timeout: DEFAULT_TIMEOUT,
+            auth: None,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -48,21 +58,50 @@ impl HlsPinger {
         self
     }
 
+    /// Authenticate with `auth` instead of any `user:password@` in
+    /// the URL.
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
         self.tls_config = Some(config);
         self
     }
+
+    /// Shared TLS settings for `https://` playlists and segments.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
 }
 
 #[async_trait]
 impl Pinger for HlsPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
         let url = self.url.clone();
         let timeout = self.timeout;
-        let tls_config = self.tls_config.clone();
+        let (tls_config, verdict) = client_config(&self.tls_config, &self.tls_options);
+        let tls = (tls_config, &self.tls_options);
+        let credentials = self
+            .auth
+            .clone()
+            .or_else(|| HttpAuth::from_userinfo(&get_uri(&url)));
+        let playlist_host = get_uri(&url).host;
+        // Credentials only go to the playlist's own host.
+        let auth_for = |target: &str| {
+            credentials
+                .as_ref()
+                .filter(|_| get_uri(target).host == playlist_host)
+        };
         with_timeout(timeout, async move {
             // 1. Fetch the playlist the user gave us.
-            let body = http_get(&url, &tls_config).await?;
+            let body = http_get(&url, &tls, auth_for(&url)).await?;
             let playlist = std::str::from_utf8(&body).map_err(|e| {
                 io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -75,11 +114,12 @@ impl Pinger for HlsPinger {
                     "response is not an M3U8 playlist (missing #EXTM3U header)",
                 ));
             }
+            observe::phase("playlist");
 
             // 2. If master, follow the first variant to get a media playlist.
             let (media_url, media_text) = if let Some(variant) = first_variant_url(playlist) {
                 let resolved = resolve_relative(&url, variant)?;
-                let variant_body = http_get(&resolved, &tls_config).await?;
+                let variant_body = http_get(&resolved, &tls, auth_for(&resolved)).await?;
                 let variant_text = std::str::from_utf8(&variant_body)
                     .map_err(|e| {
                         io::Error::new(
@@ -88,6 +128,7 @@ impl Pinger for HlsPinger {
                         )
                     })?
                     .to_string();
+                observe::phase("variant");
                 (resolved, variant_text)
             } else {
                 (url.clone(), playlist.to_string())
@@ -98,11 +139,23 @@ impl Pinger for HlsPinger {
                 io::Error::new(io::ErrorKind::InvalidData, "no segments in media playlist")
             })?;
             let segment_url = resolve_relative(&media_url, segment)?;
-            let _ = http_get_with_range(&segment_url, &tls_config, Some("bytes=0-0")).await?;
+            let _ = http_get_with_range(
+                &segment_url,
+                &tls,
+                auth_for(&segment_url),
+                Some("bytes=0-0"),
+            )
+            .await?;
+            observe::phase("segment");
 
             Ok(())
         })
-        .await
+        .await?;
+        let report = match &credentials {
+            Some(auth) => Report::new().with_detail("auth", auth.scheme()),
+            None => Report::new(),
+        };
+        Ok(verdict.annotate(report))
     }
 }
 
@@ -183,47 +236,82 @@ fn resolve_relative(base: &str, reference: &str) -> Result<String> {
 
 // -- minimal HTTP fetcher ---------------------------------------------
 
-async fn http_get(url: &str, tls_config: &Option<Arc<ClientConfig>>) -> Result<Vec<u8>> {
-    http_get_with_range(url, tls_config, None).await
+/// TLS for every fetch of one probe: the config plus the options it
+/// came from (for the SNI override, which applies to every host).
+type FetchTls<'a> = (Arc<ClientConfig>, &'a TlsOptions);
+
+async fn http_get(url: &str, tls: &FetchTls<'_>, auth: Option<&HttpAuth>) -> Result<Vec<u8>> {
+    http_get_with_range(url, tls, auth, None).await
 }
 
 async fn http_get_with_range(
     url: &str,
-    tls_config: &Option<Arc<ClientConfig>>,
+    tls: &FetchTls<'_>,
+    auth: Option<&HttpAuth>,
     range: Option<&str>,
 ) -> Result<Vec<u8>> {
     let uri = get_uri(url);
+    let preemptive = auth.and_then(HttpAuth::preemptive);
+    let response = fetch(&uri, tls, range, preemptive.as_deref()).await?;
+    // Digest: answer the challenge once.
+    let answer = match auth {
+        Some(auth) if response.status == 401 => auth.answer(
+            &auth::challenges(&response.headers),
+            "GET",
+            request_path(&uri),
+        ),
+        _ => None,
+    };
+    let response = match answer {
+        Some(header) => fetch(&uri, tls, range, Some(&header)).await?,
+        None => response,
+    };
+    response.into_body()
+}
+
+async fn fetch(
+    uri: &URI,
+    tls: &FetchTls<'_>,
+    range: Option<&str>,
+    authorization: Option<&str>,
+) -> Result<Response> {
     let scheme = uri.scheme.to_ascii_lowercase();
     match scheme.as_str() {
-        "" | "http" => fetch_plain(&uri, range).await,
-        "https" => fetch_tls(&uri, tls_config, range).await,
+        "" | "http" => fetch_plain(uri, range, authorization).await,
+        "https" => fetch_tls(uri, tls, range, authorization).await,
         other => Err(io::Error::other(format!(
             "scheme '{other}' is not supported by HlsPinger (use http:// or https://)"
         ))),
     }
 }
 
-async fn fetch_plain(uri: &URI, range: Option<&str>) -> Result<Vec<u8>> {
+async fn fetch_plain(
+    uri: &URI,
+    range: Option<&str>,
+    authorization: Option<&str>,
+) -> Result<Response> {
     let endpoint = endpoint_for(uri, 80)?;
-    let request = build_get(uri, &endpoint, range);
-    let mut stream = TcpStream::connect(&endpoint).await?;
-    fetch_response_body(&mut stream, &request).await
+    let request = build_get(uri, &endpoint, range, authorization);
+    let mut stream = observe::connect_tcp(&endpoint).await?;
+    fetch_response(&mut stream, &request).await
 }
 
 async fn fetch_tls(
     uri: &URI,
-    tls_config: &Option<Arc<ClientConfig>>,
+    tls: &FetchTls<'_>,
     range: Option<&str>,
-) -> Result<Vec<u8>> {
+    authorization: Option<&str>,
+) -> Result<Response> {
     let endpoint = endpoint_for(uri, 443)?;
-    let server_name = ServerName::try_from(uri.domain.clone())
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-    let config = tls_config.clone().unwrap_or_else(default_client_config);
-    let request = build_get(uri, &endpoint, range);
-    let tcp = TcpStream::connect(&endpoint).await?;
+    let (config, options) = tls;
+    let server_name = options.server_name(&uri.domain)?;
+    let config = config.clone();
+    let request = build_get(uri, &endpoint, range, authorization);
+    let tcp = observe::connect_tcp(&endpoint).await?;
     let connector = TlsConnector::from(config);
     let mut stream = connector.connect(server_name, tcp).await?;
-    fetch_response_body(&mut stream, &request).await
+    observe::tls_handshake(stream.get_ref().1);
+    fetch_response(&mut stream, &request).await
 }
 
 fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
@@ -241,8 +329,21 @@ fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
     Ok(format!("{}:{}", uri.domain, port))
 }
 
-fn build_get(uri: &URI, host_header: &str, range: Option<&str>) -> String {
-    let path = if uri.path.is_empty() { "/" } else { &uri.path };
+fn request_path(uri: &URI) -> &str {
+    if uri.path.is_empty() {
+        "/"
+    } else {
+        &uri.path
+    }
+}
+
+fn build_get(
+    uri: &URI,
+    host_header: &str,
+    range: Option<&str>,
+    authorization: Option<&str>,
+) -> String {
+    let path = request_path(uri);
     let mut req = format!(
         "GET {path} HTTP/1.1\r\n\
          Host: {host_header}\r\n\
@@ -253,11 +354,36 @@ fn build_get(uri: &URI, host_header: &str, range: Option<&str>) -> String {
     if let Some(r) = range {
         req.push_str(&format!("Range: {r}\r\n"));
     }
+    if let Some(value) = authorization {
+        req.push_str(&format!("Authorization: {value}\r\n"));
+    }
     req.push_str("\r\n");
     req
 }
 
-async fn fetch_response_body<S>(stream: &mut S, request: &str) -> Result<Vec<u8>>
+/// One response, read to EOF.
+struct Response {
+    status: u16,
+    status_line: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// The body of a `200 OK` or `206 Partial Content` (Range
+    /// requests); any other status is an error.
+    fn into_body(self) -> Result<Vec<u8>> {
+        if self.status != 200 && self.status != 206 {
+            return Err(io::Error::other(format!(
+                "unexpected HTTP status: {}",
+                self.status_line
+            )));
+        }
+        Ok(self.body)
+    }
+}
+
+async fn fetch_response<S>(stream: &mut S, request: &str) -> Result<Response>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
@@ -282,20 +408,29 @@ where
 
     let header = std::str::from_utf8(&buf[..split])
         .map_err(|_| io::Error::other("response headers are not valid UTF-8"))?;
-    let status_line = header.lines().next().unwrap_or("");
+    let mut lines = header.lines();
+    let status_line = lines.next().unwrap_or("");
     if !status_line.starts_with("HTTP/") {
         return Err(io::Error::other(
             "response is not HTTP/1.x (wrong port or protocol?)",
         ));
     }
-    // Accept 200 OK and 206 Partial Content (Range requests).
-    if !status_line.contains(" 200 ") && !status_line.contains(" 206 ") {
-        return Err(io::Error::other(format!(
-            "unexpected HTTP status: {status_line}"
-        )));
-    }
-
-    Ok(buf[split + 4..].to_vec())
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .unwrap_or(0);
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok(Response {
+        status,
+        status_line: status_line.to_string(),
+        headers,
+        body: buf[split + 4..].to_vec(),
+    })
 }
 
 #[cfg(test)]
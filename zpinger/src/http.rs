@@ -1,23 +1,45 @@
 use std::io::{self, Result};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
 
+use crate::auth::{self, HttpAuth};
+use crate::cors::CorsPreflight;
+use crate::expect::{AssertionError, StatusSet};
+use crate::observe;
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::resume::{self, Handshake, ResumptionMode};
+use crate::tls::{client_config, TlsOptions};
 use crate::uri::{get_uri, URI};
-use crate::util::with_timeout;
-use crate::{BUF_SIZE, HTTP_UNCONNECT_STATUS_CODE};
+use crate::util::{format_ms, parse_response_head, with_timeout};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Response head larger than this is treated as a broken server.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// A chunk-size or trailer line longer than this is a broken server.
+const MAX_LINE_BYTES: usize = 4 * 1024;
+/// Default cap on response body bytes read (`with_max_body_bytes`).
+const DEFAULT_MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Body bytes kept for the `Report`; the rest is counted and dropped.
+const KEPT_BODY_BYTES: usize = 64 * 1024;
+/// Read and generated-upload write size.
+pub(crate) const IO_CHUNK: usize = 16 * 1024;
+const HEADER_TERMINATOR: &[u8] = b"\r\n\r\n";
+const DEFAULT_USER_AGENT: &str = "Knock Knock";
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Request method. `Other` is sent exactly as given, for extension
+/// methods such as WebDAV's `PROPFIND` or CalDAV's `REPORT`.
+///
+/// Parses from a method token: the named methods case-insensitively
+/// (`"head"` is `Head`), anything else as `Other`, case kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum HttpMethod {
     Connect,
     Get,
@@ -25,10 +47,15 @@ pub enum HttpMethod {
     Put,
     Delete,
     Patch,
+    /// Like GET, but the response has no body whatever its
+    /// `Content-Length` says.
+    Head,
+    Options,
+    Other(String),
 }
 
 impl HttpMethod {
-    fn as_str(self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             HttpMethod::Connect => "CONNECT",
             HttpMethod::Get => "GET",
@@ -36,22 +63,121 @@ impl HttpMethod {
             HttpMethod::Put => "PUT",
             HttpMethod::Delete => "DELETE",
             HttpMethod::Patch => "PATCH",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Options => "OPTIONS",
+            HttpMethod::Other(method) => method,
         }
     }
 
-    fn has_body(self) -> bool {
+    /// Whether a generated `{}` / upload body goes with the request.
+    fn has_body(&self) -> bool {
         matches!(self, HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch)
     }
 }
 
+impl std::fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for HttpMethod {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        const NAMED: [HttpMethod; 8] = [
+            HttpMethod::Connect,
+            HttpMethod::Get,
+            HttpMethod::Post,
+            HttpMethod::Put,
+            HttpMethod::Delete,
+            HttpMethod::Patch,
+            HttpMethod::Head,
+            HttpMethod::Options,
+        ];
+        if let Some(method) = NAMED.iter().find(|m| m.as_str().eq_ignore_ascii_case(s)) {
+            return Ok(method.clone());
+        }
+        // RFC 9110 §9.1: a method is a token.
+        let tchar = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+        if s.is_empty() || !s.chars().all(tchar) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid HTTP method {s:?}"),
+            ));
+        }
+        Ok(HttpMethod::Other(s.to_string()))
+    }
+}
+
+/// HTTP version `HttpPinger` speaks. Non-exhaustive: the `http2`
+/// feature adds `Http2`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HttpVersion {
+    #[default]
+    Http1,
+    /// HTTP/2: ALPN `h2` over TLS, prior-knowledge h2c in plaintext.
+    #[cfg(feature = "http2")]
+    Http2,
+}
+
 /// HTTP / HTTPS pinger — opens a TCP connection (optionally wrapped in
 /// TLS for `https://`), writes a single HTTP/1.1 request, reads the
-/// response, and reports success based on the status line.
+/// whole response, and succeeds when the status is in
+/// `expected_status` (2xx / 3xx by default). Any other status fails
+/// with an `AssertionError`: the server answered, just not as hoped.
+/// `probe` reports the status, headers and body for `Expectation`s
+/// to check, plus transfer timings: `header_ms` (request sent to
+/// response head), `body_ms`, `body_bytes` and
+/// `download_bytes_per_sec`, and for requests with a body
+/// `upload_bytes`, `upload_ms` and `upload_bytes_per_sec`.
+///
+/// The body is framed by `Content-Length`, chunked transfer encoding,
+/// or the server closing the connection, and read up to
+/// `max_body_bytes`; a body cut short by the cap is reported as
+/// `body_truncated`, one cut short by the server fails the ping.
+///
+/// Credentials come from `with_auth` or, failing that, the target's
+/// `user:password@` (as Basic). Basic and Bearer go out with the
+/// request; Digest answers the server's `401` challenge with a second
+/// request, reported as `auth_challenge_ms`.
 pub struct HttpPinger {
     pub method: HttpMethod,
     pub target: String,
     pub timeout: Duration,
+    pub resumption: ResumptionMode,
+    /// Most response body bytes to read; the first 64 KiB are kept
+    /// in `Report::body`.
+    pub max_body_bytes: usize,
+    /// Size of the generated request body for POST / PUT / PATCH;
+    /// `None` sends `{}`. `body` takes precedence.
+    pub upload_bytes: Option<usize>,
+    /// Request body, sent whatever the method.
+    pub body: Option<Vec<u8>>,
+    /// Extra request headers, in order.
+    pub headers: Vec<(String, String)>,
+    pub user_agent: String,
+    /// `Content-Type` for the body; defaults to `application/json`
+    /// (`application/octet-stream` for a generated upload).
+    pub content_type: Option<String>,
+    /// Statuses that count as success.
+    pub expected_status: StatusSet,
+    /// Redirects to follow; 0 (the default) judges the first
+    /// response.
+    pub max_redirects: usize,
+    /// Only follow redirects to the target's own scheme, host and
+    /// port.
+    pub same_origin_redirects: bool,
+    pub version: HttpVersion,
+    /// HTTP/2 PING frames sent after the response, timed one by one.
+    pub h2_pings: usize,
+    /// Credentials; `None` falls back to the URL's userinfo.
+    pub auth: Option<HttpAuth>,
+    /// CORS preflight headers to send and the answer to check.
+    pub cors: Option<CorsPreflight>,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl HttpPinger {
@@ -60,7 +186,22 @@ impl HttpPinger {
             method,
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
+            resumption: ResumptionMode::Off,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            upload_bytes: None,
+            body: None,
+            headers: Vec::new(),
+            user_agent: DEFAULT_USER_AGENT.into(),
+            content_type: None,
+            expected_status: StatusSet::new(vec![200..=399]),
+            max_redirects: 0,
+            same_origin_redirects: false,
+            version: HttpVersion::Http1,
+            h2_pings: 0,
+            auth: None,
+            cors: None,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -69,81 +210,526 @@ impl HttpPinger {
         self
     }
 
+    /// For `https://`, send the request twice: over a full handshake,
+    /// then over a connection resuming that session — with the
+    /// request as 0-RTT early data for `ResumptionMode::EarlyData`.
+    /// The report is the second response's, plus `full_ms` /
+    /// `resumed_ms` (handshake through response, TCP connect
+    /// excluded, which is where 0-RTT saves its round trip),
+    /// `resumption` and `early_data`.
+    pub fn with_resumption(mut self, mode: ResumptionMode) -> Self {
+        self.resumption = mode;
+        self
+    }
+
+    /// Read at most `bytes` of the response body (default 1 MiB).
+    /// Raise it to time a large download end to end.
+    pub fn with_max_body_bytes(mut self, bytes: usize) -> Self {
+        self.max_body_bytes = bytes;
+        self
+    }
+
+    /// For POST / PUT / PATCH, upload `bytes` of generated
+    /// `application/octet-stream` instead of the default `{}`, and
+    /// time it. Ignored by methods without a body.
+    pub fn with_upload_bytes(mut self, bytes: usize) -> Self {
+        self.upload_bytes = Some(bytes);
+        self
+    }
+
+    /// Send `body` as the request body, for any method.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Add a request header. One named `Host`, `User-Agent` or
+    /// `Content-Type` replaces the default; `Content-Length` is
+    /// always computed from the body.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Statuses that count as success, replacing the 2xx / 3xx
+    /// default — e.g. `"200,401".parse()?` for an endpoint behind
+    /// auth.
+    pub fn with_expected_status(mut self, set: StatusSet) -> Self {
+        self.expected_status = set;
+        self
+    }
+
+    /// Follow up to `max_hops` redirects (301 / 302 / 303 / 307 /
+    /// 308 with a `Location`) and judge the final response. 303 —
+    /// and 301 / 302 after a POST — switch to GET without the body;
+    /// 307 / 308 repeat the request as is. The report adds
+    /// `redirects`, `final_url` and, per hop followed, `hopN_url`,
+    /// `hopN_status` and `hopN_ms`. A redirect back to a URL already
+    /// visited, or one past `max_hops`, fails. The timeout applies
+    /// to each hop.
+    pub fn with_redirects(mut self, max_hops: usize) -> Self {
+        self.max_redirects = max_hops;
+        self
+    }
+
+    /// With `with_redirects`, stop at a redirect leaving the target's
+    /// origin: that response is the final one, with the refused
+    /// location in `redirect_blocked`.
+    pub fn with_same_origin_redirects(mut self, same_origin: bool) -> Self {
+        self.same_origin_redirects = same_origin;
+        self
+    }
+
+    /// Speak `version`. HTTP/2 negotiates ALPN `h2` over TLS (failing
+    /// if the server picks anything else) and uses prior knowledge
+    /// (h2c) in plaintext; the report adds `version` and
+    /// `settings_ms`, the time from the connection preface to the
+    /// server acknowledging our SETTINGS. Session resumption is
+    /// HTTP/1.1 only.
+    pub fn with_version(mut self, version: HttpVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// With HTTP/2, send `count` PING frames over the open connection
+    /// after the response and report their round trip as
+    /// `ping_rtt_ms` (mean), `ping_rtt_min_ms` and `ping_rtt_max_ms`
+    /// — network RTT without a new request in the way.
+    pub fn with_h2_pings(mut self, count: usize) -> Self {
+        self.h2_pings = count;
+        self
+    }
+
+    /// Authenticate with `auth` instead of any `user:password@` in
+    /// the target. Credentials are only sent to the target's origin,
+    /// never to a redirect elsewhere. An explicit `Authorization`
+    /// header (`with_header`) wins over both.
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Send `preflight`'s `Origin` and `Access-Control-Request-*`
+    /// headers — usually with `HttpMethod::Options` — and fail the
+    /// probe unless the response's `Access-Control-Allow-*` headers
+    /// allow them, like a browser would. The report adds `cors`.
+    pub fn with_cors(mut self, preflight: CorsPreflight) -> Self {
+        self.cors = Some(preflight);
+        self
+    }
+
     pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
         self.tls_config = Some(config);
         self
     }
 
-    fn build_request(&self, uri: &URI, host_header: &str) -> String {
-        let method = self.method.as_str();
-        let path = if uri.path.is_empty() { "/" } else { &uri.path };
-        if self.method.has_body() {
-            format!(
-                "{method} {path} HTTP/1.1\r\n\
-                 Host: {host_header}\r\n\
-                 User-Agent: Knock Knock\r\n\
-                 Content-Type: application/json\r\n\
-                 Content-Length: 2\r\n\
-                 \r\n\
-                 {{}}\r\n\
-                 \r\n",
-            )
-        } else {
-            format!(
-                "{method} {path} HTTP/1.1\r\n\
-                 Host: {host_header}\r\n\
-                 User-Agent: Knock Knock\r\n\
-                 \r\n",
-            )
-        }
-    }
-
-    async fn ping_plain(&self, uri: &URI) -> Result<()> {
+    /// Shared TLS settings for `https://`, over HTTP/1.1 and HTTP/2
+    /// alike.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    fn build_request(
+        &self,
+        uri: &URI,
+        host_header: &str,
+        hop: &Hop,
+        authorization: Option<&str>,
+    ) -> Request {
+        let method = hop.method.to_string();
+        let path = request_target(uri);
+        let body = match (&self.body, hop.method.has_body(), self.upload_bytes) {
+            _ if !hop.body => None,
+            (Some(body), _, _) => Some(("application/json", Payload::Bytes(body.clone()))),
+            (None, false, _) => None,
+            (None, true, Some(len)) => Some(("application/octet-stream", Payload::Filler(len))),
+            (None, true, None) => Some(("application/json", Payload::Bytes(b"{}".to_vec()))),
+        };
+
+        let custom = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        };
+        let mut headers = Vec::new();
+        if !custom("host") {
+            headers.push(("Host".to_string(), host_header.to_string()));
+        }
+        if !custom("user-agent") {
+            headers.push(("User-Agent".to_string(), self.user_agent.clone()));
+        }
+        if let Some(value) = authorization.filter(|_| !custom("authorization")) {
+            headers.push(("Authorization".to_string(), value.to_string()));
+        }
+        if let Some(cors) = &self.cors {
+            headers.extend(cors.request_headers().filter(|(name, _)| !custom(name)));
+        }
+        for (name, value) in &self.headers {
+            if !name.eq_ignore_ascii_case("content-length") {
+                headers.push((name.clone(), value.clone()));
+            }
+        }
+        if let Some((default_type, payload)) = &body {
+            if !custom("content-type") {
+                let content_type = self.content_type.as_deref().unwrap_or(default_type);
+                headers.push(("Content-Type".to_string(), content_type.to_string()));
+            }
+            headers.push(("Content-Length".to_string(), payload.len().to_string()));
+        }
+        Request {
+            method,
+            path,
+            headers,
+            body: body.map(|(_, payload)| payload),
+            max_body_bytes: self.max_body_bytes,
+        }
+    }
+
+    /// Credentials for the target: `with_auth`, else its userinfo.
+    fn credentials(&self) -> Option<HttpAuth> {
+        self.auth
+            .clone()
+            .or_else(|| HttpAuth::from_userinfo(&get_uri(&self.target)))
+    }
+
+    /// One request to `target` presenting `auth`, no redirects
+    /// followed. Digest costs a second request when challenged.
+    async fn fetch(&self, target: &str, hop: &Hop, auth: Option<&HttpAuth>) -> Result<Report> {
+        let uri = get_uri(target);
+        let Some(auth) = auth else {
+            return self.send(&uri, hop, None).await;
+        };
+        if let Some(header) = auth.preemptive() {
+            let report = self.send(&uri, hop, Some(&header)).await?;
+            return Ok(report.with_detail("auth", auth.scheme()));
+        }
+        let start = Instant::now();
+        let challenged = self.send(&uri, hop, None).await?;
+        let answer = match challenged.status {
+            Some(401) => auth.answer(
+                &auth::challenges(&challenged.headers),
+                hop.method.as_str(),
+                &request_target(&uri),
+            ),
+            _ => None,
+        };
+        let Some(header) = answer else {
+            return Ok(challenged);
+        };
+        let challenge = start.elapsed();
+        let report = self.send(&uri, hop, Some(&header)).await?;
+        Ok(report
+            .with_detail("auth", auth.scheme())
+            .with_detail("auth_challenge_ms", format_ms(challenge)))
+    }
+
+    async fn send(&self, uri: &URI, hop: &Hop, authorization: Option<&str>) -> Result<Report> {
+        let scheme = uri.scheme.to_ascii_lowercase();
+        match scheme.as_str() {
+            "" | "http" => self.exchange_plain(uri, hop, authorization).await,
+            "https" => self.exchange_tls(uri, hop, authorization).await,
+            other => Err(io::Error::other(format!(
+                "scheme '{other}' is not supported by HttpPinger (use http:// or https://)"
+            ))),
+        }
+    }
+
+    /// Fetch the target, then each redirect it leads to.
+    async fn follow(&self) -> Result<Report> {
+        let mut url = canonical_url(&get_uri(&self.target));
+        let origin = origin_of(&url).to_string();
+        let auth = self.credentials();
+        let mut hop = Hop {
+            method: self.method.clone(),
+            body: true,
+        };
+        let mut visited = vec![url.clone()];
+        let mut hops = Vec::new();
+        loop {
+            let start = Instant::now();
+            // Credentials stay with the target's origin.
+            let auth = auth.as_ref().filter(|_| origin_of(&url) == origin);
+            let report = self.fetch(&url, &hop, auth).await?;
+            let elapsed = start.elapsed();
+            let status = report.status.unwrap_or_default();
+            let location = match (status, report.header("location")) {
+                (301 | 302 | 303 | 307 | 308, Some(location)) => location.to_string(),
+                _ => return Ok(annotate_redirects(report, &hops, &url)),
+            };
+            let next = resolve_location(&url, &location)?;
+            if self.same_origin_redirects && origin_of(&next) != origin {
+                let report = report.with_detail("redirect_blocked", next);
+                return Ok(annotate_redirects(report, &hops, &url));
+            }
+            hops.push((url, status, elapsed));
+            if visited.contains(&next) {
+                let chain: Vec<&str> = visited.iter().map(String::as_str).collect();
+                return Err(io::Error::other(format!(
+                    "redirect loop: {} -> {next}",
+                    chain.join(" -> ")
+                )));
+            }
+            if hops.len() > self.max_redirects {
+                return Err(io::Error::other(format!(
+                    "more than {} redirects (next: {next})",
+                    self.max_redirects
+                )));
+            }
+            hop = hop.redirected(status);
+            visited.push(next.clone());
+            url = next;
+        }
+    }
+
+    async fn exchange_plain(
+        &self,
+        uri: &URI,
+        hop: &Hop,
+        authorization: Option<&str>,
+    ) -> Result<Report> {
         let endpoint = endpoint_for(uri, 80)?;
-        let request = self.build_request(uri, &endpoint);
+        let request = self.build_request(uri, &endpoint, hop, authorization);
         with_timeout(self.timeout, async move {
-            let mut stream = TcpStream::connect(&endpoint).await?;
-            run_exchange(&mut stream, &request).await
+            let mut stream = observe::connect_tcp(&endpoint).await?;
+            match self.version {
+                HttpVersion::Http1 => run_exchange(&mut stream, &request).await,
+                #[cfg(feature = "http2")]
+                HttpVersion::Http2 => {
+                    crate::http2::run_exchange(stream, &request, "http", self.h2_pings).await
+                }
+            }
         })
         .await
     }
 
-    async fn ping_tls(&self, uri: &URI) -> Result<()> {
+    async fn exchange_tls(
+        &self,
+        uri: &URI,
+        hop: &Hop,
+        authorization: Option<&str>,
+    ) -> Result<Report> {
         let endpoint = endpoint_for(uri, 443)?;
-        let server_name = ServerName::try_from(uri.domain.clone())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-        let config = self
-            .tls_config
-            .clone()
-            .unwrap_or_else(default_client_config);
-        let request = self.build_request(uri, &endpoint);
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict) = client_config(&self.tls_config, &self.tls_options);
+        let request = self.build_request(uri, &endpoint, hop, authorization);
+        let mode = self.resumption;
+
+        #[cfg(feature = "http2")]
+        if self.version == HttpVersion::Http2 {
+            if mode != ResumptionMode::Off {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "session resumption is only supported over HTTP/1.1",
+                ));
+            }
+            let config = crate::http2::with_h2_alpn(config);
+            return with_timeout(self.timeout, async move {
+                let tcp = observe::connect_tcp(&endpoint).await?;
+                let stream = TlsConnector::from(config).connect(server_name, tcp).await?;
+                observe::tls_handshake(stream.get_ref().1);
+                crate::http2::check_alpn(stream.get_ref().1.alpn_protocol())?;
+                crate::http2::run_exchange(stream, &request, "https", self.h2_pings).await
+            })
+            .await
+            .map(|report| verdict.annotate(report));
+        }
 
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
-            let connector = TlsConnector::from(config);
-            let mut stream = connector.connect(server_name, tcp).await?;
-            run_exchange(&mut stream, &request).await
+            if mode == ResumptionMode::Off {
+                let connector = TlsConnector::from(config);
+                let (report, _) =
+                    exchange_once(&endpoint, server_name, &connector, &request).await?;
+                return Ok(report);
+            }
+            let connector = mode.connector(mode.prepare(&config));
+            let (_, full) =
+                exchange_once(&endpoint, server_name.clone(), &connector, &request).await?;
+            let (report, resumed) =
+                exchange_once(&endpoint, server_name, &connector, &request).await?;
+            Ok(resume::annotate(report, mode, full, resumed))
         })
         .await
+        .map(|report| verdict.annotate(report))
     }
 }
 
 #[async_trait]
 impl Pinger for HttpPinger {
     async fn ping(&self) -> Result<()> {
-        let uri = get_uri(&self.target);
-        let scheme = uri.scheme.to_ascii_lowercase();
-        match scheme.as_str() {
-            "" | "http" => self.ping_plain(&uri).await,
-            "https" => self.ping_tls(&uri).await,
-            other => Err(io::Error::other(format!(
-                "scheme '{other}' is not supported by HttpPinger (use http:// or https://)"
-            ))),
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let report = self.exchange().await?;
+        let mut failures = Vec::new();
+        match report.status {
+            Some(code) if !self.expected_status.contains(code) => {
+                failures.push(format!("status {code} not in {}", self.expected_status));
+            }
+            _ => {}
+        }
+        if let Some(cors) = &self.cors {
+            failures.extend(cors.check(&report.headers));
         }
+        if !failures.is_empty() {
+            return Err(AssertionError { failures }.into());
+        }
+        Ok(match &self.cors {
+            Some(_) => report.with_detail("cors", "allowed"),
+            None => report,
+        })
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        if self.max_redirects > 0 {
+            return self.follow().await;
+        }
+        let hop = Hop {
+            method: self.method.clone(),
+            body: true,
+        };
+        self.fetch(&self.target, &hop, self.credentials().as_ref())
+            .await
+    }
+}
+
+/// The method of one request in a redirect chain, and whether it
+/// still carries the body.
+#[derive(Clone)]
+struct Hop {
+    method: HttpMethod,
+    body: bool,
+}
+
+impl Hop {
+    /// The request to send after a `status` redirect (RFC 9110
+    /// §15.4): 303 becomes a bodiless GET (HEAD stays HEAD), and so do
+    /// 301 / 302 after a POST, as browsers do; 307 / 308 keep
+    /// everything.
+    fn redirected(self, status: u16) -> Hop {
+        match (status, &self.method) {
+            (303, HttpMethod::Head) => Hop {
+                method: HttpMethod::Head,
+                body: false,
+            },
+            (303, _) | (301 | 302, HttpMethod::Post) => Hop {
+                method: HttpMethod::Get,
+                body: false,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// `scheme://host[:port]/path[?query]`, lower-cased scheme and host,
+/// default port and fragment dropped — the form redirect loops are
+/// detected in. A schemeless target is `http`.
+fn canonical_url(uri: &URI) -> String {
+    let scheme = match uri.scheme.to_ascii_lowercase() {
+        s if s.is_empty() => "http".to_string(),
+        s => s,
+    };
+    let default_port = if scheme == "https" { 443 } else { 80 };
+    let host = uri.domain.to_ascii_lowercase();
+    let port = match uri.port {
+        0 => String::new(),
+        p if p == default_port => String::new(),
+        p => format!(":{p}"),
+    };
+    let path = if uri.path.is_empty() { "/" } else { &uri.path };
+    let query = if uri.query.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", uri.query)
+    };
+    format!("{scheme}://{host}{port}{path}{query}")
+}
+
+/// Path and query of `uri`, as sent in the request line.
+pub(crate) fn request_target(uri: &URI) -> String {
+    let path = if uri.path.is_empty() { "/" } else { &uri.path };
+    if uri.query.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}?{}", uri.query)
     }
 }
 
-fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
+/// `scheme://host[:port]` of a canonical URL.
+fn origin_of(url: &str) -> &str {
+    let after_scheme = url.find("://").map_or(0, |i| i + 3);
+    url[after_scheme..]
+        .find('/')
+        .map_or(url, |i| &url[..after_scheme + i])
+}
+
+/// `location` resolved against the canonical URL `base`: absolute,
+/// scheme-relative (`//host/x`), absolute-path (`/x`) or relative
+/// (`x`, `../x`) references.
+fn resolve_location(base: &str, location: &str) -> Result<String> {
+    let location = location.trim();
+    let absolute = if location.contains("://") {
+        location.to_string()
+    } else if let Some(rest) = location.strip_prefix("//") {
+        let scheme = &base[..base.find("://").unwrap_or(0)];
+        format!("{scheme}://{rest}")
+    } else if location.starts_with('/') {
+        format!("{}{location}", origin_of(base))
+    } else {
+        let origin = origin_of(base);
+        let path = base[origin.len()..].split('?').next().unwrap_or("/");
+        let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        let joined = format!("{dir}{location}");
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in joined.split('/').skip(1) {
+            match segment {
+                ".." => {
+                    segments.pop();
+                }
+                "." => {}
+                s => segments.push(s),
+            }
+        }
+        format!("{origin}/{}", segments.join("/"))
+    };
+    let uri = get_uri(&absolute);
+    match uri.scheme.to_ascii_lowercase().as_str() {
+        "http" | "https" if !uri.domain.is_empty() => Ok(canonical_url(&uri)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported redirect location {location:?}"),
+        )),
+    }
+}
+
+/// The redirect details for the final `report`, fetched from `url`
+/// after `hops` (URL, status, time) were followed.
+fn annotate_redirects(report: Report, hops: &[(String, u16, Duration)], url: &str) -> Report {
+    let mut report = report
+        .with_detail("redirects", hops.len().to_string())
+        .with_detail("final_url", url);
+    for (n, (hop_url, status, elapsed)) in hops.iter().enumerate() {
+        let n = n + 1;
+        report = report
+            .with_detail(format!("hop{n}_url"), hop_url.clone())
+            .with_detail(format!("hop{n}_status"), status.to_string())
+            .with_detail(format!("hop{n}_ms"), format_ms(*elapsed));
+    }
+    report
+}
+
+pub(crate) fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
     if uri.domain.is_empty() {
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -158,31 +744,573 @@ fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
     Ok(format!("{}:{}", uri.domain, port))
 }
 
-/// Send a request and validate the response status line. Generic over
-/// the stream type so the same code drives both the plain TCP and the
-/// rustls-wrapped paths.
-async fn run_exchange<S>(stream: &mut S, request: &str) -> Result<()>
+/// One TCP connect + TLS handshake + request / response.
+async fn exchange_once(
+    endpoint: &str,
+    server_name: ServerName<'static>,
+    connector: &TlsConnector,
+    request: &Request,
+) -> Result<(Report, Handshake)> {
+    let tcp = observe::connect_tcp(endpoint).await?;
+    let start = Instant::now();
+    let mut stream = connector.connect(server_name, tcp).await?;
+    // Still handshaking means 0-RTT is on offer: the request goes out
+    // as early data and the handshake finishes underneath it.
+    let early = stream.get_ref().1.is_handshaking();
+    if !early {
+        observe::tls_handshake(stream.get_ref().1);
+    }
+    let report = run_exchange(&mut stream, request).await?;
+    if early {
+        observe::tls_handshake(stream.get_ref().1);
+    }
+    Ok((report, Handshake::of(start.elapsed(), stream.get_ref().1)))
+}
+
+/// A request ready to send, in any HTTP version.
+pub(crate) struct Request {
+    pub(crate) method: String,
+    /// Path and query.
+    pub(crate) path: String,
+    /// Header fields in order, `Host` included.
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<Payload>,
+    pub(crate) max_body_bytes: usize,
+}
+
+impl Request {
+    /// The HTTP/1.1 request line and headers, through the blank line.
+    fn head(&self) -> String {
+        let mut head = format!("{} {} HTTP/1.1\r\n", self.method, self.path);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+        head
+    }
+}
+
+pub(crate) enum Payload {
+    Bytes(Vec<u8>),
+    /// `len` generated bytes, written in `IO_CHUNK` pieces.
+    Filler(usize),
+}
+
+impl Payload {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Payload::Bytes(bytes) => bytes.len(),
+            Payload::Filler(len) => *len,
+        }
+    }
+
+    /// `len` bytes from `offset`.
+    #[cfg(feature = "http2")]
+    pub(crate) fn slice(&self, offset: usize, len: usize) -> Vec<u8> {
+        match self {
+            Payload::Bytes(bytes) => bytes[offset..offset + len].to_vec(),
+            Payload::Filler(_) => vec![b'x'; len],
+        }
+    }
+
+    async fn write<S: AsyncWrite + Unpin>(&self, stream: &mut S) -> Result<()> {
+        match self {
+            Payload::Bytes(bytes) => stream.write_all(bytes).await,
+            Payload::Filler(len) => {
+                let filler = [b'x'; IO_CHUNK];
+                let mut left = *len;
+                while left > 0 {
+                    let n = left.min(IO_CHUNK);
+                    stream.write_all(&filler[..n]).await?;
+                    left -= n;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How the response body is delimited (RFC 9112 §6.3).
+#[derive(Clone, Copy)]
+enum Framing {
+    /// 1xx / 204 / 304, or any answer to HEAD: no body whatever the
+    /// headers say.
+    Empty,
+    Length(usize),
+    Chunked,
+    UntilClose,
+}
+
+impl Framing {
+    fn of(method: &str, status: u16, headers: &[(String, String)]) -> Result<Self> {
+        if method == "HEAD" || (100..200).contains(&status) || status == 204 || status == 304 {
+            return Ok(Framing::Empty);
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        if let Some(codings) = header("transfer-encoding") {
+            let last = codings.rsplit(',').next().unwrap_or_default().trim();
+            return Ok(if last.eq_ignore_ascii_case("chunked") {
+                Framing::Chunked
+            } else {
+                Framing::UntilClose
+            });
+        }
+        match header("content-length") {
+            Some(len) => len.parse().map(Framing::Length).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid Content-Length: {len}"),
+                )
+            }),
+            None => Ok(Framing::UntilClose),
+        }
+    }
+}
+
+/// Response body as read: every byte counted, the first
+/// `KEPT_BODY_BYTES` kept, reading stopped at `limit`.
+pub(crate) struct Body {
+    kept: Vec<u8>,
+    received: usize,
+    limit: usize,
+    pub(crate) truncated: bool,
+}
+
+impl Body {
+    pub(crate) fn new(limit: usize) -> Self {
+        Body {
+            kept: Vec::new(),
+            received: 0,
+            limit,
+            truncated: false,
+        }
+    }
+
+    pub(crate) fn room(&self) -> usize {
+        self.limit - self.received
+    }
+
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        let keep = KEPT_BODY_BYTES
+            .saturating_sub(self.kept.len())
+            .min(bytes.len());
+        self.kept.extend_from_slice(&bytes[..keep]);
+        self.received += bytes.len();
+    }
+}
+
+/// Buffered reads over the response stream.
+struct Reader<'a, S> {
+    stream: &'a mut S,
+    buf: Vec<u8>,
+    chunk: Box<[u8]>,
+}
+
+impl<S: AsyncRead + Unpin> Reader<'_, S> {
+    /// Read more into `buf`; `false` at end of stream.
+    async fn fill(&mut self) -> Result<bool> {
+        let n = self.stream.read(&mut self.chunk).await?;
+        self.buf.extend_from_slice(&self.chunk[..n]);
+        Ok(n > 0)
+    }
+
+    /// The next response head, parsed and consumed.
+    async fn head(&mut self) -> Result<(u16, Vec<(String, String)>)> {
+        let head_end = loop {
+            if let Some(pos) = find(&self.buf, HEADER_TERMINATOR) {
+                break pos;
+            }
+            if self.buf.len() >= MAX_HEAD_BYTES {
+                return Err(io::Error::other(format!(
+                    "HTTP response head exceeds {MAX_HEAD_BYTES} bytes"
+                )));
+            }
+            // End of stream, or not HTTP at all — no point waiting
+            // for a blank line either way.
+            if !self.fill().await? || !b"HTTP/".starts_with(&self.buf[..self.buf.len().min(5)]) {
+                break self.buf.len();
+            }
+        };
+        let head = String::from_utf8_lossy(&self.buf[..head_end]).into_owned();
+        self.buf
+            .drain(..(head_end + HEADER_TERMINATOR.len()).min(self.buf.len()));
+        parse_response_head(&head, "HTTP/").ok_or_else(|| {
+            io::Error::other("response is not HTTP/1.x (wrong port? wrong protocol?)")
+        })
+    }
+
+    /// The final response head: 100 Continue / 103 Early Hints come
+    /// ahead of it and are skipped; 101 is final (the protocol
+    /// switches).
+    async fn final_head(&mut self) -> Result<(u16, Vec<(String, String)>)> {
+        loop {
+            let (status, headers) = self.head().await?;
+            if !(100..200).contains(&status) || status == 101 {
+                return Ok((status, headers));
+            }
+        }
+    }
+
+    /// The size from the next chunk-size line, extensions ignored.
+    async fn chunk_size(&mut self) -> Result<usize> {
+        let line = self.line().await?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        usize::from_str_radix(size, 16)
+            .map_err(|_| invalid_body(&format!("bad chunk size {size:?}")))
+    }
+
+    /// One CRLF-terminated line (chunk size, trailer), consumed.
+    async fn line(&mut self) -> Result<String> {
+        loop {
+            if let Some(pos) = find(&self.buf, b"\r\n") {
+                let line = String::from_utf8_lossy(&self.buf[..pos]).into_owned();
+                self.buf.drain(..pos + 2);
+                return Ok(line);
+            }
+            if self.buf.len() >= MAX_LINE_BYTES {
+                return Err(invalid_body("chunk line too long"));
+            }
+            if !self.fill().await? {
+                return Err(truncated_body("in chunked framing"));
+            }
+        }
+    }
+
+    /// Move up to `len` bytes into `body`; fewer only at end of
+    /// stream. Returns how many moved.
+    async fn copy(&mut self, len: usize, body: &mut Body) -> Result<usize> {
+        let mut moved = 0;
+        while moved < len {
+            if self.buf.is_empty() && !self.fill().await? {
+                break;
+            }
+            let n = self.buf.len().min(len - moved);
+            body.push(&self.buf[..n]);
+            self.buf.drain(..n);
+            moved += n;
+        }
+        Ok(moved)
+    }
+
+    async fn body(&mut self, framing: Framing, limit: usize) -> Result<Body> {
+        let mut body = Body::new(limit);
+        match framing {
+            Framing::Empty => {}
+            Framing::Length(len) => {
+                let want = len.min(body.room());
+                if self.copy(want, &mut body).await? < want {
+                    return Err(truncated_body(&format!(
+                        "after {} of {len} bytes",
+                        body.received
+                    )));
+                }
+                body.truncated = body.received < len;
+            }
+            Framing::Chunked => loop {
+                let size = self.chunk_size().await?;
+                if size == 0 {
+                    // Trailer fields, up to the blank line.
+                    while !self.line().await?.is_empty() {}
+                    break;
+                }
+                let want = size.min(body.room());
+                if self.copy(want, &mut body).await? < want {
+                    return Err(truncated_body("mid-chunk"));
+                }
+                if want < size {
+                    body.truncated = true;
+                    break;
+                }
+                if !self.line().await?.is_empty() {
+                    return Err(invalid_body("chunk data overruns its size"));
+                }
+            },
+            Framing::UntilClose => {
+                let room = body.room();
+                self.copy(room, &mut body).await?;
+                // At the cap with more to come means truncated; peek
+                // one read to tell.
+                body.truncated = body.room() == 0 && (!self.buf.is_empty() || self.fill().await?);
+            }
+        }
+        Ok(body)
+    }
+}
+
+fn invalid_body(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("HTTP body: {msg}"))
+}
+
+fn truncated_body(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("HTTP body ended early {msg}"),
+    )
+}
+
+/// `bytes` over `elapsed`, as whole bytes per second.
+fn per_second(bytes: usize, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        format!("{:.0}", bytes as f64 / secs)
+    } else {
+        "0".into()
+    }
+}
+
+/// A response read as it arrives, for bodies that may never end
+/// (Server-Sent Events). `next` yields the body in the pieces it
+/// arrives in, framing removed.
+#[cfg(feature = "sse")]
+pub(crate) struct Streaming<'a, S> {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    reader: Reader<'a, S>,
+    framing: Framing,
+    /// Bytes left in the current chunk, or in a `Content-Length` body.
+    left: usize,
+    done: bool,
+}
+
+#[cfg(feature = "sse")]
+impl<S: AsyncRead + Unpin> Streaming<'_, S> {
+    /// The next piece of body; `None` once it has ended.
+    pub(crate) async fn next(&mut self) -> Result<Option<Vec<u8>>> {
+        while !self.done {
+            let bounded = match self.framing {
+                Framing::Empty => {
+                    self.done = true;
+                    continue;
+                }
+                Framing::Chunked if self.left == 0 => {
+                    let size = self.reader.chunk_size().await?;
+                    if size == 0 {
+                        while !self.reader.line().await?.is_empty() {}
+                        self.done = true;
+                        continue;
+                    }
+                    self.left = size;
+                    true
+                }
+                Framing::Length(_) if self.left == 0 => {
+                    self.done = true;
+                    continue;
+                }
+                Framing::Chunked | Framing::Length(_) => true,
+                Framing::UntilClose => false,
+            };
+            if self.reader.buf.is_empty() && !self.reader.fill().await? {
+                if bounded {
+                    return Err(truncated_body("mid-stream"));
+                }
+                self.done = true;
+                continue;
+            }
+            let n = if bounded {
+                self.reader.buf.len().min(self.left)
+            } else {
+                self.reader.buf.len()
+            };
+            let piece = self.reader.buf.drain(..n).collect();
+            if bounded {
+                self.left -= n;
+            }
+            if matches!(self.framing, Framing::Chunked)
+                && self.left == 0
+                && !self.reader.line().await?.is_empty()
+            {
+                return Err(invalid_body("chunk data overruns its size"));
+            }
+            return Ok(Some(piece));
+        }
+        Ok(None)
+    }
+}
+
+/// Send a request and read up to the final response head, leaving
+/// the body to `Streaming::next`.
+#[cfg(feature = "sse")]
+pub(crate) async fn open_stream<'a, S>(
+    stream: &'a mut S,
+    request: &Request,
+) -> Result<Streaming<'a, S>>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
-    stream.write_all(request.as_bytes()).await?;
+    send_request(stream, request).await?;
+    let mut reader = Reader {
+        stream,
+        buf: Vec::new(),
+        chunk: vec![0u8; IO_CHUNK].into_boxed_slice(),
+    };
+    let (status, headers) = reader.final_head().await?;
+    let framing = Framing::of(&request.method, status, &headers)?;
+    Ok(Streaming {
+        status,
+        headers,
+        reader,
+        framing,
+        left: match framing {
+            Framing::Length(len) => len,
+            _ => 0,
+        },
+        done: false,
+    })
+}
 
-    let mut buffer = [0u8; BUF_SIZE];
-    let _ = stream.read(&mut buffer).await?;
+/// Write the request head and body; returns how long the body took.
+async fn send_request<S>(stream: &mut S, request: &Request) -> Result<Duration>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream.write_all(request.head().as_bytes()).await?;
+    let upload_start = Instant::now();
+    if let Some(body) = &request.body {
+        body.write(stream).await?;
+    }
+    stream.flush().await?;
+    let upload = upload_start.elapsed();
+    observe::phase("request");
+    Ok(upload)
+}
 
-    let buffer_str = String::from_utf8_lossy(&buffer);
-    let status_line = buffer_str.split("\r\n").next().unwrap_or("");
+/// Send a request and read the whole response: interim 1xx heads
+/// skipped, the body read per its framing up to the request's cap.
+/// Generic over the stream type so the same code drives both the
+/// plain TCP and the rustls-wrapped paths. Judging the status is left
+/// to the caller.
+async fn run_exchange<S>(stream: &mut S, request: &Request) -> Result<Report>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let upload = send_request(stream, request).await?;
 
-    if !status_line.starts_with("HTTP/") {
-        return Err(io::Error::other(
-            "response is not HTTP/1.x (wrong port? wrong protocol?)",
-        ));
+    let sent = Instant::now();
+    let mut reader = Reader {
+        stream,
+        buf: Vec::new(),
+        chunk: vec![0u8; IO_CHUNK].into_boxed_slice(),
+    };
+    let (status, headers) = reader.final_head().await?;
+    let header_time = sent.elapsed();
+    observe::phase("headers");
+
+    let body_start = Instant::now();
+    let body = reader
+        .body(
+            Framing::of(&request.method, status, &headers)?,
+            request.max_body_bytes,
+        )
+        .await?;
+    let body_time = body_start.elapsed();
+    observe::phase("body");
+
+    Ok(transfer_report(
+        request,
+        upload,
+        (status, headers),
+        header_time,
+        body,
+        body_time,
+    ))
+}
+
+/// The report for one response — status, headers, kept body — with
+/// the timing details every HTTP version shares.
+pub(crate) fn transfer_report(
+    request: &Request,
+    upload: Duration,
+    (status, headers): (u16, Vec<(String, String)>),
+    header_time: Duration,
+    body: Body,
+    body_time: Duration,
+) -> Report {
+    let mut report = Report::new()
+        .with_detail("header_ms", format_ms(header_time))
+        .with_detail("body_ms", format_ms(body_time))
+        .with_detail("body_bytes", body.received.to_string())
+        .with_detail(
+            "download_bytes_per_sec",
+            per_second(body.received, body_time),
+        );
+    if body.truncated {
+        report = report.with_detail("body_truncated", "true");
     }
+    if let Some(payload) = &request.body {
+        report = report
+            .with_detail("upload_bytes", payload.len().to_string())
+            .with_detail("upload_ms", format_ms(upload))
+            .with_detail("upload_bytes_per_sec", per_second(payload.len(), upload));
+    }
+    Report {
+        status: Some(status),
+        headers,
+        body: Some(body.kept),
+        ..report
+    }
+}
 
-    for code in HTTP_UNCONNECT_STATUS_CODE {
-        if status_line.contains(code) {
-            return Err(io::Error::new(io::ErrorKind::NotFound, *code));
+fn find(buf: &[u8], needle: &[u8]) -> Option<usize> {
+    buf.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_location_handles_reference_forms() {
+        let base = "http://example.com/a/b?x=1";
+        let cases = [
+            ("https://Example.com:443/", "https://example.com/"),
+            ("//cdn.example.com/img", "http://cdn.example.com/img"),
+            ("/login?next=%2F", "http://example.com/login?next=%2F"),
+            ("c", "http://example.com/a/c"),
+            ("../c", "http://example.com/c"),
+            ("./c/", "http://example.com/a/c/"),
+        ];
+        for (location, want) in cases {
+            assert_eq!(
+                resolve_location(base, location).unwrap(),
+                want,
+                "{location}"
+            );
         }
+        assert!(resolve_location(base, "ftp://example.com/").is_err());
+    }
+
+    #[test]
+    fn http_method_parses_named_and_extension_methods() {
+        assert_eq!("head".parse::<HttpMethod>().unwrap(), HttpMethod::Head);
+        assert_eq!(
+            "Options".parse::<HttpMethod>().unwrap(),
+            HttpMethod::Options
+        );
+        let propfind: HttpMethod = "PROPFIND".parse().unwrap();
+        assert_eq!(propfind, HttpMethod::Other("PROPFIND".into()));
+        assert_eq!(propfind.to_string(), "PROPFIND");
+        assert!("".parse::<HttpMethod>().is_err());
+        assert!("GET /".parse::<HttpMethod>().is_err());
+    }
+
+    #[test]
+    fn canonical_url_drops_default_port_and_fragment() {
+        assert_eq!(
+            canonical_url(&get_uri("HTTP://Example.com:80/p?q#frag")),
+            "http://example.com/p?q"
+        );
+        assert_eq!(
+            canonical_url(&get_uri("localhost:8080")),
+            "http://localhost:8080/"
+        );
+        assert_eq!(
+            origin_of("https://example.com:8443/p"),
+            "https://example.com:8443"
+        );
     }
-    Ok(())
 }
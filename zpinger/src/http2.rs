@@ -0,0 +1,380 @@
+//! HTTP/2 transport for `HttpPinger` (`HttpVersion::Http2`), on the
+//! [`h2`](https://crates.io/crates/h2) crate. One connection, one
+//! request stream, then optionally a few PING frames timed over the
+//! same connection.
+//!
+//! h2 doesn't say when the server acknowledged our SETTINGS, so the
+//! connection's inbound bytes pass through `FrameWatch`, which reads
+//! just the 9-byte frame headers and notes when the SETTINGS ACK goes
+//! by.
+
+use std::future::poll_fn;
+use std::io::{self, Result};
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use h2::client::SendRequest;
+use h2::{PingPong, SendStream};
+use rustls::ClientConfig;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::http::{transfer_report, Body, Payload, Request, IO_CHUNK};
+use crate::observe;
+use crate::report::Report;
+use crate::util::format_ms;
+
+const ALPN_H2: &[u8] = b"h2";
+
+/// Headers HTTP/2 forbids (RFC 9113 §8.2.2); `Host` becomes
+/// `:authority`.
+const CONNECTION_SPECIFIC: &[&str] = &[
+    "connection",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// `config` offering ALPN `h2`, unless the caller chose protocols.
+pub(crate) fn with_h2_alpn(config: Arc<ClientConfig>) -> Arc<ClientConfig> {
+    if !config.alpn_protocols.is_empty() {
+        return config;
+    }
+    let mut config = (*config).clone();
+    config.alpn_protocols = vec![ALPN_H2.to_vec()];
+    Arc::new(config)
+}
+
+/// Fail unless the handshake settled on `h2`.
+pub(crate) fn check_alpn(negotiated: Option<&[u8]>) -> Result<()> {
+    match negotiated {
+        Some(ALPN_H2) => Ok(()),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "server did not negotiate HTTP/2 (ALPN: {})",
+                other.map_or("none".into(), |p| String::from_utf8_lossy(p).into_owned())
+            ),
+        )),
+    }
+}
+
+/// Run `request` as one HTTP/2 stream over `io`, then `pings` PING
+/// round trips. `scheme` goes into the `:scheme` pseudo-header.
+pub(crate) async fn run_exchange<S>(
+    io: S,
+    request: &Request,
+    scheme: &str,
+    pings: usize,
+) -> Result<Report>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let times = Arc::new(SettingsTimes::default());
+    let watch = FrameWatch {
+        inner: io,
+        header: [0; 9],
+        filled: 0,
+        skip: 0,
+        times: times.clone(),
+    };
+    let start = Instant::now();
+    let (client, mut connection) = h2::client::handshake(watch).await.map_err(h2_error)?;
+    let ping_pong = connection.ping_pong();
+
+    // The connection future does the actual I/O; drive it alongside
+    // the request until the request side is done.
+    let work = exchange(client, ping_pong, request, scheme, pings);
+    tokio::pin!(work);
+    let report = tokio::select! {
+        biased;
+        result = &mut work => result?,
+        result = &mut connection => {
+            result.map_err(h2_error)?;
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "HTTP/2 connection closed before the response",
+            ));
+        }
+    };
+
+    let report = report.with_detail("version", "HTTP/2");
+    Ok(match times.ack.get() {
+        Some(ack) => report.with_detail("settings_ms", format_ms(*ack - start)),
+        None => report,
+    })
+}
+
+async fn exchange(
+    client: SendRequest<Bytes>,
+    ping_pong: Option<PingPong>,
+    request: &Request,
+    scheme: &str,
+    pings: usize,
+) -> Result<Report> {
+    let mut client = client.ready().await.map_err(h2_error)?;
+    let upload_start = Instant::now();
+    let (response, mut stream) = client
+        .send_request(to_http(request, scheme)?, request.body.is_none())
+        .map_err(h2_error)?;
+    if let Some(payload) = &request.body {
+        send_payload(&mut stream, payload).await?;
+    }
+    let upload = upload_start.elapsed();
+    observe::phase("request");
+
+    let sent = Instant::now();
+    let response = response.await.map_err(h2_error)?;
+    let header_time = sent.elapsed();
+    observe::phase("headers");
+
+    let (parts, mut recv) = response.into_parts();
+    let headers = parts
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_string(), value)
+        })
+        .collect();
+    let body_start = Instant::now();
+    let mut body = Body::new(request.max_body_bytes);
+    while body.room() > 0 {
+        let Some(chunk) = recv.data().await else {
+            break;
+        };
+        let chunk = chunk.map_err(h2_error)?;
+        let _ = recv.flow_control().release_capacity(chunk.len());
+        let take = chunk.len().min(body.room());
+        body.push(&chunk[..take]);
+        body.truncated = take < chunk.len();
+    }
+    body.truncated |= body.room() == 0 && !recv.is_end_stream();
+    let body_time = body_start.elapsed();
+    observe::phase("body");
+
+    let report = transfer_report(
+        request,
+        upload,
+        (parts.status.as_u16(), headers),
+        header_time,
+        body,
+        body_time,
+    );
+    match ping_pong {
+        Some(ping_pong) if pings > 0 => ping_rtts(ping_pong, pings, report).await,
+        _ => Ok(report),
+    }
+}
+
+/// Time `count` PINGs, one after another, into `report`.
+async fn ping_rtts(mut ping_pong: PingPong, count: usize, report: Report) -> Result<Report> {
+    let mut rtts = Vec::with_capacity(count);
+    for _ in 0..count {
+        let start = Instant::now();
+        ping_pong.ping(h2::Ping::opaque()).await.map_err(h2_error)?;
+        rtts.push(start.elapsed());
+    }
+    observe::phase("ping");
+    let mean = rtts.iter().sum::<Duration>() / count as u32;
+    let min = rtts.iter().min().copied().unwrap_or_default();
+    let max = rtts.iter().max().copied().unwrap_or_default();
+    Ok(report
+        .with_detail("ping_rtt_ms", format_ms(mean))
+        .with_detail("ping_rtt_min_ms", format_ms(min))
+        .with_detail("ping_rtt_max_ms", format_ms(max)))
+}
+
+/// `request` as an `http::Request` for h2: `Host` moved into the URI
+/// authority, connection-specific headers dropped, names lower-cased.
+fn to_http(request: &Request, scheme: &str) -> Result<http::Request<()>> {
+    let authority = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+        .map_or("", |(_, value)| value.as_str());
+    let mut builder = http::Request::builder()
+        .method(request.method.as_str())
+        .uri(format!("{scheme}://{authority}{}", request.path))
+        .version(http::Version::HTTP_2);
+    for (name, value) in &request.headers {
+        let name = name.to_ascii_lowercase();
+        if !CONNECTION_SPECIFIC.contains(&name.as_str()) {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
+        .body(())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Write `payload` as DATA frames, as fast as flow control allows.
+async fn send_payload(stream: &mut SendStream<Bytes>, payload: &Payload) -> Result<()> {
+    let len = payload.len();
+    if len == 0 {
+        return stream.send_data(Bytes::new(), true).map_err(h2_error);
+    }
+    let mut sent = 0;
+    while sent < len {
+        stream.reserve_capacity((len - sent).min(IO_CHUNK));
+        let granted = poll_fn(|cx| stream.poll_capacity(cx))
+            .await
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "HTTP/2 stream closed during upload",
+                )
+            })?
+            .map_err(h2_error)?;
+        let n = granted.min(len - sent);
+        if n == 0 {
+            continue;
+        }
+        stream
+            .send_data(Bytes::from(payload.slice(sent, n)), sent + n == len)
+            .map_err(h2_error)?;
+        sent += n;
+    }
+    Ok(())
+}
+
+/// h2 errors as `io::Error`: I/O failures unwrapped, protocol errors
+/// (GOAWAY, RST_STREAM, bad frames) as `InvalidData`.
+fn h2_error(err: h2::Error) -> io::Error {
+    if err.is_io() {
+        if let Some(io) = err.into_io() {
+            return io;
+        }
+        return io::Error::other("HTTP/2 I/O error");
+    }
+    io::Error::new(io::ErrorKind::InvalidData, format!("HTTP/2: {err}"))
+}
+
+/// When the server's SETTINGS ACK arrived.
+#[derive(Default)]
+struct SettingsTimes {
+    ack: OnceLock<Instant>,
+}
+
+/// Pass-through stream that watches inbound frame headers for the
+/// SETTINGS ACK.
+struct FrameWatch<S> {
+    inner: S,
+    /// The frame header being assembled.
+    header: [u8; 9],
+    filled: usize,
+    /// Payload bytes left in the current frame.
+    skip: usize,
+    times: Arc<SettingsTimes>,
+}
+
+impl<S> FrameWatch<S> {
+    fn scan(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() && self.times.ack.get().is_none() {
+            if self.skip > 0 {
+                let n = self.skip.min(bytes.len());
+                self.skip -= n;
+                bytes = &bytes[n..];
+                continue;
+            }
+            let n = (self.header.len() - self.filled).min(bytes.len());
+            self.header[self.filled..self.filled + n].copy_from_slice(&bytes[..n]);
+            self.filled += n;
+            bytes = &bytes[n..];
+            if self.filled == self.header.len() {
+                self.filled = 0;
+                let [l0, l1, l2, kind, flags, ..] = self.header;
+                // SETTINGS (0x4) with the ACK flag (0x1).
+                if kind == 0x4 && flags & 0x1 != 0 {
+                    let _ = self.times.ack.set(Instant::now());
+                }
+                self.skip = u32::from_be_bytes([0, l0, l1, l2]) as usize;
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FrameWatch<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.scan(&buf.filled()[before..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FrameWatch<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(kind: u8, flags: u8, payload: &[u8]) -> Vec<u8> {
+        let len = (payload.len() as u32).to_be_bytes();
+        let mut frame = vec![len[1], len[2], len[3], kind, flags, 0, 0, 0, 0];
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn frame_watch_finds_settings_ack_across_reads() {
+        let mut watch = FrameWatch {
+            inner: (),
+            header: [0; 9],
+            filled: 0,
+            skip: 0,
+            times: Arc::default(),
+        };
+        let mut stream = frame(0x4, 0, &[0, 3, 0, 0, 0, 100]);
+        // A DATA frame whose payload looks like a SETTINGS ACK header.
+        stream.extend(frame(0x0, 0, &frame(0x4, 0x1, &[])));
+        watch.scan(&stream[..4]);
+        watch.scan(&stream[4..]);
+        assert!(watch.times.ack.get().is_none());
+        let ack = frame(0x4, 0x1, &[]);
+        watch.scan(&ack[..5]);
+        assert!(watch.times.ack.get().is_none());
+        watch.scan(&ack[5..]);
+        assert!(watch.times.ack.get().is_some());
+    }
+
+    #[test]
+    fn to_http_moves_host_and_drops_connection_headers() {
+        let request = Request {
+            method: "GET".into(),
+            path: "/a?b=1".into(),
+            headers: vec![
+                ("Host".into(), "example.com:8443".into()),
+                ("Connection".into(), "close".into()),
+                ("X-Trace".into(), "1".into()),
+            ],
+            body: None,
+            max_body_bytes: 0,
+        };
+        let http = to_http(&request, "https").unwrap();
+        assert_eq!(http.uri(), "https://example.com:8443/a?b=1");
+        assert_eq!(http.headers().len(), 1);
+        assert_eq!(http.headers()["x-trace"], "1");
+    }
+}
@@ -0,0 +1,357 @@
+//! HTTP/3 pinger — `QuicPinger`'s handshake plus one GET on a request
+//! stream, so a server that completes QUIC but doesn't actually serve
+//! HTTP/3 fails. Framing and QPACK come from
+//! [`h3`](https://crates.io/crates/h3) over
+//! [`h3-quinn`](https://crates.io/crates/h3-quinn); the QUIC side
+//! (resolution, rustls config, observer events) is shared with
+//! `quic.rs`.
+//!
+//! Schemes accepted: `https://`, `h3://`, or just `host[:port]/path`.
+//! Default port 443.
+
+use std::io::{self, Result};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Buf;
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{ClientConfig as QuinnClientConfig, Endpoint};
+use rustls::ClientConfig;
+use tokio_util::sync::CancellationToken;
+
+use crate::cancel::cancellable;
+use crate::expect::{AssertionError, StatusSet};
+use crate::observe;
+use crate::pinger::Pinger;
+use crate::quic::{build_rustls_config, report_handshake, CLOSE_GRACE};
+use crate::report::Report;
+use crate::tls::TlsOptions;
+use crate::uri::get_uri;
+use crate::util::{format_ms, resolve_first, unspecified_for};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_PORT: u16 = 443;
+const ALPN_H3: &[u8] = b"h3";
+/// Most response body bytes read before giving up on the rest.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// How much of the body `Report::body` keeps.
+const KEPT_BODY_BYTES: usize = 64 * 1024;
+
+/// HTTP/3 pinger. Completes the QUIC handshake with ALPN `h3`, sends
+/// `GET <path>` on a request stream and reads the response. Succeeds
+/// when the status is in `expected_status` (2xx / 3xx by default);
+/// any other status fails with an `AssertionError`.
+///
+/// `probe` reports the status, headers and up to 64 KiB of body, and
+/// splits the time into `handshake_ms` (QUIC + TLS) and `request_ms`
+/// (request stream opened to the last body byte), with `header_ms`
+/// (to the response HEADERS) and `body_bytes`.
+pub struct Http3Pinger {
+    pub url: String,
+    pub timeout: Duration,
+    pub expected_status: StatusSet,
+    tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
+}
+
+impl Http3Pinger {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            expected_status: StatusSet::new(vec![200..=399]),
+            tls_config: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, t: Duration) -> Self {
+        self.timeout = t;
+        self
+    }
+
+    /// Statuses that count as success. Default 200–399.
+    pub fn with_expected_status(mut self, set: StatusSet) -> Self {
+        self.expected_status = set;
+        self
+    }
+
+    /// Inject a custom rustls `ClientConfig`, e.g. one trusting a
+    /// self-signed test endpoint. Its ALPN list is replaced with `h3`.
+    pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Shared TLS settings. An `alpn` list there replaces the `h3`
+    /// this pinger offers.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    fn judge(&self, report: Report) -> Result<Report> {
+        match report.status {
+            Some(code) if !self.expected_status.contains(code) => Err(AssertionError {
+                failures: vec![format!("status {code} not in {}", self.expected_status)],
+            }
+            .into()),
+            _ => Ok(report),
+        }
+    }
+}
+
+#[async_trait]
+impl Pinger for Http3Pinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        self.judge(self.exchange().await?)
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        self.request(&CancellationToken::new()).await
+    }
+
+    /// Closes the endpoint on cancel, like `QuicPinger`.
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        self.judge(self.exchange_with_cancel(cancel).await?)
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        cancellable(cancel, self.request(cancel)).await
+    }
+}
+
+impl Http3Pinger {
+    async fn request(&self, cancel: &CancellationToken) -> Result<Report> {
+        let target = parse_url(&self.url)?;
+        let server_addr = resolve_first(&target.host, target.port).await?;
+        let mut crypto = build_rustls_config(self.tls_config.as_deref(), &[ALPN_H3.to_vec()])?;
+        let verdict = self.tls_options.configure(&mut crypto);
+        let quic_crypto = QuicClientConfig::try_from(crypto)
+            .map_err(|e| io::Error::other(format!("quinn rustls config: {e}")))?;
+
+        let mut endpoint = Endpoint::client(unspecified_for(server_addr))
+            .map_err(|e| io::Error::other(format!("quinn Endpoint::client: {e}")))?;
+        endpoint.set_default_client_config(QuinnClientConfig::new(Arc::new(quic_crypto)));
+
+        let outcome = tokio::select! {
+            outcome = tokio::time::timeout(
+                self.timeout,
+                self.exchange_on(&endpoint, server_addr, &target),
+            ) => outcome,
+            _ = cancel.cancelled() => {
+                endpoint.close(0u32.into(), b"cancelled");
+                // Flushed from its own task, as in `QuicPinger`:
+                // `cancellable` drops this future right after.
+                tokio::spawn(async move {
+                    let _ = tokio::time::timeout(CLOSE_GRACE, endpoint.wait_idle()).await;
+                });
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "HTTP/3 request cancelled",
+                ));
+            }
+        };
+        // Flush the CONNECTION_CLOSE before the socket goes away.
+        let _ = tokio::time::timeout(CLOSE_GRACE, endpoint.wait_idle()).await;
+        match outcome {
+            Ok(report) => report.map(|r| verdict.annotate(r)),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "HTTP/3 request timed out",
+            )),
+        }
+    }
+
+    async fn exchange_on(
+        &self,
+        endpoint: &Endpoint,
+        server_addr: std::net::SocketAddr,
+        target: &Target,
+    ) -> Result<Report> {
+        let start = Instant::now();
+        let connection = endpoint
+            .connect(server_addr, self.tls_options.sni_host(&target.host))
+            .map_err(|e| io::Error::other(format!("quinn connect: {e}")))?
+            .await
+            .map_err(|e| io::Error::other(format!("quinn handshake: {e}")))?;
+        let handshake = start.elapsed();
+        report_handshake(&connection);
+
+        let result = run_request(connection.clone(), target).await;
+        connection.close(0u32.into(), b"ping done");
+        let (report, request) = result?;
+        Ok(report
+            .with_detail("version", "HTTP/3")
+            .with_detail("handshake_ms", format_ms(handshake))
+            .with_detail("request_ms", format_ms(request)))
+    }
+}
+
+/// Drive the h3 connection while one GET runs on it. Returns the
+/// report and the request's duration.
+async fn run_request(connection: quinn::Connection, target: &Target) -> Result<(Report, Duration)> {
+    let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(connection))
+        .await
+        .map_err(h3_error)?;
+
+    let work = async {
+        let start = Instant::now();
+        let request = http::Request::get(format!("https://{}{}", target.authority, target.path))
+            .header("user-agent", "Knock Knock")
+            .body(())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        let mut stream = send_request.send_request(request).await.map_err(h3_error)?;
+        stream.finish().await.map_err(h3_error)?;
+        observe::phase("request");
+
+        let response = stream.recv_response().await.map_err(h3_error)?;
+        let header_time = start.elapsed();
+        observe::phase("headers");
+
+        let mut kept = Vec::new();
+        let mut received = 0;
+        let mut truncated = false;
+        while let Some(mut chunk) = stream.recv_data().await.map_err(h3_error)? {
+            let len = chunk.remaining();
+            let keep = len.min(KEPT_BODY_BYTES - kept.len());
+            kept.extend_from_slice(&chunk.copy_to_bytes(keep));
+            chunk.advance(len - keep);
+            received += len;
+            if received >= MAX_BODY_BYTES {
+                truncated = true;
+                break;
+            }
+        }
+        let elapsed = start.elapsed();
+        observe::phase("body");
+
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+        let mut report = Report::new()
+            .with_detail("header_ms", format_ms(header_time))
+            .with_detail("body_bytes", received.to_string());
+        if truncated {
+            report = report.with_detail("body_truncated", "true");
+        }
+        let report = Report {
+            status: Some(response.status().as_u16()),
+            headers,
+            body: Some(kept),
+            ..report
+        };
+        Ok::<_, io::Error>((report, elapsed))
+    };
+
+    tokio::select! {
+        biased;
+        result = work => result,
+        err = driver.wait_idle() => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("HTTP/3 connection closed before the response: {err}"),
+        )),
+    }
+}
+
+fn h3_error(err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("HTTP/3: {err}"))
+}
+
+/// Where the GET goes.
+#[derive(Debug, PartialEq)]
+struct Target {
+    host: String,
+    port: u16,
+    /// `host[:port]`, as the `:authority` pseudo-header.
+    authority: String,
+    /// Path plus query, `/` when empty.
+    path: String,
+}
+
+/// Parse `https://host[:port]/path?query`, `h3://...` or
+/// `host[:port]/path`.
+fn parse_url(url: &str) -> Result<Target> {
+    let trimmed = url.trim();
+    let uri = get_uri(trimmed);
+    match uri.scheme.to_ascii_lowercase().as_str() {
+        "" | "https" | "h3" => {}
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "scheme '{other}' is not supported by Http3Pinger \
+                     (use https://, h3://, or host:port)"
+                ),
+            ));
+        }
+    }
+    if uri.domain.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "HTTP/3 URL is missing a host",
+        ));
+    }
+    let port = if uri.port > 0 {
+        uri.port as u16
+    } else {
+        DEFAULT_PORT
+    };
+    let authority = if port == DEFAULT_PORT {
+        uri.domain.clone()
+    } else {
+        format!("{}:{port}", uri.domain)
+    };
+    let mut path = if uri.path.is_empty() {
+        "/".to_string()
+    } else {
+        uri.path.clone()
+    };
+    if !uri.query.is_empty() {
+        path = format!("{path}?{}", uri.query);
+    }
+    Ok(Target {
+        host: uri.domain,
+        port,
+        authority,
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_builds_authority_and_path() {
+        let target = parse_url("https://example.com:8443/health?full=1").unwrap();
+        assert_eq!(target.host, "example.com");
+        assert_eq!(target.port, 8443);
+        assert_eq!(target.authority, "example.com:8443");
+        assert_eq!(target.path, "/health?full=1");
+
+        let target = parse_url("example.com").unwrap();
+        assert_eq!(
+            (target.port, target.authority.as_str()),
+            (443, "example.com")
+        );
+        assert_eq!(target.path, "/");
+    }
+
+    #[test]
+    fn parse_url_rejects_other_schemes() {
+        let err = parse_url("http://example.com/").unwrap_err();
+        assert!(err.to_string().contains("scheme 'http'"), "{err}");
+        assert!(parse_url("").is_err());
+    }
+}
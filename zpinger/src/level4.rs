@@ -1,21 +1,53 @@
+#[cfg(feature = "tcp")]
+use std::io;
 use std::io::Result;
 use std::time::Duration;
 
 use async_trait::async_trait;
 
+use crate::observe;
 use crate::pinger::Pinger;
+#[cfg(feature = "tcp")]
+use crate::report::Report;
 use crate::util::with_timeout;
 use crate::BUF_SIZE;
 
 #[cfg(any(feature = "tcp", feature = "udp"))]
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 
-/// TCP pinger — opens a TCP connection to `target`, sends one byte,
-/// and waits for any response byte before closing.
+/// How `TcpPinger` decides the peer answered.
+///
+/// The default `Echo` keeps the original behaviour (write one byte,
+/// wait for any byte back), which only works against echo-style
+/// peers. Real services either wait for the client (HTTP, databases)
+/// or speak first (SSH, SMTP, FTP) — pick `Connect` or `Banner` for
+/// those.
+#[cfg(feature = "tcp")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TcpMode {
+    /// Write one byte, wait for any reply.
+    #[default]
+    Echo,
+    /// Complete the three-way handshake and close — classic `tcping`.
+    /// Nothing is written or read.
+    Connect,
+    /// Send nothing; wait for the server's greeting.
+    Banner,
+    /// Write the payload, then wait for the server's reply.
+    Send(Vec<u8>),
+}
+
+/// TCP pinger — opens a TCP connection to `target` and, depending on
+/// `mode`, stops there, reads the server's banner, or writes a probe
+/// and reads the reply. `Banner` / `Send` report the text received
+/// as the `banner` detail (and the raw bytes as the report body);
+/// `with_expect` additionally requires it to match a regex.
 #[cfg(feature = "tcp")]
 pub struct TcpPinger {
     pub target: String,
     pub timeout: Duration,
+    pub mode: TcpMode,
+    pub expect: Option<String>,
 }
 
 #[cfg(feature = "tcp")]
@@ -24,6 +56,8 @@ impl TcpPinger {
         Self {
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
+            mode: TcpMode::default(),
+            expect: None,
         }
     }
 
@@ -31,26 +65,146 @@ impl TcpPinger {
         self.timeout = t;
         self
     }
+
+    pub fn with_mode(mut self, mode: TcpMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Regex the received bytes must match. Reading continues until
+    /// it matches, the peer closes, or `MAX_BANNER_BYTES` arrive, so
+    /// multi-line greetings (SMTP `220-` continuations) work. Not
+    /// valid with `TcpMode::Connect`, which reads nothing.
+    pub fn with_expect(mut self, pattern: impl Into<String>) -> Self {
+        self.expect = Some(pattern.into());
+        self
+    }
 }
 
+#[cfg(feature = "tcp")]
+const MAX_BANNER_BYTES: usize = 4096;
+
 #[cfg(feature = "tcp")]
 #[async_trait]
 impl Pinger for TcpPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
-        use tokio::net::TcpStream;
 
-        with_timeout(self.timeout, async {
-            let mut stream = TcpStream::connect(&self.target).await?;
-            stream.write_all(&[1]).await?;
-            let mut buf = [0u8; BUF_SIZE];
-            let _ = stream.read(&mut buf).await?;
-            Ok(())
+        let expect = match &self.expect {
+            Some(pattern) => Some(regex::bytes::Regex::new(pattern).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("bad --expect regex: {e}"),
+                )
+            })?),
+            None => None,
+        };
+        if self.mode == TcpMode::Connect && expect.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "connect-only mode reads nothing, so there is no reply to match",
+            ));
+        }
+
+        // Lives outside the timeout so a pattern that never matches
+        // can still say what *did* arrive.
+        let mut received = Vec::new();
+        let outcome = with_timeout(self.timeout, async {
+            let mut stream = observe::connect_tcp(&self.target).await?;
+            match &self.mode {
+                TcpMode::Connect => return Ok(()),
+                TcpMode::Echo if expect.is_none() => {
+                    // Original semantics: any read outcome, including
+                    // an immediate EOF, counts as an answer.
+                    stream.write_all(&[1]).await?;
+                    let mut buf = [0u8; BUF_SIZE];
+                    let _ = stream.read(&mut buf).await?;
+                    return Ok(());
+                }
+                TcpMode::Echo => stream.write_all(&[1]).await?,
+                TcpMode::Banner => {}
+                TcpMode::Send(payload) => stream.write_all(payload).await?,
+            }
+            read_banner(&mut stream, expect.as_ref(), &mut received).await
         })
-        .await
+        .await;
+        match (outcome, &expect) {
+            (Err(e), Some(re)) if e.kind() == io::ErrorKind::TimedOut && !received.is_empty() => {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "timed out waiting for /{}/; reply so far {:?}",
+                        re.as_str(),
+                        banner_text(&received)
+                    ),
+                ))
+            }
+            (Err(e), _) => Err(e),
+            (Ok(()), _) if received.is_empty() => Ok(Report::default()),
+            (Ok(()), _) => {
+                let mut report = Report::default().with_detail("banner", banner_text(&received));
+                report.body = Some(received);
+                Ok(report)
+            }
+        }
     }
 }
 
+/// Read what the server sends into `banner`. Without a pattern the
+/// first non-empty read is the banner; with one, keep reading until
+/// it matches. EOF before any byte (or before a match) is an error —
+/// a service that accepts and immediately hangs up isn't healthy.
+#[cfg(feature = "tcp")]
+async fn read_banner<S>(
+    stream: &mut S,
+    expect: Option<&regex::bytes::Regex>,
+    banner: &mut Vec<u8>,
+) -> Result<()>
+where
+    S: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut chunk = [0u8; BUF_SIZE];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        banner.extend_from_slice(&chunk[..n]);
+        match expect {
+            None => return Ok(()),
+            Some(re) if re.is_match(banner) => return Ok(()),
+            Some(_) if banner.len() >= MAX_BANNER_BYTES => break,
+            Some(_) => {}
+        }
+    }
+    match expect {
+        _ if banner.is_empty() => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed before the server sent anything",
+        )),
+        Some(re) => Err(io::Error::other(format!(
+            "reply {:?} did not match /{}/",
+            banner_text(banner),
+            re.as_str()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Banners are mostly ASCII lines (`SSH-2.0-...\r\n`) but some are
+/// binary (MySQL's handshake packet). Render lossily and drop the
+/// trailing line terminator so the CLI prints it on one line.
+#[cfg(feature = "tcp")]
+fn banner_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end().to_string()
+}
+
 /// UDP pinger — sends one datagram to `target` from an ephemeral local
 /// socket and waits for a datagram in reply.
 #[cfg(feature = "udp")]
@@ -83,11 +237,49 @@ impl Pinger for UdpPinger {
         with_timeout(self.timeout, async {
             let socket = UdpSocket::bind("0.0.0.0:0").await?;
             socket.connect(&self.target).await?;
-            socket.send(&[1]).await?;
+            observe::udp_connected(&socket);
+            observe::sent(socket.send(&[1]).await?);
             let mut buf = [0u8; BUF_SIZE];
-            let _ = socket.recv(&mut buf).await?;
+            observe::received(socket.recv(&mut buf).await?);
             Ok(())
         })
         .await
     }
 }
+
+#[cfg(all(test, feature = "tcp"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn read_banner_returns_first_chunk_without_pattern() {
+        let canned: &[u8] = b"SSH-2.0-OpenSSH_9.6\r\n";
+        let mut got = Vec::new();
+        read_banner(&mut &canned[..], None, &mut got).await.unwrap();
+        assert_eq!(banner_text(&got), "SSH-2.0-OpenSSH_9.6");
+    }
+
+    #[tokio::test]
+    async fn read_banner_rejects_unmatched_reply() {
+        let canned: &[u8] = b"220 mail ready\r\n";
+        let re = regex::bytes::Regex::new("^SSH-").unwrap();
+        let err = read_banner(&mut &canned[..], Some(&re), &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("did not match"));
+    }
+
+    #[tokio::test]
+    async fn read_banner_rejects_silent_close() {
+        let canned: &[u8] = b"";
+        let err = read_banner(&mut &canned[..], None, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn banner_text_is_lossy_and_trimmed() {
+        assert_eq!(banner_text(b"\x0aJ\xff5.7\r\n"), "\nJ\u{fffd}5.7");
+    }
+}
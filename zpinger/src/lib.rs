@@ -5,41 +5,80 @@ use std::net::SocketAddr;
 mod test_pinger;
 
 // Always compiled regardless of features — the `Pinger` trait, the
-// timed helper, the URI parser, and shared utilities. No protocol
-// implementations live in here.
+// timed helpers, the probe `Report`, response expectations, observer
+// hooks, cancellation, certificate summaries, the URI parser, and
+// shared utilities. No protocol implementations live in here.
+mod cancel;
+mod cert;
+mod expect;
+mod observe;
 mod pinger;
+mod report;
 pub mod uri;
 mod util;
 
-pub use crate::pinger::{timed, Pinger};
+pub use crate::cancel::{cancellable, Cancelled, Phase};
+pub use crate::cert::{format_utc, CertInfo};
+pub use crate::expect::{AssertionError, Expect, Expectation, StatusSet};
+pub use crate::observe::{Observed, Observer, Retry, TlsInfo};
+pub use crate::pinger::{timed, timed_probe, timed_probe_with_cancel, Pinger};
+pub use crate::report::Report;
+pub use tokio_util::sync::CancellationToken;
 
 // TLS layer + rustls re-exports. Compiled whenever any protocol that
-// needs TLS is enabled (http / ws / mqtt / hls).
-#[cfg(feature = "_tls")]
+// can speak TLS is enabled (http / ws / mqtt / hls / tls / rtsp /
+// rtmp / grpc / quic).
+#[cfg(feature = "_rustls")]
 mod tls;
-#[cfg(feature = "_tls")]
-pub use crate::tls::default_client_config;
-#[cfg(feature = "_tls")]
+#[cfg(feature = "_rustls")]
+mod tls_verify;
+#[cfg(feature = "_rustls")]
+pub use crate::tls::{default_client_config, ClientIdentity, TlsOptions};
+#[cfg(feature = "_rustls")]
 pub use rustls::ClientConfig;
 
 // Per-protocol modules + re-exports — each gated behind its own
 // feature flag.
 #[cfg(any(feature = "tcp", feature = "udp"))]
 mod level4;
-#[cfg(feature = "tcp")]
-pub use crate::level4::TcpPinger;
 #[cfg(feature = "udp")]
 pub use crate::level4::UdpPinger;
+#[cfg(feature = "tcp")]
+pub use crate::level4::{TcpMode, TcpPinger};
 
 #[cfg(feature = "dns")]
 mod dns;
 #[cfg(feature = "dns")]
 pub use crate::dns::{DnsPinger, RecordType};
 
+// Full-then-resumed handshake probes, shared by `TlsPinger` and
+// `HttpPinger`.
+#[cfg(any(feature = "http", feature = "tls"))]
+mod resume;
+#[cfg(any(feature = "http", feature = "tls"))]
+pub use crate::resume::ResumptionMode;
+
+// Basic / Bearer / Digest credentials, shared by the pingers that
+// write their own HTTP requests (`http`, `hls`, `ws`).
+#[cfg(feature = "_auth")]
+mod auth;
+#[cfg(feature = "_auth")]
+pub use crate::auth::HttpAuth;
+
+#[cfg(feature = "http")]
+mod cors;
 #[cfg(feature = "http")]
 mod http;
 #[cfg(feature = "http")]
-pub use crate::http::{HttpMethod, HttpPinger};
+pub use crate::cors::CorsPreflight;
+#[cfg(feature = "http")]
+pub use crate::http::{HttpMethod, HttpPinger, HttpVersion};
+#[cfg(feature = "http2")]
+mod http2;
+#[cfg(feature = "sse")]
+mod sse;
+#[cfg(feature = "sse")]
+pub use crate::sse::SsePinger;
 
 #[cfg(feature = "ws")]
 mod websocket;
@@ -55,16 +94,39 @@ pub use crate::mqtt::{MqttPinger, MqttVersion};
 mod grpc;
 #[cfg(feature = "grpc")]
 pub use crate::grpc::{GrpcPinger, GrpcStreamPinger};
+#[cfg(feature = "grpc-web")]
+mod grpc_web;
+#[cfg(feature = "grpc-web")]
+pub use crate::grpc_web::GrpcWebPinger;
 
 #[cfg(feature = "hls")]
 mod hls;
 #[cfg(feature = "hls")]
 pub use crate::hls::HlsPinger;
 
+#[cfg(feature = "tls")]
+mod diagnose;
+#[cfg(feature = "tls")]
+mod staple;
+#[cfg(feature = "tls")]
+mod starttls;
 #[cfg(feature = "tls")]
 mod tls_handshake;
 #[cfg(feature = "tls")]
+mod tls_scan;
+#[cfg(feature = "tls")]
+pub use crate::diagnose::{TlsFailure, TlsFailureCause};
+#[cfg(feature = "tls")]
+pub use crate::starttls::StartTls;
+#[cfg(feature = "tls")]
 pub use crate::tls_handshake::TlsPinger;
+#[cfg(feature = "tls")]
+pub use crate::tls_scan::TlsScanPinger;
+
+#[cfg(feature = "dtls")]
+mod dtls;
+#[cfg(feature = "dtls")]
+pub use crate::dtls::DtlsPinger;
 
 #[cfg(feature = "ntp")]
 mod ntp;
@@ -99,12 +161,19 @@ mod quic;
 #[cfg(feature = "quic")]
 pub use crate::quic::QuicPinger;
 
-// `BUF_SIZE` is shared by `level4` (tcp / udp) and `http`.
-// `HTTP_UNCONNECT_STATUS_CODE` is http-only.
-#[cfg(any(feature = "tcp", feature = "udp", feature = "http"))]
+#[cfg(feature = "http3")]
+mod http3;
+#[cfg(feature = "http3")]
+pub use crate::http3::Http3Pinger;
+
+#[cfg(feature = "compare")]
+mod compare;
+#[cfg(feature = "compare")]
+pub use crate::compare::HttpComparePinger;
+
+// `BUF_SIZE` is shared by `level4` (tcp / udp).
+#[cfg(any(feature = "tcp", feature = "udp"))]
 pub(crate) const BUF_SIZE: usize = 0xFF;
-#[cfg(feature = "http")]
-pub(crate) const HTTP_UNCONNECT_STATUS_CODE: &[&str] = &["404", "501"];
 
 /// Resolve `url`'s host:port to a list of socket addresses for display.
 /// Falls back to scheme default ports (http → 80, https → 443) when
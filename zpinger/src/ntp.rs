@@ -25,6 +25,7 @@ use std::time::Duration;
 use async_trait::async_trait;
 use tokio::net::UdpSocket;
 
+use crate::observe;
 use crate::pinger::Pinger;
 use crate::uri::get_uri;
 use crate::util::with_timeout;
@@ -75,10 +76,12 @@ impl Pinger for NtpPinger {
         with_timeout(self.timeout, async move {
             let socket = UdpSocket::bind("0.0.0.0:0").await?;
             socket.connect(&endpoint).await?;
-            socket.send(&request).await?;
+            observe::udp_connected(&socket);
+            observe::sent(socket.send(&request).await?);
 
             let mut buf = [0u8; BUF_SIZE];
             let n = socket.recv(&mut buf).await?;
+            observe::received(n);
             validate_response(&buf[..n])
         })
         .await
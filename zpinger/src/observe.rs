@@ -0,0 +1,559 @@
+//! Observer hooks for embedding applications. An `Observer` sees what
+//! a probe does as it happens — connection established, TLS handshake
+//! finished, bytes on the wire, retries, the final result — without
+//! the pinger knowing who is listening.
+//!
+//! Wrap a pinger in `Observed` to install an observer for the
+//! duration of each probe. The observer travels in a tokio
+//! task-local, so pingers report events from wherever they are in
+//! their exchange with one call, and nothing is threaded through
+//! constructors. With no `Observed` wrapper in the stack the
+//! task-local is unset and every emit is a failed lookup; the stream
+//! adapter checks once per connection, not per read.
+//!
+//! gRPC goes through tonic's own transport, so `GrpcPinger` only
+//! reports its `connect` phase and `on_result` (and `on_retry` under
+//! `Retry`).
+
+use std::io::{self, Result};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use crate::cancel::{self, Cancelled};
+use crate::pinger::Pinger;
+use crate::report::Report;
+
+/// Receives probe events. Every method has a no-op default, so an
+/// implementation overrides only what it cares about. Methods are
+/// called synchronously from inside the probe — keep them cheap
+/// (push to a channel, bump a counter) rather than doing I/O.
+pub trait Observer: Send + Sync {
+    /// Transport connected to `peer`. For UDP pingers this fires
+    /// when the socket is connected, before anything is sent.
+    fn on_connected(&self, _peer: SocketAddr) {}
+
+    /// TLS (or QUIC) handshake completed.
+    fn on_tls_handshake(&self, _tls: &TlsInfo) {}
+
+    /// `bytes` written to the wire. TLS pingers report ciphertext,
+    /// so handshake traffic is included.
+    fn on_sent(&self, _bytes: usize) {}
+
+    /// `bytes` read from the wire.
+    fn on_received(&self, _bytes: usize) {}
+
+    /// The probe finished a step (`connect`, `tls`, `playlist`, ...).
+    /// These are the phases a `Cancelled` error lists.
+    fn on_phase(&self, _name: &str) {}
+
+    /// Attempt `attempt` (1-based) failed with `error` and `Retry`
+    /// is about to try again.
+    fn on_retry(&self, _attempt: u32, _error: &io::Error) {}
+
+    /// The probe finished after `elapsed`, successfully or not.
+    fn on_result(&self, _elapsed: Duration, _result: &Result<Report>) {}
+}
+
+/// What a completed TLS or QUIC handshake negotiated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// ALPN protocol the server picked, if any (`b"h2"`, `b"h3"`).
+    pub alpn: Option<Vec<u8>>,
+    /// Protocol version as rustls names it (`TLSv1_3`).
+    pub version: Option<String>,
+    /// Cipher suite as rustls names it (`TLS13_AES_128_GCM_SHA256`).
+    /// `None` for QUIC, where quinn doesn't expose it.
+    pub cipher_suite: Option<String>,
+    /// Key-exchange group as rustls names it (`X25519`,
+    /// `secp256r1`). `None` for a TLS 1.2 resumption, which skips key
+    /// exchange, and for QUIC.
+    pub key_exchange_group: Option<String>,
+    /// Whether the session was resumed rather than negotiated from
+    /// scratch. `None` for QUIC.
+    pub resumed: Option<bool>,
+    /// Server certificate chain, leaf first, DER-encoded.
+    pub peer_certificates: Vec<Vec<u8>>,
+}
+
+tokio::task_local! {
+    static OBSERVER: Arc<dyn Observer>;
+}
+
+/// Run `f` against the current probe's observer, if one is installed.
+pub(crate) fn emit(f: impl FnOnce(&dyn Observer)) {
+    let _ = OBSERVER.try_with(|observer| f(observer.as_ref()));
+}
+
+/// Mark the end of a probe step: tells the observer and records it
+/// for `Cancelled`. Unused when no protocol feature is on.
+#[allow(dead_code)]
+pub(crate) fn phase(name: &'static str) {
+    cancel::record_phase(name);
+    emit(|o| o.on_phase(name));
+}
+
+/// Wraps any pinger and reports its events to `observer`. Also emits
+/// `on_result` once per probe with the wrapper's own timing. Put it
+/// outermost (around `Retry` / `Expect`) to see every event.
+pub struct Observed<P> {
+    pub inner: P,
+    observer: Arc<dyn Observer>,
+}
+
+impl<P: Pinger> Observed<P> {
+    pub fn new(inner: P, observer: Arc<dyn Observer>) -> Self {
+        Self { inner, observer }
+    }
+}
+
+#[async_trait]
+impl<P: Pinger> Pinger for Observed<P> {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let start = Instant::now();
+        let result = OBSERVER
+            .scope(self.observer.clone(), self.inner.probe())
+            .await;
+        self.observer.on_result(start.elapsed(), &result);
+        result
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        let start = Instant::now();
+        let result = OBSERVER
+            .scope(self.observer.clone(), self.inner.exchange())
+            .await;
+        self.observer.on_result(start.elapsed(), &result);
+        result
+    }
+
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        let start = Instant::now();
+        let result = OBSERVER
+            .scope(self.observer.clone(), self.inner.probe_with_cancel(cancel))
+            .await;
+        self.observer.on_result(start.elapsed(), &result);
+        result
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        let start = Instant::now();
+        let result = OBSERVER
+            .scope(
+                self.observer.clone(),
+                self.inner.exchange_with_cancel(cancel),
+            )
+            .await;
+        self.observer.on_result(start.elapsed(), &result);
+        result
+    }
+}
+
+/// Re-runs a failed probe up to `attempts` times in total, sleeping
+/// `backoff` between tries and emitting `on_retry` before each one.
+/// `InvalidInput` errors (bad target, bad regex) and cancellations
+/// are returned at once — retrying can't fix them.
+pub struct Retry<P> {
+    pub inner: P,
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl<P: Pinger> Retry<P> {
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            attempts: 3,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    /// Total tries, including the first. Values below 1 count as 1.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// `cancel` also cuts a backoff short.
+    async fn run<'a, F, Fut>(
+        &'a self,
+        cancel: &CancellationToken,
+        attempt_once: F,
+    ) -> Result<Report>
+    where
+        F: Fn(&'a P) -> Fut,
+        Fut: std::future::Future<Output = Result<Report>>,
+    {
+        let start = Instant::now();
+        let mut attempt = 1;
+        loop {
+            match attempt_once(&self.inner).await {
+                Err(e)
+                    if attempt < self.attempts
+                        && e.kind() != io::ErrorKind::InvalidInput
+                        && Cancelled::from_io(&e).is_none() =>
+                {
+                    emit(|o| o.on_retry(attempt, &e));
+                    attempt += 1;
+                    if !self.backoff.is_zero() {
+                        tokio::select! {
+                            _ = tokio::time::sleep(self.backoff) => {}
+                            _ = cancel.cancelled() => {
+                                return Err(Cancelled {
+                                    phases: Vec::new(),
+                                    elapsed: start.elapsed(),
+                                }
+                                .into());
+                            }
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Pinger> Pinger for Retry<P> {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        self.run(&CancellationToken::new(), |p| p.probe()).await
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        self.run(&CancellationToken::new(), |p| p.exchange()).await
+    }
+
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        self.run(cancel, |p| p.probe_with_cancel(cancel)).await
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        self.run(cancel, |p| p.exchange_with_cancel(cancel)).await
+    }
+}
+
+/// `on_connected` for a UDP socket that has just been `connect`ed.
+#[cfg(any(
+    feature = "udp",
+    feature = "dns",
+    feature = "ntp",
+    feature = "stun",
+    feature = "turn",
+    feature = "dtls",
+))]
+pub(crate) fn udp_connected(socket: &tokio::net::UdpSocket) {
+    if let Ok(peer) = socket.peer_addr() {
+        emit(|o| o.on_connected(peer));
+    }
+    phase("connect");
+}
+
+#[cfg(any(
+    feature = "udp",
+    feature = "dns",
+    feature = "ntp",
+    feature = "stun",
+    feature = "turn",
+    feature = "quic",
+))]
+pub(crate) fn sent(bytes: usize) {
+    emit(|o| o.on_sent(bytes));
+}
+
+#[cfg(any(
+    feature = "udp",
+    feature = "dns",
+    feature = "ntp",
+    feature = "stun",
+    feature = "turn",
+    feature = "quic",
+))]
+pub(crate) fn received(bytes: usize) {
+    emit(|o| o.on_received(bytes));
+}
+
+/// Report a finished rustls handshake through `on_tls_handshake` and
+/// hand back what it negotiated.
+#[cfg(feature = "_tls")]
+pub(crate) fn tls_handshake(conn: &rustls::CommonState) -> TlsInfo {
+    let info = TlsInfo {
+        alpn: conn.alpn_protocol().map(<[u8]>::to_vec),
+        version: conn.protocol_version().map(|v| format!("{v:?}")),
+        cipher_suite: conn
+            .negotiated_cipher_suite()
+            .map(|s| format!("{:?}", s.suite())),
+        key_exchange_group: conn
+            .negotiated_key_exchange_group()
+            .map(|g| format!("{:?}", g.name())),
+        resumed: conn
+            .handshake_kind()
+            .map(|kind| kind == rustls::HandshakeKind::Resumed),
+        peer_certificates: conn
+            .peer_certificates()
+            .unwrap_or_default()
+            .iter()
+            .map(|c| c.to_vec())
+            .collect(),
+    };
+    emit(|o| o.on_tls_handshake(&info));
+    phase("tls");
+    info
+}
+
+#[cfg(any(
+    feature = "tcp",
+    feature = "http",
+    feature = "ws",
+    feature = "mqtt",
+    feature = "hls",
+    feature = "tls",
+    feature = "rtsp",
+    feature = "rtmp",
+))]
+pub(crate) use self::stream::connect_tcp;
+
+#[cfg(any(
+    feature = "tcp",
+    feature = "http",
+    feature = "ws",
+    feature = "mqtt",
+    feature = "hls",
+    feature = "tls",
+    feature = "rtsp",
+    feature = "rtmp",
+))]
+mod stream {
+    use std::io::Result;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+    use tokio::net::TcpStream;
+
+    use super::{Observer, OBSERVER};
+
+    /// Byte-counting wrapper around a transport stream. Looks the
+    /// observer up once at construction; with none installed every
+    /// poll is a plain pass-through.
+    pub(crate) struct ObservedStream<S> {
+        inner: S,
+        observer: Option<Arc<dyn Observer>>,
+    }
+
+    impl<S> ObservedStream<S> {
+        pub(crate) fn new(inner: S) -> Self {
+            Self {
+                inner,
+                observer: OBSERVER.try_with(Arc::clone).ok(),
+            }
+        }
+    }
+
+    /// `TcpStream::connect` that emits `on_connected` and counts the
+    /// connection's bytes. Every TCP-based pinger dials through this.
+    pub(crate) async fn connect_tcp(endpoint: &str) -> Result<ObservedStream<TcpStream>> {
+        let stream = ObservedStream::new(TcpStream::connect(endpoint).await?);
+        if let (Some(observer), Ok(peer)) = (&stream.observer, stream.inner.peer_addr()) {
+            observer.on_connected(peer);
+        }
+        super::phase("connect");
+        Ok(stream)
+    }
+
+    impl<S: AsyncRead + Unpin> AsyncRead for ObservedStream<S> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<Result<()>> {
+            let before = buf.filled().len();
+            let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+            if let (Poll::Ready(Ok(())), Some(observer)) = (&poll, &self.observer) {
+                let n = buf.filled().len() - before;
+                if n > 0 {
+                    observer.on_received(n);
+                }
+            }
+            poll
+        }
+    }
+
+    impl<S: AsyncWrite + Unpin> AsyncWrite for ObservedStream<S> {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<Result<usize>> {
+            let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+            if let (Poll::Ready(Ok(n)), Some(observer)) = (&poll, &self.observer) {
+                if *n > 0 {
+                    observer.on_sent(*n);
+                }
+            }
+            poll
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl Observer for Recorder {
+        fn on_retry(&self, attempt: u32, error: &io::Error) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("retry {attempt}: {error}"));
+        }
+
+        fn on_result(&self, _elapsed: Duration, result: &Result<Report>) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("result ok={}", result.is_ok()));
+        }
+    }
+
+    /// Fails `failures` times with `kind`, then succeeds.
+    struct Flaky {
+        failures: u32,
+        kind: io::ErrorKind,
+        calls: std::sync::atomic::AtomicU32,
+    }
+
+    #[async_trait]
+    impl Pinger for Flaky {
+        async fn ping(&self) -> Result<()> {
+            let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if n < self.failures {
+                Err(io::Error::new(self.kind, format!("fail {}", n + 1)))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn flaky(failures: u32, kind: io::ErrorKind) -> Flaky {
+        Flaky {
+            failures,
+            kind,
+            calls: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_reports_each_retry_then_result() {
+        let recorder = Arc::new(Recorder::default());
+        let p = Observed::new(
+            Retry::new(flaky(2, io::ErrorKind::TimedOut)),
+            recorder.clone(),
+        );
+        p.ping().await.unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["retry 1: fail 1", "retry 2: fail 2", "result ok=true"]
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_gives_up_after_attempts() {
+        let recorder = Arc::new(Recorder::default());
+        let p = Observed::new(
+            Retry::new(flaky(5, io::ErrorKind::TimedOut)).with_attempts(2),
+            recorder.clone(),
+        );
+        assert_eq!(p.ping().await.unwrap_err().to_string(), "fail 2");
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["retry 1: fail 1", "result ok=false"]
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_skips_invalid_input() {
+        let p = Retry::new(flaky(1, io::ErrorKind::InvalidInput));
+        assert!(p.ping().await.is_err());
+        assert_eq!(p.inner.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    struct Stalls;
+
+    #[async_trait]
+    impl Pinger for Stalls {
+        async fn ping(&self) -> Result<()> {
+            phase("connect");
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_stops_on_cancel() {
+        let recorder = Arc::new(Recorder::default());
+        let p = Observed::new(Retry::new(Stalls), recorder.clone());
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+        let err = p.probe_with_cancel(&token).await.unwrap_err();
+        let cancelled = Cancelled::from_io(&err).unwrap();
+        assert_eq!(cancelled.phases[0].name, "connect");
+        assert_eq!(*recorder.events.lock().unwrap(), vec!["result ok=false"]);
+    }
+
+    #[tokio::test]
+    async fn retry_backoff_stops_on_cancel() {
+        let p = Retry::new(flaky(2, io::ErrorKind::TimedOut)).with_backoff(Duration::from_secs(30));
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            trigger.cancel();
+        });
+        let start = Instant::now();
+        let err = p.probe_with_cancel(&token).await.unwrap_err();
+        assert!(Cancelled::from_io(&err).is_some());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(p.inner.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn emit_without_observer_is_a_no_op() {
+        emit(|_| panic!("no observer is installed"));
+    }
+}
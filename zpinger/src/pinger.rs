@@ -2,6 +2,10 @@ use std::io::Result;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use crate::cancel::cancellable;
+use crate::report::Report;
 
 /// Trait every protocol implementation provides. `async fn` is wrapped
 /// by `async-trait` so the trait stays object-safe — knockknock
@@ -9,6 +13,90 @@ use async_trait::async_trait;
 #[async_trait]
 pub trait Pinger: Send + Sync {
     async fn ping(&self) -> Result<()>;
+
+    /// Same exchange as `ping`, but hands back whatever the pinger
+    /// learned along the way. The default wraps `ping` and reports
+    /// nothing extra; pingers that capture detail override this and
+    /// implement `ping` in terms of it.
+    async fn probe(&self) -> Result<Report> {
+        self.ping().await?;
+        Ok(Report::default())
+    }
+
+    /// Run the exchange and report the response *without* applying
+    /// the protocol's built-in success rule (HTTP expected status, DNS
+    /// RCODE, RTSP 200). Transport and framing errors still fail.
+    /// `Expect` calls this when the caller supplies their own status
+    /// expectation. The default is `probe` — pingers with no status
+    /// rule have nothing to relax.
+    async fn exchange(&self) -> Result<Report> {
+        self.probe().await
+    }
+
+    /// `probe`, stopped early when `cancel` fires. A cancelled probe
+    /// fails with a `Cancelled` error listing the phases it finished.
+    /// The default drops the probe future at the cancel; pingers that
+    /// hold resources worth closing politely (a QUIC endpoint)
+    /// override it.
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        cancellable(cancel, self.probe()).await
+    }
+
+    /// `exchange`, stopped early when `cancel` fires — what `Expect`
+    /// runs when it judges the status itself. Pingers that override
+    /// `probe_with_cancel` override this too, so the unjudged run
+    /// gets the same cleanup.
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        cancellable(cancel, self.exchange()).await
+    }
+}
+
+/// Forwarding impls so wrappers like `Expect` accept the boxed
+/// pingers knockknock builds, or a borrowed one.
+#[async_trait]
+impl<P: Pinger + ?Sized> Pinger for &P {
+    async fn ping(&self) -> Result<()> {
+        (**self).ping().await
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        (**self).probe().await
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        (**self).exchange().await
+    }
+
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        (**self).probe_with_cancel(cancel).await
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        (**self).exchange_with_cancel(cancel).await
+    }
+}
+
+#[async_trait]
+impl<P: Pinger + ?Sized> Pinger for Box<P> {
+    async fn ping(&self) -> Result<()> {
+        (**self).ping().await
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        (**self).probe().await
+    }
+
+    async fn exchange(&self) -> Result<Report> {
+        (**self).exchange().await
+    }
+
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        (**self).probe_with_cancel(cancel).await
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        (**self).exchange_with_cancel(cancel).await
+    }
 }
 
 /// Time a single ping. Generic over `?Sized` so it accepts both
@@ -18,3 +106,21 @@ pub async fn timed<P: Pinger + ?Sized>(pinger: &P) -> Result<Duration> {
     pinger.ping().await?;
     Ok(start.elapsed())
 }
+
+/// Time a single probe and keep its `Report`. Same clock as `timed`.
+pub async fn timed_probe<P: Pinger + ?Sized>(pinger: &P) -> Result<(Duration, Report)> {
+    let start = Instant::now();
+    let report = pinger.probe().await?;
+    Ok((start.elapsed(), report))
+}
+
+/// `timed_probe` that stops when `cancel` fires. See
+/// `Pinger::probe_with_cancel`.
+pub async fn timed_probe_with_cancel<P: Pinger + ?Sized>(
+    pinger: &P,
+    cancel: &CancellationToken,
+) -> Result<(Duration, Report)> {
+    let start = Instant::now();
+    let report = pinger.probe_with_cancel(cancel).await?;
+    Ok((start.elapsed(), report))
+}
@@ -16,21 +16,30 @@
 //! `host:port`. Default port 443.
 
 use std::io::{self, Result};
-use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use quinn::crypto::rustls::QuicClientConfig;
 use quinn::{ClientConfig as QuinnClientConfig, Endpoint};
+use rustls::pki_types::CertificateDer;
 use rustls::{ClientConfig, RootCertStore};
+use tokio_util::sync::CancellationToken;
 
+use crate::cancel::cancellable;
+use crate::observe::{self, TlsInfo};
 use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::tls::TlsOptions;
 use crate::uri::get_uri;
+use crate::util::{resolve_first, unspecified_for};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_PORT: u16 = 443;
 const DEFAULT_ALPN: &[u8] = b"h3";
+/// How long to wait for a CONNECTION_CLOSE to go out before the
+/// endpoint and its UDP socket are dropped.
+pub(crate) const CLOSE_GRACE: Duration = Duration::from_millis(250);
 
 /// QUIC pinger. Reports the time taken to complete the QUIC handshake
 /// (UDP + Initial / Handshake / 1-RTT keys ready). Doesn't open
@@ -41,6 +50,7 @@ pub struct QuicPinger {
     pub timeout: Duration,
     pub alpn: Vec<Vec<u8>>,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl QuicPinger {
@@ -50,6 +60,7 @@ impl QuicPinger {
             timeout: DEFAULT_TIMEOUT,
             alpn: vec![DEFAULT_ALPN.to_vec()],
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -61,7 +72,7 @@ impl QuicPinger {
     /// Replace the ALPN list. Default is `[b"h3".to_vec()]`. Pass an
     /// empty vec to send a TLS ClientHello with no ALPN extension at
     /// all — most servers will refuse, but a few legacy QUIC stacks
-    /// expect that.
+    /// expect that. A non-empty `TlsOptions::alpn` takes precedence.
     pub fn with_alpn(mut self, alpn: Vec<Vec<u8>>) -> Self {
         self.alpn = alpn;
         self
@@ -75,14 +86,43 @@ impl QuicPinger {
         self.tls_config = Some(config);
         self
     }
+
+    /// Shared TLS settings. An `alpn` list there wins over
+    /// `with_alpn`.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
 }
 
 #[async_trait]
 impl Pinger for QuicPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        self.handshake(&CancellationToken::new()).await
+    }
+
+    /// Closes the endpoint on cancel instead of dropping it mid-
+    /// handshake, so the server sees a CONNECTION_CLOSE rather than
+    /// waiting out its idle timeout.
+    async fn probe_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        cancellable(cancel, self.handshake(cancel)).await
+    }
+
+    async fn exchange_with_cancel(&self, cancel: &CancellationToken) -> Result<Report> {
+        self.probe_with_cancel(cancel).await
+    }
+}
+
+impl QuicPinger {
+    async fn handshake(&self, cancel: &CancellationToken) -> Result<Report> {
         let (host, port) = parse_endpoint(&self.endpoint)?;
         let server_addr = resolve_first(&host, port).await?;
-        let crypto = build_rustls_config(self.tls_config.as_deref(), &self.alpn)?;
+        let mut crypto = build_rustls_config(self.tls_config.as_deref(), &self.alpn)?;
+        let verdict = self.tls_options.configure(&mut crypto);
         let quic_crypto = QuicClientConfig::try_from(crypto)
             .map_err(|e| io::Error::other(format!("quinn rustls config: {e}")))?;
         let client_config = QuinnClientConfig::new(Arc::new(quic_crypto));
@@ -92,21 +132,38 @@ impl Pinger for QuicPinger {
         endpoint.set_default_client_config(client_config);
 
         let connecting = endpoint
-            .connect(server_addr, &host)
+            .connect(server_addr, self.tls_options.sni_host(&host))
             .map_err(|e| io::Error::other(format!("quinn connect: {e}")))?;
 
         // Bound the handshake on our `timeout`. quinn's internal
         // `idle_timeout` is separate and would only fire after a stall;
         // for ping latency we want a single hard cap.
-        match tokio::time::timeout(self.timeout, connecting).await {
+        let outcome = tokio::select! {
+            outcome = tokio::time::timeout(self.timeout, connecting) => outcome,
+            _ = cancel.cancelled() => {
+                endpoint.close(0u32.into(), b"cancelled");
+                // `cancellable` drops this future as soon as it sees
+                // the token too, so flush from a task of its own: the
+                // endpoint has to outlive us until the close is sent.
+                tokio::spawn(async move {
+                    let _ = tokio::time::timeout(CLOSE_GRACE, endpoint.wait_idle()).await;
+                });
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "QUIC handshake cancelled",
+                ));
+            }
+        };
+        match outcome {
             Ok(Ok(connection)) => {
+                report_handshake(&connection);
                 connection.close(0u32.into(), b"ping done");
                 // Give quinn a beat to send the CONNECTION_CLOSE frame
                 // before we drop the endpoint and the UDP socket
                 // disappears. wait_idle returns immediately once all
                 // pending datagrams have been flushed.
-                endpoint.wait_idle().await;
-                Ok(())
+                let _ = tokio::time::timeout(CLOSE_GRACE, endpoint.wait_idle()).await;
+                Ok(verdict.annotate(Report::default()))
             }
             Ok(Err(e)) => Err(io::Error::other(format!("quinn handshake: {e}"))),
             Err(_) => Err(io::Error::new(
@@ -117,6 +174,36 @@ impl Pinger for QuicPinger {
     }
 }
 
+/// Observer events for a finished handshake. quinn hides the rustls
+/// session, so the ALPN and certificates come from its handshake data
+/// and peer identity, and the byte counts from the connection stats.
+pub(crate) fn report_handshake(connection: &quinn::Connection) {
+    observe::emit(|o| o.on_connected(connection.remote_address()));
+    let alpn = connection
+        .handshake_data()
+        .and_then(|d| d.downcast::<quinn::crypto::rustls::HandshakeData>().ok())
+        .and_then(|d| d.protocol);
+    let peer_certificates = connection
+        .peer_identity()
+        .and_then(|id| id.downcast::<Vec<CertificateDer<'static>>>().ok())
+        .map(|certs| certs.iter().map(|c| c.to_vec()).collect())
+        .unwrap_or_default();
+    observe::emit(|o| {
+        o.on_tls_handshake(&TlsInfo {
+            alpn,
+            version: Some("TLSv1_3".into()),
+            cipher_suite: None,
+            key_exchange_group: None,
+            resumed: None,
+            peer_certificates,
+        })
+    });
+    let stats = connection.stats();
+    observe::sent(stats.udp_tx.bytes as usize);
+    observe::received(stats.udp_rx.bytes as usize);
+    observe::phase("handshake");
+}
+
 /// Parse `quic://host[:port]/...`, `https://host[:port]/...`, or
 /// `host[:port]` into `(host, port)`.
 fn parse_endpoint(endpoint: &str) -> Result<(String, u16)> {
@@ -155,42 +242,15 @@ fn parse_endpoint(endpoint: &str) -> Result<(String, u16)> {
     Ok((uri.domain, port))
 }
 
-/// Resolve `host:port` and pick a single `SocketAddr` to dial. We
-/// prefer IPv4 when both are returned because most local-loopback /
-/// container test fixtures bind v4-only, and falling back from IPv6
-/// to v4 means a 5-second timeout instead of a fast hit. On a
-/// production internet host with both AAAA and A records this still
-/// works — quinn doesn't care which family we pick.
-async fn resolve_first(host: &str, port: u16) -> Result<SocketAddr> {
-    let target = format!("{host}:{port}");
-    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(&target).await?.collect();
-    if let Some(v4) = addrs.iter().find(|a| a.is_ipv4()) {
-        return Ok(*v4);
-    }
-    addrs
-        .into_iter()
-        .next()
-        .ok_or_else(|| io::Error::other(format!("DNS lookup returned no addresses for {target}")))
-}
-
-/// Pick the right wildcard local-bind address for the resolved
-/// remote: `0.0.0.0:0` for IPv4, `[::]:0` for IPv6. quinn's UDP
-/// socket has to be in the same family as the remote it's about to
-/// dial.
-fn unspecified_for(remote: SocketAddr) -> SocketAddr {
-    if remote.is_ipv6() {
-        "[::]:0".parse().unwrap()
-    } else {
-        "0.0.0.0:0".parse().unwrap()
-    }
-}
-
 /// Build a rustls `ClientConfig` suitable for quinn: TLS 1.3 only, ring
 /// crypto provider, ALPN list applied. If `inject` is supplied it's
 /// used as the base (so a caller-provided trust anchor / cert verifier
 /// flows through); otherwise we build a fresh config that trusts the
 /// Mozilla webpki roots.
-fn build_rustls_config(inject: Option<&ClientConfig>, alpn: &[Vec<u8>]) -> Result<ClientConfig> {
+pub(crate) fn build_rustls_config(
+    inject: Option<&ClientConfig>,
+    alpn: &[Vec<u8>],
+) -> Result<ClientConfig> {
     let mut config = match inject {
         Some(c) => c.clone(),
         None => default_quic_config()?,
@@ -246,12 +306,4 @@ mod tests {
         assert!(parse_endpoint("").is_err());
         assert!(parse_endpoint("   ").is_err());
     }
-
-    #[test]
-    fn unspecified_for_picks_v4_or_v6() {
-        let v4: SocketAddr = "1.2.3.4:443".parse().unwrap();
-        assert_eq!(unspecified_for(v4), "0.0.0.0:0".parse().unwrap());
-        let v6: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
-        assert_eq!(unspecified_for(v6), "[::]:0".parse().unwrap());
-    }
 }
@@ -0,0 +1,63 @@
+//! Structured result of a single probe. `Pinger::ping` only says
+//! "the exchange completed"; `Pinger::probe` additionally hands back
+//! a `Report` carrying whatever the pinger learned on the way (a TCP
+//! banner, for instance). Always compiled — the trait depends on it.
+//!
+//! Besides the free-form `details`, a report carries the typed parts
+//! of the response that `Expectation`s assert on: status code,
+//! headers, body and DNS answers. Pingers fill in whichever apply to
+//! their protocol and leave the rest empty.
+
+use crate::cert::CertInfo;
+
+/// What a successful probe learned beyond the round trip itself.
+///
+/// `details` is an ordered list of human-readable `(key, value)`
+/// facts. The CLI prints them after `time=` and the MCP server
+/// returns them per iteration, so keys should be short and stable
+/// (`banner`, not `Banner text received`). Pingers with nothing to
+/// add return `Report::default()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub details: Vec<(String, String)>,
+    /// Protocol response code: HTTP / RTSP status, DNS RCODE.
+    pub status: Option<u16>,
+    /// Response headers in the order received, names as sent.
+    pub headers: Vec<(String, String)>,
+    /// Response payload as far as the pinger read it (HTTP body, TCP
+    /// banner). `None` when the protocol has no payload to report.
+    pub body: Option<Vec<u8>>,
+    /// DNS answer records rendered as text — `93.184.216.34`,
+    /// `10 mail.example.com.` — in answer-section order.
+    pub answers: Vec<String>,
+    /// Certificate chain the server presented, leaf first
+    /// (`TlsPinger`).
+    pub certificates: Vec<CertInfo>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.push((key.into(), value.into()));
+        self
+    }
+
+    /// First value recorded under `key`, if any.
+    pub fn detail(&self, key: &str) -> Option<&str> {
+        self.details
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// First header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
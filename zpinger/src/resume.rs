@@ -0,0 +1,93 @@
+//! Two-handshake probes for `TlsPinger` and `HttpPinger`: a full
+//! handshake, then a second connection that resumes its session and,
+//! optionally, sends TLS 1.3 0-RTT early data.
+//!
+//! Each probe gets a private session cache, so the first handshake is
+//! always full and the second can only resume from it — not from a
+//! ticket an earlier probe (or another pinger sharing the default
+//! config) left behind.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::client::Resumption;
+use rustls::{ClientConfig, ClientConnection, HandshakeKind};
+use tokio_rustls::TlsConnector;
+
+use crate::report::Report;
+use crate::util::format_ms;
+
+/// Sessions kept per probe. rustls sizes its in-memory cache in
+/// whole servers' worth of tickets (eight each) and a one-server
+/// cache loses what it stores, so ask for two servers' worth.
+const SESSIONS_PER_PROBE: usize = 16;
+
+/// Whether a probe also measures a resumed handshake.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResumptionMode {
+    /// One full handshake per probe.
+    #[default]
+    Off,
+    /// A full handshake, then a second connection resuming its
+    /// session (TLS 1.3 ticket, or TLS 1.2 ticket / session ID).
+    Ticket,
+    /// `Ticket`, with the second connection offering TLS 1.3 0-RTT
+    /// early data. `HttpPinger` sends its request as early data.
+    EarlyData,
+}
+
+impl ResumptionMode {
+    /// `config` with a fresh session cache, and early data enabled
+    /// when asked.
+    pub(crate) fn prepare(self, config: &ClientConfig) -> Arc<ClientConfig> {
+        let mut config = config.clone();
+        config.resumption = Resumption::in_memory_sessions(SESSIONS_PER_PROBE);
+        config.enable_early_data = self == ResumptionMode::EarlyData;
+        Arc::new(config)
+    }
+
+    /// Connector for a `prepare`d config. With early data on offer,
+    /// `connect` returns before the server has answered; the first
+    /// flush finishes the handshake.
+    pub(crate) fn connector(self, config: Arc<ClientConfig>) -> TlsConnector {
+        TlsConnector::from(config).early_data(self == ResumptionMode::EarlyData)
+    }
+}
+
+/// How one connection of a resumption probe went.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Handshake {
+    pub(crate) elapsed: Duration,
+    pub(crate) resumed: bool,
+    pub(crate) early_data: bool,
+}
+
+impl Handshake {
+    pub(crate) fn of(elapsed: Duration, conn: &ClientConnection) -> Self {
+        Self {
+            elapsed,
+            resumed: conn.handshake_kind() == Some(HandshakeKind::Resumed),
+            early_data: conn.is_early_data_accepted(),
+        }
+    }
+}
+
+/// `report` plus how the resumed connection compared to the full
+/// one: `full_ms`, `resumed_ms`, `resumption` and, for
+/// `EarlyData`, `early_data` (`accepted` / `rejected`).
+pub(crate) fn annotate(
+    mut report: Report,
+    mode: ResumptionMode,
+    full: Handshake,
+    resumed: Handshake,
+) -> Report {
+    let verdict = |accepted: bool| if accepted { "accepted" } else { "rejected" };
+    report = report
+        .with_detail("full_ms", format_ms(full.elapsed))
+        .with_detail("resumed_ms", format_ms(resumed.elapsed))
+        .with_detail("resumption", verdict(resumed.resumed));
+    if mode == ResumptionMode::EarlyData {
+        report = report.with_detail("early_data", verdict(resumed.early_data));
+    }
+    report
+}
@@ -27,14 +27,14 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
-use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
 
+use crate::observe;
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::tls::{client_config, TlsOptions};
 use crate::uri::{get_uri, URI};
 use crate::util::with_timeout;
 
@@ -55,6 +55,7 @@ pub struct RtmpPinger {
     pub target: String,
     pub timeout: Duration,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl RtmpPinger {
@@ -63,6 +64,7 @@ impl RtmpPinger {
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -76,36 +78,44 @@ impl RtmpPinger {
         self
     }
 
+    /// Shared TLS settings for `rtmps://`.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
     async fn ping_plain(&self, uri: &URI) -> Result<()> {
         let endpoint = endpoint_for(uri, DEFAULT_PORT_PLAIN)?;
         with_timeout(self.timeout, async move {
-            let mut stream = TcpStream::connect(&endpoint).await?;
+            let mut stream = observe::connect_tcp(&endpoint).await?;
             run_handshake(&mut stream).await
         })
         .await
     }
 
-    async fn ping_tls(&self, uri: &URI) -> Result<()> {
+    async fn ping_tls(&self, uri: &URI) -> Result<Report> {
         let endpoint = endpoint_for(uri, DEFAULT_PORT_TLS)?;
-        let server_name = ServerName::try_from(uri.domain.clone())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-        let config = self
-            .tls_config
-            .clone()
-            .unwrap_or_else(default_client_config);
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict) = client_config(&self.tls_config, &self.tls_options);
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
+            let tcp = observe::connect_tcp(&endpoint).await?;
             let connector = TlsConnector::from(config);
             let mut stream = connector.connect(server_name, tcp).await?;
+            observe::tls_handshake(stream.get_ref().1);
             run_handshake(&mut stream).await
         })
         .await
+        .map(|()| verdict.annotate(Report::new()))
     }
 }
 
 #[async_trait]
 impl Pinger for RtmpPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
         let uri = get_uri(&self.target);
         if uri.domain.is_empty() {
             return Err(io::Error::new(
@@ -115,7 +125,7 @@ impl Pinger for RtmpPinger {
         }
         let scheme = uri.scheme.to_ascii_lowercase();
         match scheme.as_str() {
-            "" | "rtmp" => self.ping_plain(&uri).await,
+            "" | "rtmp" => self.ping_plain(&uri).await.map(|()| Report::new()),
             "rtmps" => self.ping_tls(&uri).await,
             other => Err(io::Error::other(format!(
                 "scheme '{other}' is not supported by RtmpPinger (use rtmp:// or rtmps://)"
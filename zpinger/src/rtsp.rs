@@ -22,7 +22,9 @@
 //!
 //! Validation stops at the first `\r\n\r\n` or `MAX_RESPONSE_BYTES`
 //! whichever comes first; we don't care about the body, only the
-//! status line.
+//! status line. `probe` reports the status code and headers (the
+//! `Public:` method list, say) for `Expectation`s; `exchange` skips
+//! the 200 check so a status expectation can replace it.
 //!
 //! [RFC 2326 §10.1]: https://www.rfc-editor.org/rfc/rfc2326#section-10.1
 
@@ -31,16 +33,16 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
 
+use crate::observe;
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::tls::{client_config, TlsOptions};
 use crate::uri::{get_uri, URI};
-use crate::util::with_timeout;
+use crate::util::{parse_response_head, with_timeout};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_PORT_PLAIN: u16 = 554;
@@ -49,8 +51,6 @@ const DEFAULT_PORT_TLS: u16 = 322;
 const MAX_RESPONSE_BYTES: usize = 4096;
 const HEADER_TERMINATOR: &[u8] = b"\r\n\r\n";
 
-const STATUS_LINE_PREFIX: &[u8] = b"RTSP/1.0 200";
-
 /// RTSP pinger.
 ///
 /// Speaks plain TCP for `rtsp://` (or schemeless) targets and rustls
@@ -61,6 +61,7 @@ pub struct RtspPinger {
     pub target: String,
     pub timeout: Duration,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl RtspPinger {
@@ -69,6 +70,7 @@ impl RtspPinger {
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -82,40 +84,54 @@ impl RtspPinger {
         self
     }
 
-    async fn ping_plain(&self, uri: &URI) -> Result<()> {
+    /// Shared TLS settings for `rtsps://`.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    async fn exchange_plain(&self, uri: &URI) -> Result<Report> {
         let endpoint = endpoint_for(uri, DEFAULT_PORT_PLAIN)?;
         let request = build_options_request(&uri.domain, port_or(uri, DEFAULT_PORT_PLAIN));
         with_timeout(self.timeout, async move {
-            let mut stream = TcpStream::connect(&endpoint).await?;
+            let mut stream = observe::connect_tcp(&endpoint).await?;
             stream.write_all(&request).await?;
-            validate_response(&mut stream).await
+            read_response(&mut stream).await
         })
         .await
     }
 
-    async fn ping_tls(&self, uri: &URI) -> Result<()> {
+    async fn exchange_tls(&self, uri: &URI) -> Result<Report> {
         let endpoint = endpoint_for(uri, DEFAULT_PORT_TLS)?;
-        let server_name = ServerName::try_from(uri.domain.clone())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-        let config = self
-            .tls_config
-            .clone()
-            .unwrap_or_else(default_client_config);
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict) = client_config(&self.tls_config, &self.tls_options);
         let request = build_options_request(&uri.domain, port_or(uri, DEFAULT_PORT_TLS));
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
+            let tcp = observe::connect_tcp(&endpoint).await?;
             let connector = TlsConnector::from(config);
             let mut stream = connector.connect(server_name, tcp).await?;
+            observe::tls_handshake(stream.get_ref().1);
             stream.write_all(&request).await?;
-            validate_response(&mut stream).await
+            read_response(&mut stream).await
         })
         .await
+        .map(|report| verdict.annotate(report))
     }
 }
 
 #[async_trait]
 impl Pinger for RtspPinger {
     async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let report = self.exchange().await?;
+        check_status(&report)?;
+        Ok(report)
+    }
+
+    async fn exchange(&self) -> Result<Report> {
         let uri = get_uri(&self.target);
         if uri.domain.is_empty() {
             return Err(io::Error::new(
@@ -125,8 +141,8 @@ impl Pinger for RtspPinger {
         }
         let scheme = uri.scheme.to_ascii_lowercase();
         match scheme.as_str() {
-            "" | "rtsp" => self.ping_plain(&uri).await,
-            "rtsps" => self.ping_tls(&uri).await,
+            "" | "rtsp" => self.exchange_plain(&uri).await,
+            "rtsps" => self.exchange_tls(&uri).await,
             other => Err(io::Error::other(format!(
                 "scheme '{other}' is not supported by RtspPinger (use rtsp:// or rtsps://)"
             ))),
@@ -162,9 +178,11 @@ fn build_options_request(domain: &str, port: u16) -> Vec<u8> {
     .into_bytes()
 }
 
-/// Read until `\r\n\r\n` (end of headers) or the buffer is full, then
-/// confirm the first line begins with `RTSP/1.0 200`.
-async fn validate_response<S>(stream: &mut S) -> Result<()>
+/// Read until `\r\n\r\n` (end of headers) or the buffer is full,
+/// then parse the status code and headers. Anything that isn't an
+/// `RTSP/1.0` status line is an error here; whether the code is
+/// acceptable is `check_status`'s call.
+async fn read_response<S>(stream: &mut S) -> Result<Report>
 where
     S: AsyncRead + Unpin,
 {
@@ -180,13 +198,30 @@ where
             break;
         }
     }
-    if !buf.starts_with(STATUS_LINE_PREFIX) {
+    let head_end = find_header_terminator(&buf).unwrap_or(buf.len());
+    let head = String::from_utf8_lossy(&buf[..head_end]);
+    let (status, headers) = parse_response_head(&head, "RTSP/1.0 ").ok_or_else(|| {
         let preview = String::from_utf8_lossy(&buf[..buf.len().min(64)]);
-        return Err(io::Error::other(format!(
+        io::Error::other(format!(
             "RTSP response did not start with `RTSP/1.0 200`: {preview:?}"
-        )));
+        ))
+    })?;
+    Ok(Report {
+        status: Some(status),
+        headers,
+        ..Report::default()
+    })
+}
+
+/// The built-in success rule: `RTSP/1.0 200`.
+fn check_status(report: &Report) -> Result<()> {
+    match report.status {
+        Some(200) => Ok(()),
+        other => Err(io::Error::other(format!(
+            "RTSP response did not start with `RTSP/1.0 200`: status {}",
+            other.map_or_else(|| "missing".to_string(), |s| s.to_string())
+        ))),
     }
-    Ok(())
 }
 
 fn find_header_terminator(buf: &[u8]) -> Option<usize> {
@@ -217,28 +252,45 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn validate_response_accepts_200_with_body() {
+    async fn check_status_accepts_200_with_body() {
         let canned = b"RTSP/1.0 200 OK\r\nCSeq: 1\r\nPublic: OPTIONS, DESCRIBE\r\n\r\n";
-        validate_response(&mut &canned[..]).await.unwrap();
+        let report = read_response(&mut &canned[..]).await.unwrap();
+        check_status(&report).unwrap();
     }
 
     #[tokio::test]
-    async fn validate_response_rejects_4xx() {
+    async fn check_status_rejects_4xx() {
         let canned = b"RTSP/1.0 404 Not Found\r\nCSeq: 1\r\n\r\n";
-        let err = validate_response(&mut &canned[..]).await.unwrap_err();
+        let report = read_response(&mut &canned[..]).await.unwrap();
+        let err = check_status(&report).unwrap_err();
         assert!(err.to_string().contains("RTSP/1.0 200"));
     }
 
     #[tokio::test]
-    async fn validate_response_rejects_http() {
+    async fn read_response_rejects_http() {
         // Real-world misconfiguration: port 80 server on RTSP host.
         let canned = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
-        assert!(validate_response(&mut &canned[..]).await.is_err());
+        assert!(read_response(&mut &canned[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_response_rejects_other_rtsp_versions() {
+        let canned = b"RTSP/2.0 200 OK\r\nCSeq: 1\r\n\r\n";
+        assert!(read_response(&mut &canned[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_response_reports_status_and_headers() {
+        let canned = b"RTSP/1.0 454 Session Not Found\r\nCSeq: 1\r\nPublic: OPTIONS\r\n\r\n";
+        let report = read_response(&mut &canned[..]).await.unwrap();
+        assert_eq!(report.status, Some(454));
+        assert_eq!(report.header("public"), Some("OPTIONS"));
+        assert!(check_status(&report).is_err());
     }
 
     #[tokio::test]
-    async fn validate_response_rejects_eof() {
+    async fn read_response_rejects_eof() {
         let canned: &[u8] = b"";
-        assert!(validate_response(&mut &canned[..]).await.is_err());
+        assert!(read_response(&mut &canned[..]).await.is_err());
     }
 }
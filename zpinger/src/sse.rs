@@ -0,0 +1,436 @@
+//! Server-Sent Events pinger — opens an `text/event-stream` over
+//! HTTP/1.1 and times the first event to arrive (HTML Living
+//! Standard, "Server-sent events").
+
+use std::io::{self, Result};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use rustls::ClientConfig;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::TlsConnector;
+
+use crate::expect::AssertionError;
+use crate::http::{endpoint_for, open_stream, request_target, Request};
+use crate::observe;
+use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::tls::{client_config, TlsOptions};
+use crate::uri::{get_uri, URI};
+use crate::util::{format_ms, with_timeout};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_USER_AGENT: &str = "Knock Knock";
+
+/// Server-Sent Events pinger — sends `GET` with
+/// `Accept: text/event-stream` to an `http://` or `https://` URL and
+/// reads the stream until an event arrives, or with `with_event` /
+/// `with_data_pattern` until one matches. The stream must answer 200
+/// with a `text/event-stream` content type.
+///
+/// The report carries the matching event's data as the body and adds
+/// `header_ms` (request to response head), `first_event_ms` (request
+/// to the event), `event`, `events_seen` (matching one included) and,
+/// when the server sent them, `id` and `retry_ms`.
+pub struct SsePinger {
+    pub url: String,
+    /// For the whole probe: connect, handshake and the wait for an
+    /// event.
+    pub timeout: Duration,
+    /// Event type to wait for (`message` for events without one);
+    /// `None` takes any.
+    pub event: Option<String>,
+    /// Regex the event's data must match.
+    pub data_pattern: Option<String>,
+    /// Sent as `Last-Event-ID`, asking the server to resume after it.
+    pub last_event_id: Option<String>,
+    /// Extra request headers, in order.
+    pub headers: Vec<(String, String)>,
+    tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
+}
+
+impl SsePinger {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            event: None,
+            data_pattern: None,
+            last_event_id: None,
+            headers: Vec::new(),
+            tls_config: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Skip events of other types. Events sent without an `event:`
+    /// field are `message`.
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Skip events whose data doesn't match `pattern`. Multi-line
+    /// data is matched joined with `\n`.
+    pub fn with_data_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.data_pattern = Some(pattern.into());
+        self
+    }
+
+    pub fn with_last_event_id(mut self, id: impl Into<String>) -> Self {
+        self.last_event_id = Some(id.into());
+        self
+    }
+
+    /// Add a request header. One named `Host`, `User-Agent`, `Accept`
+    /// or `Cache-Control` replaces the default.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Shared TLS settings for `https://` streams.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    fn build_request(&self, uri: &URI, host_header: &str) -> Request {
+        let custom = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        };
+        let defaults = [
+            ("Host", host_header),
+            ("User-Agent", DEFAULT_USER_AGENT),
+            ("Accept", "text/event-stream"),
+            ("Cache-Control", "no-cache"),
+        ];
+        let mut headers: Vec<(String, String)> = defaults
+            .into_iter()
+            .filter(|(name, _)| !custom(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        if let Some(id) = self
+            .last_event_id
+            .as_ref()
+            .filter(|_| !custom("last-event-id"))
+        {
+            headers.push(("Last-Event-ID".to_string(), id.clone()));
+        }
+        headers.extend(self.headers.iter().cloned());
+        Request {
+            method: "GET".to_string(),
+            path: request_target(uri),
+            headers,
+            body: None,
+            max_body_bytes: 0,
+        }
+    }
+
+    /// Read events off an opened stream until one is wanted.
+    async fn listen<S>(
+        &self,
+        stream: &mut S,
+        request: &Request,
+        data_pattern: Option<&regex::Regex>,
+    ) -> Result<Report>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let sent = Instant::now();
+        let mut response = open_stream(stream, request).await?;
+        let header_time = sent.elapsed();
+        observe::phase("headers");
+
+        let mut report = Report::new().with_detail("header_ms", format_ms(header_time));
+        report.status = Some(response.status);
+        report.headers = response.headers.clone();
+        if response.status != 200 || !is_event_stream(&report) {
+            return Ok(report);
+        }
+
+        let mut parser = Parser::default();
+        let mut seen = 0;
+        while let Some(piece) = response.next().await? {
+            for event in parser.push(&piece) {
+                seen += 1;
+                let wanted = self.event.as_ref().is_none_or(|name| *name == event.name)
+                    && data_pattern.is_none_or(|re| re.is_match(&event.data));
+                if !wanted {
+                    continue;
+                }
+                observe::phase("event");
+                report = report
+                    .with_detail("first_event_ms", format_ms(sent.elapsed()))
+                    .with_detail("event", event.name)
+                    .with_detail("events_seen", seen.to_string());
+                if !event.id.is_empty() {
+                    report = report.with_detail("id", event.id);
+                }
+                if let Some(retry) = parser.retry {
+                    report = report.with_detail("retry_ms", retry.to_string());
+                }
+                report.body = Some(event.data.into_bytes());
+                return Ok(report);
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            match seen {
+                0 => "event stream closed before the first event".to_string(),
+                n => format!("event stream closed after {n} events, none matching"),
+            },
+        ))
+    }
+
+    async fn exchange_plain(
+        &self,
+        uri: &URI,
+        data_pattern: Option<&regex::Regex>,
+    ) -> Result<Report> {
+        let endpoint = endpoint_for(uri, 80)?;
+        let request = self.build_request(uri, &endpoint);
+        with_timeout(self.timeout, async move {
+            let mut stream = observe::connect_tcp(&endpoint).await?;
+            self.listen(&mut stream, &request, data_pattern).await
+        })
+        .await
+    }
+
+    async fn exchange_tls(&self, uri: &URI, data_pattern: Option<&regex::Regex>) -> Result<Report> {
+        let endpoint = endpoint_for(uri, 443)?;
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict) = client_config(&self.tls_config, &self.tls_options);
+        let request = self.build_request(uri, &endpoint);
+        with_timeout(self.timeout, async move {
+            let tcp = observe::connect_tcp(&endpoint).await?;
+            let mut stream = TlsConnector::from(config).connect(server_name, tcp).await?;
+            observe::tls_handshake(stream.get_ref().1);
+            self.listen(&mut stream, &request, data_pattern).await
+        })
+        .await
+        .map(|report| verdict.annotate(report))
+    }
+}
+
+#[async_trait]
+impl Pinger for SsePinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let report = self.exchange().await?;
+        let mut failures = Vec::new();
+        match report.status {
+            Some(200) => {}
+            Some(code) => failures.push(format!("status {code} not 200")),
+            None => {}
+        }
+        if !is_event_stream(&report) {
+            let content_type = report.header("content-type").unwrap_or("none");
+            failures.push(format!(
+                "Content-Type {content_type} is not text/event-stream"
+            ));
+        }
+        if !failures.is_empty() {
+            return Err(AssertionError { failures }.into());
+        }
+        Ok(report)
+    }
+
+    /// The response head and, if it opened an event stream, the
+    /// first wanted event — any status or content type is reported
+    /// as is.
+    async fn exchange(&self) -> Result<Report> {
+        let data_pattern = match &self.data_pattern {
+            Some(pattern) => Some(regex::Regex::new(pattern).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("bad --data regex: {e}"),
+                )
+            })?),
+            None => None,
+        };
+        let uri = get_uri(&self.url);
+        match uri.scheme.to_ascii_lowercase().as_str() {
+            "" | "http" => self.exchange_plain(&uri, data_pattern.as_ref()).await,
+            "https" => self.exchange_tls(&uri, data_pattern.as_ref()).await,
+            other => Err(io::Error::other(format!(
+                "scheme '{other}' is not supported by SsePinger (use http:// or https://)"
+            ))),
+        }
+    }
+}
+
+fn is_event_stream(report: &Report) -> bool {
+    report.header("content-type").is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .eq_ignore_ascii_case("text/event-stream")
+    })
+}
+
+/// One dispatched event.
+#[derive(Debug, PartialEq, Eq)]
+struct Event {
+    /// The `event:` field, or `message`.
+    name: String,
+    data: String,
+    /// The last event ID in effect; empty when none was set.
+    id: String,
+}
+
+/// Incremental `text/event-stream` parser: feed it the body as it
+/// arrives, get back the events it completes.
+#[derive(Default)]
+struct Parser {
+    line: Vec<u8>,
+    /// The last byte was a CR, so an LF right after belongs to it.
+    after_cr: bool,
+    /// Past the first line, where a byte order mark may sit.
+    started: bool,
+    event: String,
+    data: String,
+    last_id: String,
+    retry: Option<u64>,
+}
+
+impl Parser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            if std::mem::take(&mut self.after_cr) && byte == b'\n' {
+                continue;
+            }
+            match byte {
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    events.extend(self.process(&line));
+                }
+                _ => self.line.push(byte),
+            }
+        }
+        events
+    }
+
+    fn process(&mut self, line: &[u8]) -> Option<Event> {
+        let mut line = String::from_utf8_lossy(line).into_owned();
+        if !std::mem::replace(&mut self.started, true) {
+            if let Some(rest) = line.strip_prefix('\u{feff}') {
+                line = rest.to_string();
+            }
+        }
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_str(), ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => self.last_id = value.to_string(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let name = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+        if data.is_empty() {
+            return None;
+        }
+        data.pop();
+        Some(Event {
+            name: if name.is_empty() {
+                "message".into()
+            } else {
+                name
+            },
+            data,
+            id: self.last_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, data: &str, id: &str) -> Event {
+        Event {
+            name: name.into(),
+            data: data.into(),
+            id: id.into(),
+        }
+    }
+
+    #[test]
+    fn parser_handles_every_line_ending_split_anywhere() {
+        let stream = b"\xef\xbb\xbfdata: one\r\n\r\nevent: tick\rdata:two\r\rdata: a\ndata: b\n\n";
+        let expected = [
+            event("message", "one", ""),
+            event("tick", "two", ""),
+            event("message", "a\nb", ""),
+        ];
+        for split in 0..stream.len() {
+            let mut parser = Parser::default();
+            let mut events = parser.push(&stream[..split]);
+            events.extend(parser.push(&stream[split..]));
+            assert_eq!(events, expected, "split at {split}");
+        }
+    }
+
+    #[test]
+    fn parser_skips_comments_and_empty_events_and_keeps_the_last_id() {
+        let mut parser = Parser::default();
+        let events = parser.push(
+            b": keep-alive\n\nevent: ignored\n\nid: 7\nretry: 2500\ndata: x\n\n\
+              data\n\nretry: soon\nid\n\n",
+        );
+        assert_eq!(
+            events,
+            [event("message", "x", "7"), event("message", "", "7")]
+        );
+        assert_eq!(parser.retry, Some(2500));
+        // A bare `id` resets it for later events.
+        assert_eq!(parser.push(b"data: y\n\n"), [event("message", "y", "")]);
+    }
+
+    #[test]
+    fn parser_holds_an_event_until_its_blank_line() {
+        let mut parser = Parser::default();
+        assert!(parser.push(b"data: half\n").is_empty());
+        assert_eq!(parser.push(b"\n"), [event("message", "half", "")]);
+    }
+}
@@ -0,0 +1,385 @@
+//! OCSP stapling and Certificate Transparency checks for `TlsPinger`.
+//!
+//! rustls always sends the `status_request` extension and hands the
+//! stapled response to the certificate verifier, which keeps it in a
+//! `Staple` (see `crate::tls_verify`). This module reads it: the
+//! certificate status, `thisUpdate` / `nextUpdate`, who signed it,
+//! and any SCTs it carries. The OCSP signature is not checked — the
+//! point is whether the server staples something current, not a
+//! revocation verdict to act on.
+//!
+//! SCTs are counted from the leaf certificate's embedded list and
+//! from the staple's SCT extension. rustls doesn't request SCTs in
+//! the TLS extension, so servers that only send them there count as
+//! none.
+
+use std::io::{self, Result};
+use std::time::{Duration, SystemTime};
+
+use x509_parser::der_parser::asn1_rs::{Any, Class, FromDer, Tag};
+use x509_parser::extensions::ParsedExtension;
+use x509_parser::prelude::{parse_x509_certificate, X509Name};
+use x509_parser::time::ASN1Time;
+
+use crate::cert::format_utc;
+use crate::report::Report;
+
+/// `id-pkix-ocsp-basic` (1.3.6.1.5.5.7.48.1.1), content octets.
+const OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+/// RFC 6962 OCSP SCT list extension (1.3.6.1.4.1.11129.2.4.5).
+const OCSP_SCT_LIST: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x04, 0x05];
+/// How far ahead of our clock `thisUpdate` may be before the staple
+/// counts as not yet valid.
+const CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// What a stapled OCSP response says about the leaf certificate.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct OcspStaple {
+    /// `good`, `revoked` or `unknown`.
+    pub(crate) status: &'static str,
+    pub(crate) this_update: SystemTime,
+    pub(crate) next_update: Option<SystemTime>,
+    /// Responder name (`CN=...`) or key hash (`key:ab:cd:...`).
+    pub(crate) responder: String,
+    /// SCTs in the response's SCT list extension.
+    pub(crate) scts: usize,
+}
+
+/// Parse a DER `OCSPResponse`. With `serial` (the leaf's raw serial)
+/// the matching `SingleResponse` is used, otherwise the first.
+pub(crate) fn parse_ocsp(der: &[u8], serial: Option<&[u8]>) -> Result<OcspStaple> {
+    let (response, _) = element(der)?;
+    let (status, rest) = element(response.data)?;
+    if status.tag() != Tag::Enumerated {
+        return Err(invalid("responseStatus is not an ENUMERATED"));
+    }
+    match status.data {
+        [0] => {}
+        [code] => return Err(invalid(format!("responder said {}", status_name(*code)))),
+        _ => return Err(invalid("bad responseStatus")),
+    }
+    // responseBytes [0] EXPLICIT SEQUENCE { responseType, response }
+    let (bytes, _) = element(rest)?;
+    let (bytes, _) = element(bytes.data)?;
+    let (kind, rest) = element(bytes.data)?;
+    if kind.data != OCSP_BASIC {
+        return Err(invalid("not a basic OCSP response"));
+    }
+    let (basic, _) = element(rest)?;
+    let (basic, _) = element(basic.data)?;
+    let (tbs, _) = element(basic.data)?;
+
+    let mut fields = tbs.data;
+    let (mut field, rest) = element(fields)?;
+    fields = rest;
+    if field.class() == Class::ContextSpecific && field.tag() == Tag(0) {
+        (field, fields) = element(fields)?;
+    }
+    let responder = responder(&field)?;
+    let (_produced_at, rest) = element(fields)?;
+    let (responses, _) = element(rest)?;
+
+    let mut chosen = None;
+    let mut list = responses.data;
+    while !list.is_empty() {
+        let (single, rest) = element(list)?;
+        list = rest;
+        let parsed = single_response(single.data)?;
+        let matches = serial.is_none_or(|s| parsed.serial == s);
+        if matches || chosen.is_none() {
+            chosen = Some(parsed);
+        }
+        if matches {
+            break;
+        }
+    }
+    let single = chosen.ok_or_else(|| invalid("no SingleResponse"))?;
+    Ok(OcspStaple {
+        status: single.status,
+        this_update: single.this_update,
+        next_update: single.next_update,
+        responder,
+        scts: single.scts,
+    })
+}
+
+/// SCTs embedded in a DER certificate (RFC 6962 extension
+/// 1.3.6.1.4.1.11129.2.4.2).
+pub(crate) fn embedded_scts(der: &[u8]) -> usize {
+    let Ok((_, cert)) = parse_x509_certificate(der) else {
+        return 0;
+    };
+    cert.extensions()
+        .iter()
+        .map(|ext| match ext.parsed_extension() {
+            ParsedExtension::SCT(list) => list.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The leaf's raw serial, for picking its `SingleResponse`.
+pub(crate) fn leaf_serial(der: &[u8]) -> Option<Vec<u8>> {
+    let (_, cert) = parse_x509_certificate(der).ok()?;
+    Some(cert.raw_serial().to_vec())
+}
+
+/// `report` plus the staple and SCT details: `ocsp` (status, `none`,
+/// `unavailable` or `invalid: ...`), `ocsp_this_update`,
+/// `ocsp_next_update`, `ocsp_responder`, `scts`, and a `warning` for
+/// a missing, stale or revoked staple. `staple` is `None` when the
+/// response couldn't be captured at all.
+pub(crate) fn annotate(
+    mut report: Report,
+    staple: Option<Option<Vec<u8>>>,
+    leaf: Option<&[u8]>,
+    now: SystemTime,
+) -> Report {
+    let mut scts = leaf.map(embedded_scts).unwrap_or(0);
+    match staple {
+        None => report = report.with_detail("ocsp", "unavailable"),
+        Some(None) => {
+            report = report
+                .with_detail("ocsp", "none")
+                .with_detail("warning", "no OCSP response stapled");
+        }
+        Some(Some(der)) => {
+            let serial = leaf.and_then(leaf_serial);
+            match parse_ocsp(&der, serial.as_deref()) {
+                Ok(ocsp) => {
+                    scts += ocsp.scts;
+                    report = report
+                        .with_detail("ocsp", ocsp.status)
+                        .with_detail("ocsp_this_update", format_utc(ocsp.this_update));
+                    if let Some(next) = ocsp.next_update {
+                        report = report.with_detail("ocsp_next_update", format_utc(next));
+                    }
+                    report = report.with_detail("ocsp_responder", &ocsp.responder);
+                    if let Some(warning) = staple_warning(&ocsp, now) {
+                        report = report.with_detail("warning", warning);
+                    }
+                }
+                Err(e) => {
+                    report = report
+                        .with_detail("ocsp", format!("invalid: {e}"))
+                        .with_detail("warning", "stapled OCSP response does not parse");
+                }
+            }
+        }
+    }
+    report.with_detail("scts", scts.to_string())
+}
+
+/// Why a parsed staple deserves a warning, if it does.
+fn staple_warning(ocsp: &OcspStaple, now: SystemTime) -> Option<String> {
+    if ocsp.status != "good" {
+        return Some(format!("stapled OCSP status is {}", ocsp.status));
+    }
+    if let Some(next) = ocsp.next_update.filter(|next| *next < now) {
+        return Some(format!(
+            "stale OCSP staple: nextUpdate {} has passed",
+            format_utc(next)
+        ));
+    }
+    if ocsp.this_update > now + CLOCK_SKEW {
+        return Some(format!(
+            "OCSP staple not yet valid: thisUpdate {}",
+            format_utc(ocsp.this_update)
+        ));
+    }
+    None
+}
+
+struct Single<'a> {
+    serial: &'a [u8],
+    status: &'static str,
+    this_update: SystemTime,
+    next_update: Option<SystemTime>,
+    scts: usize,
+}
+
+/// `SingleResponse ::= SEQUENCE { certID, certStatus, thisUpdate,
+/// nextUpdate [0] OPTIONAL, singleExtensions [1] OPTIONAL }`
+fn single_response(data: &[u8]) -> Result<Single<'_>> {
+    let (cert_id, rest) = element(data)?;
+    let serial = {
+        let (_hash_alg, rest) = element(cert_id.data)?;
+        let (_name_hash, rest) = element(rest)?;
+        let (_key_hash, rest) = element(rest)?;
+        element(rest)?.0.data
+    };
+    let (status, rest) = element(rest)?;
+    let status = match (status.class(), status.tag()) {
+        (Class::ContextSpecific, Tag(0)) => "good",
+        (Class::ContextSpecific, Tag(1)) => "revoked",
+        (Class::ContextSpecific, Tag(2)) => "unknown",
+        _ => return Err(invalid("bad certStatus")),
+    };
+    let (this_update, mut rest) = time(rest)?;
+    let mut next_update = None;
+    let mut scts = 0;
+    while !rest.is_empty() {
+        let (field, tail) = element(rest)?;
+        rest = tail;
+        match (field.class(), field.tag()) {
+            (Class::ContextSpecific, Tag(0)) => next_update = Some(time(field.data)?.0),
+            (Class::ContextSpecific, Tag(1)) => scts = extension_scts(field.data)?,
+            _ => {}
+        }
+    }
+    Ok(Single {
+        serial,
+        status,
+        this_update,
+        next_update,
+        scts,
+    })
+}
+
+/// SCTs in the SCT list extension of `Extensions`, if present.
+fn extension_scts(data: &[u8]) -> Result<usize> {
+    let (extensions, _) = element(data)?;
+    let mut list = extensions.data;
+    while !list.is_empty() {
+        let (extension, rest) = element(list)?;
+        list = rest;
+        let (oid, rest) = element(extension.data)?;
+        if oid.data != OCSP_SCT_LIST {
+            continue;
+        }
+        // Skip the `critical` flag when it's encoded.
+        let (value, rest) = element(rest)?;
+        let value = if value.tag() == Tag::Boolean {
+            element(rest)?.0
+        } else {
+            value
+        };
+        // extnValue wraps an OCTET STRING holding the TLS-encoded list.
+        let (inner, _) = element(value.data)?;
+        return Ok(count_sct_list(inner.data));
+    }
+    Ok(0)
+}
+
+/// Entries in a TLS `SignedCertificateTimestampList`: a 16-bit total
+/// length, then 16-bit-length-prefixed SCTs.
+fn count_sct_list(list: &[u8]) -> usize {
+    let Some((len, mut body)) = split_u16(list) else {
+        return 0;
+    };
+    body = &body[..len.min(body.len())];
+    let mut count = 0;
+    while let Some((len, rest)) = split_u16(body) {
+        if rest.len() < len {
+            break;
+        }
+        count += 1;
+        body = &rest[len..];
+    }
+    count
+}
+
+fn split_u16(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    match bytes {
+        [hi, lo, rest @ ..] => Some((usize::from(u16::from_be_bytes([*hi, *lo])), rest)),
+        _ => None,
+    }
+}
+
+/// `ResponderID ::= CHOICE { byName [1] Name, byKey [2] KeyHash }`
+fn responder(field: &Any<'_>) -> Result<String> {
+    match (field.class(), field.tag()) {
+        (Class::ContextSpecific, Tag(1)) => {
+            let (_, name) = X509Name::from_der(field.data)
+                .map_err(|e| invalid(format!("bad responder name: {e}")))?;
+            Ok(name.to_string())
+        }
+        (Class::ContextSpecific, Tag(2)) => {
+            let (hash, _) = element(field.data)?;
+            let hex: Vec<String> = hash.data.iter().map(|b| format!("{b:02x}")).collect();
+            Ok(format!("key:{}", hex.join(":")))
+        }
+        _ => Err(invalid("bad responderID")),
+    }
+}
+
+/// A GeneralizedTime (or UTCTime) element off the front of `input`.
+fn time(input: &[u8]) -> Result<(SystemTime, &[u8])> {
+    let (rest, time) =
+        ASN1Time::from_der(input).map_err(|e| invalid(format!("bad OCSP time: {e}")))?;
+    let secs = time.timestamp().max(0) as u64;
+    Ok((SystemTime::UNIX_EPOCH + Duration::from_secs(secs), rest))
+}
+
+/// One DER element off the front of `input`, and what follows it.
+fn element(input: &[u8]) -> Result<(Any<'_>, &[u8])> {
+    let (rest, any) = Any::from_der(input).map_err(|e| invalid(format!("bad DER: {e}")))?;
+    Ok((any, rest))
+}
+
+/// RFC 6960 `OCSPResponseStatus` names.
+fn status_name(code: u8) -> String {
+    match code {
+        1 => "malformedRequest".into(),
+        2 => "internalError".into(),
+        3 => "tryLater".into(),
+        5 => "sigRequired".into(),
+        6 => "unauthorized".into(),
+        other => format!("status {other}"),
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staple(status: &'static str, next_update: Option<SystemTime>) -> OcspStaple {
+        OcspStaple {
+            status,
+            this_update: SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000),
+            next_update,
+            responder: "CN=r".into(),
+            scts: 0,
+        }
+    }
+
+    #[test]
+    fn counts_sct_list_entries() {
+        assert_eq!(count_sct_list(&[0, 6, 0, 1, 0xaa, 0, 1, 0xbb]), 2);
+        assert_eq!(count_sct_list(&[0, 0]), 0);
+        // Truncated entry: only the complete one counts.
+        assert_eq!(count_sct_list(&[0, 7, 0, 1, 0xaa, 0, 9, 0xbb, 0xcc]), 1);
+        assert_eq!(count_sct_list(&[]), 0);
+    }
+
+    #[test]
+    fn warns_on_revoked_stale_and_future_staples() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000);
+        let later = Some(now + Duration::from_secs(3600));
+        assert_eq!(staple_warning(&staple("good", later), now), None);
+        assert_eq!(staple_warning(&staple("good", None), now), None);
+        assert_eq!(
+            staple_warning(&staple("revoked", later), now).as_deref(),
+            Some("stapled OCSP status is revoked")
+        );
+        let stale = staple_warning(&staple("good", Some(now - Duration::from_secs(1))), now);
+        assert!(stale.unwrap().starts_with("stale OCSP staple"));
+        let mut early = staple("good", later);
+        early.this_update = now + Duration::from_secs(3600);
+        assert!(staple_warning(&early, now)
+            .unwrap()
+            .starts_with("OCSP staple not yet valid"));
+    }
+
+    #[test]
+    fn rejects_unsuccessful_and_garbage_responses() {
+        // OCSPResponse { responseStatus tryLater }
+        let err = parse_ocsp(&[0x30, 0x03, 0x0a, 0x01, 0x03], None).unwrap_err();
+        assert!(err.to_string().contains("tryLater"), "{err}");
+        assert!(parse_ocsp(b"not der", None).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! STARTTLS — the plaintext dialogues that upgrade a connection to
+//! TLS in-band, for `TlsPinger` on mail, directory and database
+//! ports that don't speak TLS from the first byte.
+//!
+//! Each dialogue reads only as far as the server's go-ahead: the
+//! server says nothing more until our ClientHello, so the stream is
+//! handed to rustls with no plaintext left unread.
+
+use std::fmt;
+use std::io::{self, Result};
+use std::str::FromStr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use x509_parser::der_parser::asn1_rs::{Any, Class, FromDer};
+
+/// Longest plaintext reply we'll buffer before giving up on it.
+const MAX_REPLY: usize = 64 * 1024;
+
+/// LDAPMessage { messageID 1, ExtendedRequest { requestName
+/// 1.3.6.1.4.1.1466.20037 } } — RFC 4511 §4.14.1.
+const LDAP_STARTTLS: &[u8] = b"\x30\x1d\x02\x01\x01\x77\x18\x80\x161.3.6.1.4.1.1466.20037";
+
+/// Postgres SSLRequest: length 8, request code 80877103.
+const POSTGRES_SSL_REQUEST: &[u8] = &[0x00, 0x00, 0x00, 0x08, 0x04, 0xd2, 0x16, 0x2f];
+
+/// The in-band upgrade dialogue `TlsPinger::with_starttls` runs
+/// before the handshake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartTls {
+    /// SMTP (RFC 3207): greeting, `EHLO`, `STARTTLS`.
+    Smtp,
+    /// IMAP (RFC 3501): greeting, `a STARTTLS`.
+    Imap,
+    /// POP3 (RFC 2595): greeting, `STLS`.
+    Pop3,
+    /// FTP (RFC 4217): greeting, `AUTH TLS`.
+    Ftp,
+    /// XMPP client-to-server (RFC 6120): stream header, `<starttls/>`.
+    Xmpp,
+    /// LDAP (RFC 4511): the StartTLS extended operation.
+    Ldap,
+    /// PostgreSQL: an SSLRequest packet.
+    Postgres,
+}
+
+impl StartTls {
+    /// The port the protocol usually upgrades on — submission for
+    /// SMTP, client-to-server for XMPP.
+    pub fn default_port(self) -> u16 {
+        match self {
+            StartTls::Smtp => 587,
+            StartTls::Imap => 143,
+            StartTls::Pop3 => 110,
+            StartTls::Ftp => 21,
+            StartTls::Xmpp => 5222,
+            StartTls::Ldap => 389,
+            StartTls::Postgres => 5432,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            StartTls::Smtp => "smtp",
+            StartTls::Imap => "imap",
+            StartTls::Pop3 => "pop3",
+            StartTls::Ftp => "ftp",
+            StartTls::Xmpp => "xmpp",
+            StartTls::Ldap => "ldap",
+            StartTls::Postgres => "postgres",
+        }
+    }
+
+    /// Run the dialogue on `stream`, leaving it ready for the
+    /// ClientHello. `host` names the XMPP domain.
+    pub(crate) async fn negotiate<S>(self, stream: &mut S, host: &str) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        match self {
+            StartTls::Smtp => {
+                expect_code(stream, "SMTP", "220").await?;
+                stream.write_all(b"EHLO knockknock\r\n").await?;
+                let ehlo = expect_code(stream, "SMTP", "250").await?;
+                let offered = ehlo.lines().any(|line| {
+                    line.get(4..)
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("STARTTLS"))
+                });
+                if !offered {
+                    return Err(refused("SMTP", "STARTTLS not advertised"));
+                }
+                stream.write_all(b"STARTTLS\r\n").await?;
+                expect_code(stream, "SMTP", "220").await?;
+            }
+            StartTls::Imap => {
+                let greeting = read_reply(stream, |r| r.ends_with(b"\n")).await?;
+                if !greeting.starts_with("* OK") {
+                    return Err(refused("IMAP", &greeting));
+                }
+                stream.write_all(b"a STARTTLS\r\n").await?;
+                let reply = read_reply(stream, |r| tagged_line(r).is_some()).await?;
+                let status = tagged_line(reply.as_bytes()).unwrap_or_default();
+                if !status.starts_with("a OK") {
+                    return Err(refused("IMAP", status));
+                }
+            }
+            StartTls::Pop3 => {
+                expect_ok(stream).await?;
+                stream.write_all(b"STLS\r\n").await?;
+                expect_ok(stream).await?;
+            }
+            StartTls::Ftp => {
+                expect_code(stream, "FTP", "220").await?;
+                stream.write_all(b"AUTH TLS\r\n").await?;
+                expect_code(stream, "FTP", "234").await?;
+            }
+            StartTls::Xmpp => {
+                let header = format!(
+                    "<?xml version='1.0'?><stream:stream to='{host}' version='1.0' \
+                     xmlns='jabber:client' xmlns:stream='http://etherx.jabber.org/streams'>"
+                );
+                stream.write_all(header.as_bytes()).await?;
+                let features = read_reply(stream, |r| {
+                    contains(r, b"</stream:features>") || contains(r, b"</stream:stream>")
+                })
+                .await?;
+                if !features.contains("<starttls") {
+                    return Err(refused("XMPP", "STARTTLS not advertised"));
+                }
+                stream
+                    .write_all(b"<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")
+                    .await?;
+                let answer = read_reply(stream, |r| {
+                    contains(r, b"<proceed") || contains(r, b"<failure")
+                })
+                .await?;
+                if !answer.contains("<proceed") {
+                    return Err(refused("XMPP", &answer));
+                }
+            }
+            StartTls::Ldap => {
+                stream.write_all(LDAP_STARTTLS).await?;
+                let mut reply = Vec::new();
+                let code = loop {
+                    read_more(stream, &mut reply).await?;
+                    if let Some(code) = ldap_result_code(&reply)? {
+                        break code;
+                    }
+                };
+                if code != 0 {
+                    return Err(refused("LDAP", &format!("resultCode {code}")));
+                }
+            }
+            StartTls::Postgres => {
+                stream.write_all(POSTGRES_SSL_REQUEST).await?;
+                let answer = stream.read_u8().await?;
+                if answer != b'S' {
+                    return Err(refused("Postgres", "SSLRequest declined"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StartTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StartTls {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [
+            StartTls::Smtp,
+            StartTls::Imap,
+            StartTls::Pop3,
+            StartTls::Ftp,
+            StartTls::Xmpp,
+            StartTls::Ldap,
+            StartTls::Postgres,
+        ]
+        .into_iter()
+        .find(|p| p.name().eq_ignore_ascii_case(s))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown STARTTLS protocol {s:?}"),
+            )
+        })
+    }
+}
+
+/// The server answered, but not with a go-ahead.
+fn refused(protocol: &str, reply: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{protocol} server refused STARTTLS: {}", reply.trim()),
+    )
+}
+
+/// Read an SMTP / FTP style reply (continuation lines `NNN-`, last
+/// line `NNN `) and check its code.
+async fn expect_code(
+    stream: &mut (impl AsyncRead + Unpin),
+    protocol: &str,
+    code: &str,
+) -> Result<String> {
+    let reply = read_reply(stream, |r| {
+        r.ends_with(b"\n") && last_line(r).get(3).is_none_or(|b| *b != b'-')
+    })
+    .await?;
+    let last = reply.trim_end().lines().last().unwrap_or_default();
+    if !last.starts_with(code) {
+        return Err(refused(protocol, last));
+    }
+    Ok(reply)
+}
+
+/// Read a one-line POP3 reply and check it's `+OK`.
+async fn expect_ok(stream: &mut (impl AsyncRead + Unpin)) -> Result<()> {
+    let reply = read_reply(stream, |r| r.ends_with(b"\n")).await?;
+    if !reply.starts_with("+OK") {
+        return Err(refused("POP3", &reply));
+    }
+    Ok(())
+}
+
+/// Read until `complete` accepts what has arrived.
+async fn read_reply(
+    stream: &mut (impl AsyncRead + Unpin),
+    complete: impl Fn(&[u8]) -> bool,
+) -> Result<String> {
+    let mut reply = Vec::new();
+    while !complete(&reply) {
+        read_more(stream, &mut reply).await?;
+    }
+    Ok(String::from_utf8_lossy(&reply).into_owned())
+}
+
+/// Append the next read to `reply`; the server hanging up or
+/// rambling past `MAX_REPLY` is an error.
+async fn read_more(stream: &mut (impl AsyncRead + Unpin), reply: &mut Vec<u8>) -> Result<()> {
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    if n == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "server closed the connection before TLS",
+        ));
+    }
+    reply.extend_from_slice(&buf[..n]);
+    if reply.len() > MAX_REPLY {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "plaintext reply too long",
+        ));
+    }
+    Ok(())
+}
+
+/// The last complete line of `reply`.
+fn last_line(reply: &[u8]) -> &[u8] {
+    let body = reply.strip_suffix(b"\n").unwrap_or(reply);
+    match body.iter().rposition(|b| *b == b'\n') {
+        Some(i) => &body[i + 1..],
+        None => body,
+    }
+}
+
+/// The IMAP line tagged `a`, once it has arrived in full.
+fn tagged_line(reply: &[u8]) -> Option<&str> {
+    let text = std::str::from_utf8(reply).ok()?;
+    text.split_inclusive('\n')
+        .find(|line| line.starts_with("a ") && line.ends_with('\n'))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// The resultCode of an LDAP ExtendedResponse, or `None` while the
+/// message is still incomplete.
+fn ldap_result_code(reply: &[u8]) -> Result<Option<u32>> {
+    let malformed = |what: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("malformed LDAP response: {what}"),
+        )
+    };
+    let message = match Any::from_der(reply) {
+        Ok((_, message)) => message,
+        Err(x509_parser::nom::Err::Incomplete(_)) => return Ok(None),
+        Err(e) => return Err(malformed(&e.to_string())),
+    };
+    let (rest, _id) = Any::from_der(message.data).map_err(|e| malformed(&e.to_string()))?;
+    let (_, op) = Any::from_der(rest).map_err(|e| malformed(&e.to_string()))?;
+    if op.class() != Class::Application || op.tag().0 != 24 {
+        return Err(malformed("not an ExtendedResponse"));
+    }
+    let (_, result) = Any::from_der(op.data).map_err(|e| malformed(&e.to_string()))?;
+    let code = result
+        .data
+        .iter()
+        .fold(0u32, |acc, b| (acc << 8) | u32::from(*b));
+    Ok(Some(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiline_replies_end_on_a_space() {
+        assert_eq!(last_line(b"250-a\r\n250 STARTTLS\r\n"), b"250 STARTTLS\r");
+        assert_eq!(last_line(b"220-a\r\n"), b"220-a\r");
+    }
+
+    #[test]
+    fn ldap_success_and_partial_replies() {
+        let success = [
+            0x30, 0x0c, 0x02, 0x01, 0x01, 0x78, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+        ];
+        assert_eq!(ldap_result_code(&success).unwrap(), Some(0));
+        assert_eq!(ldap_result_code(&success[..6]).unwrap(), None);
+        let mut unavailable = success;
+        unavailable[9] = 52;
+        assert_eq!(ldap_result_code(&unavailable).unwrap(), Some(52));
+    }
+
+    #[test]
+    fn protocol_names_round_trip() {
+        for name in ["smtp", "imap", "pop3", "ftp", "xmpp", "ldap", "postgres"] {
+            assert_eq!(name.parse::<StartTls>().unwrap().to_string(), name);
+        }
+        assert!("gopher".parse::<StartTls>().is_err());
+    }
+}
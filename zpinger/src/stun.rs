@@ -22,6 +22,8 @@ use async_trait::async_trait;
 #[cfg(feature = "stun")]
 use tokio::net::UdpSocket;
 
+#[cfg(feature = "stun")]
+use crate::observe;
 #[cfg(feature = "stun")]
 use crate::pinger::Pinger;
 use crate::uri::get_uri;
@@ -80,10 +82,12 @@ impl Pinger for StunPinger {
         with_timeout(self.timeout, async move {
             let socket = UdpSocket::bind("0.0.0.0:0").await?;
             socket.connect(&endpoint).await?;
-            socket.send(&request).await?;
+            observe::udp_connected(&socket);
+            observe::sent(socket.send(&request).await?);
 
             let mut buf = [0u8; BUF_SIZE];
             let n = socket.recv(&mut buf).await?;
+            observe::received(n);
             validate_binding_response(&buf[..n], &txid)
         })
         .await
@@ -59,3 +59,80 @@ async fn test_timed_via_dyn_trait() {
     let elapsed = timed(p.as_ref()).await.unwrap();
     assert!(elapsed < Duration::from_millis(100));
 }
+
+#[tokio::test]
+async fn test_default_probe_reports_nothing_extra() {
+    assert_eq!(OkPinger.probe().await.unwrap(), Report::default());
+    assert!(ErrPinger.probe().await.is_err());
+}
+
+#[tokio::test]
+async fn test_timed_probe_keeps_report() {
+    struct DetailPinger;
+    #[async_trait]
+    impl Pinger for DetailPinger {
+        async fn ping(&self) -> Result<()> {
+            Ok(())
+        }
+        async fn probe(&self) -> Result<Report> {
+            Ok(Report::new().with_detail("banner", "hi"))
+        }
+    }
+    let (_, report) = timed_probe(&DetailPinger).await.unwrap();
+    assert_eq!(report.detail("banner"), Some("hi"));
+    assert_eq!(report.detail("missing"), None);
+}
+
+/// Answers 404 on the wire: `exchange` reports it, `probe` applies
+/// the built-in rule and rejects it.
+struct NotFoundPinger;
+#[async_trait]
+impl Pinger for NotFoundPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+    async fn probe(&self) -> Result<Report> {
+        Err(Error::new(ErrorKind::NotFound, "404"))
+    }
+    async fn exchange(&self) -> Result<Report> {
+        Ok(Report {
+            status: Some(404),
+            ..Report::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_default_exchange_is_probe() {
+    assert_eq!(OkPinger.exchange().await.unwrap(), Report::default());
+    assert!(ErrPinger.exchange().await.is_err());
+}
+
+#[tokio::test]
+async fn test_expect_status_uses_exchange() {
+    let p = Expect::new(NotFoundPinger).with_expectation("status=404".parse().unwrap());
+    assert_eq!(p.probe().await.unwrap().status, Some(404));
+
+    let p = Expect::new(NotFoundPinger).with_expectation("latency=1s".parse().unwrap());
+    assert_eq!(p.ping().await.unwrap_err().kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_expect_latency_fails_as_assertion() {
+    let p = Expect::new(SleepPinger {
+        duration: Duration::from_millis(20),
+    })
+    .with_expectation(Expectation::MaxLatency(Duration::from_millis(1)));
+    let err = p.ping().await.unwrap_err();
+    let assertion = AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(assertion.failures.len(), 1);
+    assert!(assertion.failures[0].starts_with("took "));
+}
+
+#[tokio::test]
+async fn test_expect_passes_transport_errors_through() {
+    let p = Expect::new(ErrPinger).with_expectation("status=200".parse().unwrap());
+    let err = p.ping().await.unwrap_err();
+    assert!(AssertionError::from_io(&err).is_none());
+    assert_eq!(err.to_string(), "Test fail");
+}
@@ -3,27 +3,51 @@
 //! then closes. Reports success when the rustls handshake completes;
 //! certificate validation errors propagate as protocol errors.
 //!
+//! `probe` also parses the chain the server presented and reports it
+//! on `Report::certificates`, with optional warning / critical
+//! thresholds on days until expiry, and puts what the handshake
+//! negotiated (version, cipher suite, key-exchange group, ALPN,
+//! resumption) in `details`. `with_resumption` adds a second,
+//! resumed handshake and compares the two; `with_stapling_checks`
+//! reports the stapled OCSP response and SCT count (`crate::staple`).
+//! `with_starttls` runs a protocol's plaintext upgrade dialogue
+//! (`crate::starttls`) before the handshake and times it separately.
+//! A failed handshake comes back as a `TlsFailure` naming the cause,
+//! with the chain the server presented (`crate::diagnose`).
+//!
 //! Reuses the shared rustls + webpki-roots stack from `crate::tls` —
 //! same default trust store as the other TLS-aware pingers
 //! (`HttpPinger`, `WebSocketPinger`, etc.).
 
 use std::io::{self, Result};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_trait::async_trait;
 use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
-use tokio::net::TcpStream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_rustls::TlsConnector;
 
+use crate::cert::{format_utc, CertInfo};
+use crate::diagnose::TlsFailure;
+use crate::expect::AssertionError;
+use crate::observe::{self, TlsInfo};
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::resume::{self, Handshake, ResumptionMode};
+use crate::staple;
+use crate::starttls::StartTls;
+use crate::tls::{recording_client_config, TlsOptions};
+use crate::tls_verify::{Presented, Verdict};
 use crate::uri::get_uri;
-use crate::util::with_timeout;
+use crate::util::{format_ms, with_timeout};
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_PORT: u16 = 443;
+/// How long a resumption probe waits for the first connection's TLS
+/// 1.3 session tickets.
+const TICKET_WAIT: Duration = Duration::from_secs(1);
 
 /// TLS handshake pinger — measures the time to complete a TLS
 /// handshake against `target`. The handshake covers TCP connect +
@@ -33,11 +57,23 @@ const DEFAULT_PORT: u16 = 443;
 ///
 /// Accepts a `host:port`, a `host` (default port 443), or an
 /// `https://host[:port]/...` URL — the URI parser is the same one
-/// `HttpPinger` uses, so any of those work.
+/// `HttpPinger` uses, so any of those work. With `with_starttls`
+/// the default port is the protocol's instead.
+///
+/// Expiry thresholds apply to whichever certificate in the chain
+/// expires first: below `expiry_critical` days the probe fails with
+/// an `AssertionError`, below `expiry_warning` it succeeds with a
+/// `warning` detail.
 pub struct TlsPinger {
     pub target: String,
     pub timeout: Duration,
+    pub expiry_warning: Option<u32>,
+    pub expiry_critical: Option<u32>,
+    pub resumption: ResumptionMode,
+    pub stapling_checks: bool,
+    pub starttls: Option<StartTls>,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl TlsPinger {
@@ -45,7 +81,13 @@ impl TlsPinger {
         Self {
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
+            expiry_warning: None,
+            expiry_critical: None,
+            resumption: ResumptionMode::Off,
+            stapling_checks: false,
+            starttls: None,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -54,15 +96,87 @@ impl TlsPinger {
         self
     }
 
+    /// Add a `warning` detail when a certificate in the chain has
+    /// fewer than `days` days left.
+    pub fn with_expiry_warning(mut self, days: u32) -> Self {
+        self.expiry_warning = Some(days);
+        self
+    }
+
+    /// Fail the probe when a certificate in the chain has fewer than
+    /// `days` days left.
+    pub fn with_expiry_critical(mut self, days: u32) -> Self {
+        self.expiry_critical = Some(days);
+        self
+    }
+
+    /// Follow the handshake with a second one resuming its session,
+    /// reporting `full_ms` / `resumed_ms` (handshake only, TCP
+    /// connect excluded), `resumption` and, for
+    /// `ResumptionMode::EarlyData`, whether the server accepted 0-RTT.
+    /// Certificates and negotiated parameters describe the full
+    /// handshake.
+    pub fn with_resumption(mut self, mode: ResumptionMode) -> Self {
+        self.resumption = mode;
+        self
+    }
+
+    /// Report the OCSP response the server stapled — `ocsp` (`good`,
+    /// `revoked`, `unknown`, `none`), `ocsp_this_update`,
+    /// `ocsp_next_update`, `ocsp_responder` — and `scts`, the SCTs
+    /// embedded in the leaf or carried by the staple. A missing,
+    /// stale or non-`good` staple adds a `warning`. With
+    /// `with_tls_config` and no trust settings in `TlsOptions` the
+    /// staple can't be captured and `ocsp` reads `unavailable`.
+    pub fn with_stapling_checks(mut self, enabled: bool) -> Self {
+        self.stapling_checks = enabled;
+        self
+    }
+
+    /// Upgrade a plaintext connection in-band — SMTP `STARTTLS`,
+    /// IMAP `a STARTTLS`, POP3 `STLS`, FTP `AUTH TLS`, XMPP
+    /// `<starttls/>`, the LDAP StartTLS extended operation or a
+    /// Postgres SSLRequest — before the handshake. Adds `starttls`
+    /// (the protocol), `starttls_ms` (greeting through go-ahead) and
+    /// `handshake_ms` (the TLS handshake alone); a server that
+    /// doesn't offer the upgrade fails the probe.
+    pub fn with_starttls(mut self, protocol: StartTls) -> Self {
+        self.starttls = Some(protocol);
+        self
+    }
+
     pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
         self.tls_config = Some(config);
         self
     }
+
+    /// Shared TLS settings for the handshake being timed, including
+    /// the second one `with_resumption` makes.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
 }
 
-#[async_trait]
-impl Pinger for TlsPinger {
-    async fn ping(&self) -> Result<()> {
+/// What `TlsPinger::handshake` brings back.
+struct Session {
+    /// What the (first) handshake negotiated, chain included.
+    info: TlsInfo,
+    /// The full and resumed handshakes, when resuming.
+    handshakes: Option<[Handshake; 2]>,
+    /// The insecure-mode verification outcome.
+    verdict: Verdict,
+    /// Where the verifier kept the chain and stapled OCSP response,
+    /// when it could be wrapped.
+    presented: Option<Presented>,
+    /// The (first) connection's plaintext negotiation and TLS
+    /// handshake times, when upgrading with STARTTLS.
+    upgrade: Option<(Duration, Duration)>,
+}
+
+impl TlsPinger {
+    /// Run the handshake, twice when resuming.
+    async fn handshake(&self) -> Result<Session> {
         let uri = get_uri(&self.target);
         if uri.domain.is_empty() {
             return Err(io::Error::new(
@@ -73,27 +187,207 @@ impl Pinger for TlsPinger {
         let port = if uri.port > 0 {
             uri.port as u16
         } else {
-            DEFAULT_PORT
+            self.starttls.map_or(DEFAULT_PORT, StartTls::default_port)
         };
         let endpoint = format!("{}:{port}", uri.domain);
-        let server_name = ServerName::try_from(uri.domain.clone())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-        let config = self
-            .tls_config
-            .clone()
-            .unwrap_or_else(default_client_config);
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict, presented) =
+            recording_client_config(&self.tls_config, &self.tls_options);
 
+        let mode = self.resumption;
+        let upgrade = self
+            .starttls
+            .map(|protocol| (protocol, uri.domain.as_str()));
+        let recorder = presented.clone();
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
-            let connector = TlsConnector::from(config);
-            // Driving `connect` to completion brings us through
-            // ClientHello → ServerHello → Certificate → Finished.
-            // rustls validates the cert chain against the configured
-            // trust anchors as part of this future; bad chains turn
-            // into io::Error here.
-            let _stream = connector.connect(server_name, tcp).await?;
-            Ok(())
+            let once = Once {
+                endpoint: &endpoint,
+                upgrade,
+                presented: recorder.as_ref(),
+            };
+            if mode == ResumptionMode::Off {
+                let connector = TlsConnector::from(config);
+                let (info, full, negotiation) =
+                    once.connect(server_name, &connector, false).await?;
+                return Ok((info, None, full, negotiation));
+            }
+            let connector = mode.connector(mode.prepare(&config));
+            let (info, full, negotiation) =
+                once.connect(server_name.clone(), &connector, true).await?;
+            let (_, resumed, _) = once.connect(server_name, &connector, false).await?;
+            Ok((info, Some([full, resumed]), full, negotiation))
         })
         .await
+        .map(|(info, handshakes, full, negotiation)| Session {
+            info,
+            handshakes,
+            verdict,
+            presented,
+            upgrade: negotiation.map(|n| (n, full.elapsed)),
+        })
     }
 }
+
+/// What every connection of one probe shares.
+struct Once<'a> {
+    endpoint: &'a str,
+    /// The STARTTLS dialogue to run first, and the host it names.
+    upgrade: Option<(StartTls, &'a str)>,
+    /// Where the verifier records the presented chain, for
+    /// classifying failures.
+    presented: Option<&'a Presented>,
+}
+
+impl Once<'_> {
+    /// One TCP connect + TLS handshake, after the STARTTLS dialogue
+    /// (timed) if there is one. `keep_tickets` waits for the session
+    /// tickets a TLS 1.3 server sends after the handshake. Handshake
+    /// errors come back as a `TlsFailure`.
+    async fn connect(
+        &self,
+        server_name: ServerName<'static>,
+        connector: &TlsConnector,
+        keep_tickets: bool,
+    ) -> Result<(TlsInfo, Handshake, Option<Duration>)> {
+        let mut tcp = observe::connect_tcp(self.endpoint).await?;
+        let mut negotiation = None;
+        if let Some((protocol, host)) = self.upgrade {
+            let start = Instant::now();
+            protocol.negotiate(&mut tcp, host).await?;
+            negotiation = Some(start.elapsed());
+        }
+        let failed = |err| {
+            let chain = self.presented.map(Presented::chain).unwrap_or_default();
+            TlsFailure::classify(err, &chain)
+        };
+        let start = Instant::now();
+        // Driving `connect` to completion brings us through
+        // ClientHello → ServerHello → Certificate → Finished. rustls
+        // validates the cert chain against the configured trust
+        // anchors as part of this future; bad chains turn into
+        // io::Error here.
+        let mut stream = connector.connect(server_name, tcp).await.map_err(failed)?;
+        // With 0-RTT on offer `connect` returns early; flushing
+        // finishes the handshake. Otherwise this is a no-op.
+        stream.flush().await.map_err(failed)?;
+        let handshake = Handshake::of(start.elapsed(), stream.get_ref().1);
+        let info = observe::tls_handshake(stream.get_ref().1);
+        if keep_tickets {
+            // TLS 1.3 servers send session tickets after the
+            // handshake, and rustls only stores them once they're
+            // read. Errors don't matter here — only the tickets do.
+            let _ = stream.shutdown().await;
+            let mut rest = Vec::new();
+            let _ = with_timeout(TICKET_WAIT, stream.read_to_end(&mut rest)).await;
+        }
+        Ok((info, handshake, negotiation))
+    }
+}
+
+#[async_trait]
+impl Pinger for TlsPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let Session {
+            info,
+            handshakes,
+            verdict,
+            presented,
+            upgrade,
+        } = self.handshake().await?;
+        // rustls has already accepted the chain; parsing is only for
+        // the report, so a certificate x509-parser can't read is noted
+        // rather than failing the ping.
+        let parsed: Vec<_> = info
+            .peer_certificates
+            .iter()
+            .map(|der| CertInfo::from_der(der))
+            .collect();
+        let mut report = negotiated(verdict.annotate(Report::new()), &info);
+        for (i, cert) in parsed.iter().enumerate() {
+            if let Err(e) = cert {
+                report = report.with_detail(format!("cert[{i}]"), format!("unparsable: {e}"));
+            }
+        }
+        let leaf_parsed = matches!(parsed.first(), Some(Ok(_)));
+        let certificates: Vec<_> = parsed.into_iter().filter_map(Result::ok).collect();
+        if let (Some(protocol), Some((negotiation, handshake))) = (self.starttls, upgrade) {
+            report = report
+                .with_detail("starttls", protocol.to_string())
+                .with_detail("starttls_ms", format_ms(negotiation))
+                .with_detail("handshake_ms", format_ms(handshake));
+        }
+        if let Some([full, resumed]) = handshakes {
+            report = resume::annotate(report, self.resumption, full, resumed);
+        }
+        if let Some(leaf) = certificates.first().filter(|_| leaf_parsed) {
+            report = report
+                .with_detail("subject", &leaf.subject)
+                .with_detail("issuer", &leaf.issuer)
+                .with_detail("not_after", format_utc(leaf.not_after))
+                .with_detail("days_left", leaf.days_until_expiry().to_string());
+        }
+        // An intermediate that lapses first breaks the chain just the
+        // same, so thresholds go by the soonest expiry.
+        if let Some(soonest) = certificates.iter().min_by_key(|c| c.not_after) {
+            let days = soonest.days_until_expiry();
+            let below = |threshold: Option<u32>| threshold.filter(|t| days < i64::from(*t));
+            if let Some(threshold) = below(self.expiry_critical) {
+                return Err(AssertionError {
+                    failures: vec![expiry_message(soonest, days, "critical", threshold)],
+                }
+                .into());
+            }
+            if let Some(threshold) = below(self.expiry_warning) {
+                report = report.with_detail(
+                    "warning",
+                    expiry_message(soonest, days, "warning", threshold),
+                );
+            }
+        }
+        if self.stapling_checks {
+            let leaf = info.peer_certificates.first().map(|der| der.as_ref());
+            let staple = presented.map(|p| p.take_staple());
+            report = staple::annotate(report, staple, leaf, SystemTime::now());
+        }
+        report.certificates = certificates;
+        Ok(report)
+    }
+}
+
+/// `report` plus the negotiated parameters `info` knows.
+fn negotiated(mut report: Report, info: &TlsInfo) -> Report {
+    let fields = [
+        ("version", info.version.clone()),
+        ("cipher_suite", info.cipher_suite.clone()),
+        ("key_exchange_group", info.key_exchange_group.clone()),
+        (
+            "alpn",
+            info.alpn
+                .as_deref()
+                .map(|p| String::from_utf8_lossy(p).into_owned()),
+        ),
+        ("resumed", info.resumed.map(|r| r.to_string())),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            report = report.with_detail(key, value);
+        }
+    }
+    report
+}
+
+fn expiry_message(cert: &CertInfo, days: i64, level: &str, threshold: u32) -> String {
+    let when = if days < 0 {
+        format!("expired {} days ago", -days)
+    } else {
+        format!("expires in {days} days")
+    };
+    format!(
+        "certificate {} {when} ({level} threshold {threshold} days)",
+        cert.subject
+    )
+}
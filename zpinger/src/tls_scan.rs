@@ -0,0 +1,367 @@
+//! TLS scan — repeated handshakes against one endpoint, each
+//! constrained to one protocol version, cipher-suite set or
+//! key-exchange group, to map what the server accepts.
+//!
+//! TLS 1.2 / 1.3 handshakes go through rustls with a `CryptoProvider`
+//! narrowed to the candidate. rustls can't speak TLS 1.0 / 1.1, so
+//! those are probed with a hand-built ClientHello: a ServerHello
+//! echoing the offered version means accepted, anything else
+//! (alert, other version, hang-up) refused. Certificates aren't
+//! judged — a scan maps protocol support, `TlsPinger` checks trust.
+
+use std::io::{self, Result};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rustls::crypto::{ring, CryptoProvider, SupportedKxGroup};
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, SupportedCipherSuite, SupportedProtocolVersion};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_rustls::TlsConnector;
+
+use crate::observe;
+use crate::pinger::Pinger;
+use crate::report::Report;
+use crate::starttls::StartTls;
+use crate::tls::TlsOptions;
+use crate::uri::get_uri;
+use crate::util::with_timeout;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_PORT: u16 = 443;
+
+/// Suites offered in the legacy ClientHello: what TLS 1.0 / 1.1
+/// servers commonly pick from (ECDHE and RSA key exchange, AES-CBC,
+/// 3DES).
+const LEGACY_SUITES: &[u16] = &[0xc014, 0xc013, 0xc00a, 0xc009, 0x0035, 0x002f, 0x000a];
+
+/// TLS scanner — maps which protocol versions, cipher suites and
+/// key-exchange groups `target` accepts, and whether it picks suites
+/// by its own preference or the client's. Every probe is a fresh
+/// connection; `timeout` applies to each one.
+///
+/// `probe` puts the result in `details`:
+///
+/// - `tls1_0` … `tls1_3`: `accepted` / `refused`
+/// - `tls1_2_suites`, `tls1_3_suites`: the accepted suites, in the
+///   order the server prefers them (by repeatedly offering all but
+///   the ones already picked)
+/// - `tls1_2_order`, `tls1_3_order`: `server` when the server's
+///   preference wins over the client's, `client` when it follows the
+///   client's (needs two accepted suites)
+/// - `groups`: the accepted key-exchange groups
+/// - `warning`: one per accepted TLS 1.0 / 1.1
+///
+/// The suites and groups tried are the ones rustls' ring provider
+/// implements, so weak suites show up only as absent; the legacy
+/// versions are what proves they're refused.
+pub struct TlsScanPinger {
+    pub target: String,
+    pub timeout: Duration,
+    pub starttls: Option<StartTls>,
+    tls_options: TlsOptions,
+}
+
+impl TlsScanPinger {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            timeout: DEFAULT_TIMEOUT,
+            starttls: None,
+            tls_options: TlsOptions::default(),
+        }
+    }
+
+    /// Time limit for each connection of the scan.
+    pub fn with_timeout(mut self, t: Duration) -> Self {
+        self.timeout = t;
+        self
+    }
+
+    /// Run `protocol`'s STARTTLS dialogue before every handshake.
+    pub fn with_starttls(mut self, protocol: StartTls) -> Self {
+        self.starttls = Some(protocol);
+        self
+    }
+
+    /// Client certificate, SNI and ALPN settings. Trust settings are
+    /// ignored: certificates aren't verified during a scan.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+}
+
+/// One scan's fixed inputs.
+struct Scan {
+    endpoint: String,
+    host: String,
+    server_name: ServerName<'static>,
+    starttls: Option<StartTls>,
+    options: TlsOptions,
+    timeout: Duration,
+}
+
+impl Scan {
+    /// TCP connect plus any STARTTLS dialogue. Failures here mean the
+    /// endpoint is unreachable, not that a candidate was refused.
+    async fn connect(&self) -> Result<impl AsyncRead + AsyncWrite + Unpin> {
+        with_timeout(self.timeout, async {
+            let mut tcp = observe::connect_tcp(&self.endpoint).await?;
+            if let Some(protocol) = self.starttls {
+                protocol.negotiate(&mut tcp, &self.host).await?;
+            }
+            Ok(tcp)
+        })
+        .await
+    }
+
+    /// Handshake offering only `suites` and `groups` at `version`;
+    /// the suite the server picked, or `None` if it refused.
+    async fn handshake(
+        &self,
+        version: &'static SupportedProtocolVersion,
+        suites: &[SupportedCipherSuite],
+        groups: &[&'static dyn SupportedKxGroup],
+    ) -> Result<Option<SupportedCipherSuite>> {
+        let provider = CryptoProvider {
+            cipher_suites: suites.to_vec(),
+            kx_groups: groups.to_vec(),
+            ..ring::default_provider()
+        };
+        let mut config = ClientConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(&[version])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+            .with_root_certificates(rustls::RootCertStore::empty())
+            .with_no_client_auth();
+        self.options.configure(&mut config);
+
+        let tcp = self.connect().await?;
+        let connector = TlsConnector::from(Arc::new(config));
+        let handshake = connector.connect(self.server_name.clone(), tcp);
+        Ok(match with_timeout(self.timeout, handshake).await {
+            Ok(stream) => stream.get_ref().1.negotiated_cipher_suite(),
+            Err(_) => None,
+        })
+    }
+
+    /// Whether the server answers a TLS 1.0 (`0x0301`) or 1.1
+    /// (`0x0302`) ClientHello with a ServerHello at that version.
+    async fn legacy(&self, version: u16) -> Result<bool> {
+        let mut stream = self.connect().await?;
+        let sni = (!self.options.disable_sni)
+            .then(|| self.options.sni_host(&self.host))
+            .filter(|name| name.parse::<IpAddr>().is_err());
+        let hello = legacy_client_hello(version, sni);
+        if stream.write_all(&hello).await.is_err() {
+            return Ok(false);
+        }
+        let reply = with_timeout(self.timeout, async {
+            let mut header = [0u8; 5];
+            stream.read_exact(&mut header).await?;
+            // Handshake record → ServerHello (type 2), whose body
+            // starts with the chosen version.
+            let mut hello = [0u8; 6];
+            if header[0] == 0x16 {
+                stream.read_exact(&mut hello).await?;
+            }
+            Ok(hello)
+        })
+        .await;
+        Ok(matches!(reply, Ok(hello) if hello[0] == 0x02 && hello[4..6] == version.to_be_bytes()))
+    }
+}
+
+/// A minimal ClientHello at `version`: zero random, no session,
+/// `LEGACY_SUITES`, null compression, and SNI / supported groups /
+/// point formats / renegotiation-info extensions. The handshake is
+/// abandoned after the ServerHello, so nothing else is needed.
+fn legacy_client_hello(version: u16, sni: Option<&str>) -> Vec<u8> {
+    fn extension(out: &mut Vec<u8>, kind: u16, body: &[u8]) {
+        out.extend_from_slice(&kind.to_be_bytes());
+        out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        out.extend_from_slice(body);
+    }
+
+    let mut extensions = Vec::new();
+    if let Some(name) = sni {
+        let name = name.as_bytes();
+        let mut list = vec![0x00];
+        list.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        list.extend_from_slice(name);
+        let mut body = (list.len() as u16).to_be_bytes().to_vec();
+        body.extend_from_slice(&list);
+        extension(&mut extensions, 0x0000, &body);
+    }
+    // x25519, secp256r1, secp384r1
+    extension(
+        &mut extensions,
+        0x000a,
+        &[0x00, 0x06, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18],
+    );
+    extension(&mut extensions, 0x000b, &[0x01, 0x00]);
+    extension(&mut extensions, 0xff01, &[0x00]);
+
+    let mut hello = version.to_be_bytes().to_vec();
+    hello.extend_from_slice(&[0; 32]);
+    hello.push(0);
+    hello.extend_from_slice(&((LEGACY_SUITES.len() * 2) as u16).to_be_bytes());
+    for suite in LEGACY_SUITES {
+        hello.extend_from_slice(&suite.to_be_bytes());
+    }
+    hello.extend_from_slice(&[0x01, 0x00]);
+    hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    hello.extend_from_slice(&extensions);
+
+    let mut handshake = vec![0x01];
+    handshake.extend_from_slice(&(hello.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&hello);
+
+    let mut record = vec![0x16, 0x03, 0x01];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend_from_slice(&handshake);
+    record
+}
+
+/// The provider's suites for `version`, in its default order.
+fn suites_for(
+    provider: &CryptoProvider,
+    version: &SupportedProtocolVersion,
+) -> Vec<SupportedCipherSuite> {
+    provider
+        .cipher_suites
+        .iter()
+        .copied()
+        .filter(|s| s.version().version == version.version)
+        .collect()
+}
+
+#[async_trait]
+impl Pinger for TlsScanPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let uri = get_uri(&self.target);
+        if uri.domain.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TLS target is missing a host",
+            ));
+        }
+        let port = if uri.port > 0 {
+            uri.port as u16
+        } else {
+            self.starttls.map_or(DEFAULT_PORT, StartTls::default_port)
+        };
+        let scan = Scan {
+            endpoint: format!("{}:{port}", uri.domain),
+            server_name: self.tls_options.server_name(&uri.domain)?,
+            host: uri.domain,
+            starttls: self.starttls,
+            options: self.tls_options.clone().with_insecure(true),
+            timeout: self.timeout,
+        };
+        let provider = ring::default_provider();
+        let groups = provider.kx_groups.clone();
+        let mut report = Report::new();
+        let mut warnings = Vec::new();
+
+        for (key, name, version) in [("tls1_0", "TLS 1.0", 0x0301), ("tls1_1", "TLS 1.1", 0x0302)] {
+            let accepted = scan.legacy(version).await?;
+            if accepted {
+                warnings.push(format!("{name} accepted"));
+            }
+            report = report.with_detail(key, verdict(accepted));
+        }
+
+        let versions = [
+            ("tls1_2", &rustls::version::TLS12),
+            ("tls1_3", &rustls::version::TLS13),
+        ];
+        let mut accepted_versions = Vec::new();
+        let mut preferences = Vec::new();
+        for (key, version) in versions {
+            // Offer everything not yet picked: the picks come out in
+            // the server's order (or the client's, see below).
+            let mut remaining = suites_for(&provider, version);
+            let mut picked = Vec::new();
+            while !remaining.is_empty() {
+                let Some(suite) = scan.handshake(version, &remaining, &groups).await? else {
+                    break;
+                };
+                remaining.retain(|s| s.suite() != suite.suite());
+                picked.push(suite);
+            }
+            report = report.with_detail(key, verdict(!picked.is_empty()));
+            if !picked.is_empty() {
+                accepted_versions.push(version);
+                preferences.push((key, version, picked));
+            }
+        }
+
+        for (key, version, picked) in preferences {
+            let names: Vec<_> = picked.iter().map(|s| format!("{:?}", s.suite())).collect();
+            report = report.with_detail(format!("{key}_suites"), names.join(", "));
+            if picked.len() > 1 {
+                // Offer the accepted suites in reverse: a server that
+                // enforces its own order still picks its favourite.
+                let reversed: Vec<_> = picked.iter().rev().copied().collect();
+                let choice = scan.handshake(version, &reversed, &groups).await?;
+                let order = match choice {
+                    Some(suite) if suite.suite() == picked[0].suite() => "server",
+                    _ => "client",
+                };
+                report = report.with_detail(format!("{key}_order"), order);
+            }
+        }
+
+        let mut accepted_groups = Vec::new();
+        for group in &groups {
+            // Newest version first; a group only TLS 1.2 takes still
+            // counts.
+            for version in accepted_versions.iter().rev() {
+                let suites = suites_for(&provider, version);
+                if scan.handshake(version, &suites, &[*group]).await?.is_some() {
+                    accepted_groups.push(format!("{:?}", group.name()));
+                    break;
+                }
+            }
+        }
+        report = report.with_detail("groups", accepted_groups.join(", "));
+
+        for warning in warnings {
+            report = report.with_detail("warning", warning);
+        }
+        Ok(report)
+    }
+}
+
+fn verdict(accepted: bool) -> &'static str {
+    if accepted {
+        "accepted"
+    } else {
+        "refused"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_hello_lengths_line_up() {
+        let hello = legacy_client_hello(0x0302, Some("example.com"));
+        assert_eq!(&hello[..3], &[0x16, 0x03, 0x01]);
+        let record_len = u16::from_be_bytes([hello[3], hello[4]]) as usize;
+        assert_eq!(record_len, hello.len() - 5);
+        assert_eq!(hello[5], 0x01);
+        let body_len = u32::from_be_bytes([0, hello[6], hello[7], hello[8]]) as usize;
+        assert_eq!(body_len, hello.len() - 9);
+        assert_eq!(&hello[9..11], &[0x03, 0x02]);
+        assert!(hello.windows(11).any(|w| w == b"example.com"));
+    }
+}
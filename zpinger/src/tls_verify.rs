@@ -0,0 +1,355 @@
+//! Server certificate verification under `TlsOptions` trust
+//! overrides: extra or replacement CA roots, SPKI SHA-256 pins, and
+//! an insecure mode that accepts the chain anyway but still records
+//! what verification concluded.
+//!
+//! Pins follow curl's `--pinnedpubkey`: they're checked even in
+//! insecure mode, so `insecure + pin` means "trust this key, whoever
+//! signed it". Any certificate in the presented chain may match.
+
+// gRPC-only builds use the pin parsing but not the verifier: tonic
+// keeps its own rustls config.
+#![cfg_attr(
+    not(any(feature = "_tls", feature = "quic", feature = "dtls")),
+    allow(dead_code, unused_imports)
+)]
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::server::ParsedCertificate;
+use rustls::{DigitallySignedStruct, OtherError, RootCertStore, SignatureScheme};
+
+use crate::report::Report;
+use crate::tls::{system_root_certs, TlsOptions};
+
+/// SHA-256 of a certificate's DER SubjectPublicKeyInfo — the value
+/// `--pin-sha256` compares against.
+pub(crate) type Pin = [u8; 32];
+
+/// Replaces rustls' default verifier whenever `TlsOptions` touches
+/// trust, or `TlsPinger` wants to see what the server presented.
+#[derive(Debug)]
+pub(crate) struct TrustVerifier {
+    /// `Err` when there are no roots to verify against (default roots
+    /// replaced and no CA given); only usable in insecure mode.
+    webpki: Result<Arc<WebPkiServerVerifier>, String>,
+    pins: Vec<Pin>,
+    insecure: bool,
+    verdict: Verdict,
+    presented: Option<Presented>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl TrustVerifier {
+    /// With `presented`, the server's chain and stapled OCSP response
+    /// are kept there.
+    pub(crate) fn new(options: &TlsOptions, presented: Option<Presented>) -> (Arc<Self>, Verdict) {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut roots = RootCertStore::empty();
+        if !options.replace_roots {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        // Certificates were checked when they were added to the
+        // options; `add_parsable_certificates` just skips any that
+        // were pushed onto the public field unchecked.
+        roots.add_parsable_certificates(options.ca_certs.iter().cloned());
+        if options.system_roots {
+            // `with_system_roots` already checked the store loads.
+            let system = system_root_certs().unwrap_or_default();
+            roots.add_parsable_certificates(system.iter().cloned());
+        }
+        let webpki = if roots.is_empty() {
+            Err("no trust anchors: default roots replaced and no CA given".to_string())
+        } else {
+            WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .map_err(|e| e.to_string())
+        };
+        let verdict = if options.insecure {
+            Verdict::recording()
+        } else {
+            Verdict::default()
+        };
+        let verifier = Self {
+            webpki,
+            pins: options.pins.clone(),
+            insecure: options.insecure,
+            verdict: verdict.clone(),
+            presented,
+            algorithms: provider.signature_verification_algorithms,
+        };
+        (Arc::new(verifier), verdict)
+    }
+
+    fn verify_chain(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<(), rustls::Error> {
+        let webpki = self
+            .webpki
+            .as_ref()
+            .map_err(|msg| rustls::Error::General(msg.clone()))?;
+        webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+        Ok(())
+    }
+
+    fn verify_pins(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+    ) -> Result<(), rustls::Error> {
+        if self.pins.is_empty() {
+            return Ok(());
+        }
+        let matched = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(spki_sha256)
+            .any(|hash| self.pins.contains(&hash));
+        if matched {
+            Ok(())
+        } else {
+            Err(rustls::Error::Other(OtherError(Arc::new(PinMismatch))))
+        }
+    }
+}
+
+/// No certificate in the presented chain matched a pin. Carried in
+/// `rustls::Error::Other` so failure diagnosis can match on the type.
+#[derive(Debug)]
+pub(crate) struct PinMismatch;
+
+impl fmt::Display for PinMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no certificate in the chain matches a pinned public key")
+    }
+}
+
+impl std::error::Error for PinMismatch {}
+
+impl ServerCertVerifier for TrustVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(presented) = &self.presented {
+            presented.record(end_entity, intermediates, ocsp_response);
+        }
+        let chain = self.verify_chain(end_entity, intermediates, server_name, ocsp_response, now);
+        if self.insecure {
+            self.verdict.record(match &chain {
+                Ok(()) => "ok".to_string(),
+                Err(e) => format!("failed: {e}"),
+            });
+        } else {
+            chain?;
+        }
+        self.verify_pins(end_entity, intermediates)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    // Signatures are checked even in insecure mode: they prove the
+    // server holds the key for the certificate it sent, which pins
+    // depend on.
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// What certificate verification concluded for an insecure
+/// handshake, read back by the pinger once the handshake is done.
+/// Empty (records nothing) unless insecure mode is on.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Verdict(Option<Arc<Mutex<Option<String>>>>);
+
+impl Verdict {
+    fn recording() -> Self {
+        Self(Some(Arc::new(Mutex::new(None))))
+    }
+
+    /// Keep the first failure: with several handshakes per probe
+    /// (HLS), one bad certificate is the news.
+    fn record(&self, outcome: String) {
+        if let Some(slot) = &self.0 {
+            if let Ok(mut slot) = slot.lock() {
+                if slot.as_deref().is_none_or(|v| v == "ok") {
+                    *slot = Some(outcome);
+                }
+            }
+        }
+    }
+
+    /// Add the recorded outcome to `report` as the `verification`
+    /// detail.
+    pub(crate) fn annotate(&self, report: Report) -> Report {
+        let outcome = self.0.as_ref().and_then(|slot| slot.lock().ok()?.clone());
+        match outcome {
+            Some(outcome) => report.with_detail("verification", outcome),
+            None => report,
+        }
+    }
+}
+
+/// What the server presented to the verifier — its certificate chain
+/// and stapled OCSP response — kept for `TlsPinger`'s staple checks
+/// and failure diagnostics. Recorded before verification, so a
+/// rejected chain is kept too.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Presented(Arc<Mutex<Presentation>>);
+
+#[derive(Debug, Default)]
+struct Presentation {
+    chain: Vec<Vec<u8>>,
+    staple: Option<Vec<u8>>,
+}
+
+impl Presented {
+    /// The first handshake's presentation wins; a resumed one isn't
+    /// verified at all. rustls passes an empty response when nothing
+    /// was stapled.
+    fn record(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        ocsp_response: &[u8],
+    ) {
+        if let Ok(mut slot) = self.0.lock() {
+            if slot.chain.is_empty() {
+                slot.chain = std::iter::once(end_entity)
+                    .chain(intermediates)
+                    .map(|c| c.to_vec())
+                    .collect();
+                if !ocsp_response.is_empty() {
+                    slot.staple = Some(ocsp_response.to_vec());
+                }
+            }
+        }
+    }
+
+    /// The stapled response, if there was one.
+    #[cfg_attr(not(feature = "tls"), allow(dead_code))]
+    pub(crate) fn take_staple(&self) -> Option<Vec<u8>> {
+        self.0.lock().ok()?.staple.take()
+    }
+
+    /// The presented chain, leaf first; empty if the handshake never
+    /// got as far as the server's certificate.
+    #[cfg_attr(not(feature = "tls"), allow(dead_code))]
+    pub(crate) fn chain(&self) -> Vec<Vec<u8>> {
+        self.0
+            .lock()
+            .map(|slot| slot.chain.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// SHA-256 of `cert`'s SubjectPublicKeyInfo, or `None` if it doesn't
+/// parse.
+pub(crate) fn spki_sha256(cert: &CertificateDer<'_>) -> Option<Pin> {
+    let parsed = ParsedCertificate::try_from(cert).ok()?;
+    Some(hash_spki(&parsed.subject_public_key_info()))
+}
+
+/// SHA-256 of a DER SubjectPublicKeyInfo.
+pub(crate) fn hash_spki(spki: &[u8]) -> Pin {
+    let digest = ring::digest::digest(&ring::digest::SHA256, spki);
+    digest
+        .as_ref()
+        .try_into()
+        .expect("SHA-256 digests are 32 bytes")
+}
+
+/// Parse a pin: base64 (optionally `sha256//`-prefixed, as curl
+/// writes them) or 64 hex digits, colons allowed.
+pub(crate) fn parse_pin(pin: &str) -> Result<Pin, String> {
+    let pin = pin.trim();
+    let pin = pin.strip_prefix("sha256//").unwrap_or(pin);
+    let hex: String = pin.chars().filter(|c| *c != ':').collect();
+    let bytes = if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        (0..32)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    } else {
+        base64::engine::general_purpose::STANDARD
+            .decode(pin)
+            .map_err(|e| format!("pin {pin:?} is neither base64 nor hex: {e}"))?
+    };
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("pin must be a SHA-256 (32 bytes), got {} bytes", b.len()))
+}
+
+/// `pin` in the base64 form `parse_pin` accepts and `CertInfo`
+/// reports.
+#[cfg(any(test, feature = "tls"))]
+pub(crate) fn format_pin(pin: &Pin) -> String {
+    base64::engine::general_purpose::STANDARD.encode(pin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_base64_and_hex_pins() {
+        let pin: Pin = core::array::from_fn(|i| i as u8);
+        let b64 = format_pin(&pin);
+        assert_eq!(parse_pin(&b64).unwrap(), pin);
+        assert_eq!(parse_pin(&format!("sha256//{b64}")).unwrap(), pin);
+        let hex: String = pin.iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(parse_pin(&hex).unwrap(), pin);
+        let colons: Vec<String> = pin.iter().map(|b| format!("{b:02X}")).collect();
+        assert_eq!(parse_pin(&colons.join(":")).unwrap(), pin);
+    }
+
+    #[test]
+    fn rejects_malformed_pins() {
+        assert!(parse_pin("not base64!").is_err());
+        let short = parse_pin("AAAA").unwrap_err();
+        assert!(short.contains("32 bytes"), "{short}");
+    }
+
+    #[test]
+    fn verdict_keeps_first_failure() {
+        let verdict = Verdict::recording();
+        verdict.record("ok".into());
+        verdict.record("failed: one".into());
+        verdict.record("failed: two".into());
+        verdict.record("ok".into());
+        let report = verdict.annotate(Report::new());
+        assert_eq!(report.detail("verification"), Some("failed: one"));
+        assert_eq!(Verdict::default().annotate(Report::new()), Report::new());
+    }
+}
@@ -21,6 +21,7 @@ use std::time::Duration;
 use async_trait::async_trait;
 use tokio::net::UdpSocket;
 
+use crate::observe;
 use crate::pinger::Pinger;
 use crate::stun::{
     build_message, random_transaction_id, server_endpoint, validate_response_header,
@@ -75,10 +76,12 @@ impl Pinger for TurnPinger {
         with_timeout(self.timeout, async move {
             let socket = UdpSocket::bind("0.0.0.0:0").await?;
             socket.connect(&endpoint).await?;
-            socket.send(&request).await?;
+            observe::udp_connected(&socket);
+            observe::sent(socket.send(&request).await?);
 
             let mut buf = [0u8; BUF_SIZE];
             let n = socket.recv(&mut buf).await?;
+            observe::received(n);
             validate_allocate_error_response(&buf[..n], &txid)
         })
         .await
@@ -32,7 +32,7 @@ impl Default for URI {
 
 impl URI {
     pub fn parse(&mut self, url: &str) -> Result<()> {
-        let rg_w_named = Regex::new(r"^((?P<scheme>[^/?#]+)://)?((?P<username>\w+):(?P<password>\w+)@)?(?P<host>(?P<domain>[^/?#:]+)(:(?P<port>\d*))?)?(?P<path>[^?#]*)(\?(?P<query>[^#]*))?(#(?P<fragment>.*))?").unwrap();
+        let rg_w_named = Regex::new(r"^((?P<scheme>[^/?#]+)://)?((?P<username>[^:@/?#]+)(:(?P<password>[^@/?#]*))?@)?(?P<host>(?P<domain>[^/?#:]+)(:(?P<port>\d*))?)?(?P<path>[^?#]*)(\?(?P<query>[^#]*))?(#(?P<fragment>.*))?").unwrap();
         match rg_w_named.captures(url) {
             Some(uri_parser) => {
                 match uri_parser.name("scheme") {
@@ -1,8 +1,8 @@
 //! Shared utilities used by every protocol module that drives a
 //! socket directly (everything except `grpc`, which lets tonic handle
-//! its own timeouts). Gated on the union of the protocol features
-//! that actually call `with_timeout` so the `--no-default-features`
-//! build doesn't warn about dead code.
+//! its own timeouts). Each helper is gated on the union of the
+//! protocol features that actually call it so the
+//! `--no-default-features` build doesn't warn about dead code.
 
 #[cfg(any(
     feature = "tcp",
@@ -18,8 +18,12 @@
     feature = "turn",
     feature = "rtsp",
     feature = "rtmp",
+    feature = "quic",
+    feature = "dtls",
 ))]
 use std::io::{self, Result};
+#[cfg(any(feature = "quic", feature = "dtls"))]
+use std::net::SocketAddr;
 #[cfg(any(
     feature = "tcp",
     feature = "udp",
@@ -34,6 +38,8 @@ use std::io::{self, Result};
     feature = "turn",
     feature = "rtsp",
     feature = "rtmp",
+    feature = "dtls",
+    feature = "http3",
 ))]
 use std::time::Duration;
 #[cfg(any(
@@ -72,9 +78,9 @@ use tokio::time::timeout;
     feature = "rtsp",
     feature = "rtmp",
 ))]
-pub(crate) async fn with_timeout<F>(d: Duration, fut: F) -> Result<()>
+pub(crate) async fn with_timeout<T, F>(d: Duration, fut: F) -> Result<T>
 where
-    F: std::future::Future<Output = Result<()>>,
+    F: std::future::Future<Output = Result<T>>,
 {
     match timeout(d, fut).await {
         Ok(inner) => inner,
@@ -84,3 +90,77 @@ where
         )),
     }
 }
+
+/// Split an HTTP-style response head (status line + `Name: value`
+/// lines, already cut at the blank line) into the numeric status code
+/// and the header list. `prefix` is the protocol token the status
+/// line must start with (`HTTP/`, `RTSP/`). Shared by the HTTP and
+/// RTSP pingers, which frame responses the same way.
+#[cfg(any(feature = "http", feature = "rtsp"))]
+pub(crate) fn parse_response_head(
+    head: &str,
+    prefix: &str,
+) -> Option<(u16, Vec<(String, String)>)> {
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next()?;
+    if !status_line.starts_with(prefix) {
+        return None;
+    }
+    let status = status_line.split_whitespace().nth(1)?.parse().ok()?;
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Some((status, headers))
+}
+
+/// Milliseconds with three decimals, the form every `*_ms` report
+/// detail takes.
+#[cfg(any(feature = "http", feature = "tls", feature = "dtls", feature = "http3"))]
+pub(crate) fn format_ms(elapsed: Duration) -> String {
+    format!("{:.3}", elapsed.as_secs_f64() * 1000.0)
+}
+
+/// Resolve `host:port` and pick a single `SocketAddr` to dial. We
+/// prefer IPv4 when both are returned because most local-loopback /
+/// container test fixtures bind v4-only, and falling back from IPv6
+/// to v4 means a 5-second timeout instead of a fast hit. On a
+/// production internet host with both AAAA and A records this still
+/// works — the UDP pingers don't care which family we pick.
+#[cfg(any(feature = "quic", feature = "dtls"))]
+pub(crate) async fn resolve_first(host: &str, port: u16) -> Result<SocketAddr> {
+    let target = format!("{host}:{port}");
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(&target).await?.collect();
+    if let Some(v4) = addrs.iter().find(|a| a.is_ipv4()) {
+        return Ok(*v4);
+    }
+    addrs
+        .into_iter()
+        .next()
+        .ok_or_else(|| io::Error::other(format!("DNS lookup returned no addresses for {target}")))
+}
+
+/// Pick the right wildcard local-bind address for the resolved
+/// remote: `0.0.0.0:0` for IPv4, `[::]:0` for IPv6. A UDP socket has
+/// to be in the same family as the remote it's about to dial.
+#[cfg(any(feature = "quic", feature = "dtls"))]
+pub(crate) fn unspecified_for(remote: SocketAddr) -> SocketAddr {
+    if remote.is_ipv6() {
+        "[::]:0".parse().unwrap()
+    } else {
+        "0.0.0.0:0".parse().unwrap()
+    }
+}
+
+#[cfg(all(test, any(feature = "quic", feature = "dtls")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unspecified_for_picks_v4_or_v6() {
+        let v4: SocketAddr = "1.2.3.4:443".parse().unwrap();
+        assert_eq!(unspecified_for(v4), "0.0.0.0:0".parse().unwrap());
+        let v6: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+        assert_eq!(unspecified_for(v6), "[::]:0".parse().unwrap());
+    }
+}
@@ -4,15 +4,18 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
-use rustls::pki_types::ServerName;
 use rustls::ClientConfig;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
 use tokio_tungstenite::tungstenite::Message;
 
+use crate::auth::{self, HttpAuth};
+use crate::observe;
 use crate::pinger::Pinger;
-use crate::tls::default_client_config;
+use crate::report::Report;
+use crate::tls::{client_config, TlsOptions};
 use crate::uri::{get_uri, URI};
 use crate::util::with_timeout;
 
@@ -24,10 +27,17 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 ///   3. send a control PING frame,
 ///   4. wait for the matching PONG frame,
 ///   5. close gracefully.
+///
+/// Credentials (`with_auth`, or the URL's `user:password@` as Basic)
+/// ride on the Upgrade request; Digest answers a `401` challenge with
+/// a second handshake on a fresh connection.
 pub struct WebSocketPinger {
     pub target: String,
     pub timeout: Duration,
+    /// Credentials; `None` falls back to the URL's userinfo.
+    pub auth: Option<HttpAuth>,
     tls_config: Option<Arc<ClientConfig>>,
+    tls_options: TlsOptions,
 }
 
 impl WebSocketPinger {
@@ -35,7 +45,9 @@ impl WebSocketPinger {
         Self {
             target: target.into(),
             timeout: DEFAULT_TIMEOUT,
+            auth: None,
             tls_config: None,
+            tls_options: TlsOptions::default(),
         }
     }
 
@@ -44,48 +56,57 @@ impl WebSocketPinger {
         self
     }
 
+    /// Authenticate the Upgrade request with `auth` instead of any
+    /// `user:password@` in the target.
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
         self.tls_config = Some(config);
         self
     }
 
-    async fn ping_plain(&self, uri: &URI) -> Result<()> {
+    /// Shared TLS settings for `wss://`.
+    pub fn with_tls_options(mut self, options: TlsOptions) -> Self {
+        self.tls_options = options;
+        self
+    }
+
+    async fn ping_plain(&self, uri: &URI, authorization: Option<&str>) -> Result<Upgrade> {
         let endpoint = endpoint_for(uri, 80)?;
         let target = self.target.clone();
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
-            run_handshake_and_ping(&target, tcp).await
+            let tcp = observe::connect_tcp(&endpoint).await?;
+            run_handshake_and_ping(&target, authorization, tcp).await
         })
         .await
     }
 
-    async fn ping_tls(&self, uri: &URI) -> Result<()> {
+    async fn ping_tls(&self, uri: &URI, authorization: Option<&str>) -> Result<(Upgrade, Report)> {
         let endpoint = endpoint_for(uri, 443)?;
-        let server_name = ServerName::try_from(uri.domain.clone())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-        let config = self
-            .tls_config
-            .clone()
-            .unwrap_or_else(default_client_config);
+        let server_name = self.tls_options.server_name(&uri.domain)?;
+        let (config, verdict) = client_config(&self.tls_config, &self.tls_options);
         let target = self.target.clone();
         with_timeout(self.timeout, async move {
-            let tcp = TcpStream::connect(&endpoint).await?;
+            let tcp = observe::connect_tcp(&endpoint).await?;
             let connector = TlsConnector::from(config);
             let stream = connector.connect(server_name, tcp).await?;
-            run_handshake_and_ping(&target, stream).await
+            observe::tls_handshake(stream.get_ref().1);
+            run_handshake_and_ping(&target, authorization, stream).await
         })
         .await
+        .map(|upgrade| (upgrade, verdict.annotate(Report::new())))
     }
-}
 
-#[async_trait]
-impl Pinger for WebSocketPinger {
-    async fn ping(&self) -> Result<()> {
-        let uri = get_uri(&self.target);
+    /// One connection: handshake presenting `authorization`, then
+    /// PING/PONG.
+    async fn attempt(&self, uri: &URI, authorization: Option<&str>) -> Result<(Upgrade, Report)> {
         let scheme = uri.scheme.to_ascii_lowercase();
         match scheme.as_str() {
-            "ws" => self.ping_plain(&uri).await,
-            "wss" => self.ping_tls(&uri).await,
+            "ws" => Ok((self.ping_plain(uri, authorization).await?, Report::new())),
+            "wss" => self.ping_tls(uri, authorization).await,
             other => Err(io::Error::other(format!(
                 "scheme '{other}' is not supported by WebSocketPinger (use ws:// or wss://)"
             ))),
@@ -93,13 +114,89 @@ impl Pinger for WebSocketPinger {
     }
 }
 
-async fn run_handshake_and_ping<S>(target: &str, stream: S) -> Result<()>
+/// How the Upgrade went when it didn't fail outright.
+enum Upgrade {
+    /// Handshake done and the PING answered.
+    Ponged,
+    /// `401`: the response headers, and the error to report if we
+    /// can't answer their challenge.
+    Unauthorized {
+        headers: Vec<(String, String)>,
+        error: io::Error,
+    },
+}
+
+#[async_trait]
+impl Pinger for WebSocketPinger {
+    async fn ping(&self) -> Result<()> {
+        self.probe().await.map(|_| ())
+    }
+
+    async fn probe(&self) -> Result<Report> {
+        let uri = get_uri(&self.target);
+        let credentials = self.auth.clone().or_else(|| HttpAuth::from_userinfo(&uri));
+        let preemptive = credentials.as_ref().and_then(HttpAuth::preemptive);
+        let (upgrade, report) = self.attempt(&uri, preemptive.as_deref()).await?;
+        let (headers, error) = match upgrade {
+            Upgrade::Ponged => {
+                return Ok(match (&credentials, preemptive) {
+                    (Some(auth), Some(_)) => report.with_detail("auth", auth.scheme()),
+                    _ => report,
+                });
+            }
+            Upgrade::Unauthorized { headers, error } => (headers, error),
+        };
+        // Digest: answer the challenge on a fresh connection.
+        let request_uri = match uri.query.as_str() {
+            "" if uri.path.is_empty() => "/".to_string(),
+            "" => uri.path.clone(),
+            query => format!("{}?{query}", uri.path),
+        };
+        let Some(answer) = credentials
+            .as_ref()
+            .and_then(|auth| auth.answer(&auth::challenges(&headers), "GET", &request_uri))
+        else {
+            return Err(error);
+        };
+        match self.attempt(&uri, Some(&answer)).await? {
+            (Upgrade::Ponged, report) => Ok(report.with_detail("auth", "digest")),
+            (Upgrade::Unauthorized { error, .. }, _) => Err(error),
+        }
+    }
+}
+
+async fn run_handshake_and_ping<S>(
+    target: &str,
+    authorization: Option<&str>,
+    stream: S,
+) -> Result<Upgrade>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
-    let (mut ws, _response) = tokio_tungstenite::client_async(target, stream)
-        .await
-        .map_err(tungstenite_err)?;
+    let mut request = target.into_client_request().map_err(tungstenite_err)?;
+    if let Some(value) = authorization {
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+    }
+    let (mut ws, _response) = match tokio_tungstenite::client_async(request, stream).await {
+        Ok(handshake) => handshake,
+        Err(tokio_tungstenite::tungstenite::Error::Http(response))
+            if response.status().as_u16() == 401 =>
+        {
+            let headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.as_str().to_string(), value)
+                })
+                .collect();
+            let error = tungstenite_err(tokio_tungstenite::tungstenite::Error::Http(response));
+            return Ok(Upgrade::Unauthorized { headers, error });
+        }
+        Err(e) => return Err(tungstenite_err(e)),
+    };
 
     ws.send(Message::Ping(Default::default()))
         .await
@@ -131,7 +228,7 @@ where
     }
 
     let _ = ws.close(None).await;
-    Ok(())
+    Ok(Upgrade::Ponged)
 }
 
 fn endpoint_for(uri: &URI, default_port: u16) -> Result<String> {
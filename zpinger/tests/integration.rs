@@ -78,6 +78,87 @@ async fn tcp_pinger_struct_fails_on_closed_port() {
     assert!(p.ping().await.is_err());
 }
 
+#[tokio::test]
+async fn tcp_pinger_connect_mode_needs_no_reply() {
+    // A bare listener never writes — Echo mode would time out, but the
+    // kernel completes the handshake so Connect mode succeeds.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let report = zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Connect)
+        .with_timeout(Duration::from_secs(1))
+        .probe()
+        .await
+        .unwrap();
+    assert!(report.details.is_empty());
+    drop(listener);
+}
+
+#[tokio::test]
+async fn tcp_pinger_connect_mode_fails_on_closed_port() {
+    let p = zpinger::TcpPinger::new(closed_tcp_addr()).with_mode(zpinger::TcpMode::Connect);
+    assert!(p.ping().await.is_err());
+}
+
+#[tokio::test]
+async fn tcp_pinger_banner_mode_reports_greeting() {
+    let addr = testserver::start_tcp_banner("127.0.0.1:0", "SSH-2.0-testserver\r\n").unwrap();
+    let report = zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Banner)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("banner"), Some("SSH-2.0-testserver"));
+}
+
+#[tokio::test]
+async fn tcp_pinger_banner_mode_checks_expected_pattern() {
+    let addr = testserver::start_tcp_banner("127.0.0.1:0", "220 smtp ready\r\n").unwrap();
+    zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Banner)
+        .with_expect("^220 ")
+        .ping()
+        .await
+        .unwrap();
+    let err = zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Banner)
+        .with_expect("^SSH-")
+        .with_timeout(Duration::from_secs(1))
+        .ping()
+        .await
+        .unwrap_err();
+    // The fixture keeps the socket open after its greeting, so the
+    // pinger waits out the timeout — and says what it did receive.
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(err.to_string().contains("220 smtp ready"), "{err}");
+}
+
+#[tokio::test]
+async fn tcp_pinger_send_mode_waits_for_expected_reply() {
+    // The fixture's banner arrives first; the pattern keeps the pinger
+    // reading until the echoed payload shows up too.
+    let addr = testserver::start_tcp_banner("127.0.0.1:0", "hello\r\n").unwrap();
+    let report = zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Send(b"PING\r\n".to_vec()))
+        .with_expect("PING")
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("banner"), Some("hello\r\nPING"));
+}
+
+#[tokio::test]
+async fn tcp_pinger_rejects_expect_in_connect_mode() {
+    let addr = testserver::start_tcp_echo("127.0.0.1:0").unwrap();
+    let err = zpinger::TcpPinger::new(addr.to_string())
+        .with_mode(zpinger::TcpMode::Connect)
+        .with_expect("x")
+        .ping()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
 #[tokio::test]
 async fn http_pinger_struct_succeeds() {
     let addr = testserver::start_http_ok("127.0.0.1:0").unwrap();
@@ -97,13 +178,112 @@ async fn http_pinger_all_methods_via_struct() {
         zpinger::HttpMethod::Delete,
         zpinger::HttpMethod::Patch,
     ] {
-        zpinger::HttpPinger::new(method, target.clone())
+        zpinger::HttpPinger::new(method.clone(), target.clone())
             .ping()
             .await
             .unwrap_or_else(|e| panic!("{:?} failed: {}", method, e));
     }
 }
 
+#[tokio::test]
+async fn http_pinger_head_reads_no_body() {
+    // A HEAD answer advertises the GET body's length but sends none;
+    // reading it as a body would wait for bytes that never come.
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nContent-Length: 5000\r\n\r\n",
+    )
+    .unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Head, format!("{addr}/"))
+        .with_timeout(Duration::from_secs(2))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.header("content-length"), Some("5000"));
+    assert_eq!(report.detail("body_bytes"), Some("0"));
+}
+
+#[tokio::test]
+async fn http_pinger_sends_extension_methods_as_given() {
+    let addr = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    let method: zpinger::HttpMethod = "PROPFIND".parse().unwrap();
+    let report = zpinger::HttpPinger::new(method, format!("{addr}/calendars/"))
+        .with_header("Depth", "1")
+        .probe()
+        .await
+        .unwrap();
+    let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+    assert!(
+        echoed.starts_with("PROPFIND /calendars/ HTTP/1.1\r\n"),
+        "{echoed}"
+    );
+    assert!(!echoed.contains("Content-Length"), "{echoed}");
+}
+
+#[tokio::test]
+async fn http_pinger_checks_cors_preflight() {
+    let allowing = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 204 No Content\r\n\
+         Access-Control-Allow-Origin: https://app.example.com\r\n\
+         Access-Control-Allow-Methods: GET, PUT\r\n\
+         Access-Control-Allow-Headers: x-trace\r\n\r\n",
+    )
+    .unwrap();
+    let preflight = zpinger::CorsPreflight::new("https://app.example.com")
+        .with_request_method("PUT")
+        .with_request_headers(["X-Trace"]);
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Options, format!("{allowing}/"))
+        .with_cors(preflight.clone())
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("cors"), Some("allowed"));
+
+    let refusing = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 204 No Content\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+    )
+    .unwrap();
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Options, format!("{refusing}/"))
+        .with_cors(preflight)
+        .ping()
+        .await
+        .unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(
+        assertion.failures,
+        [
+            "Access-Control-Allow-Methods doesn't allow PUT",
+            "Access-Control-Allow-Headers doesn't allow X-Trace",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn http_pinger_sends_cors_request_headers() {
+    let addr = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Options, format!("{addr}/api"))
+        .with_cors(
+            zpinger::CorsPreflight::new("https://app.example.com")
+                .with_request_method("DELETE")
+                .with_request_headers(["X-Trace", "Content-Type"]),
+        )
+        .exchange()
+        .await
+        .unwrap();
+    let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+    assert!(echoed.starts_with("OPTIONS /api HTTP/1.1\r\n"), "{echoed}");
+    for line in [
+        "Origin: https://app.example.com\r\n",
+        "Access-Control-Request-Method: DELETE\r\n",
+        "Access-Control-Request-Headers: x-trace, content-type\r\n",
+    ] {
+        assert!(echoed.contains(line), "{line:?} missing from {echoed}");
+    }
+}
+
 #[tokio::test]
 async fn http_pinger_via_timed_helper() {
     let addr = testserver::start_http_ok("127.0.0.1:0").unwrap();
@@ -144,7 +324,7 @@ async fn http_pinger_https_all_methods_via_struct() {
         zpinger::HttpMethod::Delete,
         zpinger::HttpMethod::Patch,
     ] {
-        zpinger::HttpPinger::new(method, target.clone())
+        zpinger::HttpPinger::new(method.clone(), target.clone())
             .with_tls_config(Arc::clone(&server.client_config))
             .ping()
             .await
@@ -189,6 +369,635 @@ async fn http_pinger_rejects_non_http_response() {
     assert!(p.ping().await.is_err());
 }
 
+#[tokio::test]
+async fn http_pinger_probe_reports_status_headers_and_body() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"status\":\"up\"}",
+    )
+    .unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/health", addr));
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.header("content-type"), Some("application/json"));
+    assert_eq!(report.body.as_deref(), Some(&b"{\"status\":\"up\"}"[..]));
+    assert_eq!(report.detail("body_bytes"), Some("15"));
+    assert!(report.detail("header_ms").is_some());
+    assert!(report.detail("download_bytes_per_sec").is_some());
+}
+
+#[tokio::test]
+async fn http_pinger_decodes_chunked_body_with_trailers() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+         4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nX-Trailer: yes\r\n\r\n",
+    )
+    .unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr));
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"Wikipedia"[..]));
+    assert_eq!(report.detail("body_bytes"), Some("9"));
+}
+
+#[tokio::test]
+async fn http_pinger_reads_large_bodies_and_keeps_a_prefix() {
+    for chunked in [false, true] {
+        let addr = testserver::start_http_download("127.0.0.1:0", 200_000, chunked).unwrap();
+        let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr));
+        let report = p.probe().await.unwrap();
+        assert_eq!(
+            report.detail("body_bytes"),
+            Some("200000"),
+            "chunked={chunked}"
+        );
+        assert_eq!(report.body.as_ref().map(Vec::len), Some(64 * 1024));
+        assert_eq!(report.detail("body_truncated"), None);
+    }
+}
+
+#[tokio::test]
+async fn http_pinger_stops_at_max_body_bytes() {
+    for chunked in [false, true] {
+        let addr = testserver::start_http_download("127.0.0.1:0", 200_000, chunked).unwrap();
+        let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr))
+            .with_max_body_bytes(10_000);
+        let report = p.probe().await.unwrap();
+        assert_eq!(
+            report.detail("body_bytes"),
+            Some("10000"),
+            "chunked={chunked}"
+        );
+        assert_eq!(report.detail("body_truncated"), Some("true"));
+    }
+}
+
+#[tokio::test]
+async fn http_pinger_reads_unframed_body_until_close() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\nuntil close",
+    )
+    .unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr));
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"until close"[..]));
+}
+
+#[tokio::test]
+async fn http_pinger_skips_interim_responses() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 100 Continue\r\n\r\n\
+         HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload\r\n\r\n\
+         HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+    )
+    .unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr));
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.body.as_deref(), Some(&b"ok"[..]));
+}
+
+#[tokio::test]
+async fn http_pinger_fails_on_short_body() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nonly this",
+    )
+    .unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr));
+    let err = p.probe().await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert!(err.to_string().contains("after 9 of 100 bytes"), "{err}");
+}
+
+#[tokio::test]
+async fn http_pinger_times_uploads() {
+    let addr = testserver::start_http_upload_sink("127.0.0.1:0").unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Put, format!("{}/upload", addr))
+        .with_upload_bytes(300_000);
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"received 300000"[..]));
+    assert_eq!(report.detail("upload_bytes"), Some("300000"));
+    assert!(report.detail("upload_bytes_per_sec").is_some());
+
+    // The default body is exactly the two bytes it declares.
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Post, format!("{}/", addr));
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"received 2"[..]));
+}
+
+#[tokio::test]
+async fn http_pinger_follows_redirect_chain() {
+    let echo = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    let to_echo = format!("HTTP/1.1 308 Permanent Redirect\r\nLocation: http://{echo}/final?x=1\r\nContent-Length: 0\r\n\r\n");
+    let addr = testserver::start_http_routes(
+        "127.0.0.1:0",
+        &[
+            (
+                "/start",
+                "HTTP/1.1 301 Moved Permanently\r\nLocation: next\r\nContent-Length: 0\r\n\r\n",
+            ),
+            ("/next", &to_echo),
+        ],
+    )
+    .unwrap();
+    let target = format!("http://{addr}/start");
+
+    // Not following: the first 301 is the answer.
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone())
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(301));
+    assert_eq!(report.detail("redirects"), None);
+
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone())
+        .with_redirects(5)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.detail("redirects"), Some("2"));
+    assert_eq!(report.detail("hop1_url"), Some(target.as_str()));
+    assert_eq!(report.detail("hop1_status"), Some("301"));
+    assert_eq!(
+        report.detail("hop2_url"),
+        Some(format!("http://{addr}/next").as_str())
+    );
+    assert_eq!(report.detail("hop2_status"), Some("308"));
+    assert!(report.detail("hop2_ms").is_some());
+    let final_url = format!("http://{echo}/final?x=1");
+    assert_eq!(report.detail("final_url"), Some(final_url.as_str()));
+    let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+    assert!(
+        echoed.starts_with("GET /final?x=1 HTTP/1.1\r\n"),
+        "{echoed}"
+    );
+
+    // Same-origin only: the hop to the echo server is refused.
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_redirects(5)
+        .with_same_origin_redirects(true)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(308));
+    assert_eq!(report.detail("redirects"), Some("1"));
+    assert_eq!(report.detail("redirect_blocked"), Some(final_url.as_str()));
+}
+
+#[tokio::test]
+async fn http_pinger_redirect_method_semantics() {
+    let echo = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    for (status, want) in [
+        (303, "GET /echo HTTP/1.1"),
+        (302, "GET /echo HTTP/1.1"),
+        (307, "POST /echo HTTP/1.1"),
+    ] {
+        let redirect = format!(
+            "HTTP/1.1 {status} Redirect\r\nLocation: http://{echo}/echo\r\nContent-Length: 0\r\n\r\n"
+        );
+        let addr = testserver::start_http_routes("127.0.0.1:0", &[("/", &redirect)]).unwrap();
+        let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Post, format!("{addr}/"))
+            .with_body("payload")
+            .with_redirects(1)
+            .probe()
+            .await
+            .unwrap();
+        let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+        assert!(echoed.starts_with(want), "{status}: {echoed}");
+        assert_eq!(
+            echoed.ends_with("payload"),
+            status == 307,
+            "{status}: {echoed}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn http_pinger_detects_redirect_loops_and_limits() {
+    let addr = testserver::start_http_routes(
+        "127.0.0.1:0",
+        &[
+            (
+                "/a",
+                "HTTP/1.1 302 Found\r\nLocation: /b\r\nContent-Length: 0\r\n\r\n",
+            ),
+            (
+                "/b",
+                "HTTP/1.1 302 Found\r\nLocation: /c\r\nContent-Length: 0\r\n\r\n",
+            ),
+            (
+                "/c",
+                "HTTP/1.1 302 Found\r\nLocation: /a\r\nContent-Length: 0\r\n\r\n",
+            ),
+        ],
+    )
+    .unwrap();
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{addr}/a"))
+        .with_redirects(10)
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("redirect loop: "), "{err}");
+    assert!(
+        err.to_string().ends_with(&format!("/c -> http://{addr}/a")),
+        "{err}"
+    );
+
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{addr}/a"))
+        .with_redirects(1)
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("more than 1 redirects"), "{err}");
+}
+
+#[tokio::test]
+async fn http_pinger_authenticates_with_basic_bearer_and_digest() {
+    let addr = testserver::start_http_auth("127.0.0.1:0", "knock", "p@ss").unwrap();
+    let target = format!("http://{addr}/private?q=1");
+    for (auth, scheme) in [
+        (zpinger::HttpAuth::basic("knock", "p@ss"), "basic"),
+        (zpinger::HttpAuth::bearer("p@ss"), "bearer"),
+        (zpinger::HttpAuth::digest("knock", "p@ss"), "digest"),
+    ] {
+        let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone())
+            .with_auth(auth)
+            .probe()
+            .await
+            .unwrap();
+        assert_eq!(report.body.as_deref(), Some(scheme.as_bytes()));
+        assert_eq!(report.detail("auth"), Some(scheme));
+        assert_eq!(
+            report.detail("auth_challenge_ms").is_some(),
+            scheme == "digest"
+        );
+    }
+
+    // Userinfo in the URL is Basic, percent-escapes decoded.
+    let report = zpinger::HttpPinger::new(
+        zpinger::HttpMethod::Get,
+        format!("http://knock:p%40ss@{addr}/"),
+    )
+    .probe()
+    .await
+    .unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"basic"[..]));
+
+    for auth in [None, Some(zpinger::HttpAuth::digest("knock", "wrong"))] {
+        let mut p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone());
+        p.auth = auth;
+        let err = p.probe().await.unwrap_err();
+        assert!(err.to_string().contains("status 401"), "{err}");
+    }
+}
+
+#[tokio::test]
+async fn http_pinger_keeps_credentials_on_the_target_origin() {
+    let other = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    let redirect = format!(
+        "HTTP/1.1 302 Found\r\nLocation: http://{other}/landing\r\nContent-Length: 0\r\n\r\n"
+    );
+    let addr = testserver::start_http_routes("127.0.0.1:0", &[("/", &redirect)]).unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{addr}/"))
+        .with_auth(zpinger::HttpAuth::bearer("secret"))
+        .with_redirects(1)
+        .probe()
+        .await
+        .unwrap();
+    let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+    assert!(echoed.starts_with("GET /landing "), "{echoed}");
+    assert!(
+        !echoed.to_ascii_lowercase().contains("authorization"),
+        "{echoed}"
+    );
+}
+
+#[tokio::test]
+async fn expect_passes_when_every_expectation_holds() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"status\":\"up\"}",
+    )
+    .unwrap();
+    let p = zpinger::Expect::new(zpinger::HttpPinger::new(
+        zpinger::HttpMethod::Get,
+        format!("{}/health", addr),
+    ))
+    .with_expectations(
+        [
+            "status=2xx",
+            "header=Content-Type:json",
+            "body=up",
+            "json=$.status=up",
+            "latency=5s",
+        ]
+        .map(|e| e.parse::<zpinger::Expectation>().unwrap()),
+    );
+    p.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn expect_reports_assertion_failures_distinct_from_transport_errors() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 4\r\n\r\ndown",
+    )
+    .unwrap();
+    let p = zpinger::Expect::new(zpinger::HttpPinger::new(
+        zpinger::HttpMethod::Get,
+        format!("{}/", addr),
+    ))
+    .with_expectation("status=200".parse().unwrap())
+    .with_expectation("body=up".parse().unwrap());
+    let err = p.ping().await.unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(
+        assertion.failures,
+        vec!["status 503 not in 200", "body does not match /up/"]
+    );
+
+    let closed = zpinger::Expect::new(zpinger::HttpPinger::new(
+        zpinger::HttpMethod::Get,
+        format!("{}/", closed_tcp_addr()),
+    ))
+    .with_expectation("status=200".parse().unwrap());
+    let err = closed.ping().await.unwrap_err();
+    assert!(zpinger::AssertionError::from_io(&err).is_none());
+}
+
+#[tokio::test]
+async fn expect_status_replaces_http_builtin_rule() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+    )
+    .unwrap();
+    let target = format!("{}/gone", addr);
+    let plain = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone());
+    assert!(plain.ping().await.is_err());
+
+    let expecting_404 = zpinger::Expect::new(plain).with_expectation("status=404".parse().unwrap());
+    expecting_404.ping().await.unwrap();
+
+    // Without a status expectation the built-in rule still applies.
+    let body_only =
+        zpinger::Expect::new(zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target))
+            .with_expectation("latency=5s".parse().unwrap());
+    let err = body_only.ping().await.unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(assertion.failures, vec!["status 404 not in 200-399"]);
+}
+
+#[tokio::test]
+async fn http_pinger_judges_status_by_expected_set() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n",
+    )
+    .unwrap();
+    let target = format!("{}/", addr);
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone())
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(zpinger::AssertionError::from_io(&err).is_some(), "{err}");
+
+    zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_expected_status("2xx,500".parse().unwrap())
+        .ping()
+        .await
+        .unwrap();
+
+    // The reason phrase doesn't matter, only the code.
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 Not 404\r\nContent-Length: 0\r\n\r\n",
+    )
+    .unwrap();
+    zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/", addr))
+        .ping()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn http_pinger_sends_custom_headers_and_body() {
+    let addr = testserver::start_http_echo("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Delete, format!("{}/item/7", addr))
+        .with_header("X-Request-Id", "abc-123")
+        .with_header("Host", "api.example.com")
+        .with_header("Content-Length", "999")
+        .with_user_agent("probe/2.0")
+        .with_content_type("text/plain")
+        .with_body("reason=stale")
+        .probe()
+        .await
+        .unwrap();
+    let echoed = String::from_utf8(report.body.unwrap()).unwrap();
+    assert_eq!(
+        echoed,
+        "DELETE /item/7 HTTP/1.1\r\n\
+         User-Agent: probe/2.0\r\n\
+         X-Request-Id: abc-123\r\n\
+         Host: api.example.com\r\n\
+         Content-Type: text/plain\r\n\
+         Content-Length: 12\r\n\
+         \r\n\
+         reason=stale"
+    );
+}
+
+#[tokio::test]
+async fn http_pinger_speaks_h2c_with_prior_knowledge() {
+    let addr = testserver::start_h2c_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/h2?q=1", addr))
+        .with_version(zpinger::HttpVersion::Http2)
+        .with_header("Connection", "keep-alive")
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.body.as_deref(), Some(&b"GET /h2 0"[..]));
+    assert_eq!(report.detail("version"), Some("HTTP/2"));
+    assert!(report.detail("settings_ms").is_some());
+    assert!(report.detail("ping_rtt_ms").is_none());
+}
+
+#[tokio::test]
+async fn http_pinger_negotiates_h2_over_tls_and_times_pings() {
+    let server = testserver::start_h2_ok("127.0.0.1:0").unwrap();
+    let target = format!("https://localhost:{}/bytes/100000", server.addr.port());
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_tls_config(server.client_config)
+        .with_version(zpinger::HttpVersion::Http2)
+        .with_h2_pings(3)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("body_bytes"), Some("100000"));
+    assert_eq!(report.detail("body_truncated"), None);
+    for key in [
+        "settings_ms",
+        "ping_rtt_ms",
+        "ping_rtt_min_ms",
+        "ping_rtt_max_ms",
+    ] {
+        assert!(report.detail(key).is_some(), "missing {key}");
+    }
+}
+
+#[tokio::test]
+async fn http_pinger_h2_uploads_and_caps_bodies() {
+    let addr = testserver::start_h2c_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Put, format!("{}/up", addr))
+        .with_version(zpinger::HttpVersion::Http2)
+        .with_upload_bytes(200_000)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"PUT /up 200000"[..]));
+    assert_eq!(report.detail("upload_bytes"), Some("200000"));
+
+    let report =
+        zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("{}/bytes/50000", addr))
+            .with_version(zpinger::HttpVersion::Http2)
+            .with_max_body_bytes(1000)
+            .probe()
+            .await
+            .unwrap();
+    assert_eq!(report.detail("body_bytes"), Some("1000"));
+    assert_eq!(report.detail("body_truncated"), Some("true"));
+}
+
+#[tokio::test]
+async fn http_pinger_h2_fails_without_alpn_h2() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_config(server.client_config)
+        .with_version(zpinger::HttpVersion::Http2)
+        .ping()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(
+        err.to_string().contains("did not negotiate HTTP/2"),
+        "{err}"
+    );
+}
+
+const SSE_EVENTS: &[(&str, &str)] = &[("message", "hello"), ("tick", "a\nb")];
+
+#[tokio::test]
+async fn sse_pinger_times_the_first_event() {
+    let addr = testserver::start_sse("127.0.0.1:0", SSE_EVENTS).unwrap();
+    let report = zpinger::SsePinger::new(format!("http://{addr}/events"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.detail("event"), Some("message"));
+    assert_eq!(report.detail("id"), Some("1"));
+    assert_eq!(report.detail("events_seen"), Some("1"));
+    assert_eq!(report.detail("retry_ms"), Some("1000"));
+    assert_eq!(report.body.as_deref(), Some(&b"hello"[..]));
+    assert!(report.detail("header_ms").is_some());
+    assert!(report.detail("first_event_ms").is_some());
+}
+
+#[tokio::test]
+async fn sse_pinger_waits_for_a_matching_event() {
+    let addr = testserver::start_sse("127.0.0.1:0", SSE_EVENTS).unwrap();
+    let report = zpinger::SsePinger::new(format!("http://{addr}/events"))
+        .with_event("tick")
+        .with_data_pattern("^a\nb$")
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("event"), Some("tick"));
+    assert_eq!(report.detail("events_seen"), Some("2"));
+    assert_eq!(report.body.as_deref(), Some(&b"a\nb"[..]));
+}
+
+#[tokio::test]
+async fn sse_pinger_resumes_after_last_event_id() {
+    let addr = testserver::start_sse("127.0.0.1:0", SSE_EVENTS).unwrap();
+    let report = zpinger::SsePinger::new(format!("http://{addr}/events"))
+        .with_last_event_id("1")
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("id"), Some("2"));
+    assert_eq!(report.detail("events_seen"), Some("1"));
+}
+
+#[tokio::test]
+async fn sse_pinger_streams_over_tls() {
+    let server = testserver::start_sse_tls("127.0.0.1:0", SSE_EVENTS).unwrap();
+    let report = zpinger::SsePinger::new(format!("https://localhost:{}/", server.addr.port()))
+        .with_tls_config(server.client_config)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("event"), Some("message"));
+}
+
+#[tokio::test]
+async fn sse_pinger_fails_when_the_stream_ends_or_stays_quiet() {
+    // No framing headers: the stream runs until the server closes.
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\ndata: hi\r\n\r\n",
+    )
+    .unwrap();
+    let report = zpinger::SsePinger::new(format!("http://{addr}/"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"hi"[..]));
+    let err = zpinger::SsePinger::new(format!("http://{addr}/"))
+        .with_event("tick")
+        .probe()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert!(err.to_string().contains("after 1 events"), "{err}");
+
+    let addr = testserver::start_sse("127.0.0.1:0", SSE_EVENTS).unwrap();
+    let err = zpinger::SsePinger::new(format!("http://{addr}/"))
+        .with_data_pattern("never")
+        .with_timeout(Duration::from_millis(300))
+        .probe()
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+#[tokio::test]
+async fn sse_pinger_rejects_other_answers() {
+    let addr = testserver::start_http_canned(
+        "127.0.0.1:0",
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/plain\r\n\
+         Content-Length: 4\r\n\r\ndown",
+    )
+    .unwrap();
+    let err = zpinger::SsePinger::new(format!("http://{addr}/"))
+        .probe()
+        .await
+        .unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(
+        assertion.failures,
+        [
+            "status 503 not 200",
+            "Content-Type text/plain is not text/event-stream"
+        ]
+    );
+}
+
 #[tokio::test]
 async fn ws_pinger_succeeds_on_ws_server() {
     let addr = testserver::start_ws_ok("127.0.0.1:0").unwrap();
@@ -205,6 +1014,37 @@ async fn ws_pinger_via_timed_helper() {
     assert!(elapsed > Duration::from_nanos(0));
 }
 
+#[tokio::test]
+async fn ws_pinger_authenticates_the_upgrade() {
+    let addr = testserver::start_ws_auth("127.0.0.1:0", "knock", "p@ss").unwrap();
+    let target = format!("ws://{addr}/feed?room=1");
+    for (auth, scheme) in [
+        (zpinger::HttpAuth::bearer("p@ss"), "bearer"),
+        (zpinger::HttpAuth::digest("knock", "p@ss"), "digest"),
+    ] {
+        let report = zpinger::WebSocketPinger::new(target.clone())
+            .with_auth(auth)
+            .probe()
+            .await
+            .unwrap();
+        assert_eq!(report.detail("auth"), Some(scheme));
+    }
+
+    let report = zpinger::WebSocketPinger::new(format!("ws://knock:p%40ss@{addr}/"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("auth"), Some("basic"));
+
+    let err = zpinger::WebSocketPinger::new(target.clone())
+        .with_auth(zpinger::HttpAuth::digest("knock", "wrong"))
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+    assert!(zpinger::WebSocketPinger::new(target).ping().await.is_err());
+}
+
 #[tokio::test]
 async fn ws_pinger_fails_on_closed_port() {
     let target = format!("ws://{}/", closed_tcp_addr());
@@ -293,6 +1133,38 @@ async fn dns_pinger_usable_as_trait_object() {
     p.ping().await.unwrap();
 }
 
+#[tokio::test]
+async fn dns_pinger_probe_reports_answers() {
+    let addr = testserver::start_dns_answer("127.0.0.1:0", [192, 0, 2, 7].into()).unwrap();
+    let p = zpinger::DnsPinger::new(addr.to_string(), "example.com");
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.status, Some(0));
+    assert_eq!(report.answers, vec!["192.0.2.7"]);
+
+    let expecting =
+        zpinger::Expect::new(p).with_expectation(r"answer=^192\.0\.2\.".parse().unwrap());
+    expecting.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn expect_answer_fails_on_empty_answer_section() {
+    let addr = testserver::start_dns_ok("127.0.0.1:0").unwrap();
+    let p = zpinger::Expect::new(zpinger::DnsPinger::new(addr.to_string(), "example.com"))
+        .with_expectation("answer=.".parse().unwrap());
+    let err = p.ping().await.unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).unwrap();
+    assert_eq!(assertion.failures, vec!["no DNS answers"]);
+}
+
+#[tokio::test]
+async fn expect_wraps_boxed_pingers() {
+    let addr = testserver::start_tcp_banner("127.0.0.1:0", "SSH-2.0-testserver\r\n").unwrap();
+    let inner: Box<dyn Pinger> =
+        Box::new(zpinger::TcpPinger::new(addr.to_string()).with_mode(zpinger::TcpMode::Banner));
+    let p = zpinger::Expect::new(inner).with_expectation("body=^SSH-2\\.0".parse().unwrap());
+    p.ping().await.unwrap();
+}
+
 #[tokio::test]
 async fn mqtt_pinger_succeeds_against_test_broker() {
     let addr = testserver::start_mqtt_ok("127.0.0.1:0").unwrap();
@@ -475,25 +1347,107 @@ async fn grpc_stream_pinger_usable_as_trait_object() {
 }
 
 #[tokio::test]
-async fn hls_pinger_succeeds_on_media_playlist() {
-    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
-    let url = format!("http://localhost:{}/playlist.m3u8", addr.port());
-    zpinger::HlsPinger::new(url).ping().await.unwrap();
+async fn grpc_web_pinger_reports_serving() {
+    let addr = testserver::start_grpc_web("127.0.0.1:0").unwrap();
+    let report = zpinger::GrpcWebPinger::new(format!("grpc://{addr}"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.detail("grpc_status"), Some("0"));
+    assert_eq!(report.detail("serving_status"), Some("SERVING"));
+    assert!(report.detail("header_ms").is_some());
 }
 
 #[tokio::test]
-async fn hls_pinger_succeeds_on_master_playlist() {
-    // Master playlist points at media playlist via STREAM-INF; the
-    // pinger should follow the variant before fetching the segment.
-    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
-    let url = format!("http://localhost:{}/master.m3u8", addr.port());
-    zpinger::HlsPinger::new(url).ping().await.unwrap();
+async fn grpc_web_pinger_fails_unless_serving() {
+    let addr = testserver::start_grpc_web("127.0.0.1:0").unwrap();
+    let err = zpinger::GrpcWebPinger::new(addr.to_string())
+        .with_service("down")
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("status 2 (expected SERVING=1)"),
+        "{err}"
+    );
+
+    let err = zpinger::GrpcWebPinger::new(addr.to_string())
+        .with_service("my.Svc")
+        .ping()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Health/Check: grpc-status 5 (NOT_FOUND): unknown service my.Svc"
+    );
 }
 
 #[tokio::test]
-async fn hls_pinger_via_timed_helper() {
-    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
-    let url = format!("http://localhost:{}/playlist.m3u8", addr.port());
+async fn grpc_web_pinger_reads_trailers_only_answers() {
+    // A path prefix the proxy doesn't route: the status comes back in
+    // the HTTP headers with no body.
+    let addr = testserver::start_grpc_web("127.0.0.1:0").unwrap();
+    let err = zpinger::GrpcWebPinger::new(format!("http://{addr}/rpc"))
+        .ping()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Health/Check: grpc-status 12 (UNIMPLEMENTED): unknown method"
+    );
+}
+
+#[tokio::test]
+async fn grpc_web_pinger_trusts_ca_cert_over_tls() {
+    let server = testserver::start_grpc_web_tls("127.0.0.1:0").unwrap();
+    let endpoint = format!("grpcs://localhost:{}", server.addr.port());
+    zpinger::GrpcWebPinger::new(endpoint.clone())
+        .with_ca_cert(server.cert_pem.clone())
+        .ping()
+        .await
+        .unwrap();
+    let err = zpinger::GrpcWebPinger::new(endpoint)
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("certificate"), "{err}");
+}
+
+#[tokio::test]
+async fn grpc_web_pinger_rejects_non_grpc_web_answers() {
+    let addr = testserver::start_http_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::GrpcWebPinger::new(addr.to_string())
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("is not application/grpc-web+proto"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn hls_pinger_succeeds_on_media_playlist() {
+    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
+    let url = format!("http://localhost:{}/playlist.m3u8", addr.port());
+    zpinger::HlsPinger::new(url).ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn hls_pinger_succeeds_on_master_playlist() {
+    // Master playlist points at media playlist via STREAM-INF; the
+    // pinger should follow the variant before fetching the segment.
+    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
+    let url = format!("http://localhost:{}/master.m3u8", addr.port());
+    zpinger::HlsPinger::new(url).ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn hls_pinger_via_timed_helper() {
+    let addr = testserver::start_hls_ok("127.0.0.1:0").unwrap();
+    let url = format!("http://localhost:{}/playlist.m3u8", addr.port());
     let p = zpinger::HlsPinger::new(url);
     let elapsed = zpinger::timed(&p).await.unwrap();
     assert!(elapsed > Duration::from_nanos(0));
@@ -508,6 +1462,27 @@ async fn hls_pinger_fails_on_non_m3u8_response() {
     assert!(zpinger::HlsPinger::new(url).ping().await.is_err());
 }
 
+#[tokio::test]
+async fn hls_pinger_authenticates_every_fetch() {
+    let addr = testserver::start_hls_auth("127.0.0.1:0", "knock", "p@ss").unwrap();
+    let url = format!("http://{addr}/master.m3u8");
+    let report = zpinger::HlsPinger::new(url.clone())
+        .with_auth(zpinger::HttpAuth::digest("knock", "p@ss"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("auth"), Some("digest"));
+
+    let report = zpinger::HlsPinger::new(format!("http://knock:p%40ss@{addr}/master.m3u8"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("auth"), Some("basic"));
+
+    let err = zpinger::HlsPinger::new(url).ping().await.unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+}
+
 #[tokio::test]
 async fn hls_pinger_fails_on_closed_port() {
     let url = format!("http://{}/playlist.m3u8", closed_tcp_addr());
@@ -605,6 +1580,579 @@ async fn tls_pinger_usable_as_trait_object() {
     p.ping().await.unwrap();
 }
 
+#[tokio::test]
+async fn tls_pinger_reports_certificate_chain() {
+    let server = testserver::start_https_expiring("127.0.0.1:0", 40).unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.certificates.len(), 1);
+    let leaf = &report.certificates[0];
+    assert_eq!(leaf.subject, "CN=expiring.localhost");
+    assert_eq!(leaf.subject_alt_names, vec!["DNS:localhost"]);
+    assert_eq!(leaf.key_type, "EC P-256");
+    assert_eq!(leaf.days_until_expiry(), 40);
+    assert_eq!(report.detail("subject"), Some("CN=expiring.localhost"));
+    assert_eq!(report.detail("days_left"), Some("40"));
+    assert_eq!(report.detail("warning"), None);
+}
+
+#[tokio::test]
+async fn tls_pinger_warns_below_expiry_warning() {
+    let server = testserver::start_https_expiring("127.0.0.1:0", 10).unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .with_expiry_warning(30)
+        .with_expiry_critical(7)
+        .probe()
+        .await
+        .unwrap();
+    let warning = report.detail("warning").expect("warning detail");
+    assert!(warning.contains("expires in 10 days"), "{warning}");
+}
+
+#[tokio::test]
+async fn tls_pinger_fails_below_expiry_critical() {
+    let server = testserver::start_https_expiring("127.0.0.1:0", 3).unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let err = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .with_expiry_warning(30)
+        .with_expiry_critical(7)
+        .probe()
+        .await
+        .unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(
+        assertion.failures,
+        vec!["certificate CN=expiring.localhost expires in 3 days (critical threshold 7 days)"]
+    );
+}
+
+// -- mutual TLS -----------------------------------------------------------
+
+#[tokio::test]
+async fn https_pinger_presents_client_certificate() {
+    let server = testserver::start_https_mtls("127.0.0.1:0").unwrap();
+    let identity =
+        zpinger::ClientIdentity::from_pem(server.client_cert_pem, server.client_key_pem).unwrap();
+    let target = format!("https://localhost:{}/", server.addr.port());
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_tls_config(server.client_config)
+        .with_tls_options(zpinger::TlsOptions::new().with_client_identity(identity))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+}
+
+#[tokio::test]
+async fn https_pinger_fails_without_required_client_certificate() {
+    let server = testserver::start_https_mtls("127.0.0.1:0").unwrap();
+    let target = format!("https://localhost:{}/", server.addr.port());
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_tls_config(server.client_config)
+        .with_timeout(Duration::from_secs(2))
+        .ping()
+        .await
+        .expect_err("server requires a client certificate");
+    assert!(
+        err.to_string().to_lowercase().contains("certificate"),
+        "unexpected error: {err}"
+    );
+}
+
+// -- custom trust ---------------------------------------------------------
+
+fn https_target(server: &testserver::HttpsServer) -> String {
+    format!("https://localhost:{}/", server.addr.port())
+}
+
+#[tokio::test]
+async fn https_pinger_trusts_extra_ca() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let options = zpinger::TlsOptions::new()
+        .with_ca_pem(server.cert_pem.as_bytes())
+        .unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_options(options)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.detail("verification"), None);
+}
+
+#[tokio::test]
+async fn https_pinger_fails_with_no_roots() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_options(zpinger::TlsOptions::new().with_replace_roots(true))
+        .ping()
+        .await
+        .expect_err("nothing to trust");
+    assert!(err.to_string().contains("no trust anchors"), "{err}");
+}
+
+#[tokio::test]
+async fn insecure_mode_records_failed_verification() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_options(zpinger::TlsOptions::new().with_insecure(true))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    let verification = report.detail("verification").unwrap();
+    assert!(verification.starts_with("failed: "), "{verification}");
+}
+
+#[tokio::test]
+async fn insecure_mode_records_passing_verification() {
+    let server = testserver::start_wss_ok("127.0.0.1:0").unwrap();
+    let options = zpinger::TlsOptions::new()
+        .with_ca_pem(server.cert_pem.as_bytes())
+        .unwrap()
+        .with_insecure(true);
+    let report = zpinger::WebSocketPinger::new(format!("wss://localhost:{}/", server.addr.port()))
+        .with_tls_options(options)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("verification"), Some("ok"));
+}
+
+#[tokio::test]
+async fn pinned_key_is_accepted_and_others_rejected() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target.clone())
+        .with_tls_config(server.client_config.clone())
+        .probe()
+        .await
+        .unwrap();
+    let pin = &report.certificates[0].spki_sha256;
+
+    let pinned = zpinger::TlsOptions::new()
+        .with_insecure(true)
+        .with_pin_sha256(pin)
+        .unwrap();
+    zpinger::TlsPinger::new(target.clone())
+        .with_tls_options(pinned)
+        .ping()
+        .await
+        .unwrap();
+
+    let wrong = zpinger::TlsOptions::new()
+        .with_insecure(true)
+        .with_pin_sha256(&"00".repeat(32))
+        .unwrap();
+    let err = zpinger::TlsPinger::new(target)
+        .with_tls_options(wrong)
+        .ping()
+        .await
+        .expect_err("pin does not match");
+    assert!(err.to_string().contains("pinned public key"), "{err}");
+}
+
+// -- SNI / ALPN -----------------------------------------------------------
+
+#[tokio::test]
+async fn server_name_override_sets_sni_and_verification_name() {
+    let server = testserver::start_https_sni_echo("127.0.0.1:0").unwrap();
+    // The certificate only names `localhost`, so dialling the IP
+    // needs the override to verify.
+    let target = format!("https://127.0.0.1:{}/", server.addr.port());
+    let err = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target.clone())
+        .with_tls_config(server.client_config.clone())
+        .ping()
+        .await
+        .expect_err("certificate does not cover 127.0.0.1");
+    assert!(err.to_string().contains("certificate"), "{err}");
+
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_tls_config(server.client_config)
+        .with_tls_options(zpinger::TlsOptions::new().with_server_name("localhost"))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"sni=localhost"[..]));
+}
+
+#[tokio::test]
+async fn sni_can_be_left_out() {
+    let server = testserver::start_https_sni_echo("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_config(server.client_config)
+        .with_tls_options(zpinger::TlsOptions::new().with_sni(false))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.body.as_deref(), Some(&b"sni=-"[..]));
+}
+
+#[tokio::test]
+async fn tls_pinger_reports_negotiated_parameters() {
+    let server = testserver::start_https_sni_echo("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .with_tls_options(zpinger::TlsOptions::new().with_alpn(vec![b"http/1.1".to_vec()]))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("version"), Some("TLSv1_3"));
+    assert!(report.detail("cipher_suite").unwrap().starts_with("TLS13_"));
+    assert_eq!(report.detail("key_exchange_group"), Some("X25519"));
+    assert_eq!(report.detail("alpn"), Some("http/1.1"));
+    assert_eq!(report.detail("resumed"), Some("false"));
+}
+
+// -- session resumption ---------------------------------------------------
+
+#[tokio::test]
+async fn tls_pinger_measures_resumed_handshake() {
+    let server = testserver::start_https_resumable("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .with_resumption(zpinger::ResumptionMode::Ticket)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("resumption"), Some("accepted"));
+    assert_eq!(report.detail("early_data"), None);
+    for key in ["full_ms", "resumed_ms"] {
+        let ms: f64 = report.detail(key).unwrap().parse().unwrap();
+        assert!(ms > 0.0, "{key}={ms}");
+    }
+    // The certificate comes from the full handshake.
+    assert_eq!(report.certificates.len(), 1);
+}
+
+#[tokio::test]
+async fn https_pinger_sends_request_as_early_data() {
+    let server = testserver::start_https_resumable("127.0.0.1:0").unwrap();
+    let report = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, https_target(&server))
+        .with_tls_config(server.client_config)
+        .with_resumption(zpinger::ResumptionMode::EarlyData)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.detail("resumption"), Some("accepted"));
+    assert_eq!(report.detail("early_data"), Some("accepted"));
+    assert_eq!(report.body.as_deref(), Some(&b"early"[..]));
+}
+
+#[tokio::test]
+async fn early_data_is_reported_rejected_when_server_does_not_allow_it() {
+    // `start_https_ok` resumes sessions but advertises no 0-RTT budget.
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let report = zpinger::TlsPinger::new(target)
+        .with_tls_config(server.client_config)
+        .with_resumption(zpinger::ResumptionMode::EarlyData)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("resumption"), Some("accepted"));
+    assert_eq!(report.detail("early_data"), Some("rejected"));
+}
+
+// -- OCSP stapling / CT ---------------------------------------------------
+
+/// Stapling checks need the verifier built from `TlsOptions`, so
+/// these trust the fixture's certificate that way.
+async fn probe_stapling(server: &testserver::HttpsServer) -> zpinger::Report {
+    let options = zpinger::TlsOptions::new()
+        .with_ca_pem(server.cert_pem.as_bytes())
+        .unwrap()
+        .with_replace_roots(true);
+    zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_options(options)
+        .with_stapling_checks(true)
+        .probe()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn tls_pinger_reports_stapled_ocsp_and_scts() {
+    let server = testserver::start_https_stapled("127.0.0.1:0", 6, 2).unwrap();
+    let report = probe_stapling(&server).await;
+    assert_eq!(report.detail("ocsp"), Some("good"));
+    assert_eq!(
+        report.detail("ocsp_responder"),
+        Some("CN=Test OCSP Responder")
+    );
+    assert!(report.detail("ocsp_this_update").is_some());
+    assert!(report.detail("ocsp_next_update").is_some());
+    // Two embedded in the certificate, one in the staple.
+    assert_eq!(report.detail("scts"), Some("3"));
+    assert_eq!(report.detail("warning"), None);
+}
+
+#[tokio::test]
+async fn stale_staple_is_a_warning() {
+    let server = testserver::start_https_stapled("127.0.0.1:0", -1, 0).unwrap();
+    let report = probe_stapling(&server).await;
+    assert_eq!(report.detail("ocsp"), Some("good"));
+    assert_eq!(report.detail("scts"), Some("1"));
+    let warning = report.detail("warning").unwrap();
+    assert!(warning.starts_with("stale OCSP staple"), "{warning}");
+}
+
+#[tokio::test]
+async fn missing_staple_is_a_warning() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let report = probe_stapling(&server).await;
+    assert_eq!(report.detail("ocsp"), Some("none"));
+    assert_eq!(report.detail("scts"), Some("0"));
+    assert_eq!(report.detail("warning"), Some("no OCSP response stapled"));
+}
+
+#[tokio::test]
+async fn staple_is_unavailable_through_an_injected_config() {
+    let server = testserver::start_https_stapled("127.0.0.1:0", 6, 1).unwrap();
+    let report = zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_config(server.client_config)
+        .with_stapling_checks(true)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("ocsp"), Some("unavailable"));
+    assert_eq!(report.detail("scts"), Some("1"));
+}
+
+// -- STARTTLS -------------------------------------------------------------
+
+#[tokio::test]
+async fn tls_pinger_upgrades_with_each_starttls_dialogue() {
+    for name in ["smtp", "imap", "pop3", "ftp", "xmpp", "ldap", "postgres"] {
+        let server = testserver::start_starttls("127.0.0.1:0", name).unwrap();
+        let protocol: zpinger::StartTls = name.parse().unwrap();
+        let report = zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+            .with_tls_config(server.client_config)
+            .with_starttls(protocol)
+            .probe()
+            .await
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(report.detail("starttls"), Some(name));
+        assert!(report.detail("starttls_ms").is_some(), "{name}");
+        assert!(report.detail("handshake_ms").is_some(), "{name}");
+        assert_eq!(report.certificates.len(), 1, "{name}");
+    }
+}
+
+#[tokio::test]
+async fn starttls_fails_when_not_advertised() {
+    let server = testserver::start_starttls("127.0.0.1:0", "smtp-refuse").unwrap();
+    let err = zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_config(server.client_config)
+        .with_starttls(zpinger::StartTls::Smtp)
+        .ping()
+        .await
+        .expect_err("server never offered STARTTLS");
+    assert!(
+        err.to_string().contains("SMTP server refused STARTTLS"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn starttls_resumes_over_a_fresh_dialogue() {
+    let server = testserver::start_starttls("127.0.0.1:0", "postgres").unwrap();
+    let report = zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_config(server.client_config)
+        .with_starttls(zpinger::StartTls::Postgres)
+        .with_resumption(zpinger::ResumptionMode::Ticket)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("resumption"), Some("accepted"));
+}
+
+// -- TLS failure diagnostics ----------------------------------------------
+
+/// Probe `target` trusting `ca_pem`, expecting a classified failure.
+async fn tls_failure(target: String, ca_pem: Option<&str>) -> std::io::Error {
+    let mut pinger = zpinger::TlsPinger::new(target);
+    if let Some(pem) = ca_pem {
+        let options = zpinger::TlsOptions::new()
+            .with_ca_pem(pem.as_bytes())
+            .unwrap()
+            .with_replace_roots(true);
+        pinger = pinger.with_tls_options(options);
+    }
+    pinger.probe().await.expect_err("handshake should fail")
+}
+
+fn cause(err: &std::io::Error) -> zpinger::TlsFailureCause {
+    zpinger::TlsFailure::from_io(err)
+        .unwrap_or_else(|| panic!("not classified: {err}"))
+        .cause
+}
+
+#[tokio::test]
+async fn tls_failure_names_an_expired_certificate() {
+    let server = testserver::start_https_expired("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let err = tls_failure(target, Some(&server.cert_pem)).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::Expired);
+    let failure = zpinger::TlsFailure::from_io(&err).unwrap();
+    assert_eq!(failure.certificates.len(), 1);
+    assert!(failure.certificates[0].days_until_expiry() < 0);
+    assert!(err.to_string().contains("(expired)"), "{err}");
+}
+
+#[tokio::test]
+async fn tls_failure_names_a_hostname_mismatch() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let err = tls_failure(server.addr.to_string(), Some(&server.cert_pem)).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::HostnameMismatch);
+}
+
+#[tokio::test]
+async fn tls_failure_names_a_self_signed_certificate() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let err = tls_failure(format!("localhost:{}", server.addr.port()), None).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::SelfSigned);
+    let failure = zpinger::TlsFailure::from_io(&err).unwrap();
+    assert_eq!(failure.certificates.len(), 1);
+    assert!(failure.cause.hint().is_some());
+}
+
+#[tokio::test]
+async fn tls_failure_names_a_missing_intermediate() {
+    let server = testserver::start_https_leaf_only("127.0.0.1:0").unwrap();
+    let target = format!("localhost:{}", server.addr.port());
+    let err = tls_failure(target, Some(&server.cert_pem)).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::MissingIntermediate);
+    let leaf = &zpinger::TlsFailure::from_io(&err).unwrap().certificates[0];
+    assert!(leaf.issuer.contains("intermediate"), "{}", leaf.issuer);
+}
+
+#[tokio::test]
+async fn tls_failure_names_a_pin_mismatch() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let options = zpinger::TlsOptions::new()
+        .with_ca_pem(server.cert_pem.as_bytes())
+        .unwrap()
+        .with_pin_sha256(&"00".repeat(32))
+        .unwrap();
+    let err = zpinger::TlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_options(options)
+        .probe()
+        .await
+        .expect_err("pin does not match");
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::PinMismatch);
+}
+
+#[tokio::test]
+async fn tls_failure_spots_plaintext_on_the_port() {
+    let addr = testserver::start_http_ok("127.0.0.1:0").unwrap();
+    let err = tls_failure(format!("localhost:{}", addr.port()), None).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::NotTls);
+}
+
+#[tokio::test]
+async fn tls_failure_spots_a_server_hanging_up() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            drop(stream);
+        }
+    });
+    let err = tls_failure(format!("localhost:{port}"), None).await;
+    assert_eq!(cause(&err), zpinger::TlsFailureCause::ConnectionClosed);
+}
+
+#[tokio::test]
+async fn connect_errors_are_not_tls_failures() {
+    let err = tls_failure(closed_tcp_addr(), None).await;
+    assert!(zpinger::TlsFailure::from_io(&err).is_none());
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
+
+// -- TLS scan -------------------------------------------------------------
+
+#[tokio::test]
+async fn tls_scan_maps_versions_suites_and_groups() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::TlsScanPinger::new(format!("localhost:{}", server.addr.port()))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("tls1_0"), Some("refused"));
+    assert_eq!(report.detail("tls1_1"), Some("refused"));
+    assert_eq!(report.detail("tls1_2"), Some("accepted"));
+    assert_eq!(report.detail("tls1_3"), Some("accepted"));
+    assert_eq!(
+        report.detail("tls1_3_suites").unwrap().split(", ").count(),
+        3
+    );
+    // The fixture's key is ECDSA, so no ECDHE_RSA suites.
+    let tls12 = report.detail("tls1_2_suites").unwrap();
+    assert_eq!(tls12.split(", ").count(), 3, "{tls12}");
+    assert!(!tls12.contains("RSA"), "{tls12}");
+    // rustls servers follow the client's order by default.
+    assert_eq!(report.detail("tls1_3_order"), Some("client"));
+    assert!(report.detail("groups").unwrap().contains("X25519"));
+    assert_eq!(report.detail("warning"), None);
+}
+
+#[tokio::test]
+async fn tls_scan_reports_server_preference() {
+    let server = testserver::start_https_preferring("127.0.0.1:0").unwrap();
+    let report = zpinger::TlsScanPinger::new(format!("localhost:{}", server.addr.port()))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("tls1_2"), Some("refused"));
+    assert_eq!(report.detail("tls1_2_suites"), None);
+    assert_eq!(
+        report.detail("tls1_3_suites"),
+        Some("TLS13_CHACHA20_POLY1305_SHA256, TLS13_AES_256_GCM_SHA384")
+    );
+    assert_eq!(report.detail("tls1_3_order"), Some("server"));
+    assert_eq!(report.detail("groups"), Some("secp384r1"));
+}
+
+#[tokio::test]
+async fn tls_scan_warns_on_legacy_versions() {
+    let addr = testserver::start_tls_legacy("127.0.0.1:0").unwrap();
+    let report = zpinger::TlsScanPinger::new(format!("localhost:{}", addr.port()))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("tls1_0"), Some("accepted"));
+    assert_eq!(report.detail("tls1_1"), Some("accepted"));
+    assert_eq!(report.detail("tls1_2"), Some("refused"));
+    assert_eq!(report.detail("tls1_3"), Some("refused"));
+    let warnings: Vec<_> = report
+        .details
+        .iter()
+        .filter(|(k, _)| k == "warning")
+        .map(|(_, v)| v.as_str())
+        .collect();
+    assert_eq!(warnings, ["TLS 1.0 accepted", "TLS 1.1 accepted"]);
+}
+
+#[tokio::test]
+async fn tls_scan_fails_on_closed_port() {
+    let err = zpinger::TlsScanPinger::new(closed_tcp_addr())
+        .ping()
+        .await
+        .expect_err("nothing to scan");
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+}
+
 // -- NTP pinger -----------------------------------------------------------
 
 #[tokio::test]
@@ -744,6 +2292,18 @@ async fn rtsp_pinger_fails_on_closed_port() {
     assert!(p.ping().await.is_err());
 }
 
+#[tokio::test]
+async fn rtsp_pinger_probe_reports_public_methods() {
+    let addr = testserver::start_rtsp_ok("127.0.0.1:0").unwrap();
+    let p = zpinger::Expect::new(zpinger::RtspPinger::new(format!(
+        "rtsp://localhost:{}",
+        addr.port()
+    )))
+    .with_expectation("header=Public:DESCRIBE".parse().unwrap());
+    let report = p.probe().await.unwrap();
+    assert_eq!(report.status, Some(200));
+}
+
 #[tokio::test]
 async fn rtsp_pinger_rejects_non_rtsp_scheme() {
     let p = zpinger::RtspPinger::new("http://example.com/").with_timeout(Duration::from_secs(1));
@@ -941,3 +2501,415 @@ async fn quic_pinger_with_alpn_override() {
         "expected ALPN error, got: {err}"
     );
 }
+
+#[tokio::test]
+async fn http3_pinger_gets_a_response() {
+    let server = testserver::start_h3_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::Http3Pinger::new(format!(
+        "https://localhost:{}/health?full=1",
+        server.addr.port()
+    ))
+    .with_tls_config(server.client_config)
+    .probe()
+    .await
+    .unwrap();
+    assert_eq!(report.status, Some(200));
+    assert_eq!(report.header("content-type"), Some("text/plain"));
+    assert_eq!(report.body.as_deref(), Some(&b"GET /health"[..]));
+    assert_eq!(report.detail("version"), Some("HTTP/3"));
+    assert_eq!(report.detail("body_bytes"), Some("11"));
+    for key in ["handshake_ms", "request_ms", "header_ms"] {
+        assert!(report.detail(key).is_some(), "missing {key}");
+    }
+}
+
+#[tokio::test]
+async fn http3_pinger_judges_status() {
+    let server = testserver::start_h3_ok("127.0.0.1:0").unwrap();
+    let target = format!("https://localhost:{}/status/503", server.addr.port());
+    let p = zpinger::Http3Pinger::new(target).with_tls_config(server.client_config);
+    let err = p.ping().await.unwrap_err();
+    let assertion = zpinger::AssertionError::from_io(&err).expect("assertion error");
+    assert_eq!(assertion.failures, ["status 503 not in 200-399"]);
+
+    let p = p.with_expected_status("503".parse().unwrap());
+    p.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn http3_pinger_fails_when_server_only_handshakes() {
+    // `start_quic_ok` finishes the handshake, then closes without
+    // serving a request — exactly what `QuicPinger` can't tell apart.
+    let server = testserver::start_quic_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::Http3Pinger::new(format!("https://localhost:{}/", server.addr.port()))
+        .with_tls_config(server.client_config)
+        .with_timeout(Duration::from_secs(2))
+        .ping()
+        .await
+        .unwrap_err();
+    assert_ne!(err.kind(), std::io::ErrorKind::TimedOut, "{err}");
+}
+
+// -- Protocol comparison -------------------------------------------------
+
+#[tokio::test]
+async fn compare_pinger_times_all_three_protocols() {
+    let server = testserver::start_https_alt_svc("127.0.0.1:0", true).unwrap();
+    let dns = testserver::start_dns_ok("127.0.0.1:0").unwrap();
+    let port = server.addr.port();
+    let report = zpinger::HttpComparePinger::new(format!("https://localhost:{port}/health"))
+        .with_tls_config(server.client_config)
+        .with_resolver(dns.to_string())
+        .probe()
+        .await
+        .unwrap();
+    for key in ["http1", "http2", "http3"] {
+        assert_eq!(
+            report.detail(&format!("{key}_status")),
+            Some("200"),
+            "{key}"
+        );
+        assert!(report.detail(&format!("{key}_ms")).is_some(), "{key}");
+    }
+    assert_eq!(
+        report.detail("alt_svc"),
+        Some(format!("h3=\":{port}\"; ma=86400").as_str())
+    );
+    assert_eq!(report.detail("https_rr"), Some("none"));
+    assert_eq!(report.detail("h3_advertised"), Some("alt-svc"));
+    assert!(report.detail("fastest").is_some());
+    assert_eq!(report.detail("failed"), None);
+    assert_eq!(report.detail("warning"), None);
+}
+
+#[tokio::test]
+async fn compare_pinger_warns_when_advertised_h3_fails() {
+    let server = testserver::start_https_alt_svc("127.0.0.1:0", false).unwrap();
+    let dns = testserver::start_dns_ok("127.0.0.1:0").unwrap();
+    let report =
+        zpinger::HttpComparePinger::new(format!("https://localhost:{}/", server.addr.port()))
+            .with_tls_config(server.client_config)
+            .with_resolver(dns.to_string())
+            .with_timeout(Duration::from_secs(1))
+            .probe()
+            .await
+            .unwrap();
+    assert_eq!(report.detail("http1_status"), Some("200"));
+    assert_eq!(report.detail("http2_status"), Some("200"));
+    assert!(report.detail("http3_error").is_some());
+    assert_eq!(report.detail("failed"), Some("HTTP/3"));
+    assert_eq!(
+        report.detail("warning"),
+        Some("HTTP/3 advertised but failed")
+    );
+}
+
+#[tokio::test]
+async fn compare_pinger_fails_when_nothing_answers() {
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = closed.local_addr().unwrap().port();
+    drop(closed);
+    let dns = testserver::start_dns_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::HttpComparePinger::new(format!("https://localhost:{port}/"))
+        .with_resolver(dns.to_string())
+        .with_timeout(Duration::from_millis(500))
+        .ping()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("no protocol answered"), "{err}");
+}
+
+// -- DTLS pinger ----------------------------------------------------------
+
+fn trusting(pem: &str) -> zpinger::TlsOptions {
+    zpinger::TlsOptions::new()
+        .with_ca_pem(pem.as_bytes())
+        .unwrap()
+        .with_replace_roots(true)
+}
+
+#[tokio::test]
+async fn dtls_pinger_completes_certificate_handshake() {
+    let server = testserver::start_dtls_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::DtlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_options(trusting(&server.cert_pem))
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("mode"), Some("certificate"));
+    assert_eq!(report.detail("version"), Some("DTLSv1_2"));
+    assert!(report.detail("handshake_ms").is_some());
+    assert_eq!(report.certificates.len(), 1);
+    assert!(report.certificates[0]
+        .subject_alt_names
+        .iter()
+        .any(|san| san.contains("localhost")));
+}
+
+#[tokio::test]
+async fn dtls_pinger_rejects_untrusted_certificate() {
+    let server = testserver::start_dtls_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::DtlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_timeout(Duration::from_secs(2))
+        .ping()
+        .await
+        .expect_err("self-signed certificate is not in webpki-roots");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{err}");
+    assert!(err.to_string().contains("certificate"), "{err}");
+}
+
+#[tokio::test]
+async fn dtls_pinger_insecure_records_verification() {
+    let server = testserver::start_dtls_ok("127.0.0.1:0").unwrap();
+    let report = zpinger::DtlsPinger::new(format!("localhost:{}", server.addr.port()))
+        .with_tls_options(zpinger::TlsOptions::new().with_insecure(true))
+        .probe()
+        .await
+        .unwrap();
+    assert!(
+        report
+            .detail("verification")
+            .is_some_and(|v| v.starts_with("failed")),
+        "{:?}",
+        report.details
+    );
+}
+
+#[tokio::test]
+async fn dtls_pinger_checks_the_host_name() {
+    let server = testserver::start_dtls_ok("127.0.0.1:0").unwrap();
+    let err = zpinger::DtlsPinger::new(server.addr.to_string())
+        .with_tls_options(trusting(&server.cert_pem))
+        .with_timeout(Duration::from_secs(2))
+        .ping()
+        .await
+        .expect_err("certificate is for localhost, not 127.0.0.1");
+    assert!(err.to_string().contains("not valid for name"), "{err}");
+}
+
+#[tokio::test]
+async fn dtls_pinger_completes_psk_handshake() {
+    let server = testserver::start_dtls_psk("127.0.0.1:0", b"sensor-7", b"s3cret").unwrap();
+    let report = zpinger::DtlsPinger::new(format!("coaps://127.0.0.1:{}", server.addr.port()))
+        .with_psk("sensor-7", "s3cret")
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.detail("mode"), Some("psk"));
+    assert!(report.certificates.is_empty());
+}
+
+#[tokio::test]
+async fn dtls_pinger_fails_with_wrong_psk() {
+    let server = testserver::start_dtls_psk("127.0.0.1:0", b"sensor-7", b"s3cret").unwrap();
+    for (identity, key) in [("sensor-7", "wrong"), ("sensor-8", "s3cret")] {
+        zpinger::DtlsPinger::new(server.addr.to_string())
+            .with_psk(identity, key)
+            .with_timeout(Duration::from_secs(2))
+            .ping()
+            .await
+            .expect_err("mismatched PSK must fail the handshake");
+    }
+}
+
+#[tokio::test]
+async fn dtls_pinger_times_out_on_silent_port() {
+    let sock = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let err = zpinger::DtlsPinger::new(sock.local_addr().unwrap().to_string())
+        .with_timeout(Duration::from_millis(300))
+        .ping()
+        .await
+        .expect_err("silent UDP port should time out");
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+}
+
+/// Records every observer event as a short string, in order.
+#[derive(Default)]
+struct EventLog {
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+impl EventLog {
+    fn push(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+}
+
+impl zpinger::Observer for EventLog {
+    fn on_connected(&self, peer: std::net::SocketAddr) {
+        self.push(format!("connected {peer}"));
+    }
+    fn on_tls_handshake(&self, tls: &zpinger::TlsInfo) {
+        self.push(format!(
+            "tls alpn={:?} certs={}",
+            tls.alpn.as_deref().map(String::from_utf8_lossy),
+            tls.peer_certificates.len()
+        ));
+    }
+    fn on_sent(&self, bytes: usize) {
+        self.push(format!("sent {bytes}"));
+    }
+    fn on_received(&self, bytes: usize) {
+        self.push(format!("received {bytes}"));
+    }
+    fn on_result(&self, _elapsed: Duration, result: &std::io::Result<zpinger::Report>) {
+        self.push(format!("result ok={}", result.is_ok()));
+    }
+}
+
+#[tokio::test]
+async fn observed_tcp_pinger_reports_connection_and_bytes() {
+    let addr = testserver::start_tcp_echo("127.0.0.1:0").unwrap();
+    let log = Arc::new(EventLog::default());
+    let p = zpinger::Observed::new(zpinger::TcpPinger::new(addr.to_string()), log.clone());
+    p.ping().await.unwrap();
+    assert_eq!(
+        log.take(),
+        vec![
+            format!("connected {addr}"),
+            "sent 1".to_string(),
+            "received 1".to_string(),
+            "result ok=true".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn observed_udp_pinger_reports_datagrams() {
+    let addr = testserver::start_dns_ok("127.0.0.1:0").unwrap();
+    let log = Arc::new(EventLog::default());
+    let p = zpinger::Observed::new(
+        zpinger::DnsPinger::new(addr.to_string(), "example.com"),
+        log.clone(),
+    );
+    p.ping().await.unwrap();
+    // 12-byte header + 13-byte qname + 4 bytes qtype/qclass, echoed.
+    assert_eq!(
+        log.take(),
+        vec![
+            format!("connected {addr}"),
+            "sent 29".to_string(),
+            "received 29".to_string(),
+            "result ok=true".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn observed_https_pinger_reports_tls_handshake() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let target = format!("https://localhost:{}/", server.addr.port());
+    let log = Arc::new(EventLog::default());
+    let p = zpinger::Observed::new(
+        zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+            .with_tls_config(server.client_config),
+        log.clone(),
+    );
+    p.ping().await.unwrap();
+    let events = log.take();
+    assert!(events[0].starts_with("connected "), "{events:?}");
+    assert!(
+        events.contains(&"tls alpn=None certs=1".to_string()),
+        "{events:?}"
+    );
+    assert!(events.iter().any(|e| e.starts_with("sent ")));
+    assert!(events.iter().any(|e| e.starts_with("received ")));
+    assert_eq!(events.last().unwrap(), "result ok=true");
+}
+
+#[tokio::test]
+async fn observed_quic_pinger_reports_handshake() {
+    let server = testserver::start_quic_ok("127.0.0.1:0").unwrap();
+    let log = Arc::new(EventLog::default());
+    let p = zpinger::Observed::new(
+        zpinger::QuicPinger::new(format!("quic://localhost:{}", server.addr.port()))
+            .with_tls_config(server.client_config.clone()),
+        log.clone(),
+    );
+    p.ping().await.unwrap();
+    let events = log.take();
+    assert_eq!(
+        events[0],
+        format!("connected {}", server.addr),
+        "{events:?}"
+    );
+    assert_eq!(events[1], "tls alpn=Some(\"h3\") certs=1");
+    assert_eq!(events.last().unwrap(), "result ok=true");
+}
+
+#[tokio::test]
+async fn unobserved_pinger_emits_nothing() {
+    // The task-local is scoped to `Observed::probe`; a bare pinger
+    // run afterwards on the same task must not reach the old observer.
+    let addr = testserver::start_tcp_echo("127.0.0.1:0").unwrap();
+    let log = Arc::new(EventLog::default());
+    zpinger::Observed::new(zpinger::TcpPinger::new(addr.to_string()), log.clone())
+        .ping()
+        .await
+        .unwrap();
+    log.take();
+    zpinger::TcpPinger::new(addr.to_string())
+        .ping()
+        .await
+        .unwrap();
+    assert!(log.take().is_empty());
+}
+
+/// A token that fires after `after`.
+fn cancel_after(after: Duration) -> zpinger::CancellationToken {
+    let token = zpinger::CancellationToken::new();
+    let trigger = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(after).await;
+        trigger.cancel();
+    });
+    token
+}
+
+#[tokio::test]
+async fn cancelled_http_probe_lists_completed_phases() {
+    // The kernel completes the handshake from the backlog, but nobody
+    // ever accepts, so the request is sent and no reply comes.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let target = format!("http://{}/", listener.local_addr().unwrap());
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, target)
+        .with_timeout(Duration::from_secs(30));
+    let err = p
+        .probe_with_cancel(&cancel_after(Duration::from_millis(100)))
+        .await
+        .expect_err("cancelled probe should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+    let cancelled = zpinger::Cancelled::from_io(&err).unwrap();
+    let phases: Vec<_> = cancelled.phases.iter().map(|p| p.name).collect();
+    assert_eq!(phases, vec!["connect", "request"]);
+    assert!(cancelled.elapsed < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn cancelled_quic_probe_returns_promptly() {
+    use std::net::UdpSocket;
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = sock.local_addr().unwrap();
+    let p = zpinger::QuicPinger::new(format!("localhost:{}", addr.port()))
+        .with_timeout(Duration::from_secs(30));
+    let start = std::time::Instant::now();
+    let err = p
+        .probe_with_cancel(&cancel_after(Duration::from_millis(100)))
+        .await
+        .expect_err("cancelled handshake should fail");
+    assert!(zpinger::Cancelled::from_io(&err).is_some(), "{err}");
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn uncancelled_probe_with_cancel_succeeds() {
+    let addr = testserver::start_http_ok("127.0.0.1:0").unwrap();
+    let p = zpinger::HttpPinger::new(zpinger::HttpMethod::Get, format!("http://{addr}/"));
+    let token = zpinger::CancellationToken::new();
+    let (_, report) = zpinger::timed_probe_with_cancel(&p, &token).await.unwrap();
+    assert_eq!(report.status, Some(200));
+}
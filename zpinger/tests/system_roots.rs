@@ -0,0 +1,37 @@
+//! `TlsOptions::with_system_roots` reads the platform store once per
+//! process, honouring `SSL_CERT_FILE` — so this lives in its own test
+//! binary, where pointing that variable at a fixture can't leak into
+//! other tests.
+#![cfg(feature = "native-roots")]
+
+use zpinger::{HttpMethod, HttpPinger, Pinger, TlsOptions};
+
+#[tokio::test]
+async fn system_store_roots_are_trusted() {
+    let server = testserver::start_https_ok("127.0.0.1:0").unwrap();
+    let bundle = std::env::temp_dir().join(format!("zpinger-roots-{}.pem", std::process::id()));
+    std::fs::write(&bundle, &server.cert_pem).unwrap();
+    std::env::set_var("SSL_CERT_FILE", &bundle);
+    std::env::remove_var("SSL_CERT_DIR");
+
+    let options = TlsOptions::new()
+        .with_system_roots()
+        .unwrap()
+        .with_replace_roots(true);
+    let target = format!("https://localhost:{}/", server.addr.port());
+    let report = HttpPinger::new(HttpMethod::Get, target.clone())
+        .with_tls_options(options)
+        .probe()
+        .await
+        .unwrap();
+    assert_eq!(report.status, Some(200));
+
+    // The bundled roots alone don't know the fixture's certificate.
+    let err = HttpPinger::new(HttpMethod::Get, target)
+        .ping()
+        .await
+        .expect_err("self-signed certificate is not in webpki-roots");
+    assert!(err.to_string().contains("certificate"), "{err}");
+
+    let _ = std::fs::remove_file(bundle);
+}